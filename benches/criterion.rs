@@ -88,5 +88,46 @@ fn query(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(criterion, query);
+/// Compares the adaptive switching between select queries and scanning in PredicateIter with the predicate index
+/// and measures the object-bound patterns for the most frequent object, which never switch strategy.
+fn adaptive(c: &mut Criterion) {
+    let file = File::open("tests/resources/persondata_en.hdt").expect("error opening file");
+    let mut hdt = Hdt::new(std::io::BufReader::new(file)).unwrap();
+    let type_id = hdt.dict.string_to_id(TYPE, &IdKind::Predicate);
+
+    let mut group = c.benchmark_group("?P? select and scan");
+    group.sample_size(10);
+    let predicates = 1..=hdt.dict.predicates.num_strings();
+    for p in [type_id, predicates.min_by_key(|&p| PredicateIter::new(&hdt.triples, p).count()).unwrap()] {
+        let count = PredicateIter::new(&hdt.triples, p).count();
+        group.bench_function(format!("5.1 (?, {p}, ?) adaptive {count} triples"), |b| {
+            b.iter(|| PredicateIter::new(&hdt.triples, p).count())
+        });
+    }
+    hdt.triples.build_predicate_index().unwrap();
+    group.bench_function("5.2 (?, type, ?) predicate index", |b| {
+        b.iter(|| PredicateIter::new(&hdt.triples, type_id).count())
+    });
+    group.finish();
+
+    let triples = &hdt.triples;
+    let frequent = (1..=triples.op_index.bitmap.rank(triples.op_index.bitmap.len()))
+        .max_by_key(|&o| triples.object_degree(o))
+        .unwrap();
+    let t = ObjectIter::new(triples, frequent).next().unwrap();
+    let mut group = c.benchmark_group(format!("frequent object {} triples", triples.object_degree(frequent)));
+    group.sample_size(10);
+    group.bench_function("6.1 (?, ?, frequent) triple IDs", |b| {
+        b.iter(|| ObjectIter::new(triples, frequent).count())
+    });
+    group.bench_function("6.2 (?, p, frequent) subject IDs", |b| {
+        b.iter(|| PredicateObjectIter::new(triples, t.predicate_id, frequent).count())
+    });
+    group.bench_function("6.3 (s, ?, frequent) triple IDs", |b| {
+        b.iter(|| SubjectIter::with_pattern(triples, &TripleId::new(t.subject_id, 0, frequent)).count())
+    });
+    group.finish();
+}
+
+criterion_group!(criterion, query, adaptive);
 criterion_main!(criterion);
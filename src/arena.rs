@@ -0,0 +1,155 @@
+//! Arena allocation of the terms materialized by a query.
+//! *This module is available only if HDT is built with the `"arena"` feature.*
+//!
+//! The results borrow their strings from a [`TermArena`], which frees all of them at once when it is dropped.
+//! Use this for queries whose results are processed and dropped immediately,
+//! where allocating and freeing an `Arc<str>` per term would dominate the runtime.
+use crate::four_sect_dict::IdKind;
+use crate::hdt::Hdt;
+use crate::triples::TripleId;
+use log::error;
+use std::fmt;
+use std::iter;
+use typed_arena::Arena;
+
+/// Triple of subject, predicate and object strings that borrow from a [`TermArena`].
+pub type ArenaTriple<'a> = (&'a str, &'a str, &'a str);
+
+/// Bump allocator for the terms of one query.
+/// Terms are allocated in large chunks and are only freed when the arena is dropped.
+#[derive(Default)]
+pub struct TermArena {
+    bytes: Arena<u8>,
+}
+
+impl fmt::Debug for TermArena {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TermArena").field("bytes", &self.bytes.len()).finish()
+    }
+}
+
+impl TermArena {
+    /// Create an empty arena.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an empty arena whose first chunk has room for the given number of bytes.
+    pub fn with_capacity(bytes: usize) -> Self {
+        TermArena { bytes: Arena::with_capacity(bytes) }
+    }
+
+    /// Copy the given string into the arena.
+    pub fn alloc(&self, s: &str) -> &str {
+        self.bytes.alloc_str(s)
+    }
+
+    /// Number of bytes of all the strings allocated so far.
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Whether no strings have been allocated yet.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Iterator over triples whose strings borrow from a [`TermArena`], see [`triples_with_pattern`].
+/// Reuses the strings of the previous triple for components with the same ID and extracts all other strings into a single reused buffer before copying them into the arena.
+pub struct ArenaTripleIter<'a> {
+    hdt: &'a Hdt,
+    arena: &'a TermArena,
+    ids: Box<dyn Iterator<Item = TripleId> + 'a>,
+    buf: String,
+    last: Option<(TripleId, ArenaTriple<'a>)>,
+}
+
+impl<'a> ArenaTripleIter<'a> {
+    fn term(&mut self, id: usize, last: Option<(usize, &'a str)>, kind: &'static IdKind) -> Option<&'a str> {
+        if let Some((last_id, term)) = last {
+            if last_id == id {
+                return Some(term);
+            }
+        }
+        match self.hdt.dict.id_to_string_into(id, kind, &mut self.buf) {
+            Ok(()) => Some(self.arena.alloc(&self.buf)),
+            Err(e) => {
+                error!("{e}");
+                None
+            }
+        }
+    }
+}
+
+impl<'a> Iterator for ArenaTripleIter<'a> {
+    type Item = ArenaTriple<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let t = self.ids.next()?;
+            let last = self.last;
+            let s = self.term(t.subject_id, last.map(|(l, lt)| (l.subject_id, lt.0)), &IdKind::Subject);
+            let p = self.term(t.predicate_id, last.map(|(l, lt)| (l.predicate_id, lt.1)), &IdKind::Predicate);
+            let o = self.term(t.object_id, last.map(|(l, lt)| (l.object_id, lt.2)), &IdKind::Object);
+            if let (Some(s), Some(p), Some(o)) = (s, p, o) {
+                self.last = Some((t, (s, p, o)));
+                return Some((s, p, o));
+            }
+        }
+    }
+}
+
+/// Get all triples that fit the given triple pattern like [`Hdt::triples_with_pattern`], with strings allocated in the given arena.
+/// # Example
+/// ```
+/// use hdt::arena::{self, TermArena};
+/// fn count_label_bytes(hdt: &hdt::Hdt) -> usize {
+///     let arena = TermArena::new();
+///     let label = Some("http://www.w3.org/2000/01/rdf-schema#label");
+///     arena::triples_with_pattern(hdt, &arena, None, label, None).map(|(_, _, o)| o.len()).sum()
+///     // all strings are freed here at once
+/// }
+/// ```
+pub fn triples_with_pattern<'a>(
+    hdt: &'a Hdt, arena: &'a TermArena, sp: Option<&str>, pp: Option<&str>, op: Option<&str>,
+) -> ArenaTripleIter<'a> {
+    let ids: Box<dyn Iterator<Item = TripleId> + 'a> = match hdt.pattern_ids(sp, pp, op) {
+        Some(pat) => hdt.triples.triples_with_pattern(&pat),
+        None => Box::new(iter::empty()),
+    };
+    ArenaTripleIter { hdt, arena, ids, buf: String::new(), last: None }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::init;
+    use pretty_assertions::assert_eq;
+    use std::fs::File;
+
+    #[test]
+    fn arena() {
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let hdt = Hdt::new(std::io::BufReader::new(file)).unwrap();
+        let arena = TermArena::new();
+        assert!(arena.is_empty());
+        let label = Some("http://www.w3.org/2000/01/rdf-schema#label");
+        let top = Some("http://www.snik.eu/ontology/meta/Top");
+        for (sp, pp, op) in [(top, None, None), (None, label, None), (None, None, None), (Some("x"), None, None)] {
+            let expected: Vec<_> = hdt.triples_with_pattern(sp, pp, op).collect();
+            let actual: Vec<_> = triples_with_pattern(&hdt, &arena, sp, pp, op).collect();
+            assert_eq!(expected.len(), actual.len());
+            for (e, a) in expected.iter().zip(&actual) {
+                assert_eq!((e.0.as_ref(), e.1.as_ref(), e.2.as_ref()), *a);
+            }
+        }
+        // repeated terms of adjacent triples are only allocated once
+        let before = arena.len();
+        let triples: Vec<_> = triples_with_pattern(&hdt, &arena, top, None, None).collect();
+        assert!(triples.len() > 1);
+        assert!(std::ptr::eq(triples[0].0, triples[1].0));
+        assert!(arena.len() - before < triples.iter().map(|t| t.0.len() + t.1.len() + t.2.len()).sum());
+    }
+}
@@ -0,0 +1,308 @@
+//! Command line tool to inspect, query and export HDT files, similar to hdtInfo, hdtSearch and hdt2rdf of hdt-cpp.
+//! Only built with the "cli" feature, for example with `cargo install hdt --features cli`.
+use bytesize::ByteSize;
+use hdt::containers::rdf::nt_term;
+use hdt::export::ErrorPolicy;
+use hdt::hdt::{Compression, ExportFormat, StringTriple};
+use hdt::lazy::LazyHdt;
+use hdt::synthetic::{Generator, Shape};
+use hdt::triples::Order;
+use hdt::Hdt;
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufRead, BufWriter, Write};
+use std::process::ExitCode;
+
+const USAGE: &str = "Usage:
+  hdt info <file.hdt>                 print statistics about the file
+  hdt query <file.hdt> <pattern>      print the triples matching a pattern such as \"?s <http://example.org/p> ?o\"
+  hdt export [--skip-errors] <file.hdt> [<out.nt>]
+                                      write all triples as N-Triples, to standard output if no output file is given,
+                                      skipping triples with terms that cannot be decoded and listing those terms at the end
+  hdt validate <file.hdt>             check checksums and the consistency of all sections and print all problems found
+  hdt cat <in.hdt>... <out.hdt>       merge the triples of two or more files into a new file, like hdtCat of hdt-java
+  hdt subset <file.hdt> <pattern> <out.hdt>
+                                      write the triples matching a pattern into a new file with only their terms
+  hdt diff <old.hdt> <new.hdt>        write the removed and added triples as an RDF Patch to standard output
+  hdt generate <star|chain|bipartite> <triples> <out.hdt>
+                                      write a deterministic synthetic graph with the given shape and number of triples
+
+Pattern terms are variables such as ?s, IRIs in angle brackets, blank nodes such as _:b1, literals in N-Triples syntax
+such as \"abc\"@en or \"1\"^^<http://www.w3.org/2001/XMLSchema#integer> and \"a\" for rdf:type.";
+
+const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let policy = if args.starts_with(&["export", "--skip-errors"]) {
+        args.remove(1);
+        ErrorPolicy::Skip
+    } else {
+        ErrorPolicy::Abort
+    };
+    let result = match args.as_slice() {
+        ["info", file] => info(file),
+        ["query", file, pattern] => query(file, pattern),
+        ["export", file] => export(file, io::stdout().lock(), policy),
+        ["export", file, "-"] => export(file, io::stdout().lock(), policy),
+        ["export", file, out] => File::create(out).map_err(Into::into).and_then(|out| export(file, out, policy)),
+        ["validate", file] => validate(file),
+        ["cat", inputs @ .., out] if inputs.len() >= 2 => cat(inputs, out),
+        ["subset", file, pattern, out] => subset(file, pattern, out),
+        ["diff", old, new] => diff(old, new),
+        ["generate", shape, triples, out] => generate(shape, triples, out),
+        _ => {
+            eprintln!("{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        // a closed pipe, for example when piping into head, is not an error
+        Err(e) if e.downcast_ref::<io::Error>().is_some_and(|e| e.kind() == io::ErrorKind::BrokenPipe) => {
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("hdt: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn load(file: &str) -> Result<Hdt, Box<dyn Error>> {
+    let mut reader = io::BufReader::new(File::open(file).map_err(|e| format!("Failed to open {file}: {e}"))?);
+    // compressed files cannot be mapped and are decompressed while reading
+    let hdt = if Compression::detect(reader.fill_buf()?).is_some() { Hdt::new(reader) } else { Hdt::map(file) };
+    hdt.map_err(|e| format!("Failed to load {file}: {e}").into())
+}
+
+fn info(file: &str) -> Result<(), Box<dyn Error>> {
+    let mut reader = io::BufReader::new(File::open(file).map_err(|e| format!("Failed to open {file}: {e}"))?);
+    if Compression::detect(reader.fill_buf()?).is_some() {
+        // sections of compressed files cannot be skipped, so the whole file is decompressed and loaded
+        return info_loaded(&load(file)?);
+    }
+    // the object section and the indexes are not needed for the counts
+    let lazy = LazyHdt::open(file).map_err(|e| format!("Failed to open {file}: {e}"))?;
+    let order = lazy.triples_ci.get("order").and_then(|order| order.parse::<u32>().ok());
+    let mut out = io::stdout().lock();
+    writeln!(out, "format: {}", lazy.global_ci.format)?;
+    writeln!(out, "dictionary: {}", lazy.dict_ci.format)?;
+    writeln!(out, "triples type: {}", lazy.triples_ci.format)?;
+    writeln!(out, "order: {:?}", Order::try_from(order.unwrap_or(0))?)?;
+    writeln!(out, "triples: {}", lazy.num_triples()?)?;
+    writeln!(out, "subjects: {}", lazy.num_subjects())?;
+    writeln!(out, "predicates: {}", lazy.num_predicates())?;
+    writeln!(out, "objects: {}", lazy.num_objects())?;
+    writeln!(out, "shared subject-objects: {}", lazy.shared.num_strings())?;
+    if let Some(graphs) = lazy.num_graphs() {
+        writeln!(out, "graphs: {graphs}")?;
+    }
+    // only the sections read for this summary, the size of the loaded file is not known without building the indexes
+    writeln!(out, "size of the sections read: {}", ByteSize(lazy.size_in_bytes() as u64))?;
+    Ok(())
+}
+
+/// Like [`info`] from a loaded file, whose size in memory includes the indexes.
+fn info_loaded(hdt: &Hdt) -> Result<(), Box<dyn Error>> {
+    let dict = &hdt.dict;
+    let capabilities = hdt.capabilities();
+    let shared = dict.shared.num_strings();
+    let mut out = io::stdout().lock();
+    writeln!(out, "format: {}", capabilities.format)?;
+    writeln!(out, "dictionary: {}", capabilities.dictionary)?;
+    writeln!(out, "triples type: {}", capabilities.triples)?;
+    writeln!(out, "order: {:?}", capabilities.order)?;
+    writeln!(out, "triples: {}", hdt.triples.len())?;
+    writeln!(out, "subjects: {}", shared + dict.subjects.num_strings())?;
+    writeln!(out, "predicates: {}", dict.predicates.num_strings())?;
+    writeln!(out, "objects: {}", shared + dict.objects.num_strings())?;
+    writeln!(out, "shared subject-objects: {shared}")?;
+    if let Some(graphs) = &dict.graphs {
+        writeln!(out, "graphs: {}", graphs.num_strings())?;
+    }
+    writeln!(out, "size in memory: {}", ByteSize(hdt.size_in_bytes() as u64))?;
+    Ok(())
+}
+
+fn query(file: &str, pattern: &str) -> Result<(), Box<dyn Error>> {
+    let [s, p, o] = parse_pattern(pattern)?;
+    let hdt = load(file)?;
+    let out = BufWriter::new(io::stdout().lock());
+    write_nt(out, hdt.triples_with_pattern(s.as_deref(), p.as_deref(), o.as_deref()))
+}
+
+fn export(file: &str, out: impl Write, policy: ErrorPolicy) -> Result<(), Box<dyn Error>> {
+    let hdt = load(file)?;
+    let mut out = BufWriter::new(out);
+    let report = hdt.export(&mut out, ExportFormat::NTriples, policy)?;
+    out.flush()?;
+    if !report.is_complete() {
+        // tab separated, so that the terms can be processed further
+        eprintln!("position\tid\ttriples\terror");
+        for f in &report.failures {
+            eprintln!("{:?}\t{}\t{}\t{}", f.kind, f.id, f.triples, f.message);
+        }
+        return Err(format!("skipped {} of {} triples", report.skipped, report.written + report.skipped).into());
+    }
+    Ok(())
+}
+
+fn validate(file: &str) -> Result<(), Box<dyn Error>> {
+    let reader = io::BufReader::new(File::open(file).map_err(|e| format!("Failed to open {file}: {e}"))?);
+    let report = Hdt::validate(reader);
+    let mut out = io::stdout().lock();
+    for problem in &report.problems {
+        writeln!(out, "{problem}")?;
+    }
+    if !report.complete {
+        writeln!(out, "stopped at the first part that cannot be read")?;
+    }
+    if !report.is_valid() {
+        return Err(format!("{file} is invalid with {} problems", report.problems.len()).into());
+    }
+    writeln!(out, "{file} is valid")?;
+    Ok(())
+}
+
+fn cat(inputs: &[&str], out: &str) -> Result<(), Box<dyn Error>> {
+    let hdts = inputs.iter().map(|file| load(file)).collect::<Result<Vec<_>, _>>()?;
+    let merged = Hdt::merge(&hdts).map_err(|e| format!("Failed to merge: {e}"))?;
+    let writer = BufWriter::new(File::create(out).map_err(|e| format!("Failed to create {out}: {e}"))?);
+    merged.write_to(writer, &format!("file://{out}"))?;
+    Ok(())
+}
+
+fn subset(file: &str, pattern: &str, out: &str) -> Result<(), Box<dyn Error>> {
+    let [s, p, o] = parse_pattern(pattern)?;
+    let hdt = load(file)?;
+    let subset =
+        hdt.subset(s.as_deref(), p.as_deref(), o.as_deref()).map_err(|e| format!("Failed to extract: {e}"))?;
+    let writer = BufWriter::new(File::create(out).map_err(|e| format!("Failed to create {out}: {e}"))?);
+    subset.write_to(writer, &format!("file://{out}"))?;
+    Ok(())
+}
+
+fn diff(old: &str, new: &str) -> Result<(), Box<dyn Error>> {
+    let (old, new) = (load(old)?, load(new)?);
+    let mut out = BufWriter::new(io::stdout().lock());
+    let (added, removed) = new.diff(&old).write_patch(&mut out)?;
+    out.flush()?;
+    eprintln!("{added} added, {removed} removed");
+    Ok(())
+}
+
+fn generate(shape: &str, triples: &str, out: &str) -> Result<(), Box<dyn Error>> {
+    let shape = match shape {
+        "star" => Shape::Star,
+        "chain" => Shape::Chain,
+        "bipartite" => Shape::Bipartite,
+        _ => return Err(format!("Unknown shape {shape}, expected star, chain or bipartite").into()),
+    };
+    let triples = triples.parse().map_err(|e| format!("Invalid number of triples {triples}: {e}"))?;
+    let hdt = Generator::new(triples).with_shape(shape).build().map_err(|e| format!("Failed to generate: {e}"))?;
+    let writer = BufWriter::new(File::create(out).map_err(|e| format!("Failed to create {out}: {e}"))?);
+    hdt.write_to(writer, &format!("file://{out}"))?;
+    Ok(())
+}
+
+fn write_nt(mut out: impl Write, triples: impl Iterator<Item = StringTriple>) -> Result<(), Box<dyn Error>> {
+    for (s, p, o) in triples {
+        writeln!(out, "{} {} {} .", nt_term(&s), nt_term(&p), nt_term(&o))?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Parse a triple pattern into terms in the string format of the HDT dictionary, with `None` for variables.
+fn parse_pattern(pattern: &str) -> Result<[Option<String>; 3], String> {
+    let mut rest = pattern.trim_start();
+    let mut terms = Vec::with_capacity(3);
+    while !rest.is_empty() && terms.len() < 3 {
+        let (term, tail) = parse_term(rest)?;
+        terms.push(term);
+        rest = tail.trim_start();
+    }
+    // optional final dot as in N-Triples
+    let rest = rest.strip_prefix('.').unwrap_or(rest).trim();
+    match <[Option<String>; 3]>::try_from(terms) {
+        Ok(terms) if rest.is_empty() => Ok(terms),
+        _ => Err(format!("Expected a pattern of exactly three terms but got {pattern}")),
+    }
+}
+
+/// Parse the term at the beginning of the input and return it together with the remaining input.
+fn parse_term(input: &str) -> Result<(Option<String>, &str), String> {
+    let token_end = input.find(char::is_whitespace).unwrap_or(input.len());
+    let (token, tail) = input.split_at(token_end);
+    if token.starts_with('?') || token.starts_with('$') {
+        return Ok((None, tail));
+    }
+    if token == "a" {
+        return Ok((Some(RDF_TYPE.to_owned()), tail));
+    }
+    if token.starts_with("_:") {
+        return Ok((Some(token.to_owned()), tail));
+    }
+    if let Some(iri) = input.strip_prefix('<') {
+        let end = iri.find('>').ok_or_else(|| format!("Unterminated IRI in {input}"))?;
+        return Ok((Some(iri[..end].to_owned()), &iri[end + 1..]));
+    }
+    let Some(literal) = input.strip_prefix('"') else {
+        return Err(format!("Expected a variable, IRI, blank node or literal at {input}"));
+    };
+    let mut lexical = String::new();
+    let mut chars = literal.char_indices();
+    let end = loop {
+        match chars.next() {
+            Some((i, '"')) => break i + 1,
+            Some((_, '\\')) => match chars.next() {
+                Some((_, 'n')) => lexical.push('\n'),
+                Some((_, 'r')) => lexical.push('\r'),
+                Some((_, 't')) => lexical.push('\t'),
+                Some((_, c @ ('"' | '\\'))) => lexical.push(c),
+                _ => return Err(format!("Unsupported escape sequence in {input}")),
+            },
+            Some((_, c)) => lexical.push(c),
+            None => return Err(format!("Unterminated literal in {input}")),
+        }
+    };
+    let tail = &literal[end..];
+    if let Some(lang) = tail.strip_prefix('@') {
+        let lang_end = lang.find(|c: char| !(c.is_ascii_alphanumeric() || c == '-')).unwrap_or(lang.len());
+        return Ok((Some(Hdt::literal_matcher(&lexical, None, Some(&lang[..lang_end]))), &lang[lang_end..]));
+    }
+    if let Some(datatype) = tail.strip_prefix("^^<") {
+        let dt_end = datatype.find('>').ok_or_else(|| format!("Unterminated datatype IRI in {input}"))?;
+        let term = Hdt::literal_matcher(&lexical, Some(&datatype[..dt_end]), None);
+        return Ok((Some(term), &datatype[dt_end + 1..]));
+    }
+    Ok((Some(Hdt::literal_matcher(&lexical, None, None)), tail))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pattern() {
+        let label = "http://www.w3.org/2000/01/rdf-schema#label";
+        assert_eq!(
+            [None, Some(label.to_owned()), Some("\"say \"hi\"\"@en".to_owned())],
+            parse_pattern(&format!("?s <{label}> \"say \\\"hi\\\"\"@en .")).unwrap()
+        );
+        assert_eq!(
+            [
+                Some("_:b1".to_owned()),
+                Some(RDF_TYPE.to_owned()),
+                Some("\"1\"^^<http://example.org/int>".to_owned())
+            ],
+            parse_pattern("_:b1 a \"1\"^^<http://example.org/int>").unwrap()
+        );
+        assert_eq!([None, None, Some("\"a b\"".to_owned())], parse_pattern("?s ?p \"a b\"").unwrap());
+        assert!(parse_pattern("?s ?p").is_err());
+        assert!(parse_pattern("?s ?p ?o ?g").is_err());
+        assert!(parse_pattern("?s ?p \"open").is_err());
+    }
+}
@@ -0,0 +1,446 @@
+//! Construction of an HDT from RDF data, so that files can be created without a round trip through hdt-cpp.
+use crate::containers::rdf::nt_term;
+use crate::containers::{Bitmap, ControlInfo, ControlType, Sequence};
+use crate::four_sect_dict::FourSectDict;
+use crate::triples::{Id, Order, TriplesBitmap};
+use crate::vocab::Vocabulary;
+use crate::{compare_terms, DictSectPFC, Hdt};
+use eyre::{eyre, Result, WrapErr};
+use ntriple::parser::triple_line;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::io::BufRead;
+
+/// Block size of the dictionary sections used by hdt-cpp and hdt-java.
+pub const DEFAULT_BLOCK_SIZE: usize = 16;
+
+const SUBJECT: u8 = 1;
+const PREDICATE: u8 = 2;
+const OBJECT: u8 = 4;
+
+/// Collects triples and builds an [`Hdt`] with a four section dictionary and bitmap triples in SPO order.
+/// Terms are interned while collecting, so each distinct term is only stored once until the dictionary is built.
+/// # Example
+/// ```
+/// use hdt::builder::HdtBuilder;
+/// let nt = "<http://example.org/a> <http://example.org/p> \"x\"@en .\n";
+/// let mut builder = HdtBuilder::new();
+/// builder.read_ntriples(nt.as_bytes()).unwrap();
+/// builder.add_triple("http://example.org/a", "http://example.org/p", "http://example.org/b");
+/// let hdt = builder.build().unwrap();
+/// assert_eq!(2, hdt.triples().count());
+/// ```
+#[derive(Debug)]
+pub struct HdtBuilder {
+    block_size: usize,
+    ids: HashMap<String, usize>,
+    // positions each term occurs in, as a combination of the SUBJECT, PREDICATE and OBJECT flags
+    roles: Vec<u8>,
+    triples: Vec<[usize; 3]>,
+    original_size: u64,
+}
+
+impl Default for HdtBuilder {
+    fn default() -> Self {
+        HdtBuilder {
+            block_size: DEFAULT_BLOCK_SIZE,
+            ids: HashMap::new(),
+            roles: Vec::new(),
+            triples: Vec::new(),
+            original_size: 0,
+        }
+    }
+}
+
+impl HdtBuilder {
+    /// Builder without triples and with the [`DEFAULT_BLOCK_SIZE`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of strings per block of the front coded dictionary sections.
+    /// Larger blocks compress better but make lookups slower.
+    /// Panics if the block size is 0.
+    #[must_use]
+    pub const fn with_block_size(mut self, block_size: usize) -> Self {
+        assert!(block_size > 0, "block size 0");
+        self.block_size = block_size;
+        self
+    }
+
+    fn intern(&mut self, term: &str, role: u8) -> usize {
+        let index = if let Some(&index) = self.ids.get(term) {
+            index
+        } else {
+            let index = self.roles.len();
+            self.ids.insert(term.to_owned(), index);
+            self.roles.push(0);
+            index
+        };
+        self.roles[index] |= role;
+        index
+    }
+
+    fn push(&mut self, s: &str, p: &str, o: &str) {
+        let triple = [self.intern(s, SUBJECT), self.intern(p, PREDICATE), self.intern(o, OBJECT)];
+        self.triples.push(triple);
+    }
+
+    /// Add a triple of terms in the string format of the HDT dictionary, see [`Hdt::literal_matcher`].
+    /// Duplicate triples are removed when building.
+    pub fn add_triple(&mut self, s: &str, p: &str, o: &str) {
+        // length of the triple as an N-Triples line
+        self.original_size += (nt_term(s).len() + nt_term(p).len() + nt_term(o).len() + 5) as u64;
+        self.push(s, p, o);
+    }
+
+    /// Add all triples of the given N-Triples data.
+    /// Empty lines and comments are skipped, the first line that cannot be parsed results in an error.
+    pub fn read_ntriples<R: BufRead>(&mut self, reader: R) -> Result<()> {
+        for (i, line) in reader.lines().enumerate() {
+            let line = line.wrap_err_with(|| format!("Failed to read line {}", i + 1))?;
+            self.original_size += line.len() as u64 + 1;
+            if let Some([s, p, o]) =
+                ntriples_line(&line).wrap_err_with(|| format!("Invalid N-Triples in line {}", i + 1))?
+            {
+                self.push(&s, &p, &o);
+            }
+        }
+        Ok(())
+    }
+
+    /// Add all triples of the given parser of the rio crates, such as a Turtle or RDF/XML parser of rio_turtle or rio_xml,
+    /// so that such files can be converted without an intermediate N-Triples file.
+    /// Quoted triples of RDF-star result in an error.
+    /// # Example
+    /// ```
+    /// use hdt::builder::HdtBuilder;
+    /// fn build<P: rio_api::parser::TriplesParser>(parser: P) -> hdt::Hdt
+    /// where
+    ///     P::Error: Send + Sync + 'static,
+    /// {
+    ///     let mut builder = HdtBuilder::new();
+    ///     builder.read_rio(parser).unwrap();
+    ///     builder.build().unwrap()
+    /// }
+    /// ```
+    #[cfg(feature = "rio")]
+    pub fn read_rio<P: rio_api::parser::TriplesParser>(&mut self, mut parser: P) -> Result<()>
+    where
+        P::Error: Send + Sync + 'static,
+    {
+        parser.parse_all(&mut |t: rio_api::model::Triple<'_>| -> Result<()> {
+            let (Some(s), Some(o)) = (rio_subject(t.subject), rio_term(t.object)) else {
+                return Err(eyre!("Quoted triples are not supported: {t}"));
+            };
+            self.add_triple(&s, t.predicate.iri, &o);
+            Ok(())
+        })
+    }
+
+    /// Like [`Self::read_rio`] for parsers of quads, such as the TriG parser of rio_turtle, whose graph names are ignored.
+    #[cfg(feature = "rio")]
+    pub fn read_rio_quads<P: rio_api::parser::QuadsParser>(&mut self, mut parser: P) -> Result<()>
+    where
+        P::Error: Send + Sync + 'static,
+    {
+        parser.parse_all(&mut |q: rio_api::model::Quad<'_>| -> Result<()> {
+            let (Some(s), Some(o)) = (rio_subject(q.subject), rio_term(q.object)) else {
+                return Err(eyre!("Quoted triples are not supported: {q}"));
+            };
+            self.add_triple(&s, q.predicate.iri, &o);
+            Ok(())
+        })
+    }
+
+    /// Number of triples added so far, including duplicates.
+    pub const fn len(&self) -> usize {
+        self.triples.len()
+    }
+
+    /// Size in bytes of the added data as N-Triples, for the header written by [`Hdt::write_with_metadata`].
+    /// Includes the empty lines and comments read by [`Self::read_ntriples`], other triples are counted as if serialized as N-Triples.
+    pub const fn original_size(&self) -> u64 {
+        self.original_size
+    }
+
+    /// Whether no triples have been added yet.
+    pub const fn is_empty(&self) -> bool {
+        self.triples.is_empty()
+    }
+
+    /// Build the dictionary and the triples, which sorts all terms and triples.
+    pub fn build(self) -> Result<Hdt> {
+        let HdtBuilder { block_size, ids, roles, mut triples, .. } = self;
+        let mut terms = vec![String::new(); roles.len()];
+        for (term, index) in ids {
+            terms[index] = term;
+        }
+        let section = |role: u8| -> Vec<(&str, usize)> {
+            let mut sect: Vec<_> =
+                (0..terms.len()).filter(|&i| roles[i] & role != 0).map(|i| (terms[i].as_str(), i)).collect();
+            sect.sort_unstable_by(|a, b| compare_terms(a.0, b.0));
+            sect
+        };
+        let n = terms.len();
+        let (dict, new_ids) =
+            dictionary([&section(SUBJECT), &section(PREDICATE), &section(OBJECT)], [n, n, n], block_size);
+        drop(terms);
+        for t in &mut triples {
+            *t = [new_ids[0][t[0]], new_ids[1][t[1]], new_ids[2][t[2]]];
+        }
+        triples.sort_unstable();
+        triples.dedup();
+        Ok(assemble(dict, bitmap_triples(&triples)?))
+    }
+}
+
+/// Four section dictionary of the given subject, predicate and object terms, which need to be unique and sorted by [`compare_terms`],
+/// with sections of the given block size, for example [`DEFAULT_BLOCK_SIZE`].
+/// Terms that are both subjects and objects are stored once in the shared section.
+/// Each term comes with a key below the given number of keys of its position, such as its ID in another dictionary,
+/// and the returned tables map these keys to the IDs in the new dictionary, 0 for keys without a term.
+pub(crate) fn dictionary<S: AsRef<str>>(
+    [subjects, predicates, objects]: [&[(S, usize)]; 3], keys: [usize; 3], block_size: usize,
+) -> (FourSectDict, [Vec<Id>; 3]) {
+    let (mut shared, mut only_subjects, mut only_objects) = (Vec::new(), Vec::new(), Vec::new());
+    // whether each subject and object is shared and its position in its section
+    let (mut s_pos, mut o_pos) = (Vec::with_capacity(subjects.len()), Vec::with_capacity(objects.len()));
+    let (mut i, mut j) = (0, 0);
+    loop {
+        let order = match (subjects.get(i), objects.get(j)) {
+            (Some(s), Some(o)) => compare_terms(s.0.as_ref(), o.0.as_ref()),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => break,
+        };
+        match order {
+            Ordering::Less => {
+                s_pos.push((false, only_subjects.len()));
+                only_subjects.push(subjects[i].0.as_ref());
+                i += 1;
+            }
+            Ordering::Greater => {
+                o_pos.push((false, only_objects.len()));
+                only_objects.push(objects[j].0.as_ref());
+                j += 1;
+            }
+            Ordering::Equal => {
+                s_pos.push((true, shared.len()));
+                o_pos.push((true, shared.len()));
+                shared.push(subjects[i].0.as_ref());
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    // IDs count from 1 and the subject and object sections continue after the shared one
+    let num_shared = shared.len();
+    let id = |(is_shared, pos): (bool, usize)| if is_shared { pos + 1 } else { num_shared + pos + 1 };
+    let mut ids = keys.map(|keys| vec![0; keys]);
+    for (&(_, key), &pos) in subjects.iter().zip(&s_pos) {
+        ids[0][key] = id(pos);
+    }
+    for (i, &(_, key)) in predicates.iter().enumerate() {
+        ids[1][key] = i + 1;
+    }
+    for (&(_, key), &pos) in objects.iter().zip(&o_pos) {
+        ids[2][key] = id(pos);
+    }
+    let predicates: Vec<&str> = predicates.iter().map(|(p, _)| p.as_ref()).collect();
+    let dict = FourSectDict {
+        shared: DictSectPFC::new(&shared, block_size),
+        subjects: DictSectPFC::new(&only_subjects, block_size),
+        predicates: DictSectPFC::new(&predicates, block_size),
+        objects: DictSectPFC::new(&only_objects, block_size),
+        graphs: None,
+    };
+    (dict, ids)
+}
+
+/// Bitmap triples in SPO order from the given sorted triple IDs without duplicates.
+pub(crate) fn bitmap_triples(triples: &[[usize; 3]]) -> Result<TriplesBitmap> {
+    // a one bit marks the last predicate of each subject and the last object of each subject-predicate pair
+    let (mut sequence_y, mut bits_y) = (Vec::new(), Vec::new());
+    let (mut sequence_z, mut bits_z) = (Vec::with_capacity(triples.len()), Vec::with_capacity(triples.len()));
+    for (i, t) in triples.iter().enumerate() {
+        let next = triples.get(i + 1);
+        let last_object = next.is_none_or(|n| n[..2] != t[..2]);
+        sequence_z.push(t[2]);
+        bits_z.push(last_object);
+        if last_object {
+            sequence_y.push(t[1]);
+            bits_y.push(next.is_none_or(|n| n[0] != t[0]));
+        }
+    }
+    TriplesBitmap::new(
+        Order::SPO,
+        Bitmap::from_bits(bits_y),
+        Bitmap::from_bits(bits_z),
+        Sequence::new(&sequence_y),
+        Sequence::new(&sequence_z),
+    )
+}
+
+/// HDT version 1 with the given dictionary and triples.
+pub(crate) fn assemble(dict: FourSectDict, triples: TriplesBitmap) -> Hdt {
+    let global_ci = ControlInfo::new(ControlType::Global, "<http://purl.org/HDT/hdt#HDTv1>");
+    let vocab = Vocabulary::resolve(&dict);
+    Hdt {
+        global_ci,
+        dict,
+        triples,
+        vocab,
+        #[cfg(feature = "mmap")]
+        mapped: None,
+    }
+}
+
+/// Subject in the string format of the HDT dictionary, `None` for quoted triples.
+#[cfg(feature = "rio")]
+fn rio_subject(s: rio_api::model::Subject<'_>) -> Option<String> {
+    use rio_api::model::{Subject, Term};
+    match s {
+        Subject::NamedNode(n) => rio_term(Term::NamedNode(n)),
+        Subject::BlankNode(b) => rio_term(Term::BlankNode(b)),
+        #[allow(unreachable_patterns)]
+        _ => None,
+    }
+}
+
+/// Term in the string format of the HDT dictionary, `None` for quoted triples.
+#[cfg(feature = "rio")]
+fn rio_term(t: rio_api::model::Term<'_>) -> Option<String> {
+    use rio_api::model::{Literal, Term};
+    match t {
+        Term::NamedNode(n) => Some(n.iri.to_owned()),
+        Term::BlankNode(b) => Some(format!("_:{}", b.id)),
+        Term::Literal(Literal::Simple { value }) => Some(Hdt::literal_matcher(value, None, None)),
+        Term::Literal(Literal::LanguageTaggedString { value, language }) => {
+            Some(Hdt::literal_matcher(value, None, Some(language)))
+        }
+        Term::Literal(Literal::Typed { value, datatype }) => {
+            Some(Hdt::literal_matcher(value, Some(datatype.iri), None))
+        }
+        #[allow(unreachable_patterns)]
+        _ => None,
+    }
+}
+
+/// Parse a line of N-Triples into terms in the string format of the HDT dictionary, `None` for empty lines and comments.
+pub(crate) fn ntriples_line(line: &str) -> Result<Option<[String; 3]>> {
+    let Some(triple) = triple_line(line).map_err(|e| eyre!("{e:?}"))? else {
+        return Ok(None);
+    };
+    let s = match triple.subject {
+        ntriple::Subject::IriRef(iri) => iri,
+        ntriple::Subject::BNode(id) => format!("_:{id}"),
+    };
+    let ntriple::Predicate::IriRef(p) = triple.predicate;
+    let o = match triple.object {
+        ntriple::Object::IriRef(iri) => iri,
+        ntriple::Object::BNode(id) => format!("_:{id}"),
+        ntriple::Object::Lit(lit) => match lit.data_type {
+            ntriple::TypeLang::Lang(lang) => Hdt::literal_matcher(&lit.data, None, Some(&lang)),
+            ntriple::TypeLang::Type(datatype) => Hdt::literal_matcher(&lit.data, Some(&datatype), None),
+        },
+    };
+    Ok(Some([s, p, o]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::init;
+    use crate::IdKind;
+    use pretty_assertions::assert_eq;
+    use std::fmt::Write;
+
+    #[test]
+    fn build() {
+        init();
+        let original = std::fs::read("tests/resources/snikmeta.hdt").expect("error reading file");
+        let hdt = Hdt::new(original.as_slice()).unwrap();
+        let mut nt = String::new();
+        for (s, p, o) in hdt.triples() {
+            writeln!(nt, "{} {} {} .", nt_term(&s), nt_term(&p), nt_term(&o)).unwrap();
+        }
+        let mut builder = HdtBuilder::new();
+        // duplicates and comments
+        let input = format!("# comment\n{nt}\n{nt}");
+        builder.read_ntriples(input.as_bytes()).unwrap();
+        assert_eq!(2 * hdt.triples.len(), builder.len());
+        assert_eq!(input.len() as u64, builder.original_size());
+        let mut added = HdtBuilder::new();
+        for (s, p, o) in hdt.triples() {
+            added.add_triple(&s, &p, &o);
+        }
+        assert_eq!(nt.len() as u64, added.original_size());
+        let built = builder.build().unwrap();
+        // same sort order as hdt-cpp, so the IDs are the same
+        assert_eq!(hdt.triples.into_iter().collect::<Vec<_>>(), built.triples.into_iter().collect::<Vec<_>>());
+        assert_eq!(hdt.triples().collect::<Vec<_>>(), built.triples().collect::<Vec<_>>());
+        for id in 1..=hdt.dict.shared.num_strings() + hdt.dict.objects.num_strings() {
+            assert_eq!(
+                hdt.dict.id_to_string(id, &IdKind::Object).unwrap(),
+                built.dict.id_to_string(id, &IdKind::Object).unwrap()
+            );
+        }
+        let label = Some("http://www.w3.org/2000/01/rdf-schema#label");
+        assert_eq!(
+            hdt.triples_with_pattern(None, label, None).collect::<Vec<_>>(),
+            built.triples_with_pattern(None, label, None).collect::<Vec<_>>()
+        );
+        // the written file can be read again
+        let mut bytes = Vec::new();
+        built.write_to(&mut bytes, "file://snikmeta.nt").unwrap();
+        let read = Hdt::new(bytes.as_slice()).unwrap();
+        assert_eq!(hdt.triples().collect::<Vec<_>>(), read.triples().collect::<Vec<_>>());
+    }
+
+    #[cfg(feature = "rio")]
+    #[test]
+    fn read_rio() {
+        use rio_turtle::{TriGParser, TurtleParser};
+        init();
+        let prefix = "@prefix ex: <http://example.org/> .\n";
+        let body = r#"ex:a ex:p "x"@en, "1"^^<http://www.w3.org/2001/XMLSchema#integer>, "plain", ex:b ."#;
+        let nt = r#"<http://example.org/a> <http://example.org/p> "x"@en .
+<http://example.org/a> <http://example.org/p> "1"^^<http://www.w3.org/2001/XMLSchema#integer> .
+<http://example.org/a> <http://example.org/p> "plain" .
+<http://example.org/a> <http://example.org/p> <http://example.org/b> .
+"#;
+        let mut builder = HdtBuilder::new();
+        builder.read_ntriples(nt.as_bytes()).unwrap();
+        let expected: Vec<_> = builder.build().unwrap().triples().collect();
+        assert_eq!(4, expected.len());
+        let mut builder = HdtBuilder::new();
+        builder.read_rio(TurtleParser::new(format!("{prefix}{body}").as_bytes(), None)).unwrap();
+        assert_eq!(expected, builder.build().unwrap().triples().collect::<Vec<_>>());
+
+        let trig = format!("{prefix}ex:g {{ {body} }}");
+        let mut builder = HdtBuilder::new();
+        builder.read_rio_quads(TriGParser::new(trig.as_bytes(), None)).unwrap();
+        assert_eq!(expected, builder.build().unwrap().triples().collect::<Vec<_>>());
+
+        assert!(HdtBuilder::new().read_rio(TurtleParser::new("ex:a ex:p ex:b .".as_bytes(), None)).is_err());
+    }
+
+    #[test]
+    fn build_errors() {
+        init();
+        let e = HdtBuilder::new().read_ntriples("\n<http://example.org/a> .\n".as_bytes()).unwrap_err();
+        assert!(e.to_string().contains("line 2"), "{e}");
+        let mut builder = HdtBuilder::new().with_block_size(2);
+        for o in ["\"a\"", "\"ab\"", "\"abc\"", "http://example.org/a"] {
+            builder.add_triple("http://example.org/a", "http://example.org/p", o);
+        }
+        let hdt = builder.build().unwrap();
+        assert_eq!(2, hdt.dict.objects.block_size());
+        // the subject is also an object and thus in the shared section
+        assert_eq!(1, hdt.dict.shared.num_strings());
+        for o in ["\"a\"", "\"ab\"", "\"abc\"", "http://example.org/a"] {
+            assert_eq!(1, hdt.triples_with_pattern(None, None, Some(o)).count(), "{o}");
+        }
+    }
+}
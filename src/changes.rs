@@ -0,0 +1,319 @@
+//! Subjects whose triples differ between two versions of a dataset and export of their current descriptions,
+//! so that downstream search indexes and caches can be updated incrementally instead of being rebuilt, see [`Hdt::changed_subjects`].
+use crate::containers::rdf::nt_term;
+use crate::four_sect_dict::IdKind;
+use crate::hdt::Hdt;
+use crate::triples::{Id, ObjectIter, SubjectIter, TripleId};
+use crate::{compare_terms, DictSectPFC};
+use log::error;
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashSet};
+use std::io::{self, Write};
+
+/// Subjects that were added, removed or modified between an old and a new version, each sorted by [`compare_terms`].
+/// Blank nodes are only listed if they are not the object of any triple,
+/// otherwise their changes are attributed to the subjects whose concise bounded descriptions contain them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SubjectChanges {
+    /// Subjects that only have triples in the new version.
+    pub added: Vec<String>,
+    /// Subjects that only have triples in the old version.
+    pub removed: Vec<String>,
+    /// Subjects with triples in both versions whose concise bounded descriptions differ.
+    pub modified: Vec<String>,
+}
+
+impl SubjectChanges {
+    /// Whether no subject changed.
+    pub const fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Change {
+    Added,
+    Removed,
+    Modified,
+}
+
+/// Terms of the given kind with their IDs in the order of [`compare_terms`], merged from the shared and the own section.
+/// Strings that cannot be extracted are logged and skipped.
+pub(crate) fn sorted_terms<'a>(hdt: &'a Hdt, kind: &IdKind) -> impl Iterator<Item = (String, Id)> + 'a {
+    let dict = &hdt.dict;
+    let numbered = |sect: &'a DictSectPFC, first: Id, len: usize| {
+        sect.into_iter()
+            .take(len)
+            .zip(first..)
+            .filter_map(|(s, id)| {
+                s.map_err(|e| error!("Error extracting the string with ID {id}: {e}")).ok().map(|s| (s, id))
+            })
+            .peekable()
+    };
+    let shared = dict.shared.num_strings();
+    let (mut a, mut b) = match kind {
+        // the predicates are a single section
+        IdKind::Predicate => (numbered(&dict.predicates, 1, usize::MAX), numbered(&dict.shared, 1, 0)),
+        IdKind::Subject => (numbered(&dict.shared, 1, shared), numbered(&dict.subjects, shared + 1, usize::MAX)),
+        IdKind::Object => (numbered(&dict.shared, 1, shared), numbered(&dict.objects, shared + 1, usize::MAX)),
+    };
+    std::iter::from_fn(move || match (a.peek(), b.peek()) {
+        (Some((x, _)), Some((y, _))) if compare_terms(x, y) == Ordering::Greater => b.next(),
+        (Some(_), _) => a.next(),
+        (None, _) => b.next(),
+    })
+}
+
+/// Terms of the given kind in either version with their IDs in the old and the new version, 0 if a term only occurs in one of them.
+pub(crate) fn join<'a>(old: &'a Hdt, new: &'a Hdt, kind: &IdKind) -> impl Iterator<Item = (String, Id, Id)> + 'a {
+    let (mut a, mut b) = (sorted_terms(old, kind).peekable(), sorted_terms(new, kind).peekable());
+    std::iter::from_fn(move || {
+        let order = match (a.peek(), b.peek()) {
+            (Some((x, _)), Some((y, _))) => compare_terms(x, y),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => return None,
+        };
+        match order {
+            Ordering::Less => a.next().map(|(s, id)| (s, id, 0)),
+            Ordering::Greater => b.next().map(|(s, id)| (s, 0, id)),
+            Ordering::Equal => {
+                let (s, old_id) = a.next()?;
+                Some((s, old_id, b.next()?.1))
+            }
+        }
+    })
+}
+
+/// IDs in the new version indexed by the IDs of the same terms in the old version, 0 for terms that are missing in the new version.
+pub(crate) fn id_map(old: &Hdt, new: &Hdt, kind: &IdKind) -> Vec<Id> {
+    let mut map = Vec::new();
+    for (_, old_id, new_id) in join(old, new, kind).filter(|(_, old_id, _)| *old_id != 0) {
+        if map.len() <= old_id {
+            map.resize(old_id + 1, 0);
+        }
+        map[old_id] = new_id;
+    }
+    map
+}
+
+/// Triples of the given subject in the old version as pairs of predicate and object IDs in the new version with the original triple,
+/// sorted like the triples of a subject in the new version, see [`id_map`] for the given predicate and object maps.
+pub(crate) fn mapped_triples(old: &Hdt, s: Id, predicates: &[Id], objects: &[Id]) -> Vec<((Id, Id), TripleId)> {
+    let mut po: Vec<_> = SubjectIter::with_s(&old.triples, s)
+        .map(|t| {
+            let p = predicates.get(t.predicate_id).copied().unwrap_or(0);
+            ((p, objects.get(t.object_id).copied().unwrap_or(0)), t)
+        })
+        .collect();
+    // the mapped IDs are not sorted when a term moved between the shared and the own section
+    po.sort_unstable();
+    po
+}
+
+impl Hdt {
+    /// Subjects whose triples differ between the given old version and this version.
+    /// Terms are compared by their strings and triples by IDs: the dictionaries of both versions are decoded once in sorted order to map
+    /// the predicate and object IDs of the old version to this one, and only the triples of subjects in both versions are compared.
+    /// A change of the triples of a blank node modifies all subjects that reach it through blank nodes, following concise bounded descriptions.
+    /// As blank node labels are not stable across versions, relabeled blank nodes count as changes.
+    /// # Example
+    /// ```
+    /// fn reindex(old: &hdt::Hdt, new: &hdt::Hdt) {
+    ///     let changes = new.changed_subjects(old);
+    ///     for s in changes.removed.iter().chain(&changes.modified) {
+    ///         println!("delete {s} from the search index");
+    ///     }
+    /// }
+    /// ```
+    pub fn changed_subjects(&self, old: &Hdt) -> SubjectChanges {
+        let predicates = id_map(old, self, &IdKind::Predicate);
+        let objects = id_map(old, self, &IdKind::Object);
+        let mut changes = SubjectChanges::default();
+        let mut blank_nodes = Vec::new();
+        for (s, old_id, new_id) in join(old, self, &IdKind::Subject) {
+            let change = if old_id == 0 {
+                Change::Added
+            } else if new_id == 0 {
+                Change::Removed
+            } else {
+                let old_po = mapped_triples(old, old_id, &predicates, &objects);
+                let new_po = SubjectIter::with_s(&self.triples, new_id).map(|t| (t.predicate_id, t.object_id));
+                if old_po.iter().map(|&(pair, _)| pair).eq(new_po) {
+                    continue;
+                }
+                Change::Modified
+            };
+            if s.starts_with("_:") {
+                blank_nodes.push((s, change));
+                continue;
+            }
+            match change {
+                Change::Added => changes.added.push(s),
+                Change::Removed => changes.removed.push(s),
+                Change::Modified => changes.modified.push(s),
+            }
+        }
+
+        // attribute changes of blank nodes to the subjects that refer to them
+        let mut modified = BTreeSet::new();
+        let mut seen: HashSet<String> = blank_nodes.iter().map(|(b, _)| b.clone()).collect();
+        while let Some((b, change)) = blank_nodes.pop() {
+            let mut referenced = false;
+            for hdt in [old, self] {
+                let o = hdt.dict.string_to_id(&b, &IdKind::Object);
+                if o == 0 {
+                    continue;
+                }
+                for t in ObjectIter::new(&hdt.triples, o) {
+                    referenced = true;
+                    let s = match hdt.dict.id_to_string(t.subject_id, &IdKind::Subject) {
+                        Ok(s) => s,
+                        Err(e) => {
+                            error!("{e}");
+                            continue;
+                        }
+                    };
+                    if !s.starts_with("_:") {
+                        modified.insert(s);
+                    } else if seen.insert(s.clone()) {
+                        blank_nodes.push((s, Change::Modified));
+                    }
+                }
+            }
+            if !referenced {
+                match change {
+                    Change::Added => changes.added.push(b),
+                    Change::Removed => changes.removed.push(b),
+                    Change::Modified => {
+                        modified.insert(b);
+                    }
+                }
+            }
+        }
+        // subjects that refer to a changed blank node may have been added or removed themselves
+        let listed: HashSet<&String> = changes.added.iter().chain(&changes.removed).collect();
+        modified.retain(|s| !listed.contains(s));
+        changes.modified.extend(modified);
+        for list in [&mut changes.added, &mut changes.removed, &mut changes.modified] {
+            list.sort_unstable_by(|a, b| compare_terms(a, b));
+            list.dedup();
+        }
+        changes
+    }
+
+    /// Write the concise bounded description of the given subject as N-Triples, which are its triples and recursively those of its blank node objects.
+    /// Returns the number of written triples, 0 if the subject does not exist.
+    /// Triples that cannot be translated, for example from damaged dictionary sections, are logged and skipped.
+    pub fn write_cbd<W: Write>(&self, writer: &mut W, subject: &str) -> io::Result<usize> {
+        let sid = self.dict.string_to_id(subject, &IdKind::Subject);
+        if sid == 0 {
+            return Ok(0);
+        }
+        let shared = self.dict.shared.num_strings();
+        let mut stack = vec![(sid, nt_term(subject))];
+        let mut visited = HashSet::from([sid]);
+        let mut written = 0;
+        while let Some((sid, s)) = stack.pop() {
+            for t in SubjectIter::with_s(&self.triples, sid) {
+                let (p, o) = match (
+                    self.dict.id_to_string(t.predicate_id, &IdKind::Predicate),
+                    self.dict.id_to_string(t.object_id, &IdKind::Object),
+                ) {
+                    (Ok(p), Ok(o)) => (p, o),
+                    (Err(e), _) | (_, Err(e)) => {
+                        error!("{e}");
+                        continue;
+                    }
+                };
+                let o_nt = nt_term(&o);
+                writeln!(writer, "{s} {} {o_nt} .", nt_term(&p))?;
+                written += 1;
+                // only objects in the shared section are also subjects
+                if t.object_id <= shared && o.starts_with("_:") && visited.insert(t.object_id) {
+                    stack.push((t.object_id, o_nt));
+                }
+            }
+        }
+        Ok(written)
+    }
+
+    /// Write the concise bounded descriptions of all subjects that were added or modified since the given old version as N-Triples,
+    /// see [`Self::changed_subjects`] and [`Self::write_cbd`].
+    /// Returns the changes, whose removed and modified subjects need to be deleted from downstream indexes before adding the written descriptions.
+    /// # Example
+    /// ```
+    /// fn update(old: &hdt::Hdt, new: &hdt::Hdt) -> std::io::Result<()> {
+    ///     let mut out = std::io::BufWriter::new(std::fs::File::create("changes.nt")?);
+    ///     let changes = new.export_changes(old, &mut out)?;
+    ///     eprintln!("{} added, {} modified, {} removed", changes.added.len(), changes.modified.len(), changes.removed.len());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn export_changes<W: Write>(&self, old: &Hdt, writer: &mut W) -> io::Result<SubjectChanges> {
+        let changes = self.changed_subjects(old);
+        for s in changes.added.iter().chain(&changes.modified) {
+            self.write_cbd(writer, s)?;
+        }
+        Ok(changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::HdtBuilder;
+    use crate::tests::init;
+    use pretty_assertions::assert_eq;
+    use std::fs::File;
+
+    fn build(triples: &[[&str; 3]]) -> Hdt {
+        let mut builder = HdtBuilder::new();
+        for [s, p, o] in triples {
+            builder.add_triple(s, p, o);
+        }
+        builder.build().unwrap()
+    }
+
+    #[test]
+    fn changed_subjects() {
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let hdt = Hdt::new(std::io::BufReader::new(file)).unwrap();
+        assert!(hdt.changed_subjects(&hdt).is_empty());
+
+        let ex = |s: &str| format!("http://example.org/{s}");
+        let [alice, bob, carol, dave, eve, grace] = ["alice", "bob", "carol", "dave", "eve", "grace"].map(ex);
+        let (knows, note) = (ex("knows"), ex("note"));
+        let old = build(&[
+            [&alice, &knows, &bob],
+            [&alice, &note, "_:x"],
+            ["_:x", &knows, "\"1\""],
+            [&carol, &knows, &dave],
+            [&eve, &knows, &alice],
+            ["_:root", &knows, "\"old\""],
+        ]);
+        let new = build(&[
+            [&alice, &knows, &bob],
+            [&alice, &note, "_:x"],
+            ["_:x", &knows, "\"2\""],
+            // dave is now also a subject and moves to the shared section
+            [&carol, &knows, &dave],
+            [&dave, &knows, &carol],
+            [&grace, &knows, &alice],
+            ["_:root", &knows, "\"new\""],
+        ]);
+        let changes = new.changed_subjects(&old);
+        assert_eq!(vec![dave, grace], changes.added);
+        assert_eq!(vec![eve], changes.removed);
+        assert_eq!(vec!["_:root".to_owned(), alice.clone()], changes.modified);
+        assert!(old.changed_subjects(&old).is_empty());
+
+        let mut out = Vec::new();
+        assert_eq!(changes, new.export_changes(&old, &mut out).unwrap());
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains(&format!("<{alice}> <{note}> _:x .\n_:x <{knows}> \"2\" .\n")), "{out}");
+        assert_eq!(6, out.lines().count(), "{out}");
+        assert_eq!(0, new.write_cbd(&mut Vec::new(), &ex("missing")).unwrap());
+    }
+}
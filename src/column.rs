@@ -0,0 +1,206 @@
+//! Projection of a predicate into a typed column of the parsed values of its objects, for analytics and plotting, see [`Hdt::column`].
+use crate::four_sect_dict::IdKind;
+use crate::hdt::Hdt;
+use crate::triples::{Id, PredicateIter};
+use std::collections::HashMap;
+
+/// Target type of the values of a [`Column`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Datatype {
+    /// Floating point numbers such as `xsd:double` and `xsd:decimal`, including `INF` and `NaN`.
+    F64,
+    /// Integers such as `xsd:integer` and `xsd:int`.
+    I64,
+    /// Booleans as in `xsd:boolean`, which are `true`, `false`, `1` and `0`.
+    Bool,
+    /// The lexical form without parsing.
+    String,
+}
+
+/// Parsed values of a [`Column`], the variant corresponds to its [`Datatype`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColumnValues {
+    /// Values of [`Datatype::F64`].
+    F64(Vec<f64>),
+    /// Values of [`Datatype::I64`].
+    I64(Vec<i64>),
+    /// Values of [`Datatype::Bool`].
+    Bool(Vec<bool>),
+    /// Values of [`Datatype::String`].
+    String(Vec<String>),
+}
+
+impl ColumnValues {
+    /// Number of values.
+    pub const fn len(&self) -> usize {
+        match self {
+            ColumnValues::F64(v) => v.len(),
+            ColumnValues::I64(v) => v.len(),
+            ColumnValues::Bool(v) => v.len(),
+            ColumnValues::String(v) => v.len(),
+        }
+    }
+}
+
+/// An object of the projected predicate that could not be converted into the datatype of the column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnError {
+    /// ID of the subject of the triple.
+    pub subject_id: Id,
+    /// ID of the object, which is the same for all errors caused by the same object.
+    pub object_id: Id,
+    /// The object in the HDT dictionary string format, empty if it could not be extracted.
+    pub object: String,
+    /// Why the object could not be converted.
+    pub message: String,
+}
+
+/// Subject IDs and parsed object values of all triples with a given predicate, in the order of the subject IDs.
+/// The value at index i belongs to the subject at index i, a subject with several values occurs several times.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Column {
+    /// Subject IDs of the values.
+    pub subjects: Vec<Id>,
+    /// Parsed object values.
+    pub values: ColumnValues,
+    /// Objects that were skipped because they could not be converted, in the order of their subjects.
+    pub errors: Vec<ColumnError>,
+}
+
+/// An object converted into a value of the target datatype, or the reason why it could not be.
+type Parsed = Result<Value, (String, String)>;
+
+#[derive(Clone)]
+enum Value {
+    F64(f64),
+    I64(i64),
+    Bool(bool),
+    String(String),
+}
+
+impl Datatype {
+    /// Parse the lexical form of the given literal in the HDT dictionary string format.
+    fn parse(self, object: &str) -> Result<Value, String> {
+        let Some(lexical) = object.strip_prefix('"').and_then(|o| o.get(..o.rfind('"')?)) else {
+            return Err("not a literal".to_owned());
+        };
+        let trimmed = lexical.trim();
+        match self {
+            Datatype::F64 => trimmed.parse().map(Value::F64).map_err(|e| format!("invalid number: {e}")),
+            Datatype::I64 => trimmed.parse().map(Value::I64).map_err(|e| format!("invalid integer: {e}")),
+            Datatype::Bool => match trimmed {
+                "true" | "1" => Ok(Value::Bool(true)),
+                "false" | "0" => Ok(Value::Bool(false)),
+                _ => Err("invalid boolean".to_owned()),
+            },
+            Datatype::String => Ok(Value::String(lexical.to_owned())),
+        }
+    }
+
+    const fn values(self) -> ColumnValues {
+        match self {
+            Datatype::F64 => ColumnValues::F64(Vec::new()),
+            Datatype::I64 => ColumnValues::I64(Vec::new()),
+            Datatype::Bool => ColumnValues::Bool(Vec::new()),
+            Datatype::String => ColumnValues::String(Vec::new()),
+        }
+    }
+}
+
+impl ColumnValues {
+    fn push(&mut self, value: Value) {
+        match (self, value) {
+            (ColumnValues::F64(v), Value::F64(x)) => v.push(x),
+            (ColumnValues::I64(v), Value::I64(x)) => v.push(x),
+            (ColumnValues::Bool(v), Value::Bool(x)) => v.push(x),
+            (ColumnValues::String(v), Value::String(x)) => v.push(x),
+            _ => unreachable!("value of a different datatype"),
+        }
+    }
+}
+
+impl Hdt {
+    /// Project the predicate with the given IRI into a column of the values of its objects parsed as the given datatype.
+    /// Works on IDs and extracts and parses each distinct object only once, instead of translating every triple into strings.
+    /// Objects that are not literals or cannot be parsed are skipped and reported in [`Column::errors`].
+    /// The column is empty if the predicate does not exist.
+    /// # Example
+    /// ```
+    /// use hdt::column::{ColumnValues, Datatype};
+    /// fn mean_population(hdt: &hdt::Hdt) -> Option<f64> {
+    ///     let column = hdt.column("http://dbpedia.org/ontology/populationTotal", Datatype::F64);
+    ///     let ColumnValues::F64(values) = column.values else { unreachable!() };
+    ///     (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+    /// }
+    /// ```
+    pub fn column(&self, p: &str, datatype: Datatype) -> Column {
+        let mut column = Column { subjects: Vec::new(), values: datatype.values(), errors: Vec::new() };
+        let pid = self.dict.string_to_id(p, &IdKind::Predicate);
+        if pid == 0 {
+            return column;
+        }
+        let mut cache = HashMap::<Id, Parsed>::new();
+        for t in PredicateIter::new(&self.triples, pid) {
+            let parsed = cache.entry(t.object_id).or_insert_with(|| {
+                let object = self
+                    .dict
+                    .id_to_string(t.object_id, &IdKind::Object)
+                    .map_err(|e| (String::new(), format!("cannot extract object: {e}")))?;
+                datatype.parse(&object).map_err(|message| (object, message))
+            });
+            match parsed {
+                Ok(value) => {
+                    column.subjects.push(t.subject_id);
+                    column.values.push(value.clone());
+                }
+                Err((object, message)) => column.errors.push(ColumnError {
+                    subject_id: t.subject_id,
+                    object_id: t.object_id,
+                    object: object.clone(),
+                    message: message.clone(),
+                }),
+            }
+        }
+        column
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::init;
+    use pretty_assertions::assert_eq;
+    use std::fs::File;
+
+    #[test]
+    fn column() {
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let hdt = Hdt::new(std::io::BufReader::new(file)).unwrap();
+        let label = "http://www.w3.org/2000/01/rdf-schema#label";
+        let labels = hdt.column(label, Datatype::String);
+        let expected: Vec<_> = hdt.triples_with_pattern(None, Some(label), None).collect();
+        assert_eq!(expected.len(), labels.values.len() + labels.errors.len());
+        assert_eq!(labels.subjects.len(), labels.values.len());
+        assert!(labels.subjects.windows(2).all(|w| w[0] <= w[1]));
+        let ColumnValues::String(values) = &labels.values else { panic!("wrong column type") };
+        assert!(values.iter().any(|v| v == "SNIK Meta Ontology"));
+
+        // labels are not numbers
+        let numbers = hdt.column(label, Datatype::F64);
+        assert_eq!(ColumnValues::F64(Vec::new()), numbers.values);
+        assert_eq!(expected.len(), numbers.errors.len());
+        assert!(numbers.errors.iter().all(|e| e.message.starts_with("invalid number")));
+
+        let rdf_type = hdt.column("http://www.w3.org/1999/02/22-rdf-syntax-ns#type", Datatype::I64);
+        assert!(rdf_type.errors.iter().all(|e| e.message == "not a literal"));
+        assert!(!rdf_type.errors.is_empty());
+        assert_eq!(0, hdt.column("http://example.org/missing", Datatype::Bool).values.len());
+
+        let double = Datatype::F64.parse("\"1.5e3\"^^<http://www.w3.org/2001/XMLSchema#double>");
+        assert!(matches!(double, Ok(Value::F64(x)) if (x - 1500.0).abs() < f64::EPSILON));
+        assert!(matches!(Datatype::I64.parse("\" +42 \""), Ok(Value::I64(42))));
+        assert!(matches!(Datatype::Bool.parse("\"0\""), Ok(Value::Bool(false))));
+        assert!(Datatype::Bool.parse("\"yes\"").is_err());
+    }
+}
@@ -0,0 +1,212 @@
+//! Collected triple IDs that need half the memory as long as all IDs fit into 32 bits, see [`TripleIds`].
+use crate::hdt::Hdt;
+use crate::triples::{TripleId, TriplesBitmap};
+use std::mem::size_of;
+use std::num::TryFromIntError;
+
+/// Triple ID with 32 bit IDs, which takes 12 instead of 24 bytes on 64 bit platforms.
+/// The HDT format limits the number of terms of most files to 32 bits, so that this usually fits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CompactTripleId {
+    /// Index starting at 1 in the combined shared and subject section.
+    pub subject_id: u32,
+    /// Index starting at 1 in the predicate section.
+    pub predicate_id: u32,
+    /// Index starting at 1 in the combined shared and object section.
+    pub object_id: u32,
+}
+
+impl TryFrom<TripleId> for CompactTripleId {
+    type Error = TryFromIntError;
+
+    fn try_from(t: TripleId) -> Result<Self, Self::Error> {
+        Ok(CompactTripleId {
+            subject_id: t.subject_id.try_into()?,
+            predicate_id: t.predicate_id.try_into()?,
+            object_id: t.object_id.try_into()?,
+        })
+    }
+}
+
+impl From<CompactTripleId> for TripleId {
+    fn from(t: CompactTripleId) -> Self {
+        TripleId::new(t.subject_id as usize, t.predicate_id as usize, t.object_id as usize)
+    }
+}
+
+/// Triple IDs that are stored as [`CompactTripleId`] while all IDs fit and as [`TripleId`] after the first one that does not.
+/// Collect any iterator of triple IDs into it, for example for result sets that are kept for a while or joined later.
+/// # Example
+/// ```
+/// fn objects_of(hdt: &hdt::Hdt, p: &str) -> hdt::compact::TripleIds {
+///     hdt.triple_ids_with_pattern(None, Some(p), None)
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TripleIds {
+    /// All IDs fit into 32 bits.
+    Compact(Vec<CompactTripleId>),
+    /// At least one ID needs more than 32 bits.
+    Wide(Vec<TripleId>),
+}
+
+impl Default for TripleIds {
+    fn default() -> Self {
+        TripleIds::Compact(Vec::new())
+    }
+}
+
+impl TripleIds {
+    /// Empty compact triple IDs.
+    pub const fn new() -> Self {
+        TripleIds::Compact(Vec::new())
+    }
+
+    /// Append a triple ID, which converts all triple IDs to [`TripleId`] if one of its IDs does not fit into 32 bits.
+    pub fn push(&mut self, t: TripleId) {
+        match self {
+            TripleIds::Compact(ids) => {
+                if let Ok(compact) = CompactTripleId::try_from(t) {
+                    ids.push(compact);
+                } else {
+                    let mut wide: Vec<TripleId> = Vec::with_capacity(ids.capacity().max(ids.len() + 1));
+                    wide.extend(ids.iter().map(|&c| TripleId::from(c)));
+                    wide.push(t);
+                    *self = TripleIds::Wide(wide);
+                }
+            }
+            TripleIds::Wide(ids) => ids.push(t),
+        }
+    }
+
+    /// Number of triple IDs.
+    pub const fn len(&self) -> usize {
+        match self {
+            TripleIds::Compact(ids) => ids.len(),
+            TripleIds::Wide(ids) => ids.len(),
+        }
+    }
+
+    /// Whether there are no triple IDs.
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether the triple IDs are stored with 32 bit IDs.
+    pub const fn is_compact(&self) -> bool {
+        matches!(self, TripleIds::Compact(_))
+    }
+
+    /// The triple ID at the given position.
+    pub fn get(&self, index: usize) -> Option<TripleId> {
+        match self {
+            TripleIds::Compact(ids) => ids.get(index).map(|&c| c.into()),
+            TripleIds::Wide(ids) => ids.get(index).copied(),
+        }
+    }
+
+    /// Iterate over the triple IDs in the order they were added.
+    pub fn iter(&self) -> Box<dyn Iterator<Item = TripleId> + '_> {
+        match self {
+            TripleIds::Compact(ids) => Box::new(ids.iter().map(|&c| c.into())),
+            TripleIds::Wide(ids) => Box::new(ids.iter().copied()),
+        }
+    }
+
+    /// Size in bytes on the heap.
+    pub const fn size_in_bytes(&self) -> usize {
+        match self {
+            TripleIds::Compact(ids) => ids.capacity() * size_of::<CompactTripleId>(),
+            TripleIds::Wide(ids) => ids.capacity() * size_of::<TripleId>(),
+        }
+    }
+
+    /// Release the capacity that is not needed.
+    pub fn shrink_to_fit(&mut self) {
+        match self {
+            TripleIds::Compact(ids) => ids.shrink_to_fit(),
+            TripleIds::Wide(ids) => ids.shrink_to_fit(),
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a TripleIds {
+    type Item = TripleId;
+    type IntoIter = Box<dyn Iterator<Item = TripleId> + 'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl Extend<TripleId> for TripleIds {
+    fn extend<I: IntoIterator<Item = TripleId>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        match self {
+            TripleIds::Compact(ids) => ids.reserve(iter.size_hint().0),
+            TripleIds::Wide(ids) => ids.reserve(iter.size_hint().0),
+        }
+        for t in iter {
+            self.push(t);
+        }
+    }
+}
+
+impl FromIterator<TripleId> for TripleIds {
+    fn from_iter<I: IntoIterator<Item = TripleId>>(iter: I) -> Self {
+        let mut ids = TripleIds::new();
+        ids.extend(iter);
+        ids
+    }
+}
+
+impl TriplesBitmap {
+    /// Collect the IDs of all triples matching the given ID pattern, compact if the IDs fit, see [`TripleIds`].
+    pub fn collect_ids(&self, pat: &TripleId) -> TripleIds {
+        self.triples_with_pattern(pat).collect()
+    }
+}
+
+impl Hdt {
+    /// Collect the IDs of all triples matching the given pattern, compact if the IDs fit, see [`TripleIds`].
+    /// Terms that do not exist in the graph result in no triple IDs, see [`Self::pattern_ids`].
+    pub fn triple_ids_with_pattern(&self, sp: Option<&str>, pp: Option<&str>, op: Option<&str>) -> TripleIds {
+        self.pattern_ids(sp, pp, op).map(|pat| self.triples.collect_ids(&pat)).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::init;
+    use pretty_assertions::assert_eq;
+    use std::fs::File;
+
+    #[test]
+    fn compact() {
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let hdt = Hdt::new(std::io::BufReader::new(file)).unwrap();
+        let label = Some("http://www.w3.org/2000/01/rdf-schema#label");
+        let ids = hdt.triple_ids_with_pattern(None, label, None);
+        let expected: Vec<TripleId> =
+            hdt.triples.triples_with_pattern(&hdt.pattern_ids(None, label, None).unwrap()).collect();
+        assert!(ids.is_compact());
+        assert_eq!(expected, ids.iter().collect::<Vec<_>>());
+        assert_eq!(expected.get(3).copied(), ids.get(3));
+        assert!(hdt.triple_ids_with_pattern(Some("http://example.org/missing"), None, None).is_empty());
+
+        let mut all = hdt.triples.collect_ids(&TripleId::new(0, 0, 0));
+        all.shrink_to_fit();
+        assert_eq!(hdt.triples.len(), all.len());
+        assert_eq!(hdt.triples.len() * 12, all.size_in_bytes());
+
+        // falls back to wide IDs
+        let large = TripleId::new(1, 2, u32::MAX as usize + 1);
+        all.push(large);
+        assert!(!all.is_compact());
+        assert_eq!(hdt.triples.len() + 1, all.len());
+        assert_eq!(Some(large), all.get(hdt.triples.len()));
+        assert_eq!(expected[0], all.iter().find(|t| t.predicate_id == expected[0].predicate_id).unwrap());
+    }
+}
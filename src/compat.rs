@@ -0,0 +1,183 @@
+//! Compatibility checks against reference implementations such as hdt-cpp, using golden outputs instead of running them.
+//! Given an N-Triples file and the HDT file that hdt-cpp generated from it, [`check`] verifies that this crate reads the same triples,
+//! answers triple patterns of all shapes with the same results as a naive evaluation over the N-Triples,
+//! and writes files with dictionary sections that are byte for byte identical to those of hdt-cpp and that result in the same triple IDs when read again.
+//! Run it on your own datasets to find incompatibilities before relying on this crate, and please report them.
+use crate::builder::ntriples_line;
+use crate::containers::ControlInfo;
+use crate::header::Header;
+use crate::Hdt;
+use eyre::{Result, WrapErr};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::io::BufRead;
+use std::path::Path;
+
+/// Maximum number of mismatches recorded per check, further ones are only counted.
+const MAX_MISMATCHES: usize = 20;
+
+/// Result of a compatibility check, see [`check`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompatReport {
+    /// Number of distinct triples in the N-Triples file.
+    pub reference_triples: usize,
+    /// Number of triple patterns whose results were compared.
+    pub patterns: usize,
+    /// Descriptions of the first mismatches of each check, empty if the crate behaves like the reference implementation.
+    pub mismatches: Vec<String>,
+    /// Number of mismatches, including those not recorded in [`Self::mismatches`].
+    pub mismatch_count: usize,
+}
+
+impl CompatReport {
+    /// Whether no mismatches were found.
+    pub const fn is_compatible(&self) -> bool {
+        self.mismatch_count == 0
+    }
+
+    fn mismatch(&mut self, recorded: &mut usize, description: impl FnOnce() -> String) {
+        self.mismatch_count += 1;
+        if *recorded < MAX_MISMATCHES {
+            *recorded += 1;
+            self.mismatches.push(description());
+        }
+    }
+}
+
+type StringTriple = [String; 3];
+
+/// Compare this crate with the reference implementation that generated the given HDT file from the given N-Triples data.
+/// The patterns are derived from up to `max_samples` triples spread evenly over the N-Triples data.
+/// Results in an error if the N-Triples cannot be parsed or the HDT file cannot be loaded at all.
+/// # Example
+/// ```
+/// fn compare() -> eyre::Result<()> {
+///     let nt = std::io::BufReader::new(std::fs::File::open("dataset.nt")?);
+///     let report = hdt::compat::check(nt, "dataset.hdt", 1000)?;
+///     assert!(report.is_compatible(), "{:#?}", report.mismatches);
+///     Ok(())
+/// }
+/// ```
+pub fn check<R: BufRead>(nt: R, hdt_path: impl AsRef<Path>, max_samples: usize) -> Result<CompatReport> {
+    let mut reference = BTreeSet::<StringTriple>::new();
+    for (i, line) in nt.lines().enumerate() {
+        let line = line.wrap_err_with(|| format!("Failed to read line {}", i + 1))?;
+        if let Some(triple) =
+            ntriples_line(&line).wrap_err_with(|| format!("Invalid N-Triples in line {}", i + 1))?
+        {
+            reference.insert(triple);
+        }
+    }
+    let original = fs::read(hdt_path.as_ref()).wrap_err("Failed to read HDT file")?;
+    let hdt = Hdt::new(original.as_slice()).map_err(|e| eyre::eyre!("Failed to load HDT file: {e}"))?;
+    let mut report = CompatReport { reference_triples: reference.len(), ..CompatReport::default() };
+    check_triples(&hdt, &reference, &mut report);
+    check_patterns(&hdt, &reference, max_samples, &mut report);
+    check_sections(&hdt, &original, &mut report)?;
+    Ok(report)
+}
+
+/// All triples of the HDT file are in the reference and vice versa.
+fn check_triples(hdt: &Hdt, reference: &BTreeSet<StringTriple>, report: &mut CompatReport) {
+    let actual: BTreeSet<StringTriple> =
+        hdt.triples().map(|(s, p, o)| [s.to_string(), p.to_string(), o.to_string()]).collect();
+    let mut recorded = 0;
+    for t in reference.difference(&actual) {
+        report.mismatch(&mut recorded, || format!("triple {t:?} of the N-Triples is missing in the HDT"));
+    }
+    for t in actual.difference(reference) {
+        report.mismatch(&mut recorded, || format!("triple {t:?} of the HDT is not in the N-Triples"));
+    }
+}
+
+/// Triple patterns of all shapes with constants taken from sample triples have the same results as a naive evaluation.
+fn check_patterns(hdt: &Hdt, reference: &BTreeSet<StringTriple>, max_samples: usize, report: &mut CompatReport) {
+    // reference triples by each of their terms, so that the expected results do not require a full scan per pattern
+    let mut by_term: [HashMap<&str, Vec<&StringTriple>>; 3] = Default::default();
+    for t in reference {
+        for (index, term) in by_term.iter_mut().zip(t) {
+            index.entry(term.as_str()).or_default().push(t);
+        }
+    }
+    let step = reference.len().div_ceil(max_samples.max(1)).max(1);
+    let mut recorded = 0;
+    for t in reference.iter().step_by(step) {
+        // all combinations of constants and variables except for the pattern without constants, which check_triples covers
+        for mask in 1..8_u8 {
+            let pat: [Option<&str>; 3] = [0, 1, 2].map(|i| (mask & (4 >> i) != 0).then_some(t[i].as_str()));
+            // at least one position is constant, use the index of the first one
+            let Some(i) = pat.iter().position(Option::is_some) else { continue };
+            let expected: BTreeSet<&StringTriple> = by_term[i][t[i].as_str()]
+                .iter()
+                .copied()
+                .filter(|r| pat.iter().zip(r.iter()).all(|(c, term)| c.is_none_or(|c| c == term.as_str())))
+                .collect();
+            let actual: Vec<StringTriple> = hdt
+                .triples_with_pattern(pat[0], pat[1], pat[2])
+                .map(|(s, p, o)| [s.to_string(), p.to_string(), o.to_string()])
+                .collect();
+            report.patterns += 1;
+            if actual.len() != expected.len() || !actual.iter().all(|t| expected.contains(t)) {
+                report.mismatch(&mut recorded, || {
+                    format!("pattern {pat:?} has {} results instead of {}", actual.len(), expected.len())
+                });
+            }
+        }
+    }
+}
+
+/// Writing the HDT results in the same dictionary sections as the reference implementation and in the same triple IDs when read again.
+/// The control information and the triples section may differ, because hdt-cpp counts the dictionary size differently
+/// and may use more bits per entry than needed for the sequences.
+fn check_sections(hdt: &Hdt, original: &[u8], report: &mut CompatReport) -> Result<()> {
+    let mut written = Vec::new();
+    hdt.write_to(&mut written, "http://example.org/compat")?;
+    let dict_sections = |mut data: &[u8]| -> Result<Vec<u8>> {
+        ControlInfo::read(&mut data)?;
+        Header::read(&mut data)?;
+        ControlInfo::read(&mut data)?;
+        // the dictionary ends where the control information of the triples starts
+        let end = data.windows(5).position(|w| w == b"$HDT\x04").unwrap_or(data.len());
+        Ok(data[..end].to_vec())
+    };
+    let mut recorded = 0;
+    if dict_sections(original)? != dict_sections(&written)? {
+        report.mismatch(&mut recorded, || "written dictionary sections differ from the original".to_owned());
+    }
+    let copy = Hdt::new(written.as_slice()).map_err(|e| eyre::eyre!("Failed to read written HDT: {e}"))?;
+    if !hdt.triples.into_iter().eq(&copy.triples) {
+        report.mismatch(&mut recorded, || "written triples section results in different triple IDs".to_owned());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::containers::rdf::nt_term;
+    use crate::tests::init;
+    use pretty_assertions::assert_eq;
+
+    const SNIKMETA: &str = "tests/resources/snikmeta.hdt";
+
+    #[test]
+    fn compat() -> Result<()> {
+        init();
+        let hdt = Hdt::new(std::io::BufReader::new(fs::File::open(SNIKMETA)?)).unwrap();
+        // golden N-Triples, hdt-cpp generated snikmeta.hdt from the same triples
+        let lines: Vec<String> =
+            hdt.triples().map(|(s, p, o)| format!("{} {} {} .", nt_term(&s), nt_term(&p), nt_term(&o))).collect();
+        let report = check(lines.join("\n").as_bytes(), SNIKMETA, 50)?;
+        assert!(report.is_compatible(), "{:#?}", report.mismatches);
+        assert_eq!(328, report.reference_triples);
+        // every seventh of the 328 triples is sampled, with 7 patterns each
+        assert_eq!(47 * 7, report.patterns);
+
+        let changed = format!("{}\n<http://example.org/a> <http://example.org/p> \"x\" .", lines[1..].join("\n"));
+        let report = check(changed.as_bytes(), SNIKMETA, 1000)?;
+        assert!(!report.is_compatible());
+        assert_eq!(2, report.mismatches.iter().filter(|m| m.starts_with("triple")).count());
+        assert!(check("invalid".as_bytes(), SNIKMETA, 1).is_err());
+        Ok(())
+    }
+}
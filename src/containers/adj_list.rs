@@ -36,12 +36,12 @@ impl AdjList {
 
     /// Number of entries in both the integer sequence and the bitmap.
     pub const fn len(&self) -> usize {
-        self.sequence.entries
+        self.sequence.len()
     }
 
     /// Whether the list is emtpy
     pub const fn is_empty(&self) -> bool {
-        self.sequence.entries == 0
+        self.sequence.is_empty()
     }
 
     /// Find the first position for the given ID, counting from 1.
@@ -1,13 +1,41 @@
 //! Bitmap with rank and select support read from an HDT file.
-use crate::containers::vbyte::read_vbyte;
+use crate::containers::read_bytes;
+use crate::containers::vbyte::{encode_vbyte, read_vbyte};
+use crate::error::{HdtError, Part};
 use bytesize::ByteSize;
 use eyre::{eyre, Result};
 use std::fmt;
-use std::io::BufRead;
+#[cfg(feature = "mmap")]
+use std::io::Seek;
+use std::io::{self, BufRead, Write};
 use std::mem::size_of;
 use sucds::bit_vectors::{Access, BitVector, Rank, Rank9Sel, Select};
 use sucds::Serializable;
 
+/// Serialization type of a bitmap as given by its type byte.
+#[repr(u8)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum BitmapType {
+    /// Bits stored in little endian 64 bit words, with the last word truncated to whole bytes.
+    /// Shared by the Bitmap64 and Bitmap375 implementations of hdt-cpp and hdt-java, which only differ in their in-memory rank and select structures.
+    Plain = 1,
+}
+
+impl TryFrom<u8> for BitmapType {
+    type Error = HdtError;
+
+    fn try_from(original: u8) -> Result<Self, Self::Error> {
+        match original {
+            1 => Ok(BitmapType::Plain),
+            _ => Err(HdtError::UnsupportedFormat {
+                part: Part::Bitmap,
+                format: format!("type {original}"),
+                supported: "only plain bitmaps (type 1) are supported",
+            }),
+        }
+    }
+}
+
 /// Compact bitmap representation with rank and select support.
 #[derive(Clone)]
 pub struct Bitmap {
@@ -32,6 +60,11 @@ impl Bitmap {
         Bitmap { dict }
     }
 
+    /// Construct a bitmap with exactly the given bits.
+    pub fn from_bits(bits: impl IntoIterator<Item = bool>) -> Self {
+        Bitmap { dict: Rank9Sel::new(BitVector::from_bits(bits)).select1_hints() }
+    }
+
     /// Size in bytes on the heap.
     pub fn size_in_bytes(&self) -> usize {
         self.dict.size_in_bytes()
@@ -52,6 +85,11 @@ impl Bitmap {
         self.dict.rank1(k).unwrap_or_else(|| panic!("Out of bounds position: {} >= {}", k, self.dict.len()))
     }
 
+    /// Whether the bit at the given position is set, false if the position is out of bounds.
+    pub fn get(&self, k: usize) -> bool {
+        self.dict.access(k).unwrap_or(false)
+    }
+
     /// Whether the node given position is the last child of its parent.
     pub fn at_last_sibling(&self, word_index: usize) -> bool {
         self.dict.access(word_index).expect("word index out of bounds")
@@ -65,10 +103,27 @@ impl Bitmap {
         let mut bitmap_type = [0u8];
         reader.read_exact(&mut bitmap_type)?;
         history.extend_from_slice(&bitmap_type);
-        if bitmap_type[0] != 1 {
-            return Err(eyre!("Read unsupported bitmap type {} != 1", bitmap_type[0]));
+        match BitmapType::try_from(bitmap_type[0])? {
+            BitmapType::Plain => Self::read_plain(reader, history),
         }
+    }
 
+    /// Skip a bitmap without reading its data and return the CRC32C checksum of the data as stored after it.
+    /// Only the preamble is read and validated.
+    #[cfg(feature = "mmap")]
+    pub(crate) fn skip<R: BufRead + Seek>(reader: &mut R) -> Result<u32> {
+        let mut bitmap_type = [0u8];
+        reader.read_exact(&mut bitmap_type)?;
+        let BitmapType::Plain = BitmapType::try_from(bitmap_type[0])?;
+        let num_bits = Self::read_preamble(reader, bitmap_type.to_vec())?;
+        reader.seek_relative(i64::try_from(num_bits.div_ceil(8))?)?;
+        let mut crc_code = [0_u8; 4];
+        reader.read_exact(&mut crc_code)?;
+        Ok(u32::from_le_bytes(crc_code))
+    }
+
+    /// Read the number of bits and the CRC8 of a plain bitmap after the type byte, which is already contained in the history.
+    fn read_preamble<R: BufRead>(reader: &mut R, mut history: Vec<u8>) -> Result<usize> {
         // read the number of bits
         let (num_bits, bytes_read) = read_vbyte(reader)?;
         history.extend_from_slice(&bytes_read);
@@ -82,17 +137,21 @@ impl Bitmap {
         let crc8 = crc::Crc::<u8>::new(&crc::CRC_8_SMBUS);
         let mut digest = crc8.digest();
         digest.update(&history);
-        let crc_calculated = digest.finalize();
-        if crc_calculated != crc_code {
-            return Err(eyre!("Invalid CRC8-CCIT checksum {crc_calculated}, expected {crc_code}"));
+        if digest.finalize() != crc_code {
+            return Err(HdtError::checksum(Part::Bitmap, "CRC8-CCIT", "bitmap preamble").into());
         }
+        Ok(num_bits)
+    }
+
+    /// Read the rest of a plain bitmap after the type byte, which is already contained in the history.
+    fn read_plain<R: BufRead>(reader: &mut R, history: Vec<u8>) -> Result<Self> {
+        let num_bits = Self::read_preamble(reader, history)?;
 
         // read all but the last word, last word is byte aligned
-        let full_byte_amount = ((num_bits - 1) >> 6) * 8;
-        let mut full_words = vec![0_u8; full_byte_amount];
+        let full_byte_amount = (num_bits.saturating_sub(1) >> 6) * 8;
+        let full_words = read_bytes(reader, full_byte_amount)?;
         // div_ceil is unstable
         let mut data: Vec<u64> = Vec::with_capacity(full_byte_amount / 8 + usize::from(full_byte_amount % 8 != 0));
-        reader.read_exact(&mut full_words)?;
 
         for word in full_words.chunks_exact(size_of::<u64>()) {
             if let Ok(word_data) = <[u8; 8]>::try_from(word) {
@@ -126,11 +185,71 @@ impl Bitmap {
         let crc_code = u32::from_le_bytes(crc_code);
 
         // validate entry body CRC32
-        let crc_calculated = digest.finalize();
-        if crc_calculated != crc_code {
-            return Err(eyre!("Invalid CRC32C checksum {crc_calculated}, expected {crc_code}"));
+        if digest.finalize() != crc_code {
+            return Err(HdtError::checksum(Part::Bitmap, "CRC32C", "bitmap data").into());
         }
 
         Ok(Self::new(data))
     }
+
+    /// Parse a bitmap from the beginning of the given bytes, for example for fuzzing.
+    /// Malformed input results in an error and never causes a panic.
+    pub fn parse(mut data: &[u8]) -> Result<Self> {
+        Self::read(&mut data)
+    }
+
+    /// Write the first `num_bits` bits as a plain bitmap in the format expected by [`Self::read`].
+    /// The number of bits needs to be given, because the bitmap is padded to whole words in memory.
+    pub fn write<W: Write>(&self, writer: &mut W, num_bits: usize) -> io::Result<()> {
+        let bits = self.dict.bit_vector();
+        if num_bits > bits.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("cannot write {num_bits} bits of a bitmap with {} bits", bits.len()),
+            ));
+        }
+        let mut preamble = vec![BitmapType::Plain as u8];
+        preamble.extend(encode_vbyte(num_bits));
+        preamble.push(crc::Crc::<u8>::new(&crc::CRC_8_SMBUS).checksum(&preamble));
+        writer.write_all(&preamble)?;
+
+        let data: Vec<u8> = (0..num_bits)
+            .step_by(8)
+            .map(|pos| bits.get_bits(pos, (num_bits - pos).min(8)).unwrap() as u8)
+            .collect();
+        writer.write_all(&data)?;
+        writer.write_all(&crc::Crc::<u32>::new(&crc::CRC_32_ISCSI).checksum(&data).to_le_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::init;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn read_types() {
+        init();
+        let mut data = vec![BitmapType::Plain as u8];
+        data.extend(encode_vbyte(10));
+        data.push(crc::Crc::<u8>::new(&crc::CRC_8_SMBUS).checksum(&data));
+        let bits = [0b0000_0101, 0b10];
+        data.extend_from_slice(&bits);
+        data.extend(crc::Crc::<u32>::new(&crc::CRC_32_ISCSI).checksum(&bits).to_le_bytes());
+        let bitmap = Bitmap::parse(&data).unwrap();
+        assert_eq!(vec![0, 2, 9], (0..3).map(|k| bitmap.select1(k).unwrap()).collect::<Vec<_>>());
+
+        let mut written = Vec::new();
+        bitmap.write(&mut written, 10).unwrap();
+        assert_eq!(data, written);
+        assert!(bitmap.write(&mut written, 65).is_err());
+
+        data[0] = 2;
+        let e = Bitmap::parse(&data).unwrap_err();
+        assert!(
+            matches!(HdtError::find(e.as_ref()), Some(HdtError::UnsupportedFormat { part: Part::Bitmap, .. })),
+            "{e}"
+        );
+    }
 }
@@ -0,0 +1,243 @@
+//! Storage of the bytes of dictionary sections and sequences, which is independent of how they are decoded, see [`ByteStore`].
+#[cfg(feature = "mmap")]
+use memmap2::Mmap;
+use std::io;
+#[cfg(feature = "mmap")]
+use std::ops::{Deref, Range};
+use std::sync::{Arc, OnceLock};
+
+/// Immutable bytes, such as the packed strings of a [`DictSectPFC`](crate::DictSectPFC), that are kept in memory, in a memory mapped file,
+/// compressed or on a web server.
+/// Only the packed strings of dictionary sections can be stored this way for now, not the sequences and bitmaps of the triples.
+pub trait ByteStore: Send + Sync {
+    /// Number of bytes, which is known without loading them.
+    fn len(&self) -> usize;
+
+    /// The bytes, which may be loaded on the first call and are then kept, so that later calls return them without error.
+    /// Fails if they cannot be loaded, in which case the next call tries again.
+    fn bytes(&self) -> io::Result<&[u8]>;
+
+    /// Number of bytes allocated on the heap, which excludes mapped bytes and bytes that are not loaded.
+    fn heap_len(&self) -> usize;
+}
+
+impl ByteStore for Vec<u8> {
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn bytes(&self) -> io::Result<&[u8]> {
+        Ok(self)
+    }
+
+    fn heap_len(&self) -> usize {
+        self.len()
+    }
+}
+
+/// Range of a memory mapped file, see [`Hdt::map`](crate::Hdt::map).
+#[cfg(feature = "mmap")]
+pub struct MappedBytes {
+    map: Arc<Mmap>,
+    range: Range<usize>,
+}
+
+#[cfg(feature = "mmap")]
+impl MappedBytes {
+    /// The bytes of the given range of the given memory map.
+    pub fn new(map: Arc<Mmap>, range: Range<usize>) -> Self {
+        assert!(range.start <= range.end && range.end <= map.len(), "range outside of the memory map");
+        MappedBytes { map, range }
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl Deref for MappedBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.map[self.range.clone()]
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl ByteStore for MappedBytes {
+    fn len(&self) -> usize {
+        self.range.len()
+    }
+
+    fn bytes(&self) -> io::Result<&[u8]> {
+        Ok(self)
+    }
+
+    fn heap_len(&self) -> usize {
+        0
+    }
+}
+
+/// Zstandard compressed bytes that are decompressed on first access and then kept in memory,
+/// for example for sections that are rarely queried.
+/// # Example
+/// ```
+/// let file = std::fs::File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+/// let mut hdt = hdt::Hdt::new(std::io::BufReader::new(file)).unwrap();
+/// let before = hdt.size_in_bytes();
+/// let compressed = hdt::containers::ZstdBytes::compress(hdt.dict.objects.packed_data().unwrap(), 3).unwrap();
+/// hdt.dict.objects.set_packed_data(compressed).unwrap();
+/// assert!(hdt.size_in_bytes() < before);
+/// // the first access decompresses the section and checks it against the checksum in the file
+/// assert!(hdt.dict.objects.verify_crc());
+/// ```
+#[cfg(feature = "zstd")]
+pub struct ZstdBytes {
+    compressed: Vec<u8>,
+    len: usize,
+    bytes: OnceLock<Vec<u8>>,
+}
+
+#[cfg(feature = "zstd")]
+impl ZstdBytes {
+    /// Compress the given bytes with the given Zstandard level, where 0 selects the default level.
+    pub fn compress(bytes: &[u8], level: i32) -> io::Result<Self> {
+        Ok(ZstdBytes { compressed: zstd::bulk::compress(bytes, level)?, len: bytes.len(), bytes: OnceLock::new() })
+    }
+
+    fn decompress(&self) -> io::Result<Vec<u8>> {
+        let bytes = zstd::bulk::decompress(&self.compressed, self.len)?;
+        if bytes.len() != self.len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(bytes)
+    }
+}
+
+#[cfg(feature = "zstd")]
+impl ByteStore for ZstdBytes {
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn bytes(&self) -> io::Result<&[u8]> {
+        if let Some(bytes) = self.bytes.get() {
+            return Ok(bytes);
+        }
+        let bytes = self.decompress()?;
+        Ok(self.bytes.get_or_init(|| bytes))
+    }
+
+    fn heap_len(&self) -> usize {
+        self.compressed.len() + self.bytes.get().map_or(0, Vec::len)
+    }
+}
+
+/// Store whose bytes are compared with a CRC32C checksum when they are loaded, see [`DictSectPFC::set_packed_data`](crate::DictSectPFC::set_packed_data).
+pub(crate) struct CheckedBytes<S> {
+    store: S,
+    crc32: u32,
+    valid: OnceLock<bool>,
+}
+
+impl<S: ByteStore> CheckedBytes<S> {
+    pub const fn new(store: S, crc32: u32) -> Self {
+        CheckedBytes { store, crc32, valid: OnceLock::new() }
+    }
+}
+
+impl<S: ByteStore> ByteStore for CheckedBytes<S> {
+    fn len(&self) -> usize {
+        self.store.len()
+    }
+
+    fn bytes(&self) -> io::Result<&[u8]> {
+        let bytes = self.store.bytes()?;
+        if *self.valid.get_or_init(|| crc::Crc::<u32>::new(&crc::CRC_32_ISCSI).checksum(bytes) == self.crc32) {
+            Ok(bytes)
+        } else {
+            Err(io::Error::new(io::ErrorKind::InvalidData, "CRC32C checksum mismatch of the bytes of the store"))
+        }
+    }
+
+    fn heap_len(&self) -> usize {
+        self.store.heap_len()
+    }
+}
+
+/// Byte store that can be cheaply cloned, for example to validate a checksum in another thread.
+#[derive(Clone)]
+pub(crate) struct SharedBytes(Arc<dyn ByteStore>);
+
+impl SharedBytes {
+    /// Number of bytes, which does not load them.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// The bytes, which may be loaded first, see [`ByteStore::bytes`].
+    pub fn bytes(&self) -> io::Result<&[u8]> {
+        self.0.bytes()
+    }
+
+    /// Number of bytes allocated on the heap, which excludes mapped bytes.
+    pub fn heap_len(&self) -> usize {
+        self.0.heap_len()
+    }
+}
+
+impl<T: ByteStore + 'static> From<T> for SharedBytes {
+    fn from(store: T) -> Self {
+        SharedBytes(Arc::new(store))
+    }
+}
+
+/// Take the next `len` bytes of the given reader over the memory map without copying them.
+#[cfg(feature = "mmap")]
+pub(crate) fn take_mapped(reader: &mut &[u8], map: &Arc<Mmap>, len: usize) -> io::Result<MappedBytes> {
+    if len > reader.len() {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    let start = reader.as_ptr() as usize - map.as_ptr() as usize;
+    assert!(start + reader.len() <= map.len(), "reader outside of the memory map");
+    *reader = &reader[len..];
+    Ok(MappedBytes::new(Arc::clone(map), start..start + len))
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "zstd")]
+    use super::*;
+    use crate::dict_sect_pfc::ExtractError;
+    use crate::tests::init;
+    use crate::DictSectPFC;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn byte_store() {
+        init();
+        let strings = ["http://example.org/a", "http://example.org/b", "http://example.org/c"];
+        let mut sect = DictSectPFC::new(&strings, 2);
+        let bytes = sect.packed_data().unwrap().to_vec();
+        assert!(sect.set_packed_data(bytes[1..].to_vec()).is_err());
+        // the checksum is compared when the bytes are accessed
+        let mut other = bytes.clone();
+        *other.last_mut().unwrap() ^= 1;
+        sect.set_packed_data(other).unwrap();
+        let e = sect.extract(3).unwrap_err();
+        assert!(
+            matches!(&e, ExtractError::Unavailable { id: 3, source } if source.kind() == std::io::ErrorKind::InvalidData),
+            "{e}"
+        );
+        assert_eq!(0, sect.string_to_id("http://example.org/b"));
+        assert!(sect.iter().all(|s| s.is_err()));
+        assert!(!sect.verify_crc());
+        sect.set_packed_data(bytes.clone()).unwrap();
+        assert_eq!("http://example.org/c", sect.extract(3).unwrap());
+        #[cfg(feature = "zstd")]
+        {
+            let store = ZstdBytes::compress(&bytes, 0).unwrap();
+            assert_eq!(bytes.len(), store.len());
+            sect.set_packed_data(store).unwrap();
+            assert_eq!(2, sect.string_to_id("http://example.org/b"));
+            assert_eq!(bytes, sect.packed_data().unwrap());
+        }
+    }
+}
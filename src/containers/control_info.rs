@@ -1,7 +1,8 @@
-use eyre::{eyre, Result};
+use crate::error::{HdtError, Part};
+use eyre::Result;
 use std::collections::HashMap;
 use std::io;
-use std::io::BufRead;
+use std::io::{BufRead, Write};
 use std::str;
 
 /// Type of Control Information.
@@ -18,7 +19,7 @@ pub enum ControlType {
 }
 
 impl TryFrom<u8> for ControlType {
-    type Error = std::io::Error;
+    type Error = HdtError;
 
     fn try_from(original: u8) -> Result<Self, Self::Error> {
         match original {
@@ -28,7 +29,7 @@ impl TryFrom<u8> for ControlType {
             3 => Ok(ControlType::Dictionary),
             4 => Ok(ControlType::Triples),
             5 => Ok(ControlType::Index),
-            _ => Err(Self::Error::new(io::ErrorKind::InvalidData, "Unrecognized control type")),
+            _ => Err(HdtError::invalid(Part::ControlInfo, format!("unrecognized control type {original}"))),
         }
     }
 }
@@ -45,10 +46,15 @@ pub struct ControlInfo {
 }
 
 impl ControlInfo {
+    /// Control information of the given type and format without properties.
+    pub fn new(control_type: ControlType, format: &str) -> Self {
+        ControlInfo { control_type, format: format.to_owned(), properties: HashMap::new() }
+    }
+
     /// Read and verify control information.
     pub fn read<R: BufRead>(reader: &mut R) -> Result<Self> {
         use io::Error;
-        use io::ErrorKind::{InvalidData, UnexpectedEof};
+        use io::ErrorKind::UnexpectedEof;
 
         // Keep track of what we are reading for computing the CRC afterwards.
         let crc = crc::Crc::<u16>::new(&crc::CRC_16_ARC);
@@ -58,7 +64,8 @@ impl ControlInfo {
         let mut hdt_cookie: [u8; 4] = [0; 4];
         reader.read_exact(&mut hdt_cookie)?;
         if &hdt_cookie != b"$HDT" {
-            return Err(eyre!("Chunk {hdt_cookie:?} does not equal the HDT cookie '$HDT'"));
+            let message = format!("chunk {hdt_cookie:?} does not equal the HDT cookie '$HDT'");
+            return Err(HdtError::invalid(Part::ControlInfo, message).into());
         }
         digest.update(&hdt_cookie);
 
@@ -73,9 +80,9 @@ impl ControlInfo {
         reader.read_until(0x00, &mut format)?;
         digest.update(&format);
         if format.pop() != Some(0x00) {
-            return Err(eyre!("invalid separator"));
+            return Err(HdtError::invalid(Part::ControlInfo, "invalid separator").into());
         }
-        let format = String::from_utf8(format).map_err(|e| Error::new(InvalidData, e))?;
+        let format = String::from_utf8(format).map_err(|e| HdtError::invalid(Part::ControlInfo, e.to_string()))?;
 
         // 4. Read the Properties
         let mut prop_str = Vec::new();
@@ -84,7 +91,8 @@ impl ControlInfo {
         if prop_str.pop() != Some(0x00) {
             return Err(Error::from(UnexpectedEof).into());
         }
-        let prop_str = String::from_utf8(prop_str).map_err(|e| Error::new(InvalidData, e))?;
+        let prop_str =
+            String::from_utf8(prop_str).map_err(|e| HdtError::invalid(Part::ControlInfo, e.to_string()))?;
         let mut properties = HashMap::new();
         for item in prop_str.split(';') {
             if let Some(index) = item.find('=') {
@@ -100,7 +108,7 @@ impl ControlInfo {
 
         // 6. Check the CRC
         if digest.finalize() != crc_code {
-            return Err(eyre!("Invalid CRC16-ANSI checksum"));
+            return Err(HdtError::checksum(Part::ControlInfo, "CRC16-ANSI", "control information").into());
         }
 
         Ok(ControlInfo { control_type, format, properties })
@@ -110,6 +118,29 @@ impl ControlInfo {
     pub fn get(&self, key: &str) -> Option<String> {
         self.properties.get(key).cloned()
     }
+
+    /// Set the property value for the given key.
+    pub fn set(&mut self, key: &str, value: &str) {
+        self.properties.insert(key.to_owned(), value.to_owned());
+    }
+
+    /// Write the control information including its CRC in the format expected by [`Self::read`].
+    /// Properties are sorted by key, so that the output is deterministic.
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let mut bytes = b"$HDT".to_vec();
+        bytes.push(self.control_type as u8);
+        bytes.extend_from_slice(self.format.as_bytes());
+        bytes.push(0x00);
+        let mut properties: Vec<_> = self.properties.iter().collect();
+        properties.sort_unstable();
+        for (key, value) in properties {
+            bytes.extend_from_slice(format!("{key}={value};").as_bytes());
+        }
+        bytes.push(0x00);
+        let crc = crc::Crc::<u16>::new(&crc::CRC_16_ARC);
+        bytes.extend_from_slice(&crc.checksum(&bytes).to_le_bytes());
+        writer.write_all(&bytes)
+    }
 }
 
 #[cfg(test)]
@@ -132,4 +163,21 @@ mod tests {
             panic!("Failed to read control info");
         }
     }
+
+    #[test]
+    fn write_info() {
+        init();
+        let mut info = ControlInfo::new(ControlType::Global, "<http://purl.org/HDT/hdt#HDTv1>");
+        let mut bytes = Vec::new();
+        info.write(&mut bytes).unwrap();
+        assert_eq!(&b"$HDT\x01<http://purl.org/HDT/hdt#HDTv1>\x00\x00\x76\x35"[..], bytes);
+
+        info.set("order", "1");
+        info.set("length", "20");
+        bytes.clear();
+        info.write(&mut bytes).unwrap();
+        assert!(bytes.windows(17).any(|w| w == b"length=20;order=1"));
+        let read = ControlInfo::read(&mut bytes.as_slice()).unwrap();
+        assert_eq!((Some("1".to_owned()), Some("20".to_owned())), (read.get("order"), read.get("length")));
+    }
 }
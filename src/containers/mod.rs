@@ -7,12 +7,38 @@ pub mod vbyte;
 // byte containers
 mod adj_list;
 mod bitmap;
+mod byte_store;
 mod sequence;
 
-// control info section reader
+// control info section reader and writer
 mod control_info;
 
 pub use adj_list::AdjList;
-pub use bitmap::Bitmap;
+pub use bitmap::{Bitmap, BitmapType};
+#[cfg(feature = "mmap")]
+pub(crate) use byte_store::take_mapped;
+pub use byte_store::ByteStore;
+#[cfg(feature = "mmap")]
+pub use byte_store::MappedBytes;
+#[cfg(feature = "zstd")]
+pub use byte_store::ZstdBytes;
+pub(crate) use byte_store::{CheckedBytes, SharedBytes};
 pub use control_info::{ControlInfo, ControlType};
-pub use sequence::Sequence;
+#[cfg(feature = "mmap")]
+pub(crate) use sequence::read_sequence_mapped;
+#[cfg(feature = "mmap")]
+pub use sequence::MappedSequence;
+pub use sequence::{read_sequence, write_sequence, IntSequence, LogSequence, Sequence, Sequence32, Sequence64};
+pub(crate) use sequence::{write_sequence_min_bits, SequenceLayout};
+
+/// Read exactly `len` bytes, growing the buffer while reading instead of allocating it upfront.
+/// A corrupted length thus results in an error instead of an excessive allocation.
+pub(crate) fn read_bytes<R: std::io::BufRead>(reader: &mut R, len: usize) -> std::io::Result<Vec<u8>> {
+    use std::io::Read;
+    let mut bytes = Vec::new();
+    reader.take(len as u64).read_to_end(&mut bytes)?;
+    if bytes.len() != len {
+        return Err(std::io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(bytes)
+}
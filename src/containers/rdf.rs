@@ -1,3 +1,4 @@
+use std::borrow::Cow;
 use std::fmt;
 
 /// Represents an RDF triple.
@@ -137,4 +138,122 @@ impl Literal {
 
         Literal { form, datatype: Some(datatype), lang: Some(lang) }
     }
+
+    /// The lexical form without quotes, datatype and language tag.
+    pub fn form(&self) -> &str {
+        &self.form
+    }
+}
+
+/// Convert a term in the string format of the HDT dictionary into an N-Triples term.
+/// IRIs are enclosed in angle brackets and special characters in the lexical form of literals are escaped.
+/// # Examples
+/// ```
+/// use hdt::containers::rdf::nt_term;
+/// assert_eq!("<http://example.org/a>", nt_term("http://example.org/a"));
+/// assert_eq!("_:b1", nt_term("_:b1"));
+/// assert_eq!("\"say \\\"hi\\\"\"@en", nt_term("\"say \"hi\"\"@en"));
+/// ```
+pub fn nt_term(s: &str) -> String {
+    if s.starts_with("_:") {
+        return s.to_owned();
+    }
+    if let Some(rest) = s.strip_prefix('"') {
+        if let Some(end) = rest.rfind('"') {
+            let mut nt = String::with_capacity(s.len() + 2);
+            nt.push('"');
+            for c in rest[..end].chars() {
+                match c {
+                    '"' => nt.push_str("\\\""),
+                    '\\' => nt.push_str("\\\\"),
+                    '\n' => nt.push_str("\\n"),
+                    '\r' => nt.push_str("\\r"),
+                    _ => nt.push(c),
+                }
+            }
+            // closing quotation mark with optional language tag or datatype
+            nt.push_str(&rest[end..]);
+            return nt;
+        }
+    }
+    format!("<{s}>")
+}
+
+/// How literals with the datatype `xsd:string` are written, which RDF 1.1 allows both with and without the datatype.
+/// HDT dictionaries usually store such literals without it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum XsdString {
+    /// Omit the datatype, as in `"abc"`.
+    #[default]
+    Implicit,
+    /// Write the datatype, as in `"abc"^^<http://www.w3.org/2001/XMLSchema#string>`, which some strict validators require.
+    Explicit,
+}
+
+impl XsdString {
+    const SUFFIX: &'static str = "^^<http://www.w3.org/2001/XMLSchema#string>";
+
+    /// Convert a term in the string format of the HDT dictionary to this form, other terms are returned unchanged.
+    /// # Examples
+    /// ```
+    /// use hdt::containers::rdf::XsdString;
+    /// let explicit = "\"abc\"^^<http://www.w3.org/2001/XMLSchema#string>";
+    /// assert_eq!(explicit, XsdString::Explicit.apply("\"abc\""));
+    /// assert_eq!("\"abc\"", XsdString::Implicit.apply(explicit));
+    /// assert_eq!("\"abc\"@en", XsdString::Explicit.apply("\"abc\"@en"));
+    /// ```
+    pub fn apply(self, s: &str) -> Cow<'_, str> {
+        match self {
+            XsdString::Implicit => match s.strip_suffix(Self::SUFFIX) {
+                Some(plain) if plain.len() > 1 && plain.starts_with('"') && plain.ends_with('"') => {
+                    Cow::Borrowed(plain)
+                }
+                _ => Cow::Borrowed(s),
+            },
+            XsdString::Explicit if s.len() > 1 && s.starts_with('"') && s.ends_with('"') => {
+                Cow::Owned(format!("{s}{}", Self::SUFFIX))
+            }
+            XsdString::Explicit => Cow::Borrowed(s),
+        }
+    }
+}
+
+/// Like [`nt_term`] but with `xsd:string` literals in the given form.
+/// # Examples
+/// ```
+/// use hdt::containers::rdf::{nt_term_as, XsdString};
+/// assert_eq!("\"abc\"^^<http://www.w3.org/2001/XMLSchema#string>", nt_term_as("\"abc\"", XsdString::Explicit));
+/// ```
+pub fn nt_term_as(s: &str, xsd_string: XsdString) -> String {
+    nt_term(&xsd_string.apply(s))
+}
+
+/// Convert a language tag to the canonical BCP 47 case, for example `en-us` to `en-US` and `zh-hant-tw` to `zh-Hant-TW`.
+/// Following RFC 5646 section 2.1.1, the language is lowercase, four letter script subtags are titlecase and two letter region subtags are uppercase.
+/// Everything from the first singleton, such as private use subtags after `x`, is lowercase.
+/// # Examples
+/// ```
+/// use hdt::containers::rdf::canonical_language_tag;
+/// assert_eq!("en-US", canonical_language_tag("EN-us"));
+/// assert_eq!("sr-Latn-RS", canonical_language_tag("sr-latn-rs"));
+/// assert_eq!("de-CH-x-phonebk", canonical_language_tag("de-ch-x-PHONEBK"));
+/// ```
+pub fn canonical_language_tag(tag: &str) -> String {
+    let mut canonical = String::with_capacity(tag.len());
+    let mut singleton = false;
+    for (i, subtag) in tag.split('-').enumerate() {
+        if i > 0 {
+            canonical.push('-');
+        }
+        singleton |= subtag.len() == 1;
+        match subtag.len() {
+            2 if i > 0 && !singleton => canonical.push_str(&subtag.to_ascii_uppercase()),
+            4 if i > 0 && !singleton && subtag.is_ascii() => {
+                canonical.push_str(&subtag[..1].to_ascii_uppercase());
+                canonical.push_str(&subtag[1..].to_ascii_lowercase());
+            }
+            _ => canonical.push_str(&subtag.to_ascii_lowercase()),
+        }
+    }
+    canonical
 }
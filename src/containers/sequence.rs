@@ -1,16 +1,280 @@
-use crate::containers::vbyte::read_vbyte;
+use crate::containers::read_bytes;
+use crate::containers::vbyte::{encode_vbyte, read_vbyte};
+#[cfg(feature = "mmap")]
+use crate::containers::{take_mapped, MappedBytes};
+use crate::error::{HdtError, Part};
 use bytesize::ByteSize;
-use eyre::{eyre, Result};
+use eyre::Result;
+#[cfg(feature = "mmap")]
+use memmap2::Mmap;
 use std::fmt;
-use std::io::BufRead;
+use std::io::{self, BufRead, Seek, Write};
 use std::mem::size_of;
+use std::ops::Range;
+#[cfg(feature = "mmap")]
+use std::sync::Arc;
 use std::thread;
 
 const USIZE_BITS: usize = usize::BITS as usize;
 
-/// Integer sequence with a given number of bits, which means numbers may be represented along byte boundaries.
+// sequence type bytes as in SequenceFactory of hdt-java and HDTVocabulary of hdt-cpp
+/// Sequence type byte of [`LogSequence`].
+const TYPE_LOG: u8 = 1;
+/// Sequence type byte of [`Sequence32`].
+const TYPE_32: u8 = 2;
+/// Sequence type byte of [`Sequence64`].
+const TYPE_64: u8 = 3;
+/// Offset of the number of entries of a log array, which follows the type byte and the number of bits per entry.
+const LOG_ENTRIES_OFFSET: usize = 2;
+/// Offset of the number of entries of a fixed width sequence, which follows the type byte.
+const FIXED_ENTRIES_OFFSET: usize = 1;
+
+/// Integer sequence as stored in HDT sections.
+pub trait IntSequence: fmt::Debug + Send + Sync {
+    /// Get the integer at the given index, counting from 0.
+    fn get(&self, index: usize) -> usize;
+
+    /// Number of integers in the sequence.
+    fn len(&self) -> usize;
+
+    /// Whether the sequence contains no integers.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of bits that each integer uses.
+    fn bits_per_entry(&self) -> usize;
+
+    /// Size in bytes on the heap.
+    fn size_in_bytes(&self) -> usize;
+
+    /// Handle of a running CRC check, which yields whether it was successful.
+    /// Returns `None` if the check already happened while reading or if the handle has already been taken.
+    fn take_crc_handle(&mut self) -> Option<thread::JoinHandle<bool>> {
+        None
+    }
+}
+
+/// Integer sequence of any of the implementations, chosen by the sequence type byte when reading.
+/// Dispatches statically to the implementation, so that accessing entries costs no virtual call.
+#[derive(Debug)]
+pub enum Sequence {
+    /// Log array, the default of hdt-cpp and hdt-java.
+    Log(LogSequence),
+    /// 32 bit integers.
+    Fixed32(Sequence32),
+    /// 64 bit integers.
+    Fixed64(Sequence64),
+    /// Log array that stays in a memory map.
+    #[cfg(feature = "mmap")]
+    Mapped(MappedSequence),
+}
+
+impl Sequence {
+    /// Log array of the given values with the minimal number of bits per entry.
+    pub fn new(values: &[usize]) -> Self {
+        Sequence::Log(LogSequence::new(values))
+    }
+
+    /// Get the integer at the given index, counting from 0.
+    pub fn get(&self, index: usize) -> usize {
+        match self {
+            Sequence::Log(s) => s.get(index),
+            Sequence::Fixed32(s) => s.data[index] as usize,
+            Sequence::Fixed64(s) => s.data[index] as usize,
+            #[cfg(feature = "mmap")]
+            Sequence::Mapped(s) => s.get(index),
+        }
+    }
+
+    /// Number of integers in the sequence.
+    pub const fn len(&self) -> usize {
+        match self {
+            Sequence::Log(s) => s.entries,
+            Sequence::Fixed32(s) => s.data.len(),
+            Sequence::Fixed64(s) => s.data.len(),
+            #[cfg(feature = "mmap")]
+            Sequence::Mapped(s) => s.entries,
+        }
+    }
+
+    /// Whether the sequence contains no integers.
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of bits that each integer uses.
+    pub const fn bits_per_entry(&self) -> usize {
+        match self {
+            Sequence::Log(s) => s.bits_per_entry,
+            Sequence::Fixed32(_) => 32,
+            Sequence::Fixed64(_) => 64,
+            #[cfg(feature = "mmap")]
+            Sequence::Mapped(s) => s.bits_per_entry,
+        }
+    }
+
+    /// Size in bytes on the heap.
+    pub fn size_in_bytes(&self) -> usize {
+        match self {
+            Sequence::Log(s) => s.size_in_bytes(),
+            Sequence::Fixed32(s) => s.size_in_bytes(),
+            Sequence::Fixed64(s) => s.size_in_bytes(),
+            #[cfg(feature = "mmap")]
+            Sequence::Mapped(s) => s.size_in_bytes(),
+        }
+    }
+
+    /// Handle of a running CRC check, which yields whether it was successful, see [`IntSequence::take_crc_handle`].
+    pub const fn take_crc_handle(&mut self) -> Option<thread::JoinHandle<bool>> {
+        match self {
+            Sequence::Log(s) => s.crc_handle.take(),
+            _ => None,
+        }
+    }
+}
+
+impl IntSequence for Sequence {
+    fn get(&self, index: usize) -> usize {
+        self.get(index)
+    }
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn bits_per_entry(&self) -> usize {
+        self.bits_per_entry()
+    }
+
+    fn size_in_bytes(&self) -> usize {
+        self.size_in_bytes()
+    }
+
+    fn take_crc_handle(&mut self) -> Option<thread::JoinHandle<bool>> {
+        self.take_crc_handle()
+    }
+}
+
+/// Read a sequence including metadata from HDT data, choosing the implementation from the sequence type byte.
+pub fn read_sequence<R: BufRead>(reader: &mut R) -> Result<Sequence> {
+    let sequence_type = reader.fill_buf()?.first().copied();
+    Ok(match sequence_type {
+        Some(TYPE_LOG) => Sequence::Log(LogSequence::read(reader)?),
+        Some(TYPE_32) => Sequence::Fixed32(Sequence32::read(reader)?),
+        Some(TYPE_64) => Sequence::Fixed64(Sequence64::read(reader)?),
+        Some(t) => {
+            return Err(HdtError::UnsupportedFormat {
+                part: Part::Sequence,
+                format: format!("type {t}"),
+                supported: "only log arrays (type 1) and 32 or 64 bit sequences (types 2 and 3) are supported",
+            }
+            .into())
+        }
+        None => return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into()),
+    })
+}
+
+/// Like [`read_sequence`] but log arrays stay in the memory map, see [`MappedSequence`].
+#[cfg(feature = "mmap")]
+pub(crate) fn read_sequence_mapped(reader: &mut &[u8], map: &Arc<Mmap>) -> Result<Sequence> {
+    if reader.first() == Some(&TYPE_LOG) {
+        Ok(Sequence::Mapped(MappedSequence::read(reader, map)?))
+    } else {
+        read_sequence(reader)
+    }
+}
+
+/// Number of bits needed for the largest of the given values.
+/// At least one bit, so that reading does not have to special case zero width entries.
+fn bits_for(values: impl Iterator<Item = usize>) -> usize {
+    values.max().map_or(1, |max| (USIZE_BITS - max.leading_zeros() as usize).max(1))
+}
+
+/// Write the given values as a log array with the minimal number of bits per entry, in the format expected by [`LogSequence::read`].
+/// The values are iterated twice, first to find the maximum and then to pack them.
+pub fn write_sequence<W: Write, I: Iterator<Item = usize> + Clone>(writer: &mut W, values: I) -> io::Result<()> {
+    write_sequence_min_bits(writer, values, 1)
+}
+
+/// Like [`write_sequence`] but with at least the given number of bits per entry,
+/// such as the width of a sequence that was read from a file, so that it is written as it was read.
+pub(crate) fn write_sequence_min_bits<W: Write, I: Iterator<Item = usize> + Clone>(
+    writer: &mut W, values: I, min_bits: usize,
+) -> io::Result<()> {
+    let bits_per_entry = bits_for(values.clone()).max(min_bits.min(USIZE_BITS));
+    let mut entries = 0;
+    let mut data = Vec::new();
+    // holds less than 8 bits between entries, so that an entry of up to 64 bits always fits
+    let (mut buffer, mut buffered): (u128, usize) = (0, 0);
+    for value in values {
+        buffer |= (value as u128) << buffered;
+        buffered += bits_per_entry;
+        while buffered >= 8 {
+            data.push(buffer as u8);
+            buffer >>= 8;
+            buffered -= 8;
+        }
+        entries += 1;
+    }
+    if buffered > 0 {
+        data.push(buffer as u8);
+    }
+    let mut preamble = vec![TYPE_LOG, bits_per_entry as u8];
+    preamble.extend(encode_vbyte(entries));
+    preamble.push(crc::Crc::<u8>::new(&crc::CRC_8_SMBUS).checksum(&preamble));
+    writer.write_all(&preamble)?;
+    writer.write_all(&data)?;
+    writer.write_all(&crc::Crc::<u32>::new(&crc::CRC_32_ISCSI).checksum(&data).to_le_bytes())
+}
+
+/// Read and validate the metadata of a log array, returning the number of bits per entry and the number of entries.
+fn read_log_header<R: BufRead>(reader: &mut R) -> Result<(usize, usize)> {
+    // read entry metadata
+    // keep track of history for CRC8
+    let mut history: Vec<u8> = Vec::new();
+
+    // read and validate type
+    let mut buffer = [0_u8];
+    reader.read_exact(&mut buffer)?;
+    history.extend_from_slice(&buffer);
+    if buffer[0] != TYPE_LOG {
+        let message = format!("invalid log array type {} != {TYPE_LOG}", buffer[0]);
+        return Err(HdtError::invalid(Part::Sequence, message).into());
+    }
+
+    // read number of bits per entry
+    let mut buffer = [0_u8];
+    reader.read_exact(&mut buffer)?;
+    history.extend_from_slice(&buffer);
+    let bits_per_entry = buffer[0] as usize;
+    if bits_per_entry > USIZE_BITS {
+        let message = format!("entry size of {bits_per_entry} bit too large (>{USIZE_BITS} bit on this platform)");
+        return Err(HdtError::Invalid { part: Part::Sequence, offset: Some(1), message }.into());
+    }
+
+    // read number of entries
+    let (entries, bytes_read) = read_vbyte(reader)?;
+    history.extend_from_slice(&bytes_read);
+
+    // read entry metadata CRC8
+    let mut crc_code = [0_u8];
+    reader.read_exact(&mut crc_code)?;
+    let crc_code = crc_code[0];
+
+    // validate entry metadata CRC8
+    let crc8 = crc::Crc::<u8>::new(&crc::CRC_8_SMBUS);
+    let mut digest = crc8.digest();
+    digest.update(&history);
+    if digest.finalize() != crc_code {
+        return Err(HdtError::checksum(Part::Sequence, "CRC8-CCIT", "log array preamble").into());
+    }
+    Ok((bits_per_entry, entries))
+}
+
+/// Log array, an integer sequence with a given number of bits, which means numbers may be represented along byte boundaries.
 //#[derive(Clone)]
-pub struct Sequence {
+pub struct LogSequence {
     /// Number of integers in the sequence.
     pub entries: usize,
     /// Number of bits that each integer uses.
@@ -21,7 +285,7 @@ pub struct Sequence {
     pub crc_handle: Option<thread::JoinHandle<bool>>,
 }
 
-impl fmt::Debug for Sequence {
+impl fmt::Debug for LogSequence {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
@@ -33,15 +297,15 @@ impl fmt::Debug for Sequence {
     }
 }
 
-pub struct SequenceIter<'a> {
-    sequence: &'a Sequence,
+pub struct SequenceIter<'a, S: ?Sized = Sequence> {
+    sequence: &'a S,
     i: usize,
 }
 
-impl Iterator for SequenceIter<'_> {
+impl<S: IntSequence + ?Sized> Iterator for SequenceIter<'_, S> {
     type Item = usize;
     fn next(&mut self) -> Option<Self::Item> {
-        if self.i >= self.sequence.entries {
+        if self.i >= self.sequence.len() {
             return None;
         }
         let e = self.sequence.get(self.i);
@@ -59,7 +323,66 @@ impl<'a> IntoIterator for &'a Sequence {
     }
 }
 
-impl Sequence {
+impl<'a> IntoIterator for &'a LogSequence {
+    type Item = usize;
+    type IntoIter = SequenceIter<'a, LogSequence>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        SequenceIter { sequence: self, i: 0 }
+    }
+}
+
+impl IntSequence for LogSequence {
+    fn get(&self, index: usize) -> usize {
+        self.get(index)
+    }
+
+    fn len(&self) -> usize {
+        self.entries
+    }
+
+    fn bits_per_entry(&self) -> usize {
+        self.bits_per_entry
+    }
+
+    fn size_in_bytes(&self) -> usize {
+        self.size_in_bytes()
+    }
+
+    fn take_crc_handle(&mut self) -> Option<thread::JoinHandle<bool>> {
+        self.crc_handle.take()
+    }
+}
+
+impl LogSequence {
+    /// Pack the given values with the minimal number of bits per entry.
+    pub fn new(values: &[usize]) -> Self {
+        let mut sequence = Self::with_capacity(bits_for(values.iter().copied()), values.len());
+        for &value in values {
+            sequence.push(value);
+        }
+        sequence
+    }
+
+    /// Empty sequence with the given number of bits per entry and room for the given number of entries.
+    pub fn with_capacity(bits_per_entry: usize, capacity: usize) -> Self {
+        let mut data = Vec::with_capacity((capacity * bits_per_entry).div_ceil(USIZE_BITS).max(1));
+        data.push(0);
+        LogSequence { entries: 0, bits_per_entry, data, crc_handle: None }
+    }
+
+    /// Append the given value, which needs to fit into the number of bits per entry.
+    pub fn push(&mut self, value: usize) {
+        let bits = self.entries * self.bits_per_entry;
+        let (block_index, bit_index) = (bits / USIZE_BITS, bits % USIZE_BITS);
+        self.data.resize((bits + self.bits_per_entry).div_ceil(USIZE_BITS).max(1), 0);
+        self.data[block_index] |= value << bit_index;
+        if bit_index + self.bits_per_entry > USIZE_BITS {
+            self.data[block_index + 1] |= value >> (USIZE_BITS - bit_index);
+        }
+        self.entries += 1;
+    }
+
     /// Get the integer at the given index, counting from 0.
     pub fn get(&self, index: usize) -> usize {
         let scaled_index = index * self.bits_per_entry;
@@ -87,61 +410,21 @@ impl Sequence {
 
     /// Read sequence including metadata from HDT data.
     pub fn read<R: BufRead>(reader: &mut R) -> Result<Self> {
-        // read entry metadata
-        // keep track of history for CRC8
-        let mut history: Vec<u8> = Vec::new();
-
-        // read and validate type
-        let mut buffer = [0_u8];
-        reader.read_exact(&mut buffer)?;
-        history.extend_from_slice(&buffer);
-        if buffer[0] != 1 {
-            return Err(eyre!("Invalid LogArray type {} != 1", buffer[0]));
-        }
-
-        // read number of bits per entry
-        let mut buffer = [0_u8];
-        reader.read_exact(&mut buffer)?;
-        history.extend_from_slice(&buffer);
-        let bits_per_entry = buffer[0] as usize;
-        if bits_per_entry > USIZE_BITS {
-            return Err(eyre!("entry size of {bits_per_entry} bit too large (>64 bit)"));
-        }
-
-        // read number of entries
-        let (entries, bytes_read) = read_vbyte(reader)?;
-        history.extend_from_slice(&bytes_read);
-
-        // read entry metadata CRC8
-        let mut crc_code = [0_u8];
-        reader.read_exact(&mut crc_code)?;
-        let crc_code = crc_code[0];
-
-        // validate entry metadata CRC8
-        let crc8 = crc::Crc::<u8>::new(&crc::CRC_8_SMBUS);
-        let mut digest = crc8.digest();
-        digest.update(&history);
-        if digest.finalize() != crc_code {
-            return Err(eyre!("Invalid CRC8-CCIT checksum"));
-        }
+        let (bits_per_entry, entries) = read_log_header(reader)?;
 
         // read body data
         // read all but the last entry, since the last one is byte aligned
-        let total_bits = bits_per_entry * entries;
+        let total_bits = total_bits(bits_per_entry, entries, LOG_ENTRIES_OFFSET)?;
         let full_byte_amount = (total_bits.div_ceil(USIZE_BITS).saturating_sub(1)) * size_of::<usize>();
-        let mut full_words = vec![0_u8; full_byte_amount];
-        reader.read_exact(&mut full_words)?;
-        let mut data: Vec<usize> = Vec::with_capacity(full_byte_amount / 8 + 2);
+        let full_words = read_bytes(reader, full_byte_amount)?;
+        let mut data: Vec<usize> = Vec::with_capacity(full_byte_amount / size_of::<usize>() + 2);
         // read entry body
 
         // turn the raw bytes into usize values
-        for word in full_words.chunks_exact(size_of::<usize>()) {
-            if let Ok(word_data) = <[u8; 8]>::try_from(word) {
-                data.push(usize::from_le_bytes(word_data));
-            } else {
-                return Err(eyre!("failed to read usize"));
-            }
-        }
+        // chunks_exact only yields whole words
+        data.extend(
+            full_words.chunks_exact(size_of::<usize>()).map(|w| usize::from_le_bytes(w.try_into().unwrap())),
+        );
 
         // keep track of history for CRC32
         let mut history = full_words;
@@ -156,7 +439,7 @@ impl Sequence {
             reader.read_exact(&mut buffer)?;
             history.extend_from_slice(&buffer);
             last_value |= (buffer[0] as usize) << bits_read;
-            bits_read += size_of::<usize>();
+            bits_read += 8;
         }
         data.push(last_value);
         // read entry body CRC32
@@ -172,6 +455,377 @@ impl Sequence {
             digest.finalize() == crc_code
         }));
 
-        Ok(Sequence { entries, bits_per_entry, data, crc_handle })
+        Ok(LogSequence { entries, bits_per_entry, data, crc_handle })
+    }
+
+    /// Parse a sequence from the beginning of the given bytes, for example for fuzzing.
+    /// Unlike [`Self::read`], the CRC32 checksum is validated before returning and malformed input never causes a panic, neither while parsing nor when accessing the result.
+    pub fn parse(mut data: &[u8]) -> Result<Self> {
+        let mut sequence = Self::read(&mut data)?;
+        if sequence.bits_per_entry == 0 && sequence.entries > 0 {
+            let message = format!("sequence with {} entries of 0 bits", sequence.entries);
+            return Err(HdtError::invalid(Part::Sequence, message).into());
+        }
+        if let Some(handle) = sequence.crc_handle.take() {
+            if !handle.join().unwrap_or(false) {
+                return Err(HdtError::checksum(Part::Sequence, "CRC32C", "log array data").into());
+            }
+        }
+        Ok(sequence)
+    }
+}
+
+/// Sequence of 32 bit integers, which are stored without compression.
+#[derive(Clone)]
+pub struct Sequence32 {
+    /// The integers of the sequence.
+    pub data: Vec<u32>,
+}
+
+/// Sequence of 64 bit integers, which are stored without compression.
+#[derive(Clone)]
+pub struct Sequence64 {
+    /// The integers of the sequence.
+    pub data: Vec<u64>,
+}
+
+impl fmt::Debug for Sequence32 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} with {} entries, 32 bits per entry", ByteSize(self.size_in_bytes() as u64), self.data.len())
+    }
+}
+
+impl fmt::Debug for Sequence64 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} with {} entries, 64 bits per entry", ByteSize(self.size_in_bytes() as u64), self.data.len())
+    }
+}
+
+impl IntSequence for Sequence32 {
+    fn get(&self, index: usize) -> usize {
+        self.data[index] as usize
+    }
+
+    fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    fn bits_per_entry(&self) -> usize {
+        32
+    }
+
+    fn size_in_bytes(&self) -> usize {
+        self.data.len() * size_of::<u32>()
+    }
+}
+
+impl IntSequence for Sequence64 {
+    fn get(&self, index: usize) -> usize {
+        self.data[index] as usize
+    }
+
+    fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    fn bits_per_entry(&self) -> usize {
+        64
+    }
+
+    fn size_in_bytes(&self) -> usize {
+        self.data.len() * size_of::<u64>()
+    }
+}
+
+impl Sequence32 {
+    /// Read sequence including metadata from HDT data and verify checksums.
+    pub fn read<R: BufRead>(reader: &mut R) -> Result<Self> {
+        let bytes = read_fixed(reader, TYPE_32, size_of::<u32>())?;
+        let data =
+            bytes.chunks_exact(size_of::<u32>()).map(|c| u32::from_le_bytes(c.try_into().unwrap())).collect();
+        Ok(Sequence32 { data })
+    }
+}
+
+impl Sequence64 {
+    /// Read sequence including metadata from HDT data and verify checksums.
+    pub fn read<R: BufRead>(reader: &mut R) -> Result<Self> {
+        let bytes = read_fixed(reader, TYPE_64, size_of::<u64>())?;
+        let data: Vec<u64> =
+            bytes.chunks_exact(size_of::<u64>()).map(|c| u64::from_le_bytes(c.try_into().unwrap())).collect();
+        // entries are returned as usize, which only has 32 bit on some platforms
+        if let Some(i) = data.iter().position(|&v| usize::try_from(v).is_err()) {
+            // type byte, number of entries and CRC8 precede the data
+            let offset = Some(FIXED_ENTRIES_OFFSET + encode_vbyte(data.len()).len() + 1 + i * size_of::<u64>());
+            let message =
+                format!("entry {} does not fit into {USIZE_BITS} bit, a 64 bit platform is required", data[i]);
+            return Err(HdtError::Invalid { part: Part::Sequence, offset, message }.into());
+        }
+        Ok(Sequence64 { data })
+    }
+}
+
+/// Read the little endian data of a fixed width sequence with the given type and entry size in bytes.
+/// Layout: type, number of entries as vbyte, CRC8 of the preceding bytes, data, CRC32C of the data.
+fn read_fixed<R: BufRead>(reader: &mut R, sequence_type: u8, entry_bytes: usize) -> Result<Vec<u8>> {
+    let entries = read_fixed_header(reader, sequence_type)?;
+    let len = total_bits(entry_bytes * 8, entries, FIXED_ENTRIES_OFFSET)? / 8;
+    let data = read_bytes(reader, len)?;
+    let mut crc_code = [0_u8; 4];
+    reader.read_exact(&mut crc_code)?;
+    let crc32 = crc::Crc::<u32>::new(&crc::CRC_32_ISCSI);
+    let mut digest = crc32.digest();
+    digest.update(&data);
+    if digest.finalize() != u32::from_le_bytes(crc_code) {
+        return Err(HdtError::checksum(Part::Sequence, "CRC32C", "sequence data").into());
+    }
+    Ok(data)
+}
+
+/// Read and validate the metadata of a fixed width sequence with the given type, returning the number of entries.
+fn read_fixed_header<R: BufRead>(reader: &mut R, sequence_type: u8) -> Result<usize> {
+    let mut history: Vec<u8> = Vec::new();
+    let mut buffer = [0_u8];
+    reader.read_exact(&mut buffer)?;
+    history.extend_from_slice(&buffer);
+    if buffer[0] != sequence_type {
+        let message = format!("invalid sequence type {} != {sequence_type}", buffer[0]);
+        return Err(HdtError::invalid(Part::Sequence, message).into());
+    }
+    let (entries, bytes_read) = read_vbyte(reader)?;
+    history.extend_from_slice(&bytes_read);
+
+    let mut crc_code = [0_u8];
+    reader.read_exact(&mut crc_code)?;
+    let crc8 = crc::Crc::<u8>::new(&crc::CRC_8_SMBUS);
+    let mut digest = crc8.digest();
+    digest.update(&history);
+    if digest.finalize() != crc_code[0] {
+        return Err(HdtError::checksum(Part::Sequence, "CRC8-CCIT", "sequence preamble").into());
+    }
+    Ok(entries)
+}
+
+/// Number of bits of the data of a sequence with the given entries, an error if it does not fit into usize.
+/// The number of entries is stored at the given offset within the sequence.
+fn total_bits(bits_per_entry: usize, entries: usize, offset: usize) -> Result<usize, HdtError> {
+    bits_per_entry.checked_mul(entries).ok_or_else(|| HdtError::Invalid {
+        part: Part::Sequence,
+        offset: Some(offset),
+        message: format!("sequence of {entries} entries too large"),
+    })
+}
+
+/// Entry of the given width that starts at the given bit of little endian packed data, missing bytes at the end count as zero.
+fn get_bits(data: &[u8], bit: usize, bits_per_entry: usize) -> usize {
+    let start = bit / 8;
+    // up to 64 bits shifted by up to 7 bits fit into 16 bytes
+    let mut bytes = [0_u8; 16];
+    let available = data.get(start..data.len().min(start + 16)).unwrap_or_default();
+    bytes[..available.len()].copy_from_slice(available);
+    let mask = (1_u128 << bits_per_entry) - 1;
+    ((u128::from_le_bytes(bytes) >> (bit % 8)) & mask) as usize
+}
+
+/// Position and width of the entries of a sequence in a file, for reading a few entries without the rest of the sequence.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SequenceLayout {
+    /// Position of the first byte of the entries in the file.
+    pub offset: u64,
+    /// Number of integers in the sequence.
+    pub entries: usize,
+    /// Number of bits that each integer uses.
+    pub bits_per_entry: usize,
+    /// CRC32C checksum of the data as stored after it, which is not checked.
+    /// Only used for identifying mapped files, see [`Hdt::map_with_index`](crate::Hdt::map_with_index).
+    #[cfg_attr(not(feature = "mmap"), allow(dead_code))]
+    pub crc32: u32,
+}
+
+impl SequenceLayout {
+    /// Read the metadata of a sequence of any type and its stored checksum and seek over its data.
+    pub fn skip<R: BufRead + Seek>(reader: &mut R) -> Result<Self> {
+        let (bits_per_entry, entries, entries_offset) = match reader.fill_buf()?.first().copied() {
+            Some(TYPE_LOG) => {
+                let (bits_per_entry, entries) = read_log_header(reader)?;
+                (bits_per_entry, entries, LOG_ENTRIES_OFFSET)
+            }
+            Some(TYPE_32) => (32, read_fixed_header(reader, TYPE_32)?, FIXED_ENTRIES_OFFSET),
+            Some(TYPE_64) => (64, read_fixed_header(reader, TYPE_64)?, FIXED_ENTRIES_OFFSET),
+            Some(t) => {
+                return Err(HdtError::UnsupportedFormat {
+                    part: Part::Sequence,
+                    format: format!("type {t}"),
+                    supported: "only log arrays (type 1) and 32 or 64 bit sequences (types 2 and 3) are supported",
+                }
+                .into())
+            }
+            None => return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into()),
+        };
+        let total_bits = total_bits(bits_per_entry, entries, entries_offset)?;
+        let offset = reader.stream_position()?;
+        reader.seek_relative(i64::try_from(total_bits.div_ceil(8))?)?;
+        let mut crc_code = [0_u8; 4];
+        reader.read_exact(&mut crc_code)?;
+        Ok(SequenceLayout { offset, entries, bits_per_entry, crc32: u32::from_le_bytes(crc_code) })
+    }
+
+    /// Byte range in the file that contains the entries in the given range of indexes.
+    pub fn byte_range(&self, entries: Range<usize>) -> Range<u64> {
+        let start = (entries.start * self.bits_per_entry / 8) as u64;
+        let end = (entries.end * self.bits_per_entry).div_ceil(8) as u64;
+        self.offset + start..self.offset + end.max(start)
+    }
+
+    /// Entry at the given index from the bytes of [`Self::byte_range`] for a range starting at `first`.
+    pub fn get(&self, bytes: &[u8], first: usize, index: usize) -> usize {
+        let bit = index * self.bits_per_entry - first * self.bits_per_entry / 8 * 8;
+        get_bits(bytes, bit, self.bits_per_entry)
+    }
+}
+
+/// Log array whose data stays in a memory map, see [`Hdt::map`](crate::Hdt::map).
+/// Entries are read unaligned from the mapped bytes, which is slightly slower than [`LogSequence`] but needs no heap memory.
+#[cfg(feature = "mmap")]
+pub struct MappedSequence {
+    entries: usize,
+    bits_per_entry: usize,
+    data: MappedBytes,
+    crc32: u32,
+}
+
+#[cfg(feature = "mmap")]
+impl fmt::Debug for MappedSequence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} mapped with {} entries, {} bits per entry",
+            ByteSize(self.data.len() as u64),
+            self.entries,
+            self.bits_per_entry
+        )
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl IntSequence for MappedSequence {
+    fn get(&self, index: usize) -> usize {
+        get_bits(&self.data, index * self.bits_per_entry, self.bits_per_entry)
+    }
+
+    fn len(&self) -> usize {
+        self.entries
+    }
+
+    fn bits_per_entry(&self) -> usize {
+        self.bits_per_entry
+    }
+
+    fn size_in_bytes(&self) -> usize {
+        0
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl MappedSequence {
+    /// Read a log array from the given reader over the memory map without copying its data.
+    /// The CRC32 checksum of the data is not validated, because that would read the whole sequence.
+    pub(crate) fn read(reader: &mut &[u8], map: &Arc<Mmap>) -> Result<Self> {
+        let (bits_per_entry, entries) = read_log_header(reader)?;
+        let total_bits = total_bits(bits_per_entry, entries, LOG_ENTRIES_OFFSET)?;
+        let data = take_mapped(reader, map, total_bits.div_ceil(8))?;
+        let crc32 = u32::from_le_bytes(take_mapped(reader, map, 4)?[..].try_into()?);
+        Ok(MappedSequence { entries, bits_per_entry, data, crc32 })
+    }
+
+    /// Whether the mapped data matches the CRC32C checksum that follows it in the file, which reads the whole sequence.
+    pub fn verify_crc(&self) -> bool {
+        crc::Crc::<u32>::new(&crc::CRC_32_ISCSI).checksum(&self.data) == self.crc32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::containers::vbyte::encode_vbyte;
+    use crate::tests::init;
+    use pretty_assertions::assert_eq;
+
+    /// Fixed width sequence in the layout of SequenceInt32 and SequenceInt64 of hdt-java.
+    /// No file with such sequences written by hdt-java or hdt-cpp is available to test against.
+    fn fixed(sequence_type: u8, bytes: &[u8], entries: usize) -> Vec<u8> {
+        let mut data = vec![sequence_type];
+        data.extend(encode_vbyte(entries));
+        data.push(crc::Crc::<u8>::new(&crc::CRC_8_SMBUS).checksum(&data));
+        data.extend_from_slice(bytes);
+        data.extend(crc::Crc::<u32>::new(&crc::CRC_32_ISCSI).checksum(bytes).to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn read_fixed_width() {
+        init();
+        let values: [u32; 3] = [7, 0, u32::MAX];
+        let bytes: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+        let sequence = read_sequence(&mut fixed(TYPE_32, &bytes, 3).as_slice()).unwrap();
+        assert_eq!(32, sequence.bits_per_entry());
+        assert!(matches!(sequence, Sequence::Fixed32(_)));
+        assert_eq!(vec![7, 0, u32::MAX as usize], sequence.into_iter().collect::<Vec<_>>());
+
+        let values: [u64; 2] = [1 << 40, 5];
+        let bytes: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+        let sequence = read_sequence(&mut fixed(TYPE_64, &bytes, 2).as_slice()).unwrap();
+        assert_eq!(64, sequence.bits_per_entry());
+        assert!(matches!(sequence, Sequence::Fixed64(_)));
+        assert_eq!(vec![1 << 40, 5], sequence.into_iter().collect::<Vec<_>>());
+
+        let mut corrupted = fixed(TYPE_64, &bytes, 2);
+        corrupted[5] ^= 1;
+        assert!(read_sequence(&mut corrupted.as_slice()).is_err());
+        assert!(read_sequence(&mut fixed(TYPE_64, &bytes[..8], 2).as_slice()).is_err());
+        assert!(read_sequence(&mut fixed(4, &bytes, 2).as_slice()).is_err());
+        // the position of an invalid number of entries is reported
+        let e = read_sequence(&mut fixed(TYPE_64, &[], usize::MAX / 8).as_slice()).unwrap_err();
+        assert!(matches!(
+            HdtError::find(e.as_ref()),
+            Some(HdtError::Invalid { part: Part::Sequence, offset: Some(FIXED_ENTRIES_OFFSET), .. })
+        ));
+    }
+
+    #[test]
+    fn write_log_array() {
+        init();
+        for values in
+            [vec![], vec![0, 0, 0], vec![5, 3, 0, 7, 1], (0..1000).collect(), vec![1 << 40, 3, usize::MAX]]
+        {
+            let mut bytes = Vec::new();
+            write_sequence(&mut bytes, values.iter().copied()).unwrap();
+            let sequence = LogSequence::parse(&bytes).unwrap();
+            let max = values.iter().max().copied().unwrap_or(0);
+            assert_eq!((USIZE_BITS - max.leading_zeros() as usize).max(1), sequence.bits_per_entry);
+            assert_eq!(values, sequence.into_iter().collect::<Vec<_>>());
+            let packed = LogSequence::new(&values);
+            assert_eq!(sequence.bits_per_entry, packed.bits_per_entry);
+            assert_eq!(values, packed.into_iter().collect::<Vec<_>>());
+        }
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn mapped_sequence() {
+        init();
+        for values in [vec![0, 0, 0], vec![5, 3, 0, 7, 1], (0..1000).collect(), vec![1 << 40, 3, usize::MAX]] {
+            // unaligned start
+            let mut bytes = vec![9, 9, 9];
+            write_sequence(&mut bytes, values.iter().copied()).unwrap();
+            let mut map = memmap2::MmapMut::map_anon(bytes.len()).unwrap();
+            map.copy_from_slice(&bytes);
+            let map = Arc::new(map.make_read_only().unwrap());
+            let mut reader = &map[3..];
+            let sequence = MappedSequence::read(&mut reader, &map).unwrap();
+            assert!(reader.is_empty());
+            assert_eq!(values, (0..sequence.len()).map(|i| sequence.get(i)).collect::<Vec<_>>());
+            assert_eq!(0, sequence.size_in_bytes());
+        }
     }
 }
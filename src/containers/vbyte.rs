@@ -57,6 +57,18 @@ pub const fn decode_vbyte_delta(data: &[u8], offset: usize) -> (usize, usize) {
     (n, byte_amount)
 }
 
+/// decode vbyte with offset like [`decode_vbyte_delta`] but return `None` instead of panicking on malformed data
+pub fn try_decode_vbyte_delta(data: &[u8], offset: usize) -> Option<(usize, usize)> {
+    let mut n: usize = 0;
+    for (byte_amount, byte) in data.get(offset..)?.iter().take(MAX_VBYTE_BYTES).enumerate() {
+        n |= usize::try_from(u128::from(byte & 127) << (7 * byte_amount)).ok()?;
+        if byte & 0x80 != 0 {
+            return Some((n, byte_amount + 1));
+        }
+    }
+    None
+}
+
 /// little endian
 pub fn encode_vbyte(n: usize) -> Vec<u8> {
     let mut bytes = Vec::new();
@@ -107,6 +119,17 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_try_decode() {
+        init();
+        let mut buffer = vec![0];
+        buffer.extend(encode_vbyte(824));
+        assert_eq!(Some(decode_vbyte_delta(&buffer, 1)), try_decode_vbyte_delta(&buffer, 1));
+        assert_eq!(None, try_decode_vbyte_delta(&buffer[..2], 1));
+        assert_eq!(None, try_decode_vbyte_delta(&buffer, 3));
+        assert_eq!(None, try_decode_vbyte_delta(&[0; 20], 0));
+    }
+
     #[test]
     #[should_panic(expected = "Tried to read a VByte that does not fit into a usize")]
     fn test_decode_too_large() {
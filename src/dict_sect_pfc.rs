@@ -1,26 +1,43 @@
 /// Dictionary section with plain front coding.
 /// See <https://www.rdfhdt.org/hdt-binary-format/#DictionarySectionPlainFrontCoding>.
-use crate::containers::vbyte::{decode_vbyte_delta, read_vbyte};
-use crate::containers::Sequence;
+use crate::containers::read_bytes;
+#[cfg(feature = "mmap")]
+use crate::containers::take_mapped;
+use crate::containers::vbyte::{encode_vbyte, read_vbyte, try_decode_vbyte_delta};
+use crate::containers::{read_sequence, write_sequence, ByteStore, CheckedBytes, Sequence, SharedBytes};
+use crate::error::{HdtError, Part};
 use crate::triples::Id;
 use bytesize::ByteSize;
-use eyre::{eyre, Result};
+use eyre::Result;
 use log::error;
+#[cfg(feature = "mmap")]
+use memmap2::Mmap;
 use std::cmp::{min, Ordering};
 use std::fmt;
-use std::io::BufRead;
+use std::io::{self, BufRead, Seek, Write};
+use std::ops::Range;
 use std::str;
+#[cfg(feature = "mmap")]
 use std::sync::Arc;
 use std::thread::{spawn, JoinHandle};
 use thiserror::Error;
 
+/// Type byte of Hu-Tucker front coded (HTFC) dictionary sections, which are not supported.
+/// Type bytes follow the CSD types of hdt-cpp, HDT files written by hdt-java only contain plain front coded sections.
+const TYPE_HTFC: u8 = 1;
+/// Type byte of plain front coded dictionary sections.
+const TYPE_PFC: u8 = 2;
+
 /// Dictionary section with plain front coding.
 //#[derive(Clone)]
 pub struct DictSectPFC {
     num_strings: usize,
     block_size: usize,
     sequence: Sequence,
-    packed_data: Arc<[u8]>,
+    packed_data: SharedBytes,
+    /// Expected CRC32C checksum of the packed data as stored in the file.
+    crc32: u32,
+    damaged: bool,
 }
 
 impl fmt::Debug for DictSectPFC {
@@ -38,15 +55,70 @@ impl fmt::Debug for DictSectPFC {
 
 #[derive(Error, Debug)]
 pub enum ExtractError {
-    #[error("index out of bounds: id {id} > dictionary section len {len}")]
+    #[error("index out of bounds: id {id} not in 1..={len} of the dictionary section")]
     IdOutOfBounds { id: Id, len: usize },
     #[error("Read invalid UTF-8 sequence in {data:?}, recovered: '{recovered}'")]
     InvalidUtf8 { source: std::str::Utf8Error, data: Vec<u8>, recovered: String },
+    #[error("id {id} is in a dictionary section that failed the CRC check")]
+    Damaged { id: Id },
+    #[error("id {id} is in a dictionary section whose strings could not be loaded")]
+    Unavailable { id: Id, source: io::Error },
+    #[error("id {id} is in a block of the dictionary section that cannot be decoded")]
+    Malformed { id: Id },
+}
+
+/// Order of the strings in a dictionary section, which is the bytewise order of their UTF-8 encoding.
+/// It does not depend on the locale and equals the order of Unicode code points, but differs from the UTF-16 order of
+/// Java's `String::compareTo` for characters outside the basic multilingual plane.
+/// Strings are compared in the HDT dictionary format, so literals start with a quotation mark and IRIs have no angle brackets.
+/// Use it to sort lookup keys or build external indexes that follow the ID order of a section.
+/// # Example
+/// ```
+/// use std::cmp::Ordering;
+/// // uppercase before lowercase before non-ASCII, regardless of the locale
+/// assert_eq!(Ordering::Less, hdt::compare_terms("Z", "a"));
+/// assert_eq!(Ordering::Less, hdt::compare_terms("z", "ä"));
+/// // code point order: U+FFFD before U+1F600, although UTF-16 has it the other way around
+/// assert_eq!(Ordering::Less, hdt::compare_terms("\u{FFFD}", "\u{1F600}"));
+/// ```
+pub fn compare_terms(a: &str, b: &str) -> Ordering {
+    a.as_bytes().cmp(b.as_bytes())
 }
 
 impl DictSectPFC {
+    /// Front code the given strings, which need to be unique and sorted by [`compare_terms`], in blocks of the given size.
+    /// The first string of each block is stored completely, the others as the length of the prefix shared with their predecessor and the remaining suffix.
+    pub fn new<S: AsRef<str>>(strings: &[S], block_size: usize) -> Self {
+        assert_ne!(0, block_size, "block size 0");
+        let mut packed_data = Vec::new();
+        let mut offsets = Vec::with_capacity(strings.len() / block_size + 2);
+        for (i, string) in strings.iter().enumerate() {
+            let string = string.as_ref().as_bytes();
+            if i % block_size == 0 {
+                offsets.push(packed_data.len());
+                packed_data.extend_from_slice(string);
+            } else {
+                let delta = Self::longest_common_prefix(strings[i - 1].as_ref().as_bytes(), string);
+                packed_data.extend(encode_vbyte(delta));
+                packed_data.extend_from_slice(&string[delta..]);
+            }
+            packed_data.push(0);
+        }
+        // end of the last block
+        offsets.push(packed_data.len());
+        DictSectPFC {
+            num_strings: strings.len(),
+            block_size,
+            sequence: Sequence::new(&offsets),
+            crc32: crc::Crc::<u32>::new(&crc::CRC_32_ISCSI).checksum(&packed_data),
+            packed_data: SharedBytes::from(packed_data),
+            damaged: false,
+        }
+    }
+
+    /// Size in bytes on the heap.
     pub fn size_in_bytes(&self) -> usize {
-        self.sequence.size_in_bytes() + self.packed_data.len()
+        self.sequence.size_in_bytes() + self.packed_data.heap_len()
     }
 
     /*
@@ -64,23 +136,37 @@ impl DictSectPFC {
     }
     */
 
-    fn index_str(&self, index: usize) -> &str {
-        let position: usize = self.sequence.get(index);
-        let length = self.strlen(position);
-        str::from_utf8(&self.packed_data[position..position + length]).unwrap()
+    /// The front coded strings, which are loaded on first access for lazy stores, see [`Self::set_packed_data`].
+    fn data(&self) -> io::Result<&[u8]> {
+        self.packed_data.bytes()
     }
 
+    /// Like [`Self::data`] for lookups that cannot return an error, which log it and find nothing instead.
+    fn data_or_log(&self) -> Option<&[u8]> {
+        self.data().map_err(|e| error!("error loading the strings of a dictionary section: {e}")).ok()
+    }
+
+    /// The first string of the block with the given index, empty if the block starts outside of the packed data.
+    fn first_string<'a>(&self, data: &'a [u8], block: usize) -> &'a [u8] {
+        let position: usize = self.sequence.get(block);
+        data.get(position..position + strlen(data, position)).unwrap_or_default()
+    }
+
+    /// Get the ID of the given string within this section, where 0 means not found.
+    /// Strings in a lazy store that cannot be loaded are not found either, which is logged as an error.
     // translated from Java
     // https://github.com/rdfhdt/hdt-java/blob/master/hdt-java-core/src/main/java/org/rdfhdt/hdt/dictionary/impl/section/PFCDictionarySection.java
-    // 0 means not found
     pub fn string_to_id(&self, element: &str) -> Id {
-        if self.num_strings == 0 {
-            // shared dictionary may be empty
+        if self.num_strings == 0 || self.damaged {
+            // shared dictionary may be empty, damaged data may not be sorted
             return 0;
         }
+        let Some(data) = self.data_or_log() else {
+            return 0;
+        };
         // binary search
         let mut low: usize = 0;
-        let mut high = self.sequence.entries.saturating_sub(2); // should be -1 but only works with -2, investigate
+        let mut high = self.sequence.len().saturating_sub(2); // should be -1 but only works with -2, investigate
         let max = high;
         let mut mid = high;
         while low <= high {
@@ -90,8 +176,8 @@ impl DictSectPFC {
                 mid = max;
                 break;
             } else {
-                let text = self.index_str(mid);
-                element.cmp(text)
+                // bytewise like compare_terms
+                element.as_bytes().cmp(self.first_string(data, mid))
                 //println!("mid: {} text: {} cmp: {:?}", mid, text, cmp);
             };
             match cmp {
@@ -108,7 +194,7 @@ impl DictSectPFC {
         if high < mid {
             mid = high;
         }
-        let idblock = self.locate_in_block(mid, element);
+        let idblock = self.locate_in_block(data, mid, element);
         if idblock == 0 {
             return 0;
         }
@@ -124,8 +210,8 @@ impl DictSectPFC {
         delta
     }
 
-    fn locate_in_block(&self, block: usize, element: &str) -> usize {
-        if block >= self.sequence.entries {
+    fn locate_in_block(&self, data: &[u8], block: usize, element: &str) -> usize {
+        if block >= self.sequence.len() {
             return 0;
         }
         let element = element.as_bytes();
@@ -134,20 +220,23 @@ impl DictSectPFC {
         let mut cshared = 0;
 
         // Read the first string in the block
-        let slen = self.strlen(pos);
-        let mut temp_string: Vec<u8> = self.packed_data[pos..pos + slen].to_vec();
+        let slen = strlen(data, pos);
+        let mut temp_string: Vec<u8> = data.get(pos..pos + slen).unwrap_or_default().to_vec();
         pos += slen + 1;
         id_in_block += 1;
 
-        while (id_in_block < self.block_size) && (pos < self.packed_data.len()) {
+        while (id_in_block < self.block_size) && (pos < data.len()) {
             // Decode prefix
-            let (delta, vbyte_bytes) = decode_vbyte_delta(&self.packed_data, pos);
+            let Some((delta, vbyte_bytes)) = try_decode_vbyte_delta(data, pos) else {
+                id_in_block = 0;
+                break;
+            };
             pos += vbyte_bytes;
 
             //Copy suffix
-            let slen = self.strlen(pos);
+            let slen = strlen(data, pos);
             temp_string.truncate(delta);
-            temp_string.extend_from_slice(&self.packed_data[pos..pos + slen]);
+            temp_string.extend_from_slice(&data[pos..pos + slen]);
             if delta >= cshared {
                 // Current delta value means that this string has a larger long common prefix than the previous one
                 cshared += Self::longest_common_prefix(&temp_string[cshared..], &element[cshared..]);
@@ -165,7 +254,7 @@ impl DictSectPFC {
             id_in_block += 1;
         }
 
-        if pos >= self.packed_data.len() || id_in_block == self.block_size {
+        if pos >= data.len() || id_in_block == self.block_size {
             id_in_block = 0;
         }
         id_in_block
@@ -173,62 +262,234 @@ impl DictSectPFC {
 
     /// extract the string with the given ID from the dictionary
     pub fn extract(&self, id: Id) -> Result<String, ExtractError> {
-        if id as usize > self.num_strings {
+        let mut string = String::new();
+        self.extract_into(id, &mut string)?;
+        Ok(string)
+    }
+
+    /// Like [`Self::extract`] but replaces the content of the given buffer, so that its allocation can be reused.
+    /// The buffer is left empty on error.
+    pub fn extract_into(&self, id: Id, buf: &mut String) -> Result<(), ExtractError> {
+        buf.clear();
+        if id == 0 || id as usize > self.num_strings {
             return Err(ExtractError::IdOutOfBounds { id, len: self.num_strings });
         }
-        let block_index = id.saturating_sub(1) as usize / self.block_size;
-        let string_index = id.saturating_sub(1) as usize % self.block_size;
-        let mut position = self.sequence.get(block_index);
-        let mut slen = self.strlen(position);
-        let mut string: Vec<u8> = self.packed_data[position..position + slen].to_vec();
-        //println!("block_index={} string_index={}, string={}", block_index, string_index, str::from_utf8(&string).unwrap());
-        // loop takes around nearly half the time of the function
-        for _ in 0..string_index {
-            position += slen + 1;
-            let (delta, vbyte_bytes) = decode_vbyte_delta(&self.packed_data, position);
-            position += vbyte_bytes;
-            slen = self.strlen(position);
-            string.truncate(delta);
-            string.extend_from_slice(&self.packed_data[position..position + slen]);
+        if self.damaged {
+            return Err(ExtractError::Damaged { id });
         }
-        // tried simdutf8::basic::from_utf8 but that didn't speed up extract that much
-        match str::from_utf8(&string) {
-            Ok(string) => Ok(String::from(string)),
-            Err(e) => Err(ExtractError::InvalidUtf8 {
-                source: e,
-                data: string.clone(),
-                recovered: String::from_utf8_lossy(&string).into_owned(),
-            }),
+        let data = self.data().map_err(|source| ExtractError::Unavailable { id, source })?;
+        let block_index = id.saturating_sub(1) as usize / self.block_size;
+        decode_string(data, self.sequence.get(block_index), id, self.block_size, buf)
+    }
+
+    /// Byte range of the packed data of the block that contains the string with the given ID.
+    pub(crate) fn block_range(&self, id: Id) -> Result<Range<usize>, ExtractError> {
+        if id == 0 || id as usize > self.num_strings {
+            return Err(ExtractError::IdOutOfBounds { id, len: self.num_strings });
         }
+        let block_index = (id - 1) as usize / self.block_size;
+        Ok(self.sequence.get(block_index)..self.sequence.get(block_index + 1))
+    }
+
+    /// Extract the string with the given ID from the packed data of its block, see [`Self::block_range`].
+    pub(crate) fn extract_from_block(&self, id: Id, block: &[u8]) -> Result<String, ExtractError> {
+        let mut string = String::new();
+        decode_string(block, 0, id, self.block_size, &mut string)?;
+        Ok(string)
     }
 
-    fn strlen(&self, offset: usize) -> usize {
-        let length = self.packed_data.len();
-        let mut position = offset;
+    /// Iterator over all strings of the section in ID order, which decodes each block only once.
+    /// Yields an error for each string of a damaged section and of a lazy store that cannot be loaded.
+    pub fn iter(&self) -> DictSectIter<'_> {
+        DictSectIter { sect: self, data: self.data(), id: 0, pos: 0, string: Vec::new() }
+    }
 
-        while position < length && self.packed_data[position] != 0 {
-            position += 1;
+    /// Like [`Self::iter`] but starting at the string with the given ID, which skips the preceding strings of its block.
+    pub fn iter_from(&self, id: Id) -> DictSectIter<'_> {
+        let start = (id.saturating_sub(1) as usize).min(self.num_strings);
+        let mut iter = self.iter();
+        iter.id = start - start % self.block_size;
+        if let (false, Some(data)) = (self.damaged, iter.data.as_ref().ok().copied()) {
+            while iter.id < start {
+                iter.next_bytes(data);
+            }
         }
+        iter.id = start;
+        iter
+    }
 
-        position - offset
+    /// Range of the IDs of all strings that start with the given prefix, which is empty if there are none.
+    /// As the strings are sorted by [`compare_terms`], they are found with a binary search over the first strings of the blocks, so only two blocks are decoded.
+    /// # Example
+    /// ```
+    /// let sect = hdt::DictSectPFC::new(&["ab", "abc", "abd", "b"], 2);
+    /// assert_eq!(1..4, sect.prefix_range("ab"));
+    /// assert_eq!(2..3, sect.prefix_range("abc"));
+    /// assert!(sect.prefix_range("c").is_empty());
+    /// ```
+    pub fn prefix_range(&self, prefix: &str) -> Range<Id> {
+        if self.damaged {
+            // damaged data may not be sorted
+            return 0..0;
+        }
+        let Some(data) = self.data_or_log() else {
+            return 0..0;
+        };
+        let prefix = prefix.as_bytes();
+        let start = self.partition_point(data, |s| s < prefix);
+        let end = self.partition_point(data, |s| s.get(..prefix.len()).unwrap_or(s) <= prefix);
+        (start + 1) as Id..(end + 1) as Id
+    }
+
+    /// Number of strings at the beginning of the section that satisfy the given predicate, which must be true for a prefix of the sorted strings only.
+    fn partition_point(&self, data: &[u8], pred: impl Fn(&[u8]) -> bool) -> usize {
+        let num_blocks = self.num_strings.div_ceil(self.block_size);
+        let (mut low, mut high) = (0, num_blocks);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let pos = self.sequence.get(mid);
+            if data.get(pos..pos + strlen(data, pos)).is_some_and(&pred) {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        // all strings before the block in which the predicate becomes false satisfy it
+        let Some(block) = low.checked_sub(1) else {
+            return 0;
+        };
+        let mut iter =
+            DictSectIter { sect: self, data: Ok(data), id: block * self.block_size, pos: 0, string: Vec::new() };
+        let mut count = block * self.block_size;
+        for _ in 0..self.block_size {
+            match iter.next_bytes(data) {
+                Some(Ok(s)) if pred(s) => count += 1,
+                _ => break,
+            }
+        }
+        count
     }
 
+    /// Number of strings in the section.
     pub const fn num_strings(&self) -> usize {
         self.num_strings
     }
 
+    /// Number of strings per block, the first string of each block is stored completely.
+    pub const fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    /// CRC32C checksum of the packed data as stored in the file, which is not necessarily valid.
+    #[cfg(feature = "mmap")]
+    pub(crate) const fn stored_crc(&self) -> u32 {
+        self.crc32
+    }
+
+    /// Index of the block that contains the string with the given ID and the offset of that block in the packed data,
+    /// None if the ID is out of bounds.
+    pub fn block_of(&self, id: Id) -> Option<(usize, usize)> {
+        if id == 0 || id > self.num_strings {
+            return None;
+        }
+        let block = (id - 1) / self.block_size;
+        Some((block, self.sequence.get(block)))
+    }
+
+    /// Length in bytes of the front coded strings.
+    pub fn packed_len(&self) -> usize {
+        self.packed_data.len()
+    }
+
+    /// The front coded strings, which loads them if they are stored lazily, see [`Self::set_packed_data`].
+    pub fn packed_data(&self) -> io::Result<&[u8]> {
+        self.data()
+    }
+
+    /// Store the front coded strings in the given store instead, for example compressed or on a web server.
+    /// The store needs to contain the same bytes, which are compared with the stored checksum when they are first accessed,
+    /// so that they are loaded only once. Until then, only the length of the store is checked.
+    /// Accesses fail with an [`io::ErrorKind::InvalidData`] error if the checksum does not match
+    /// and with the error of the store if it cannot be loaded, see [`ExtractError::Unavailable`].
+    pub fn set_packed_data(&mut self, store: impl ByteStore + 'static) -> Result<(), HdtError> {
+        if store.len() != self.packed_data.len() {
+            let message = format!("store of {} bytes instead of {}", store.len(), self.packed_data.len());
+            return Err(HdtError::invalid(Part::Dictionary, message));
+        }
+        self.packed_data = SharedBytes::from(CheckedBytes::new(store, self.crc32));
+        Ok(())
+    }
+
+    /// Whether the packed data failed the CRC check and was loaded in recovery mode.
+    pub const fn is_damaged(&self) -> bool {
+        self.damaged
+    }
+
+    /// Mark the section as damaged, so that no strings are extracted from it.
+    pub(crate) const fn mark_damaged(&mut self) {
+        self.damaged = true;
+    }
+
+    /// Read a section from HDT data, the returned handle yields whether the CRC32 checksum of the packed data is valid.
     pub fn read<R: BufRead>(reader: &mut R) -> Result<(Self, JoinHandle<bool>)> {
+        let sect = Self::read_with(reader, |r, len| read_bytes(r, len).map(SharedBytes::from))?;
+        let (cloned_data, crc_code) = (sect.packed_data.clone(), sect.crc32);
+        let crc_handle = spawn(move || {
+            let crc = crc::Crc::<u32>::new(&crc::CRC_32_ISCSI);
+            let mut digest = crc.digest();
+            digest.update(cloned_data.bytes().unwrap_or_default());
+            digest.finalize() == crc_code
+        });
+        Ok((sect, crc_handle))
+    }
+
+    /// Like [`Self::read`] but the packed data stays in the memory map instead of being copied.
+    /// Its CRC32 checksum is not validated, because that would read the whole section, see [`Self::verify_crc`].
+    /// Only the block offsets are checked against the length of the packed data, which does not read it.
+    #[cfg(feature = "mmap")]
+    pub(crate) fn read_mapped(reader: &mut &[u8], map: &Arc<Mmap>) -> Result<Self> {
+        let sect = Self::read_with(reader, |r, len| take_mapped(r, map, len).map(SharedBytes::from))?;
+        sect.validate_offsets()?;
+        Ok(sect)
+    }
+
+    /// Skip the packed data of a section and return the section without it together with the position of the packed data,
+    /// see [`LazyHdt`](crate::lazy::LazyHdt).
+    /// Only the preamble and the block offsets are read, single blocks can be read later with [`Self::block_range`] and
+    /// [`Self::extract_from_block`], which do not check the checksum of the packed data.
+    pub(crate) fn skip<R: BufRead + Seek>(reader: &mut R) -> Result<(Self, u64)> {
+        let mut offset = 0;
+        let sect = Self::read_with(reader, |r, len| {
+            offset = r.stream_position()?;
+            r.seek_relative(i64::try_from(len).map_err(io::Error::other)?)?;
+            Ok(SharedBytes::from(Vec::new()))
+        })?;
+        Ok((sect, offset))
+    }
+
+    /// Read the section with the given function for the packed data.
+    fn read_with<R: BufRead>(
+        reader: &mut R, read_packed: impl FnOnce(&mut R, usize) -> io::Result<SharedBytes>,
+    ) -> Result<Self> {
         let mut preamble = [0_u8];
         reader.read_exact(&mut preamble)?;
-        if preamble[0] != 2 {
-            return Err(eyre!("Implementation only supports plain front coded dictionary sections.",));
+        if preamble[0] != TYPE_PFC {
+            let t = preamble[0];
+            let format =
+                if t == TYPE_HTFC { format!("type {t} (Hu-Tucker front coding)") } else { format!("type {t}") };
+            return Err(HdtError::UnsupportedFormat {
+                part: Part::Dictionary,
+                format,
+                supported: "only plain front coded sections (type 2) are supported",
+            }
+            .into());
         }
 
         // read section meta data
         let crc = crc::Crc::<u8>::new(&crc::CRC_8_SMBUS);
         let mut digest = crc.digest();
         // The CRC includes the type of the block, inaccuracy in the spec, careful.
-        digest.update(&[0x02]);
+        digest.update(&preamble);
         // This was determined based on https://git.io/JthMG because the spec on this
         // https://www.rdfhdt.org/hdt-binary-format was inaccurate, it's 3 vbytes, not 2.
         let (num_strings, bytes_read) = read_vbyte(reader)?;
@@ -245,29 +506,318 @@ impl DictSectPFC {
 
         // validate section CRC8
         if digest.finalize() != crc_code {
-            return Err(eyre!("Invalid CRC8-CCIT checksum"));
+            return Err(HdtError::checksum(Part::Dictionary, "CRC8-CCIT", "dictionary section preamble").into());
         }
 
         // read sequence log array
-        let sequence = Sequence::read(reader)?;
+        let sequence = read_sequence(reader)?;
+        // the sequence contains an additional entry for the end of the packed data, which empty sections may lack
+        if num_strings > 0 && (block_size == 0 || sequence.len() != num_strings.div_ceil(block_size) + 1) {
+            let message = format!(
+                "{num_strings} strings in blocks of {block_size} do not match {} block offsets",
+                sequence.len()
+            );
+            return Err(HdtError::invalid(Part::Dictionary, message).into());
+        }
 
         // read packed data
-        let mut packed_data = vec![0u8; packed_length];
-        reader.read_exact(&mut packed_data)?;
-        let packed_data = Arc::<[u8]>::from(packed_data);
+        let packed_data = read_packed(reader, packed_length)?;
 
         // read packed data CRC32
         let mut crc_code = [0_u8; 4];
         reader.read_exact(&mut crc_code)?;
-        let cloned_data = Arc::clone(&packed_data);
-        let crc_handle = spawn(move || {
-            let crc = crc::Crc::<u32>::new(&crc::CRC_32_ISCSI);
-            let mut digest = crc.digest();
-            digest.update(&cloned_data[..]);
-            digest.finalize() == u32::from_le_bytes(crc_code)
-        });
+        let crc32 = u32::from_le_bytes(crc_code);
+        Ok(DictSectPFC { num_strings, block_size, sequence, packed_data, crc32, damaged: false })
+    }
+
+    /// Whether the packed data still matches the CRC32C checksum it was loaded with, false if it cannot be loaded.
+    /// Reads the whole section, which for memory mapped files detects changes of the underlying file since it was mapped.
+    pub fn verify_crc(&self) -> bool {
+        self.data().is_ok_and(|data| crc::Crc::<u32>::new(&crc::CRC_32_ISCSI).checksum(data) == self.crc32)
+    }
+
+    /// Write the section in the format expected by [`Self::read`].
+    /// The packed data is written as is with the checksum it was loaded with instead of a new one,
+    /// so that damaged sections keep their original content and still fail the check of [`Self::read`].
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let mut preamble = vec![TYPE_PFC];
+        preamble.extend(encode_vbyte(self.num_strings));
+        preamble.extend(encode_vbyte(self.packed_data.len()));
+        preamble.extend(encode_vbyte(self.block_size));
+        preamble.push(crc::Crc::<u8>::new(&crc::CRC_8_SMBUS).checksum(&preamble));
+        writer.write_all(&preamble)?;
+        write_sequence(writer, (0..self.sequence.len()).map(|i| self.sequence.get(i)))?;
+        writer.write_all(self.data()?)?;
+        writer.write_all(&self.crc32.to_le_bytes())
+    }
 
-        Ok((DictSectPFC { num_strings, block_size, sequence, packed_data }, crc_handle))
+    /// Parse a dictionary section from the beginning of the given bytes, for example for fuzzing.
+    /// Unlike [`Self::read`], the checksums are validated before returning and the structure of all blocks is verified,
+    /// so that malformed input never causes a panic, neither while parsing nor when looking up strings in the result.
+    pub fn parse(mut data: &[u8]) -> Result<Self> {
+        let (mut sect, crc_handle) = Self::read(&mut data)?;
+        if !crc_handle.join().unwrap_or(false) {
+            return Err(HdtError::checksum(Part::Dictionary, "CRC32C", "packed data").into());
+        }
+        if let Some(handle) = sect.sequence.take_crc_handle() {
+            if !handle.join().unwrap_or(false) {
+                return Err(HdtError::checksum(Part::Dictionary, "CRC32C", "block offsets").into());
+            }
+        }
+        sect.validate()?;
+        Ok(sect)
+    }
+
+    /// Verify that the block offsets are increasing and within the packed data, without reading the packed data.
+    /// The number of offsets is checked when reading the section.
+    fn validate_offsets(&self) -> Result<(), HdtError> {
+        if self.num_strings == 0 {
+            return Ok(());
+        }
+        let blocks = self.num_strings.div_ceil(self.block_size);
+        let len = self.packed_data.len();
+        let mut previous = None;
+        for block in 0..blocks {
+            let pos = self.sequence.get(block);
+            if pos >= len {
+                let message = format!("block {block} starts outside of packed data of length {len}");
+                return Err(HdtError::Invalid { part: Part::Dictionary, offset: Some(pos), message });
+            }
+            if previous.is_some_and(|previous| pos <= previous) {
+                let message = format!("block {block} does not start after the previous block");
+                return Err(HdtError::Invalid { part: Part::Dictionary, offset: Some(pos), message });
+            }
+            previous = Some(pos);
+        }
+        Ok(())
+    }
+
+    /// Verify that all blocks can be decoded, which reads the whole packed data.
+    pub(crate) fn validate(&self) -> Result<(), HdtError> {
+        let invalid = |offset, message| HdtError::Invalid { part: Part::Dictionary, offset, message };
+        if self.block_size == 0 {
+            return Err(HdtError::invalid(Part::Dictionary, "block size 0"));
+        }
+        let blocks = self.num_strings.div_ceil(self.block_size);
+        // the sequence contains an additional entry for the end of the packed data
+        if self.sequence.len() != blocks + 1 || self.sequence.bits_per_entry() == 0 {
+            return Err(HdtError::invalid(
+                Part::Dictionary,
+                format!(
+                    "{} strings in blocks of {} need {} offsets",
+                    self.num_strings,
+                    self.block_size,
+                    blocks + 1
+                ),
+            ));
+        }
+        self.validate_offsets()?;
+        let data = self.data()?;
+        let len = data.len();
+        let mut previous_end = 0;
+        for block in 0..blocks {
+            let mut pos = self.sequence.get(block);
+            if pos < previous_end {
+                let message =
+                    format!("block {block} starts within the previous block, which ends at {previous_end}");
+                return Err(invalid(Some(pos), message));
+            }
+            let mut slen = strlen(data, pos);
+            str::from_utf8(&data[pos..pos + slen]).map_err(|e| invalid(Some(pos), e.to_string()))?;
+            let mut string_len = slen;
+            for _ in 1..self.block_size.min(self.num_strings - block * self.block_size) {
+                pos += slen + 1;
+                let (delta, vbyte_bytes) = try_decode_vbyte_delta(data, pos)
+                    .ok_or_else(|| invalid(Some(pos), format!("invalid prefix length in block {block}")))?;
+                if delta > string_len {
+                    let message = format!("prefix length {delta} in block {block} exceeds previous string length");
+                    return Err(invalid(Some(pos), message));
+                }
+                pos += vbyte_bytes;
+                slen = strlen(data, pos);
+                string_len = delta + slen;
+            }
+            previous_end = pos + slen + 1;
+            // lookups within the last block stop at the end of the packed data
+            if block == blocks - 1 && previous_end < len {
+                return Err(invalid(Some(previous_end), "unexpected data after the last string".to_owned()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Problems of a freshly read section for [`Hdt::validate`](crate::Hdt::validate), whose name is included in the messages:
+    /// the checksum of the block offsets, the structure of the blocks and the order of the strings, which lookups rely on.
+    pub(crate) fn problems(&mut self, name: &str) -> Vec<HdtError> {
+        let mut problems = Vec::new();
+        if let Some(handle) = self.sequence.take_crc_handle() {
+            if !handle.join().unwrap_or(false) {
+                problems.push(HdtError::checksum(
+                    Part::Dictionary,
+                    "CRC32C",
+                    format!("block offsets of the {name} section"),
+                ));
+            }
+        }
+        if let Err(e) = self.validate() {
+            problems.push(match e {
+                HdtError::Invalid { part, offset, message } => {
+                    HdtError::Invalid { part, offset, message: format!("{name} section: {message}") }
+                }
+                e => e,
+            });
+            // the strings cannot be decoded
+            return problems;
+        }
+        // validated strings could be loaded
+        let Ok(data) = self.data() else { return problems };
+        let mut iter = self.iter();
+        let mut previous = Vec::new();
+        let (mut unsorted, mut first) = (0, 0);
+        // bytewise like compare_terms, which also works for strings that are not valid UTF-8
+        for id in 1.. {
+            let Some(Ok(string)) = iter.next_bytes(data) else { break };
+            if id > 1 && string <= previous.as_slice() {
+                unsorted += 1;
+                if first == 0 {
+                    first = id;
+                }
+            }
+            previous.clear();
+            previous.extend_from_slice(string);
+        }
+        if unsorted > 0 {
+            let message =
+                format!("{name} section: {unsorted} strings are not greater than their predecessors, the first has ID {first}");
+            problems.push(HdtError::invalid(Part::Dictionary, message));
+        }
+        problems
+    }
+}
+
+/// Decode the string with the given ID from the block that starts at the given position of the packed data into the buffer.
+fn decode_string(
+    data: &[u8], mut position: usize, id: Id, block_size: usize, buf: &mut String,
+) -> Result<(), ExtractError> {
+    let string_index = id.saturating_sub(1) as usize % block_size;
+    let mut slen = strlen(data, position);
+    let mut string: Vec<u8> = std::mem::take(buf).into_bytes();
+    string.extend_from_slice(data.get(position..position + slen).ok_or(ExtractError::Malformed { id })?);
+    // loop takes around nearly half the time of the function
+    for _ in 0..string_index {
+        position += slen + 1;
+        let (delta, vbyte_bytes) = try_decode_vbyte_delta(data, position).ok_or(ExtractError::Malformed { id })?;
+        position += vbyte_bytes;
+        slen = strlen(data, position);
+        string.truncate(delta);
+        string.extend_from_slice(&data[position..position + slen]);
+    }
+    // tried simdutf8::basic::from_utf8 but that didn't speed up extract that much
+    match String::from_utf8(string) {
+        Ok(string) => {
+            *buf = string;
+            Ok(())
+        }
+        Err(e) => {
+            let recovered = String::from_utf8_lossy(e.as_bytes()).into_owned();
+            Err(ExtractError::InvalidUtf8 { source: e.utf8_error(), data: e.into_bytes(), recovered })
+        }
+    }
+}
+
+/// Length of the null terminated string at the given offset of the packed data.
+fn strlen(data: &[u8], offset: usize) -> usize {
+    let mut position = offset;
+    while position < data.len() && data[position] != 0 {
+        position += 1;
+    }
+    position - offset
+}
+
+/// Iterator over the strings of a [`DictSectPFC`] in ID order, see [`DictSectPFC::iter`].
+pub struct DictSectIter<'a> {
+    sect: &'a DictSectPFC,
+    // packed data or the error of loading it
+    data: io::Result<&'a [u8]>,
+    // number of strings already returned
+    id: usize,
+    // position in the packed data after the last string
+    pos: usize,
+    // last string, the prefix of the next one in the same block
+    string: Vec<u8>,
+}
+
+impl DictSectIter<'_> {
+    /// Decode the next string from the given packed data without checking for damage and UTF-8 validity.
+    fn next_bytes(&mut self, data: &[u8]) -> Option<Result<&[u8], ExtractError>> {
+        let sect = self.sect;
+        if self.id >= sect.num_strings {
+            return None;
+        }
+        self.id += 1;
+        let malformed = ExtractError::Malformed { id: self.id as Id };
+        if (self.id - 1).is_multiple_of(sect.block_size) {
+            self.pos = sect.sequence.get((self.id - 1) / sect.block_size);
+            self.string.clear();
+        } else {
+            let Some((delta, vbyte_bytes)) = try_decode_vbyte_delta(data, self.pos) else {
+                return Some(Err(malformed));
+            };
+            self.pos += vbyte_bytes;
+            self.string.truncate(delta);
+        }
+        let slen = strlen(data, self.pos);
+        let Some(suffix) = data.get(self.pos..self.pos + slen) else {
+            return Some(Err(malformed));
+        };
+        self.string.extend_from_slice(suffix);
+        self.pos += slen + 1;
+        Some(Ok(&self.string))
+    }
+}
+
+impl Iterator for DictSectIter<'_> {
+    type Item = Result<String, ExtractError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let data = match (&self.data, self.sect.damaged) {
+            (Ok(data), false) => *data,
+            (data, damaged) => {
+                if self.id >= self.sect.num_strings {
+                    return None;
+                }
+                self.id += 1;
+                let id = self.id as Id;
+                return Some(Err(match data {
+                    Err(e) if !damaged => {
+                        ExtractError::Unavailable { id, source: io::Error::new(e.kind(), e.to_string()) }
+                    }
+                    _ => ExtractError::Damaged { id },
+                }));
+            }
+        };
+        Some(self.next_bytes(data)?.and_then(|bytes| {
+            String::from_utf8(bytes.to_vec()).map_err(|e| {
+                let recovered = String::from_utf8_lossy(e.as_bytes()).into_owned();
+                ExtractError::InvalidUtf8 { source: e.utf8_error(), data: e.into_bytes(), recovered }
+            })
+        }))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.sect.num_strings - self.id;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for DictSectIter<'_> {}
+
+impl<'a> IntoIterator for &'a DictSectPFC {
+    type Item = Result<String, ExtractError>;
+    type IntoIter = DictSectIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
     }
 }
 
@@ -314,9 +864,12 @@ mod tests {
             let back = shared.extract(id).unwrap();
             assert_eq!(term, back, "term does not translate back to itself {} -> {} -> {}", term, id, back);
         }
+        let strings: Vec<String> = shared.iter().map(Result::unwrap).collect();
+        assert_eq!((1..=43).map(|id| shared.extract(id).unwrap()).collect::<Vec<_>>(), strings);
+        assert_eq!(43, shared.iter().len());
         let sequence = shared.sequence;
-        let data_size = (sequence.bits_per_entry * sequence.entries + 63) / 64;
-        assert_eq!(sequence.data.len(), data_size);
+        let data_size = (sequence.bits_per_entry() * sequence.len() + 63) / 64;
+        assert_eq!(sequence.size_in_bytes(), data_size * 8);
 
         let (subjects, _) = DictSectPFC::read(&mut reader).unwrap();
         assert_eq!(subjects.num_strings, 6);
@@ -330,7 +883,121 @@ mod tests {
             assert_eq!(term, back, "term does not translate back to itself {} -> {} -> {}", term, id, back);
         }
         let sequence = subjects.sequence;
-        let data_size = (sequence.bits_per_entry * sequence.entries + 63) / 64;
-        assert_eq!(sequence.data.len(), data_size);
+        let data_size = (sequence.bits_per_entry() * sequence.len() + 63) / 64;
+        assert_eq!(sequence.size_in_bytes(), data_size * 8);
+    }
+
+    #[test]
+    fn test_parse_malformed() {
+        init();
+        let data = std::fs::read("tests/resources/snikmeta.hdt").unwrap();
+        let mut reader = data.as_slice();
+        ControlInfo::read(&mut reader).unwrap();
+        Header::read(&mut reader).unwrap();
+        ControlInfo::read(&mut reader).unwrap();
+        let shared = DictSectPFC::parse(reader).unwrap();
+        assert_eq!(43, shared.num_strings);
+        let sect_len = 614 + shared.sequence.size_in_bytes() + 20;
+        let sect = &reader[..sect_len.min(reader.len())];
+        // none of these may panic
+        let check = |bytes: &[u8]| {
+            if let Ok(parsed) = DictSectPFC::parse(bytes) {
+                for id in 0..=parsed.num_strings + 1 {
+                    if let Ok(s) = parsed.extract(id) {
+                        parsed.string_to_id(&s);
+                    }
+                }
+                parsed.string_to_id("http://www.snik.eu/ontology/meta/Top");
+                parsed.iter().for_each(drop);
+            }
+        };
+        for len in 0..sect.len() {
+            check(&sect[..len]);
+        }
+        let mut mutated = sect.to_vec();
+        for i in 0..mutated.len() {
+            for flip in [0x01, 0x80, 0xFF] {
+                mutated[i] ^= flip;
+                check(&mutated);
+                mutated[i] ^= flip;
+            }
+        }
+        check(&[2, 0x81, 0x80, 0x80, 0]);
+        let err = DictSectPFC::parse(&[3, 0x81, 0x80, 0x80, 0]).unwrap_err();
+        assert!(err.to_string().contains("type 3"), "{err}");
+    }
+
+    #[test]
+    fn empty_section() {
+        init();
+        let sect = DictSectPFC::new(&[] as &[&str], 16);
+        let mut bytes = Vec::new();
+        sect.write(&mut bytes).unwrap();
+        for sect in [sect, DictSectPFC::parse(&bytes).unwrap()] {
+            assert_eq!(0, sect.num_strings());
+            assert_eq!(0, sect.string_to_id(""));
+            assert_eq!(0, sect.string_to_id("http://www.snik.eu/ontology/meta/Top"));
+            assert!(sect.extract(0).is_err());
+            assert!(sect.extract(1).is_err());
+            assert_eq!(0, sect.iter().count());
+            assert!(sect.prefix_range("").is_empty());
+        }
+    }
+
+    #[test]
+    fn prefix_range() {
+        init();
+        let strings = ["a", "ab", "abc", "abd", "b", "ba", "bab", "c"];
+        for block_size in 1..=strings.len() {
+            let sect = DictSectPFC::new(&strings, block_size);
+            for prefix in ["", "a", "ab", "abc", "abe", "b", "ba", "bb", "c", "d", "0"] {
+                let expected: Vec<_> = (1..).zip(strings).filter(|(_, s)| s.starts_with(prefix)).collect();
+                let range = sect.prefix_range(prefix);
+                let found: Vec<_> =
+                    range.clone().zip(sect.iter_from(range.start).map(Result::unwrap)).take(range.len()).collect();
+                assert_eq!(expected, found.iter().map(|(id, s)| (*id, s.as_str())).collect::<Vec<_>>());
+            }
+        }
+    }
+
+    #[test]
+    fn order() {
+        init();
+        let mut strings = vec!["b", "B", "\"ä\"", "\"a\"@de", "ä", "é", "z", "\u{1F600}", "\u{FFFD}", "Z"];
+        strings.sort_unstable_by(|a, b| compare_terms(a, b));
+        assert_eq!(vec!["\"a\"@de", "\"ä\"", "B", "Z", "b", "z", "ä", "é", "\u{FFFD}", "\u{1F600}"], strings);
+        for block_size in [1, 3, 16] {
+            let sect = DictSectPFC::new(&strings, block_size);
+            for (id, s) in (1..).zip(&strings) {
+                assert_eq!(id, sect.string_to_id(s), "{s}");
+            }
+            assert_eq!(strings, sect.iter().map(Result::unwrap).collect::<Vec<_>>());
+            assert_eq!(7..8, sect.prefix_range("ä"));
+        }
+    }
+
+    #[test]
+    fn htfc_section() {
+        init();
+        let mut bytes = vec![TYPE_HTFC];
+        bytes.extend(encode_vbyte(3));
+        bytes.extend(encode_vbyte(0));
+        bytes.extend(encode_vbyte(16));
+        bytes.push(crc::Crc::<u8>::new(&crc::CRC_8_SMBUS).checksum(&bytes));
+        let err = DictSectPFC::parse(&bytes).unwrap_err();
+        assert!(err.to_string().contains("Hu-Tucker"), "{err}");
+    }
+
+    #[test]
+    fn offsets_mismatch() {
+        init();
+        let sect = DictSectPFC::new(&["a", "b", "c"], 2);
+        let mut bytes = Vec::new();
+        sect.write(&mut bytes).unwrap();
+        // claim 5 strings, which need 3 blocks and 4 offsets instead of 3
+        bytes[1] = encode_vbyte(5)[0];
+        bytes[4] = crc::Crc::<u8>::new(&crc::CRC_8_SMBUS).checksum(&bytes[..4]);
+        let err = DictSectPFC::parse(&bytes).unwrap_err();
+        assert!(err.to_string().contains("block offsets"), "{err}");
     }
 }
@@ -0,0 +1,277 @@
+//! Triples that were added and removed between two versions of a dataset, like hdtDiff of hdt-java, see [`Hdt::diff`].
+use crate::changes::{id_map, join, mapped_triples};
+use crate::containers::rdf::nt_term;
+use crate::four_sect_dict::IdKind;
+use crate::hdt::{Hdt, StringTriple, TripleCache};
+use crate::triples::{Id, SubjectIter, TripleId};
+use log::error;
+use std::collections::VecDeque;
+use std::io::{self, Write};
+
+/// Triple that only occurs in one of two versions, with the IDs of the version it occurs in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TripleChange {
+    /// Triple of the new version that is not in the old one.
+    Added(TripleId),
+    /// Triple of the old version that is not in the new one.
+    Removed(TripleId),
+}
+
+/// Iterator over the differences between an old and a new version, see [`Hdt::diff`].
+/// The changes are ordered by subject in the order of [`compare_terms`](crate::compare_terms),
+/// with the removed before the added triples of each subject.
+/// Holds the tables that map the IDs of terms between both versions, see [`Self::new_id`] and [`Self::old_id`].
+pub struct TripleDiff<'a> {
+    old: &'a Hdt,
+    new: &'a Hdt,
+    /// IDs of each subject in the old and the new version in the order of its string, 0 if missing in that version
+    subjects: std::vec::IntoIter<(Id, Id)>,
+    /// IDs in the new version by the ID in the old version for subjects, predicates and objects
+    new_ids: [Vec<Id>; 3],
+    /// IDs in the old version by the ID in the new version for subjects, predicates and objects
+    old_ids: [Vec<Id>; 3],
+    pending: VecDeque<TripleChange>,
+}
+
+/// Index of the tables for the given kind.
+const fn index(kind: &IdKind) -> usize {
+    match kind {
+        IdKind::Subject => 0,
+        IdKind::Predicate => 1,
+        IdKind::Object => 2,
+    }
+}
+
+/// Table from IDs in the new version to IDs in the old version.
+fn invert(new_ids: &[Id]) -> Vec<Id> {
+    let mut old_ids = vec![0; new_ids.iter().max().map_or(1, |&max| max + 1)];
+    for (old_id, &new_id) in new_ids.iter().enumerate() {
+        old_ids[new_id] = old_id;
+    }
+    // terms that are missing in the new version map to 0
+    old_ids[0] = 0;
+    old_ids
+}
+
+impl<'a> TripleDiff<'a> {
+    fn new(old: &'a Hdt, new: &'a Hdt) -> Self {
+        let subjects: Vec<(Id, Id)> = join(old, new, &IdKind::Subject).map(|(_, o, n)| (o, n)).collect();
+        let mut subject_ids = Vec::new();
+        for &(old_id, new_id) in &subjects {
+            if old_id != 0 {
+                if subject_ids.len() <= old_id {
+                    subject_ids.resize(old_id + 1, 0);
+                }
+                subject_ids[old_id] = new_id;
+            }
+        }
+        let new_ids = [subject_ids, id_map(old, new, &IdKind::Predicate), id_map(old, new, &IdKind::Object)];
+        let old_ids = [invert(&new_ids[0]), invert(&new_ids[1]), invert(&new_ids[2])];
+        TripleDiff { old, new, subjects: subjects.into_iter(), new_ids, old_ids, pending: VecDeque::new() }
+    }
+
+    /// ID in the new version of the term with the given ID and kind in the old version, 0 if the term is not in the new version.
+    pub fn new_id(&self, old_id: Id, kind: &IdKind) -> Id {
+        self.new_ids[index(kind)].get(old_id).copied().unwrap_or(0)
+    }
+
+    /// ID in the old version of the term with the given ID and kind in the new version, 0 if the term is not in the old version.
+    pub fn old_id(&self, new_id: Id, kind: &IdKind) -> Id {
+        self.old_ids[index(kind)].get(new_id).copied().unwrap_or(0)
+    }
+
+    /// Queue the changes of the subject with the given IDs.
+    fn compare(&mut self, old_s: Id, new_s: Id) {
+        let old_po = if old_s == 0 {
+            Vec::new()
+        } else {
+            mapped_triples(
+                self.old,
+                old_s,
+                &self.new_ids[index(&IdKind::Predicate)],
+                &self.new_ids[index(&IdKind::Object)],
+            )
+        };
+        let new_po: Vec<TripleId> =
+            if new_s == 0 { Vec::new() } else { SubjectIter::with_s(&self.new.triples, new_s).collect() };
+        let mut added = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < old_po.len() || j < new_po.len() {
+            let new_pair = new_po.get(j).map(|t| (t.predicate_id, t.object_id));
+            match (old_po.get(i), new_pair) {
+                // terms that are missing in the new version map to 0, so their triples never match
+                (Some(&(pair, _)), Some(new_pair)) if pair == new_pair => {
+                    i += 1;
+                    j += 1;
+                }
+                (Some(&(pair, t)), Some(new_pair)) if pair <= new_pair => {
+                    self.pending.push_back(TripleChange::Removed(t));
+                    i += 1;
+                }
+                (Some(&(_, t)), None) => {
+                    self.pending.push_back(TripleChange::Removed(t));
+                    i += 1;
+                }
+                _ => {
+                    added.push(TripleChange::Added(new_po[j]));
+                    j += 1;
+                }
+            }
+        }
+        self.pending.extend(added);
+    }
+
+    /// Translate the changes into strings, changes whose triples cannot be translated, for example from damaged dictionary sections,
+    /// are logged and skipped.
+    pub fn strings(self) -> impl Iterator<Item = (TripleChange, StringTriple)> + 'a {
+        let (mut old_cache, mut new_cache) = (TripleCache::new(self.old), TripleCache::new(self.new));
+        self.filter_map(move |change| {
+            let translated = match change {
+                TripleChange::Added(t) => new_cache.translate(t),
+                TripleChange::Removed(t) => old_cache.translate(t),
+            };
+            translated.map_err(|e| error!("{e}")).ok().map(|triple| (change, triple))
+        })
+    }
+
+    /// Write the changes in the [RDF Patch](https://afs.github.io/rdf-patch/) format, with `D` for removed and `A` for added triples,
+    /// and return the numbers of added and removed triples.
+    /// Changes whose triples cannot be translated are logged and skipped.
+    pub fn write_patch<W: Write>(self, writer: &mut W) -> io::Result<(usize, usize)> {
+        let (mut added, mut removed) = (0, 0);
+        for (change, (s, p, o)) in self.strings() {
+            let op = match change {
+                TripleChange::Added(_) => {
+                    added += 1;
+                    'A'
+                }
+                TripleChange::Removed(_) => {
+                    removed += 1;
+                    'D'
+                }
+            };
+            writeln!(writer, "{op} {} {} {} .", nt_term(&s), nt_term(&p), nt_term(&o))?;
+        }
+        Ok((added, removed))
+    }
+}
+
+impl Iterator for TripleDiff<'_> {
+    type Item = TripleChange;
+
+    fn next(&mut self) -> Option<TripleChange> {
+        loop {
+            if let Some(change) = self.pending.pop_front() {
+                return Some(change);
+            }
+            let (old_s, new_s) = self.subjects.next()?;
+            self.compare(old_s, new_s);
+        }
+    }
+}
+
+impl std::iter::FusedIterator for TripleDiff<'_> {}
+
+impl Hdt {
+    /// Triples that were added and removed since the given old version, streamed subject by subject.
+    /// Like [`Self::changed_subjects`], terms are compared by their strings while the dictionaries of both versions are decoded once
+    /// in sorted order for the tables that map IDs between both versions, after which triples are compared by ID.
+    /// The memory needed besides these tables is proportional to the number of triples of a single subject.
+    /// Blank nodes are compared by their labels, which are not stable across versions created by different tools.
+    /// # Example
+    /// ```
+    /// fn patch(old: &hdt::Hdt, new: &hdt::Hdt) -> std::io::Result<()> {
+    ///     let (added, removed) = new.diff(old).write_patch(&mut std::io::stdout().lock())?;
+    ///     eprintln!("{added} added, {removed} removed");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn diff<'a>(&'a self, old: &'a Hdt) -> TripleDiff<'a> {
+        TripleDiff::new(old, self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::HdtBuilder;
+    use crate::tests::init;
+    use pretty_assertions::assert_eq;
+    use std::collections::BTreeSet;
+    use std::fs::File;
+
+    fn build(triples: &[[&str; 3]]) -> Hdt {
+        let mut builder = HdtBuilder::new();
+        for [s, p, o] in triples {
+            builder.add_triple(s, p, o);
+        }
+        builder.build().unwrap()
+    }
+
+    fn strings(changes: impl Iterator<Item = (TripleChange, StringTriple)>) -> Vec<(char, String)> {
+        changes
+            .map(|(change, (s, p, o))| {
+                let op = if matches!(change, TripleChange::Added(_)) { 'A' } else { 'D' };
+                (op, format!("{s} {p} {o}"))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn diff() {
+        init();
+        let old = build(&[
+            ["http://example.org/a", "http://example.org/p", "http://example.org/b"],
+            ["http://example.org/a", "http://example.org/p", "\"x\""],
+            ["http://example.org/c", "http://example.org/q", "\"y\""],
+        ]);
+        // b becomes a subject and thus moves to the shared section, c is removed and d added
+        let new = build(&[
+            ["http://example.org/a", "http://example.org/p", "http://example.org/b"],
+            ["http://example.org/a", "http://example.org/r", "\"x\""],
+            ["http://example.org/b", "http://example.org/p", "\"x\""],
+            ["http://example.org/d", "http://example.org/q", "\"y\""],
+        ]);
+        let expected = vec![
+            ('D', "http://example.org/a http://example.org/p \"x\"".to_owned()),
+            ('A', "http://example.org/a http://example.org/r \"x\"".to_owned()),
+            ('A', "http://example.org/b http://example.org/p \"x\"".to_owned()),
+            ('D', "http://example.org/c http://example.org/q \"y\"".to_owned()),
+            ('A', "http://example.org/d http://example.org/q \"y\"".to_owned()),
+        ];
+        assert_eq!(expected, strings(new.diff(&old).strings()));
+        assert!(new.diff(&new).next().is_none());
+
+        let diff = new.diff(&old);
+        let b = |hdt: &Hdt| hdt.dict.string_to_id("http://example.org/b", &IdKind::Object);
+        assert_eq!(b(&new), diff.new_id(b(&old), &IdKind::Object));
+        assert_eq!(b(&old), diff.old_id(b(&new), &IdKind::Object));
+        let r = new.dict.string_to_id("http://example.org/r", &IdKind::Predicate);
+        assert_eq!(0, diff.old_id(r, &IdKind::Predicate));
+        let mut patch = Vec::new();
+        assert_eq!((3, 2), diff.write_patch(&mut patch).unwrap());
+        let patch = String::from_utf8(patch).unwrap();
+        assert!(patch.starts_with("D <http://example.org/a> <http://example.org/p> \"x\" .\nA "), "{patch}");
+
+        // compare with the difference of the sets of all triples
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let snik = Hdt::new(std::io::BufReader::new(file)).unwrap();
+        let mut builder = HdtBuilder::new();
+        for (i, (s, p, o)) in snik.triples().enumerate() {
+            if i % 7 != 0 {
+                builder.add_triple(&s, &p, &o);
+            }
+        }
+        builder.add_triple("http://www.snik.eu/ontology/meta", "http://example.org/p", "\"new\"");
+        let changed = builder.build().unwrap();
+        let set =
+            |hdt: &Hdt| -> BTreeSet<String> { hdt.triples().map(|(s, p, o)| format!("{s} {p} {o}")).collect() };
+        let (old_set, new_set) = (set(&snik), set(&changed));
+        let mut expected: Vec<_> = old_set.difference(&new_set).map(|t| ('D', t.clone())).collect();
+        expected.extend(new_set.difference(&old_set).map(|t| ('A', t.clone())));
+        expected.sort_unstable();
+        let mut actual = strings(changed.diff(&snik).strings());
+        actual.sort_unstable();
+        assert_eq!(expected, actual);
+    }
+}
@@ -0,0 +1,162 @@
+//! Structured errors for reading HDT files, so that applications can tell damaged files from unsupported formats and I/O errors.
+//! Loading functions return errors with additional context, use [`HdtError::find`] to get the underlying error.
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// Structure of an HDT file in which an error was detected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Part {
+    /// Control information that precedes each section.
+    ControlInfo,
+    /// Header with metadata in N-Triples.
+    Header,
+    /// Dictionary with its sections.
+    Dictionary,
+    /// Triples section.
+    Triples,
+    /// Object index file.
+    Index,
+    /// Sequence of integers within the dictionary, triples or index.
+    Sequence,
+    /// Bitmap within the triples or index.
+    Bitmap,
+}
+
+impl fmt::Display for Part {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Part::ControlInfo => "control information",
+            Part::Header => "header",
+            Part::Dictionary => "dictionary",
+            Part::Triples => "triples",
+            Part::Index => "index",
+            Part::Sequence => "sequence",
+            Part::Bitmap => "bitmap",
+        })
+    }
+}
+
+/// Reason why HDT data could not be read.
+#[derive(Error, Debug)]
+pub enum HdtError {
+    /// Reading failed or the data ended too early.
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    /// A stored checksum does not match the data, which is damaged.
+    #[error("invalid {algorithm} checksum of the {what}")]
+    Checksum {
+        /// Structure that contains the data.
+        part: Part,
+        /// Checksum algorithm, such as `CRC32C`.
+        algorithm: &'static str,
+        /// Data covered by the checksum.
+        what: String,
+    },
+    /// Data of a format or type that is valid HDT but not supported by this crate.
+    #[error("unsupported {part} format {format}, {supported}")]
+    UnsupportedFormat {
+        /// Structure with the unsupported format.
+        part: Part,
+        /// Format identifier or type byte as found in the data.
+        format: String,
+        /// Which formats are supported instead.
+        supported: &'static str,
+    },
+    /// Data that violates the HDT format.
+    #[error("invalid {part}{}: {message}", at(*.offset))]
+    Invalid {
+        /// Structure that contains the data.
+        part: Part,
+        /// Position in bytes within the data of the structure, if known.
+        offset: Option<usize>,
+        /// What is wrong with the data.
+        message: String,
+    },
+    /// The file of a memory mapped HDT was changed after it was mapped, see [`Hdt::check_mapping`](crate::Hdt::check_mapping).
+    #[error("memory mapped file {} was changed: {what}", path.display())]
+    Modified {
+        /// Path of the mapped file.
+        path: PathBuf,
+        /// What changed, such as the length.
+        what: String,
+    },
+}
+
+fn at(offset: Option<usize>) -> String {
+    offset.map(|offset| format!(" at offset {offset}")).unwrap_or_default()
+}
+
+impl HdtError {
+    /// Invalid data without a known offset.
+    pub(crate) fn invalid(part: Part, message: impl Into<String>) -> Self {
+        HdtError::Invalid { part, offset: None, message: message.into() }
+    }
+
+    /// Mismatch of the given checksum algorithm for the given data.
+    pub(crate) fn checksum(part: Part, algorithm: &'static str, what: impl Into<String>) -> Self {
+        HdtError::Checksum { part, algorithm, what: what.into() }
+    }
+
+    /// The given error or the first structured error in its chain of sources, if any.
+    /// Works both with the boxed errors of [`Hdt::new`](crate::Hdt::new) and with [`eyre::Report`] by calling `as_ref()`.
+    /// I/O errors of the underlying reader usually remain an [`io::Error`] in the chain instead.
+    /// # Example
+    /// ```
+    /// use hdt::error::HdtError;
+    /// fn load(data: &[u8]) -> Option<hdt::Hdt> {
+    ///     match hdt::Hdt::new(data) {
+    ///         Ok(hdt) => Some(hdt),
+    ///         Err(e) => {
+    ///             match HdtError::find(e.as_ref()) {
+    ///                 Some(HdtError::Checksum { .. }) => eprintln!("damaged file: {e:?}"),
+    ///                 Some(HdtError::UnsupportedFormat { .. }) => eprintln!("convert the file first: {e:?}"),
+    ///                 _ => eprintln!("{e:?}"),
+    ///             }
+    ///             None
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn find<'a>(error: &'a (dyn std::error::Error + 'static)) -> Option<&'a HdtError> {
+        std::iter::successors(Some(error), |e| e.source()).find_map(|e| e.downcast_ref::<HdtError>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::containers::ControlInfo;
+    use crate::tests::init;
+    use crate::Hdt;
+
+    #[test]
+    fn find() {
+        init();
+        let original = std::fs::read("tests/resources/snikmeta.hdt").expect("error reading file");
+        // the CRC16 of the global control information directly precedes the header
+        let mut data = original.clone();
+        data[original.windows(5).position(|w| w == b"$HDT\x02").unwrap() - 1] ^= 1;
+        let e = Hdt::new(data.as_slice()).unwrap_err();
+        assert!(
+            matches!(HdtError::find(e.as_ref()), Some(HdtError::Checksum { part: Part::ControlInfo, .. })),
+            "{e:?}"
+        );
+        // type byte of the shared section after the dictionary control information
+        let mut data = original.clone();
+        let dict = original.windows(5).position(|w| w == b"$HDT\x03").unwrap();
+        let mut reader = &original[dict..];
+        ControlInfo::read(&mut reader).unwrap();
+        let shared = original.len() - reader.len();
+        assert_eq!(2, data[shared]);
+        data[shared] = 3;
+        let e = Hdt::new(data.as_slice()).unwrap_err();
+        let Some(HdtError::UnsupportedFormat { part: Part::Dictionary, format, .. }) = HdtError::find(e.as_ref())
+        else {
+            panic!("{e:?}");
+        };
+        assert_eq!("type 3", format);
+        assert!(HdtError::find(Hdt::new(&original[..100]).unwrap_err().as_ref()).is_none());
+    }
+}
@@ -0,0 +1,174 @@
+//! Approximate statistics computed on a uniform random sample of triples.
+//! For huge graphs, these complete in a fraction of the time needed for the exact values,
+//! see [`SchemaSummary`](crate::schema::SchemaSummary) for an exact but slower alternative.
+use crate::four_sect_dict::IdKind;
+use crate::hdt::Hdt;
+use crate::triples::{Id, SubjectIter, TripleId};
+use crate::vocab::Vocab;
+use log::error;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+
+/// z-score of the 95% confidence level
+const Z: f64 = 1.96;
+
+/// Estimated number of triples with a 95% confidence interval.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Estimate {
+    /// Point estimate.
+    pub value: usize,
+    /// Lower bound of the confidence interval.
+    pub low: usize,
+    /// Upper bound of the confidence interval.
+    pub high: usize,
+}
+
+impl Estimate {
+    /// Scale the number of hits in a uniform sample to the population using the Wilson score interval.
+    #[allow(clippy::cast_precision_loss, clippy::cast_sign_loss)]
+    pub fn from_sample(hits: usize, sample_size: usize, population: usize) -> Self {
+        if sample_size == 0 {
+            return Estimate { value: 0, low: 0, high: population };
+        }
+        let n = sample_size as f64;
+        let p = hits as f64 / n;
+        let denominator = 1.0 + Z * Z / n;
+        let center = (p + Z * Z / (2.0 * n)) / denominator;
+        let half_width = Z / denominator * (p * (1.0 - p) / n + Z * Z / (4.0 * n * n)).sqrt();
+        let scale = |x: f64| ((x * population as f64).round().max(0.0) as usize).min(population);
+        // at least one triple exists if there was a hit
+        let at_least = usize::from(hits > 0);
+        Estimate {
+            value: scale(p).max(at_least),
+            low: scale(center - half_width).max(at_least),
+            high: scale(center + half_width).max(at_least),
+        }
+    }
+
+    /// Whether the given exact value lies within the confidence interval.
+    pub const fn contains(&self, exact: usize) -> bool {
+        self.low <= exact && exact <= self.high
+    }
+}
+
+/// Estimated usage of the properties by the instances of a class.
+#[derive(Debug, Clone)]
+pub struct ClassEstimate {
+    /// IRI of the class.
+    pub class: String,
+    /// Estimated number of triples whose subject is an instance of the class.
+    pub triples: Estimate,
+    /// Estimated number of triples per property, sorted by descending estimate.
+    pub properties: Vec<(String, Estimate)>,
+}
+
+/// Estimate the number of triples of each predicate from `sample_size` triples sampled with the given seed.
+/// Predicates that do not occur in the sample are not included.
+/// # Example
+/// ```
+/// fn print_predicates(hdt: &hdt::Hdt) {
+///     for (p, e) in hdt::estimate::predicates(hdt, 10_000, 42) {
+///         println!("{p}: about {} triples ({} to {})", e.value, e.low, e.high);
+///     }
+/// }
+/// ```
+pub fn predicates(hdt: &Hdt, sample_size: usize, seed: u64) -> Vec<(String, Estimate)> {
+    let mut hits = BTreeMap::<Id, usize>::new();
+    for t in hdt.triples.sample(sample_size, seed) {
+        *hits.entry(t.predicate_id).or_default() += 1;
+    }
+    estimates(hdt, hits, sample_size, &IdKind::Predicate)
+}
+
+/// Estimate the number of triples per `rdf:type` class and property of the subject from a sample.
+/// Each sampled triple counts for all classes of its subject.
+pub fn classes(hdt: &Hdt, sample_size: usize, seed: u64) -> Vec<ClassEstimate> {
+    let type_id = hdt.vocab.predicate(Vocab::RdfType);
+    if type_id == 0 {
+        return Vec::new();
+    }
+    // class ID -> (hits, property ID -> hits)
+    let mut hits = BTreeMap::<Id, (usize, BTreeMap<Id, usize>)>::new();
+    for t in hdt.triples.sample(sample_size, seed) {
+        for c in SubjectIter::with_pattern(&hdt.triples, &TripleId::new(t.subject_id, type_id, 0)) {
+            let (class_hits, property_hits) = hits.entry(c.object_id).or_default();
+            *class_hits += 1;
+            *property_hits.entry(t.predicate_id).or_default() += 1;
+        }
+    }
+    let population = hdt.triples.len();
+    let mut classes: Vec<ClassEstimate> = hits
+        .into_iter()
+        .filter_map(|(class_id, (class_hits, property_hits))| {
+            let class = hdt
+                .dict
+                .id_to_string(class_id, &IdKind::Object)
+                .map_err(|e| error!("Error translating class: {e}"))
+                .ok()?;
+            Some(ClassEstimate {
+                class,
+                triples: Estimate::from_sample(class_hits, sample_size, population),
+                properties: estimates(hdt, property_hits, sample_size, &IdKind::Predicate),
+            })
+        })
+        .collect();
+    classes.sort_by_key(|c| Reverse(c.triples.value));
+    classes
+}
+
+/// Translate IDs with sample hits to strings with estimates, sorted by descending estimate.
+fn estimates(
+    hdt: &Hdt, hits: BTreeMap<Id, usize>, sample_size: usize, kind: &'static IdKind,
+) -> Vec<(String, Estimate)> {
+    let population = hdt.triples.len();
+    let mut estimates: Vec<(String, Estimate)> = hits
+        .into_iter()
+        .filter_map(|(id, hits)| {
+            let s = hdt.dict.id_to_string(id, kind).map_err(|e| error!("Error translating estimate: {e}")).ok()?;
+            Some((s, Estimate::from_sample(hits, sample_size, population)))
+        })
+        .collect();
+    estimates.sort_by_key(|e| Reverse(e.1.value));
+    estimates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::SchemaSummary;
+    use crate::tests::init;
+    use pretty_assertions::assert_eq;
+    use std::fs::File;
+
+    #[test]
+    fn estimate() {
+        init();
+        assert_eq!(Estimate { value: 0, low: 0, high: 10 }, Estimate::from_sample(0, 0, 10));
+        let e = Estimate::from_sample(500, 1000, 1_000_000);
+        assert_eq!(500_000, e.value);
+        assert!(e.low > 460_000 && e.high < 540_000, "{e:?}");
+
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let hdt = Hdt::new(std::io::BufReader::new(file)).unwrap();
+        let predicates = predicates(&hdt, 2000, 1);
+        assert!(!predicates.is_empty());
+        let mut misses = 0;
+        for (p, e) in &predicates {
+            assert!(e.low <= e.value && e.value <= e.high, "{p} {e:?}");
+            if !e.contains(hdt.triples_with_pattern(None, Some(p), None).count()) {
+                misses += 1;
+            }
+        }
+        // 95% confidence intervals, so a few may miss
+        assert!(misses * 10 <= predicates.len(), "{misses} of {} estimates missed", predicates.len());
+
+        let exact = SchemaSummary::new(&hdt, 0);
+        let classes = classes(&hdt, 2000, 1);
+        assert!(!classes.is_empty());
+        for c in &classes {
+            let summary = exact.classes.iter().find(|s| s.class == c.class).expect("estimated class not found");
+            let triples: usize = summary.properties.iter().map(|p| p.triples).sum();
+            assert!(c.triples.low <= triples && c.triples.value > 0, "{}: {triples} {:?}", c.class, c.triples);
+        }
+    }
+}
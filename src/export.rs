@@ -0,0 +1,176 @@
+//! Export of all triples that can skip terms which cannot be decoded instead of aborting, see [`Hdt::export`].
+use crate::containers::rdf::nt_term;
+use crate::four_sect_dict::IdKind;
+use crate::hdt::{ExportFormat, Hdt, TripleCache};
+use crate::triples::{Id, TripleId};
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+/// What [`Hdt::export`] does with a triple that has a term which cannot be decoded,
+/// for example because of invalid UTF-8 or a corrupt block of a damaged dictionary section, see [`Hdt::recover`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ErrorPolicy {
+    /// Stop at the first such triple with an error of kind [`io::ErrorKind::InvalidData`].
+    #[default]
+    Abort,
+    /// Skip such triples and report their terms in the [`ExportReport`].
+    Skip,
+}
+
+/// Term that could not be decoded, whose triples were skipped.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TermFailure {
+    /// Position of the term in the triples.
+    pub kind: IdKind,
+    /// ID of the term in the dictionary for its position.
+    pub id: Id,
+    /// Message of the error of the first attempt, the term is not decoded again for later triples.
+    pub message: String,
+    /// Number of skipped triples with this term.
+    pub triples: usize,
+}
+
+/// Result of [`Hdt::export`], serializable with the `serde` feature as a machine-readable summary.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExportReport {
+    /// Number of triples written.
+    pub written: usize,
+    /// Number of triples skipped, each of which has at least one failed term.
+    pub skipped: usize,
+    /// Failed terms ordered by position and ID.
+    pub failures: Vec<TermFailure>,
+}
+
+impl ExportReport {
+    /// Whether all triples were written.
+    pub const fn is_complete(&self) -> bool {
+        self.skipped == 0
+    }
+}
+
+const KINDS: [&IdKind; 3] = [&IdKind::Subject, &IdKind::Predicate, &IdKind::Object];
+
+impl Hdt {
+    /// Write all triples in the given RDF format with the given policy for terms that cannot be decoded and return how many were written.
+    /// With [`ErrorPolicy::Skip`], a long running export of a partially damaged file finishes with all other triples
+    /// and the report lists the failed terms, which are only decoded once, so that a bad term shared by many triples does not slow it down.
+    /// Turtle output groups the triples of each subject with predicate and object lists.
+    /// The writer is not flushed.
+    /// # Example
+    /// ```
+    /// use hdt::export::ErrorPolicy;
+    /// use hdt::hdt::ExportFormat;
+    /// use std::io::Write;
+    /// fn dump(hdt: &hdt::Hdt) -> std::io::Result<()> {
+    ///     let mut out = std::io::BufWriter::new(std::fs::File::create("dump.nt")?);
+    ///     let report = hdt.export(&mut out, ExportFormat::NTriples, ErrorPolicy::Skip)?;
+    ///     for failure in &report.failures {
+    ///         eprintln!("skipped {} triples with {:?} {}: {}", failure.triples, failure.kind, failure.id, failure.message);
+    ///     }
+    ///     out.flush()
+    /// }
+    /// ```
+    pub fn export<W: Write>(
+        &self, writer: &mut W, format: ExportFormat, policy: ErrorPolicy,
+    ) -> io::Result<ExportReport> {
+        let mut cache = TripleCache::new(self);
+        let mut failures: BTreeMap<(usize, Id), TermFailure> = BTreeMap::new();
+        let mut report = ExportReport::default();
+        let mut last: Option<TripleId> = None;
+        for t in &self.triples {
+            let ids = [t.subject_id, t.predicate_id, t.object_id];
+            let mut terms = Vec::with_capacity(3);
+            for (pos, (id, kind)) in ids.into_iter().zip(KINDS).enumerate() {
+                if let Some(failure) = failures.get_mut(&(pos, id)) {
+                    failure.triples += 1;
+                    continue;
+                }
+                let term = match pos {
+                    0 => cache.get_s_string(id),
+                    1 => cache.get_p_string(id),
+                    _ => cache.get_o_string(id),
+                };
+                match term {
+                    Ok(term) => terms.push(nt_term(&term)),
+                    Err(e) if policy == ErrorPolicy::Abort => {
+                        return Err(io::Error::new(io::ErrorKind::InvalidData, e))
+                    }
+                    Err(e) => {
+                        let failure = TermFailure { kind: kind.clone(), id, message: e.to_string(), triples: 1 };
+                        failures.insert((pos, id), failure);
+                    }
+                }
+            }
+            let [s, p, o] = &terms[..] else {
+                report.skipped += 1;
+                continue;
+            };
+            match (format, last) {
+                (ExportFormat::NTriples, _) => writeln!(writer, "{s} {p} {o} .")?,
+                (ExportFormat::Turtle, Some(l))
+                    if l.subject_id == t.subject_id && l.predicate_id == t.predicate_id =>
+                {
+                    write!(writer, ", {o}")?;
+                }
+                (ExportFormat::Turtle, Some(l)) if l.subject_id == t.subject_id => {
+                    write!(writer, " ;\n    {p} {o}")?;
+                }
+                (ExportFormat::Turtle, Some(_)) => write!(writer, " .\n{s} {p} {o}")?,
+                (ExportFormat::Turtle, None) => write!(writer, "{s} {p} {o}")?,
+            }
+            last = Some(t);
+            report.written += 1;
+        }
+        if format == ExportFormat::Turtle && last.is_some() {
+            writeln!(writer, " .")?;
+        }
+        report.failures = failures.into_values().collect();
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::init;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn export() {
+        init();
+        let data = std::fs::read("tests/resources/snikmeta.hdt").unwrap();
+        let hdt = Hdt::new(data.as_slice()).unwrap();
+        let mut out = Vec::new();
+        let report = hdt.export(&mut out, ExportFormat::NTriples, ErrorPolicy::Abort).unwrap();
+        assert!(report.is_complete());
+        assert_eq!(hdt.triples.len(), report.written);
+        let mut expected = Vec::new();
+        for (s, p, o) in hdt.triples() {
+            writeln!(expected, "{} {} {} .", nt_term(&s), nt_term(&p), nt_term(&o)).unwrap();
+        }
+        assert_eq!(String::from_utf8(expected).unwrap(), String::from_utf8(out).unwrap());
+
+        let mut turtle = Vec::new();
+        assert_eq!(report, hdt.export(&mut turtle, ExportFormat::Turtle, ErrorPolicy::Skip).unwrap());
+        let turtle = String::from_utf8(turtle).unwrap();
+        // one statement per subject
+        assert_eq!(hdt.triples.bitmap_y.rank(hdt.triples.bitmap_y.len()), turtle.matches(" .\n").count());
+        assert!(turtle.contains(" ;\n    "));
+
+        // the object section fails its checksum, so that recover marks it as damaged and none of its strings can be extracted
+        let mut data = data;
+        let packed = hdt.dict.objects.packed_data().unwrap();
+        let offset = data.windows(packed.len()).position(|w| w == packed).unwrap();
+        data[offset + packed.len() / 2] ^= 1;
+        let damaged = Hdt::recover(data.as_slice()).unwrap();
+        let error = damaged.export(&mut io::sink(), ExportFormat::NTriples, ErrorPolicy::Abort).unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, error.kind());
+        let report = damaged.export(&mut io::sink(), ExportFormat::NTriples, ErrorPolicy::Skip).unwrap();
+        assert!(!report.is_complete());
+        assert_eq!(hdt.triples.len(), report.written + report.skipped);
+        assert!(report.failures.iter().all(|f| matches!(f.kind, IdKind::Object)));
+        assert_eq!(report.skipped, report.failures.iter().map(|f| f.triples).sum::<usize>());
+    }
+}
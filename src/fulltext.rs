@@ -0,0 +1,269 @@
+//! Keyword search over the lexical forms of the literals in the object section, see [`FullTextIndex`].
+//! Literals are split into tokens at every character that is not alphanumeric and the tokens are lowercased,
+//! so that the keywords `Leipzig university` find the literal `"University of Leipzig"@en`.
+use crate::containers::{read_sequence, write_sequence, ControlInfo, ControlType, Sequence};
+use crate::hdt::Hdt;
+use crate::triples::Id;
+use crate::DictSectPFC;
+use bytesize::ByteSize;
+use eyre::{eyre, Result, WrapErr};
+use log::error;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Appended to the path of an HDT file to get the path of its full-text index file, see [`FullTextIndex::load_or_build`].
+pub const FULLTEXT_SUFFIX: &str = ".fulltext.rs-v1";
+
+const FULLTEXT_INDEX: &str = "<https://github.com/konradhoeffner/hdt#fullTextIndex>";
+/// Control information property with the number of strings in the object section the index was built from.
+const OBJECTS_PROPERTY: &str = "objects";
+/// Block size of the front coded token dictionary, the same as hdt-cpp uses for dictionary sections.
+const BLOCK_SIZE: usize = 16;
+
+/// Inverted index from the tokens of literal lexical forms to the IDs of the literals that contain them.
+/// Built from an [`Hdt`] with [`Self::new`] and stored in a separate file with [`Self::write`], because building it reads all literals.
+pub struct FullTextIndex {
+    /// Sorted distinct tokens, the token with ID t occurs in the literals at `postings[offsets[t - 1]..offsets[t]]`.
+    tokens: DictSectPFC,
+    /// Start of the postings of each token followed by the total number of postings.
+    offsets: Sequence,
+    /// Object IDs of the literals containing each token, ascending within each token.
+    postings: Sequence,
+    /// Number of strings in the object section the index was built from, used to detect outdated index files.
+    objects: usize,
+}
+
+impl fmt::Debug for FullTextIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} with {} tokens and {} postings",
+            ByteSize(self.size_in_bytes() as u64),
+            self.tokens.num_strings(),
+            self.postings.len()
+        )
+    }
+}
+
+/// Split the given text into lowercase tokens at every character that is not alphanumeric.
+pub fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric()).filter(|token| !token.is_empty()).map(str::to_lowercase)
+}
+
+impl FullTextIndex {
+    /// Index the lexical forms of all literals in the object section of the given HDT.
+    pub fn new(hdt: &Hdt) -> Self {
+        let offset = hdt.dict.shared.num_strings();
+        let range = hdt.dict.objects.prefix_range("\"");
+        let mut index = BTreeMap::<String, Vec<Id>>::new();
+        for (literal, id) in hdt.dict.objects.iter_from(range.start).take(range.len()).zip(range) {
+            let Ok(literal) = literal.map_err(|e| error!("Error extracting literal {id}: {e}")) else { continue };
+            // strip the quotation marks and the language tag or datatype
+            let Some(lexical) = literal.rfind('"').and_then(|end| literal.get(1..end)) else { continue };
+            for token in tokenize(lexical) {
+                let postings = index.entry(token).or_default();
+                // literals are visited in ascending order, so a repeated token can only be the last entry
+                if postings.last() != Some(&(id + offset)) {
+                    postings.push(id + offset);
+                }
+            }
+        }
+        let tokens: Vec<&String> = index.keys().collect();
+        let mut offsets = Vec::with_capacity(tokens.len() + 1);
+        offsets.push(0);
+        offsets.extend(index.values().scan(0, |end, postings| {
+            *end += postings.len();
+            Some(*end)
+        }));
+        let postings: Vec<usize> = index.values().flatten().copied().collect();
+        FullTextIndex {
+            tokens: DictSectPFC::new(&tokens, BLOCK_SIZE),
+            offsets: Sequence::new(&offsets),
+            postings: Sequence::new(&postings),
+            objects: hdt.dict.objects.num_strings(),
+        }
+    }
+
+    /// Object IDs of the literals that contain all of the given keywords as tokens, in ascending order.
+    /// The keywords are tokenized like the literals, so they are case insensitive and punctuation is ignored.
+    /// Returns no IDs if there are no keywords.
+    /// # Example
+    /// ```
+    /// use hdt::IdKind;
+    /// fn labels(hdt: &hdt::Hdt, index: &hdt::fulltext::FullTextIndex) -> Vec<String> {
+    ///     index.search("entity type").into_iter().filter_map(|id| hdt.dict.id_to_string(id, &IdKind::Object).ok()).collect()
+    /// }
+    /// ```
+    pub fn search(&self, keywords: &str) -> Vec<Id> {
+        let mut lists = Vec::new();
+        for token in tokenize(keywords) {
+            let t = self.tokens.string_to_id(&token);
+            if t == 0 {
+                return Vec::new();
+            }
+            lists.push(self.offsets.get(t - 1)..self.offsets.get(t));
+        }
+        // start with the shortest list and look up its entries in the others
+        lists.sort_unstable_by_key(ExactSizeIterator::len);
+        let Some((first, rest)) = lists.split_first() else { return Vec::new() };
+        first
+            .clone()
+            .map(|i| self.postings.get(i))
+            .filter(|&id| rest.iter().all(|list| self.contains(list.clone(), id)))
+            .collect()
+    }
+
+    /// Whether the given ascending range of postings contains the given object ID, using binary search.
+    fn contains(&self, mut list: std::ops::Range<usize>, id: Id) -> bool {
+        while !list.is_empty() {
+            let mid = list.start + list.len() / 2;
+            match self.postings.get(mid).cmp(&id) {
+                std::cmp::Ordering::Less => list.start = mid + 1,
+                std::cmp::Ordering::Greater => list.end = mid,
+                std::cmp::Ordering::Equal => return true,
+            }
+        }
+        false
+    }
+
+    /// Number of distinct tokens.
+    pub const fn num_tokens(&self) -> usize {
+        self.tokens.num_strings()
+    }
+
+    /// Size in bytes on the heap.
+    pub fn size_in_bytes(&self) -> usize {
+        self.tokens.size_in_bytes() + self.offsets.size_in_bytes() + self.postings.size_in_bytes()
+    }
+
+    /// Write the index in the format expected by [`Self::read`].
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let mut ci = ControlInfo::new(ControlType::Index, FULLTEXT_INDEX);
+        ci.set(OBJECTS_PROPERTY, &self.objects.to_string());
+        ci.write(writer)?;
+        self.tokens.write(writer)?;
+        write_sequence(writer, (0..self.offsets.len()).map(|i| self.offsets.get(i)))?;
+        write_sequence(writer, (0..self.postings.len()).map(|i| self.postings.get(i)))
+    }
+
+    /// Read an index written by [`Self::write`].
+    pub fn read<R: BufRead>(reader: &mut R) -> Result<Self> {
+        let ci = ControlInfo::read(reader).wrap_err("Failed to read full-text index control info")?;
+        if ci.format != FULLTEXT_INDEX {
+            return Err(eyre!("Unknown full-text index format {}", ci.format));
+        }
+        let objects = ci
+            .get(OBJECTS_PROPERTY)
+            .and_then(|objects| objects.parse().ok())
+            .ok_or_else(|| eyre!("Missing number of objects in full-text index control info"))?;
+        let (tokens, crc_handle) = DictSectPFC::read(reader).wrap_err("Failed to read full-text index tokens")?;
+        let offsets = read_sequence(reader).wrap_err("Failed to read full-text index offsets")?;
+        let postings = read_sequence(reader).wrap_err("Failed to read full-text index postings")?;
+        if !crc_handle.join().unwrap() {
+            return Err(eyre!("Invalid CRC32 checksum of the full-text index tokens"));
+        }
+        if offsets.len() != tokens.num_strings() + 1 || offsets.get(offsets.len() - 1) != postings.len() {
+            return Err(eyre!(
+                "Inconsistent full-text index with {} tokens and {} offsets",
+                tokens.num_strings(),
+                offsets.len()
+            ));
+        }
+        Ok(FullTextIndex { tokens, offsets, postings, objects })
+    }
+
+    /// Read the index of the given HDT from the file next to it, with the path of the HDT file followed by [`FULLTEXT_SUFFIX`].
+    /// If that file does not exist or belongs to a different HDT file, the index is built and written to it for the next time.
+    /// If it cannot be written, for example because the directory is read-only, the built index is still returned.
+    /// The index file must be deleted when the HDT file changes.
+    /// # Example
+    /// ```no_run
+    /// let hdt = hdt::Hdt::new(std::io::BufReader::new(std::fs::File::open("dbpedia.hdt").unwrap())).unwrap();
+    /// let index = hdt::fulltext::FullTextIndex::load_or_build(&hdt, "dbpedia.hdt").unwrap();
+    /// println!("{:?}", index.search("Leipzig"));
+    /// ```
+    pub fn load_or_build(hdt: &Hdt, hdt_path: impl AsRef<Path>) -> Result<Self> {
+        let mut index_path = hdt_path.as_ref().as_os_str().to_owned();
+        index_path.push(FULLTEXT_SUFFIX);
+        let index_path = PathBuf::from(index_path);
+        if index_path.exists() {
+            let read =
+                File::open(&index_path).map_err(Into::into).and_then(|f| Self::read(&mut BufReader::new(f)));
+            match read {
+                Ok(index) if index.objects == hdt.dict.objects.num_strings() => return Ok(index),
+                Ok(_) => {
+                    log::warn!("Rebuilding full-text index file {} of a different HDT file", index_path.display());
+                }
+                Err(e) => log::warn!("Rebuilding invalid full-text index file {}: {e}", index_path.display()),
+            }
+        }
+        let index = Self::new(hdt);
+        let mut tmp_path = index_path.clone().into_os_string();
+        tmp_path.push(format!(".{}.tmp", std::process::id()));
+        let written = File::create(&tmp_path).and_then(|file| {
+            let mut writer = BufWriter::new(file);
+            index.write(&mut writer)?;
+            writer.into_inner()?.sync_all()?;
+            std::fs::rename(&tmp_path, &index_path)
+        });
+        if let Err(e) = written {
+            log::warn!("Cannot write full-text index file {}: {e}", index_path.display());
+            let _ = std::fs::remove_file(&tmp_path);
+        }
+        Ok(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::init;
+    use crate::IdKind;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn search() -> Result<()> {
+        init();
+        let path = std::env::temp_dir().join(format!("hdt-fulltext-{}", std::process::id()));
+        std::fs::create_dir_all(&path)?;
+        let hdt_path = path.join("snikmeta.hdt");
+        std::fs::copy("tests/resources/snikmeta.hdt", &hdt_path)?;
+        let hdt = Hdt::new(BufReader::new(File::open(&hdt_path)?)).unwrap();
+        let index = FullTextIndex::load_or_build(&hdt, &hdt_path)?;
+        let index_path = path.join(format!("snikmeta.hdt{FULLTEXT_SUFFIX}"));
+        assert!(index_path.exists());
+        assert!(index.num_tokens() > 10);
+
+        let matches = |keywords: &str| -> Vec<Id> {
+            let keywords: Vec<String> = tokenize(keywords).collect();
+            hdt.dict
+                .terms(&IdKind::Object)
+                .filter(|(_, o)| o.starts_with('"'))
+                .filter(|(_, o)| {
+                    let tokens: Vec<String> = tokenize(&o[1..o.rfind('"').unwrap()]).collect();
+                    keywords.iter().all(|k| tokens.contains(k))
+                })
+                .map(|(id, _)| id)
+                .collect()
+        };
+        let expected = matches("Chapter");
+        assert!(expected.len() > 1);
+        assert_eq!(expected, index.search("chapter"));
+        let expected = matches("snik meta ontology");
+        assert!(!expected.is_empty());
+        assert_eq!(expected, index.search("Ontology, META snik"));
+        assert_eq!(Vec::<Id>::new(), index.search("chapter nonexistingtoken"));
+        assert_eq!(Vec::<Id>::new(), index.search(" ,. "));
+
+        let read = FullTextIndex::load_or_build(&hdt, &hdt_path)?;
+        assert_eq!(index.search("chapter"), read.search("chapter"));
+        std::fs::write(&index_path, b"garbage")?;
+        assert!(FullTextIndex::read(&mut BufReader::new(File::open(&index_path)?)).is_err());
+        assert_eq!(index.search("chapter"), FullTextIndex::load_or_build(&hdt, &hdt_path)?.search("chapter"));
+        std::fs::remove_dir_all(&path)?;
+        Ok(())
+    }
+}
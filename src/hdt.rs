@@ -1,29 +1,156 @@
+use crate::containers::rdf::{nt_term, nt_term_as, XsdString};
 use crate::containers::ControlInfo;
+#[cfg(feature = "mmap")]
+use crate::containers::Sequence;
+use crate::error::{HdtError, Part};
 use crate::four_sect_dict::{DictErr, IdKind};
-use crate::header::Header;
-use crate::triples::{ObjectIter, PredicateIter, PredicateObjectIter, SubjectIter, TripleId, TriplesBitmap};
-use crate::FourSectDict;
+use crate::header::{Header, HeaderMetadata};
+use crate::triples::{
+    pattern_iter, BoundedCount, Cardinality, Id, IndexKind, IndexMemory, ObjectIter, Order, PredicateIter,
+    PredicateObjectIter, SplitMix64, SubjectIter, TripleId, TriplesBitmap,
+};
+use crate::vocab::Vocabulary;
+use crate::{DictSectPFC, FourSectDict};
 use bytesize::ByteSize;
 use eyre::WrapErr;
 use log::{debug, error};
+use std::borrow::Borrow;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet};
 use std::error::Error;
 use std::iter;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
 use thiserror::Error;
 
 /// In-memory representation of an RDF graph loaded from an HDT file.
 /// Allows queries by triple patterns.
 #[derive(Debug)]
 pub struct Hdt {
-    //global_ci: ControlInfo,
+    /// global control information, whose format identifies the HDT version
+    pub global_ci: ControlInfo,
     //header: Header,
     /// in-memory representation of dictionary
     pub dict: FourSectDict,
     /// in-memory representation of triples
     pub triples: TriplesBitmap,
+    /// IDs of well-known vocabulary terms, resolved when loading
+    pub vocab: Vocabulary,
+    /// file that the data is mapped from, see [`Hdt::check_mapping`]
+    #[cfg(feature = "mmap")]
+    pub(crate) mapped: Option<MappedFile>,
 }
 
-type StringTriple = (Arc<str>, Arc<str>, Arc<str>);
+/// Memory mapped file with its length and modification time when it was mapped, to detect later changes.
+#[cfg(feature = "mmap")]
+#[derive(Debug)]
+pub(crate) struct MappedFile {
+    path: std::path::PathBuf,
+    /// open handle, whose metadata stays that of the mapped file even if the path is replaced
+    file: std::fs::File,
+    len: u64,
+    modified: Option<std::time::SystemTime>,
+}
+
+const XSD_STRING: &str = "http://www.w3.org/2001/XMLSchema#string";
+
+/// Appended to the path of an HDT file to get the path of its index file, see [`Hdt::map_with_index`].
+/// Differs from the `.index.v1-1` suffix of hdt-cpp and hdt-java, because the format is not compatible.
+#[cfg(feature = "mmap")]
+pub const INDEX_SUFFIX: &str = ".index.rs-v2";
+
+/// All optional features of this crate with whether each is active, see [`Capabilities::features`].
+/// Every new feature in Cargo.toml needs an entry here, which the tests check.
+pub const FEATURES: [(&str, bool); 15] = [
+    ("sophia", cfg!(feature = "sophia")),
+    ("async", cfg!(feature = "async")),
+    ("arena", cfg!(feature = "arena")),
+    ("mmap", cfg!(feature = "mmap")),
+    ("oxrdf", cfg!(feature = "oxrdf")),
+    ("rio", cfg!(feature = "rio")),
+    ("cli", cfg!(feature = "cli")),
+    ("regex", cfg!(feature = "regex")),
+    ("fulltext", cfg!(feature = "fulltext")),
+    ("rayon", cfg!(feature = "rayon")),
+    ("serde", cfg!(feature = "serde")),
+    ("gzip", cfg!(feature = "gzip")),
+    ("zstd", cfg!(feature = "zstd")),
+    ("http", cfg!(feature = "http")),
+    ("hdtq", cfg!(feature = "hdtq")),
+];
+
+/// Format and implementation details of a loaded HDT file and the active crate features, see [`Hdt::capabilities`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Format of the global control information, which identifies the HDT version, such as `<http://purl.org/HDT/hdt#HDTv1>`.
+    pub format: String,
+    /// Dictionary type, at the moment always the four section dictionary, with a graph section for quad files, because other types are rejected when loading.
+    pub dictionary: &'static str,
+    /// Triples type, at the moment always bitmap triples, with graph information for quad files, because other types are rejected when loading.
+    pub triples: &'static str,
+    /// Order of the triples section, only SPO is fully supported.
+    pub order: Order,
+    /// Whether the object index for ?PO and ??O patterns is available.
+    /// It is built in memory when loading unless it is mapped from an index file with [`Hdt::map_with_index`], index files of other implementations are not read.
+    pub object_index: bool,
+    /// Whether the predicate index for ?P? patterns has been built, see [`TriplesBitmap::build_predicate_index`].
+    pub predicate_index: bool,
+    /// Whether any dictionary section has an invalid checksum, only possible when loaded with [`Hdt::recover`].
+    pub damaged_sections: bool,
+    /// Optional crate features that are active, such as `sophia` and `mmap`, in the order of [`FEATURES`].
+    pub features: Vec<&'static str>,
+}
+
+/// RDF serialization formats for exporting triples, see [`Hdt::export_predicate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// One triple per line, see <https://www.w3.org/TR/n-triples/>.
+    NTriples,
+    /// Triples with the same subject in one statement with an object list, see <https://www.w3.org/TR/turtle/>.
+    Turtle,
+}
+
+/// Score for ordering the results of [`Hdt::triples_with_pattern_ranked`] by one of their components.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rank {
+    /// Number of triples of the term in the whole graph, see [`Hdt::degree`], so that well connected terms come first.
+    Degree,
+    /// Number of results of the pattern with the term, so that the most frequent values come first.
+    Frequency,
+}
+
+/// Compression of HDT data, which [`Hdt::new`] detects and decompresses on the fly if the corresponding feature is active.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// gzip, as in `file.hdt.gz`, decompressed with the `gzip` feature.
+    Gzip,
+    /// Zstandard, as in `file.hdt.zst`, decompressed with the `zstd` feature.
+    Zstd,
+}
+
+impl Compression {
+    /// Detect the compression by the magic bytes at the beginning of the given data, `None` for uncompressed data.
+    /// # Example
+    /// ```
+    /// use hdt::hdt::Compression;
+    /// assert_eq!(Some(Compression::Gzip), Compression::detect(&[0x1f, 0x8b, 8, 0]));
+    /// assert_eq!(None, Compression::detect(b"$HDT"));
+    /// ```
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        if data.starts_with(&[0x1f, 0x8b]) {
+            Some(Compression::Gzip)
+        } else if data.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(Compression::Zstd)
+        } else {
+            None
+        }
+    }
+}
+
+/// Triple of subject, predicate and object strings.
+pub type StringTriple = (Arc<str>, Arc<str>, Arc<str>);
+/// (dictionary section, ID) -> term
+type TermMap = HashMap<(u8, Id), Weak<str>>;
 
 /// The error type for the `translate_id` method.
 #[derive(Error, Debug)]
@@ -37,6 +164,8 @@ pub struct TranslateErr {
 impl Hdt {
     /// Creates an immutable HDT instance containing the dictionary and triples from the given reader.
     /// The reader must point to the beginning of the data of an HDT file as produced by hdt-cpp.
+    /// Data compressed with gzip or Zstandard is decompressed while reading with the `gzip` and `zstd` features, see [`Compression`],
+    /// so that published files such as `dataset.hdt.gz` can be loaded without decompressing them to disk first.
     /// FourSectionDictionary with DictionarySectionPlainFrontCoding and SPO order is the only supported implementation.
     /// The format is specified at <https://www.rdfhdt.org/hdt-binary-format/>, however there are some deviations.
     /// The initial HDT specification at <http://www.w3.org/Submission/2011/03/> is outdated and not supported.
@@ -45,13 +174,238 @@ impl Hdt {
     /// let file = std::fs::File::open("tests/resources/snikmeta.hdt").expect("error opening file");
     /// let hdt = hdt::Hdt::new(std::io::BufReader::new(file)).unwrap();
     /// ```
-    pub fn new<R: std::io::BufRead>(mut reader: R) -> Result<Self, Box<dyn Error>> {
-        ControlInfo::read(&mut reader).wrap_err("Failed to read HDT control info")?;
+    pub fn new<R: std::io::BufRead>(reader: R) -> Result<Self, Box<dyn Error>> {
+        Self::read(reader, false)
+    }
+
+    /// Like [`Hdt::new`] but loads dictionary sections with invalid checksums instead of failing.
+    /// Translating IDs from damaged sections into strings results in an error instead of garbage.
+    /// [`TermPool`] and [`Hdt::facet`] log such errors and skip the affected results.
+    /// Use [`FourSectDict::damaged_ids`] to find out which IDs are affected.
+    /// Only intended for salvaging data from corrupted files, for which no intact copy exists.
+    /// # Example
+    /// ```
+    /// let file = std::fs::File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+    /// let hdt = hdt::Hdt::recover(std::io::BufReader::new(file)).unwrap();
+    /// assert!(hdt.dict.damaged_ids(&hdt::IdKind::Object).is_empty());
+    /// ```
+    pub fn recover<R: std::io::BufRead>(reader: R) -> Result<Self, Box<dyn Error>> {
+        Self::read(reader, true)
+    }
+
+    /// Like [`Hdt::new`] but memory maps the file at the given path instead of reading it into memory.
+    /// The packed strings of the dictionary and the object IDs of the triples (sequence Z) stay in the map,
+    /// so loading is faster, needs less memory and the pages of the file are shared between processes that map it.
+    /// The block offsets of the dictionary, the bitmaps and the predicate IDs (sequence Y) are copied into memory.
+    /// The wavelet matrix of the predicate IDs and the object index are built when loading, see [`Hdt::map_with_index`] for mapping the latter.
+    /// The block offsets are checked against the length of the packed strings, so that lookups stay within the map,
+    /// but the CRC32 checksums of the mapped data are not validated, because that would read the whole file, see [`Hdt::map_verified`].
+    /// The file must not be modified while it is mapped: accessing pages of a truncated file kills the process with SIGBUS,
+    /// which cannot be handled, and changes in place can return wrong results.
+    /// Replace the file by renaming a new file to its path instead, which keeps the mapped file intact,
+    /// and use [`Hdt::check_mapping`] to detect changes, for example before serving queries.
+    /// # Example
+    /// ```
+    /// let hdt = hdt::Hdt::map("tests/resources/snikmeta.hdt").unwrap();
+    /// assert!(hdt.check_mapping().is_ok());
+    /// ```
+    #[cfg(feature = "mmap")]
+    pub fn map<P: AsRef<std::path::Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        Self::map_with(path.as_ref(), None)
+    }
+
+    /// Like [`Hdt::map`] but also verifies the CRC32C checksums of the mapped packed strings and object IDs
+    /// and that all blocks of the dictionary sections can be decoded, which reads the whole file once.
+    /// Intended for files from untrusted sources, for which the structural checks of [`Hdt::map`] are not enough.
+    /// # Example
+    /// ```
+    /// let hdt = hdt::Hdt::map_verified("tests/resources/snikmeta.hdt").unwrap();
+    /// ```
+    #[cfg(feature = "mmap")]
+    pub fn map_verified<P: AsRef<std::path::Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        let hdt = Self::map(path)?;
+        hdt.dict.verify()?;
+        if let Sequence::Mapped(z) = &hdt.triples.adjlist_z.sequence {
+            if !z.verify_crc() {
+                return Err(HdtError::checksum(Part::Sequence, "CRC32C", "Z level sequence").into());
+            }
+        }
+        Ok(hdt)
+    }
+
+    /// Like [`Hdt::map`] but also reads the indexes of the triples from an index file next to the HDT file, with [`INDEX_SUFFIX`] appended to its name,
+    /// see [`TriplesIndex`](crate::triples::TriplesIndex).
+    /// If there is no valid index file yet, the bitmaps, the wavelet matrix and the object index are built and written to it,
+    /// so that later calls skip building them and do not read the bitmaps and the predicate IDs of the HDT file.
+    /// Several processes that map the same file on one host thus share the pages of the dictionary, the object IDs and the object index instead of each holding a copy,
+    /// only the wavelet matrix and the rank and select indexes of the bitmaps are copied into the memory of every process.
+    /// The index file is written to a temporary file first and then renamed, so that other processes never map a partially written index.
+    /// If it cannot be written, for example because the directory is read-only, the indexes stay in memory.
+    /// The index file records a fingerprint of the HDT file and is rebuilt if it does not match,
+    /// for example after the HDT file was regenerated with the same number of triples.
+    /// The fingerprint consists of the length and modification time of the file, the triples control information and the CRC32C checksums
+    /// that are stored in the file for each dictionary section and each part of the triples section, which does not read the data of the sections.
+    /// # Example
+    /// ```no_run
+    /// // each worker process maps the same file
+    /// let hdt = hdt::Hdt::map_with_index("dbpedia.hdt").unwrap();
+    /// ```
+    #[cfg(feature = "mmap")]
+    pub fn map_with_index<P: AsRef<std::path::Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        let path = path.as_ref();
+        let mut index_path = path.as_os_str().to_owned();
+        index_path.push(INDEX_SUFFIX);
+        Self::map_with(path, Some(std::path::Path::new(&index_path)))
+    }
+
+    /// Map the file at the given path and, if an index path is given, map the object index from there or build and write it.
+    #[cfg(feature = "mmap")]
+    fn map_with(path: &std::path::Path, index_path: Option<&std::path::Path>) -> Result<Self, Box<dyn Error>> {
+        let file = std::fs::File::open(path)?;
+        // SAFETY: undefined behaviour if the file is modified while mapped, which is documented above
+        let map = Arc::new(unsafe { memmap2::Mmap::map(&file)? });
+        let mut reader = &map[..];
+        let global_ci = ControlInfo::read(&mut reader).wrap_err("Failed to read HDT control info")?;
+        Header::read(&mut reader).wrap_err("Failed to read HDT header")?;
+        let dict = FourSectDict::read_mapped(&mut reader, &map).wrap_err("Failed to read HDT dictionary")?;
+        let metadata = file.metadata()?;
+        let (len, modified) = (metadata.len(), metadata.modified().ok());
+        let mut fingerprint = None;
+        let mut triples = None;
+        if let Some(index_path) = index_path {
+            let layout =
+                crate::triples::SectionLayout::read(reader).wrap_err("Failed to read HDT triples section")?;
+            let print = Self::fingerprint(&dict, reader, &layout, len, modified);
+            triples = Some(index_path).filter(|index_path| index_path.exists()).and_then(|index_path| {
+                crate::triples::TriplesIndex::map(index_path, &print)
+                    .and_then(|index| TriplesBitmap::read_sect_indexed(reader, &layout, &map, index))
+                    .map_err(|e| log::warn!("Rebuilding invalid index file {}: {e}", index_path.display()))
+                    .ok()
+            });
+            fingerprint = Some((index_path, print)).filter(|_| triples.is_none());
+        }
+        let triples = match triples {
+            Some(triples) => triples,
+            None => TriplesBitmap::read_sect_mapped(&mut reader, &map, None)
+                .wrap_err("Failed to read HDT triples section")?,
+        };
+        let vocab = Vocabulary::resolve(&dict);
+        let mapped_file = MappedFile { path: path.to_owned(), file, len, modified };
+        let mut hdt = Hdt { global_ci, dict, triples, vocab, mapped: Some(mapped_file) };
+        if let Some((index_path, fingerprint)) = fingerprint {
+            hdt.write_index(index_path, &fingerprint);
+        }
+        debug!("HDT size in memory {}, details:", ByteSize(hdt.size_in_bytes() as u64));
+        Ok(hdt)
+    }
+
+    /// Check that the file of an HDT loaded with [`Hdt::map`] still has the length and modification time it had when it was mapped.
+    /// A changed length means the file was truncated or extended in place, so that further queries may crash the process, see [`Hdt::map`].
+    /// Always succeeds for HDT data that is not mapped and for mapped files that were replaced by renaming another file to their path.
+    #[cfg(feature = "mmap")]
+    pub fn check_mapping(&self) -> Result<(), HdtError> {
+        let Some(mapped) = &self.mapped else {
+            return Ok(());
+        };
+        let metadata = mapped.file.metadata()?;
+        let what = if metadata.len() != mapped.len {
+            format!("length {} instead of {}", metadata.len(), mapped.len)
+        } else if metadata.modified().ok() != mapped.modified {
+            "modification time differs".to_owned()
+        } else {
+            return Ok(());
+        };
+        Err(HdtError::Modified { path: mapped.path.clone(), what })
+    }
+
+    /// Identifies the HDT file an index file belongs to from the triples section with the given layout, see [`Hdt::map_with_index`].
+    #[cfg(feature = "mmap")]
+    fn fingerprint(
+        dict: &FourSectDict, section: &[u8], layout: &crate::triples::SectionLayout, len: u64,
+        modified: Option<std::time::SystemTime>,
+    ) -> String {
+        let nanos = modified
+            .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
+            .map_or(0, |duration| duration.as_nanos());
+        let crc32 = crc::Crc::<u32>::new(&crc::CRC_32_ISCSI);
+        let mut digest = crc32.digest();
+        digest.update(&section[..layout.ci_len]);
+        let sections =
+            [&dict.shared, &dict.subjects, &dict.predicates, &dict.objects].into_iter().chain(&dict.graphs);
+        for crc in sections.map(DictSectPFC::stored_crc).chain(layout.crcs()) {
+            digest.update(&crc.to_le_bytes());
+        }
+        format!("{len}-{nanos}-{:08x}", digest.finalize())
+    }
+
+    /// Write the indexes of the triples to the given index file with the given fingerprint of the HDT file and map the object index from there.
+    /// The index file is written to a temporary file first and then renamed, so that other processes never map a partially written index.
+    /// If that fails, the indexes stay in memory.
+    #[cfg(feature = "mmap")]
+    fn write_index(&mut self, index_path: &std::path::Path, fingerprint: &str) {
+        let mut tmp_path = index_path.as_os_str().to_owned();
+        tmp_path.push(format!(".{}.tmp", std::process::id()));
+        let written = std::fs::File::create(&tmp_path)
+            .and_then(|file| {
+                let mut writer = std::io::BufWriter::new(file);
+                self.triples.write_index(&mut writer, fingerprint)?;
+                writer.into_inner()?.sync_all()?;
+                std::fs::rename(&tmp_path, index_path)
+            })
+            .map_err(Into::into)
+            .and_then(|()| crate::triples::TriplesIndex::map(index_path, fingerprint));
+        match written {
+            Ok(index) => self.triples.op_index = index.op_index,
+            Err(e) => {
+                log::warn!(
+                    "Cannot write index file {}, keeping the object index in memory: {e}",
+                    index_path.display()
+                );
+                let _ = std::fs::remove_file(&tmp_path);
+            }
+        }
+    }
+
+    fn read<R: std::io::BufRead>(mut reader: R, recover: bool) -> Result<Self, Box<dyn Error>> {
+        match Compression::detect(reader.fill_buf()?) {
+            None => Self::read_uncompressed(reader, recover),
+            #[cfg(feature = "gzip")]
+            Some(Compression::Gzip) => {
+                // concatenated members, as written by parallel compressors such as pigz, form one file
+                let decoder = flate2::bufread::MultiGzDecoder::new(reader);
+                Self::read_uncompressed(std::io::BufReader::new(decoder), recover)
+            }
+            #[cfg(feature = "zstd")]
+            Some(Compression::Zstd) => {
+                let decoder = zstd::stream::read::Decoder::with_buffer(reader)?;
+                Self::read_uncompressed(std::io::BufReader::new(decoder), recover)
+            }
+            #[allow(unreachable_patterns)]
+            Some(compression) => Err(HdtError::UnsupportedFormat {
+                part: Part::ControlInfo,
+                format: format!("{compression:?} compressed data"),
+                supported:
+                    "compressed data is only supported with the gzip and zstd features, decompress it first",
+            }
+            .into()),
+        }
+    }
+
+    fn read_uncompressed<R: std::io::BufRead>(mut reader: R, recover: bool) -> Result<Self, Box<dyn Error>> {
+        let global_ci = ControlInfo::read(&mut reader).wrap_err("Failed to read HDT control info")?;
         Header::read(&mut reader).wrap_err("Failed to read HDT header")?;
         let unvalidated_dict = FourSectDict::read(&mut reader).wrap_err("Failed to read HDT dictionary")?;
         let triples = TriplesBitmap::read_sect(&mut reader).wrap_err("Failed to read HDT triples section")?;
-        let dict = unvalidated_dict.validate()?;
-        let hdt = Hdt { dict, triples };
+        let dict = if recover { unvalidated_dict.recover() } else { unvalidated_dict.validate()? };
+        let vocab = Vocabulary::resolve(&dict);
+        let hdt = Hdt {
+            global_ci,
+            dict,
+            triples,
+            vocab,
+            #[cfg(feature = "mmap")]
+            mapped: None,
+        };
         debug!("HDT size in memory {}, details:", ByteSize(hdt.size_in_bytes() as u64));
         debug!("{hdt:#?}");
         Ok(hdt)
@@ -62,6 +416,217 @@ impl Hdt {
         self.dict.size_in_bytes() + self.triples.size_in_bytes()
     }
 
+    /// Estimate the memory needed to build the given index for this file, see [`IndexKind::estimate_memory`].
+    /// Allows deciding whether to build the predicate index or to rebuild the object index on a given machine,
+    /// instead of running out of memory halfway through the construction.
+    /// # Example
+    /// ```
+    /// use hdt::triples::IndexKind;
+    /// fn build_if_possible(hdt: &mut hdt::Hdt, available: usize) -> eyre::Result<()> {
+    ///     if hdt.estimate_index_memory(IndexKind::Predicate).peak < available {
+    ///         hdt.triples.build_predicate_index()?;
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub const fn estimate_index_memory(&self, kind: IndexKind) -> IndexMemory {
+        let objects = self.dict.shared.num_strings() + self.dict.objects.num_strings();
+        kind.estimate_memory(
+            self.triples.len(),
+            self.triples.bitmap_y.len(),
+            self.dict.predicates.num_strings(),
+            objects,
+        )
+    }
+
+    /// Format and implementation details of the loaded file and the features this crate was built with,
+    /// so that generic tooling can adapt to them instead of finding out by trial and error.
+    /// # Example
+    /// ```
+    /// fn check(hdt: &hdt::Hdt) {
+    ///     let capabilities = hdt.capabilities();
+    ///     assert_eq!("<http://purl.org/HDT/hdt#HDTv1>", capabilities.format);
+    ///     if !capabilities.features.contains(&"sophia") {
+    ///         println!("no Sophia adapter, only string queries available");
+    ///     }
+    /// }
+    /// ```
+    pub fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            format: self.global_ci.format.clone(),
+            dictionary: self.dict.format(),
+            triples: self.triples.format(),
+            order: self.triples.order().clone(),
+            object_index: true,
+            predicate_index: self.triples.predicate_index.is_some(),
+            damaged_sections: [IdKind::Subject, IdKind::Predicate, IdKind::Object]
+                .iter()
+                .any(|kind| !self.dict.damaged_ids(kind).is_empty()),
+            features: FEATURES.into_iter().filter_map(|(feature, active)| active.then_some(feature)).collect(),
+        }
+    }
+
+    /// Write the HDT in the binary format read by [`Hdt::new`] and by other HDT implementations, such as hdt-cpp and hdt-java.
+    /// The header is generated from the dictionary and triples and describes the dataset with the given base IRI.
+    /// The dictionary and triples sections are written as loaded, so reading the output results in the same IDs.
+    /// # Example
+    /// ```
+    /// fn save(hdt: &hdt::Hdt) -> std::io::Result<()> {
+    ///     let file = std::fs::File::create("copy.hdt")?;
+    ///     hdt.write_to(std::io::BufWriter::new(file), "http://example.org/dataset")
+    /// }
+    /// ```
+    pub fn write_to<W: std::io::Write>(&self, writer: W, base_iri: &str) -> std::io::Result<()> {
+        self.write_with_metadata(writer, base_iri, &HeaderMetadata::default())
+    }
+
+    /// Like [`Self::write_to`] but with additional metadata in the header, such as the original size and provenance triples.
+    /// The dictionary and triples sections are serialized twice, first only to count their size for `hdt:hdtSize`.
+    /// # Example
+    /// ```
+    /// use hdt::builder::HdtBuilder;
+    /// use hdt::header::HeaderMetadata;
+    /// fn convert(nt: &[u8], out: impl std::io::Write) -> eyre::Result<()> {
+    ///     let mut builder = HdtBuilder::new();
+    ///     builder.read_ntriples(nt)?;
+    ///     let metadata = HeaderMetadata::new().with_original_size(builder.original_size());
+    ///     builder.build()?.write_with_metadata(out, "http://example.org/dataset", &metadata)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn write_with_metadata<W: std::io::Write>(
+        &self, mut writer: W, base_iri: &str, metadata: &HeaderMetadata,
+    ) -> std::io::Result<()> {
+        let mut counter = ByteCounter(0);
+        self.dict.write(&mut counter)?;
+        self.triples.write(&mut counter)?;
+        self.global_ci.write(&mut writer)?;
+        Header::write(&mut writer, &self.header_ntriples(base_iri, metadata, counter.0))?;
+        self.dict.write(&mut writer)?;
+        self.triples.write(&mut writer)?;
+        writer.flush()
+    }
+
+    /// Write all triples with the given predicate in the given RDF format and return the number of triples written.
+    /// Objects with the datatype `xsd:string` are written in the given form.
+    /// Uses [`PredicateIter`], so only the triples with that predicate are visited, which is much faster than filtering all triples,
+    /// for example to extract all labels or all `owl:sameAs` links of a large dataset.
+    /// Triples that cannot be translated, for example from damaged dictionary sections, are logged and skipped.
+    /// # Example
+    /// ```
+    /// use hdt::containers::rdf::XsdString;
+    /// use hdt::hdt::ExportFormat;
+    /// fn labels(hdt: &hdt::Hdt) -> std::io::Result<usize> {
+    ///     let mut out = std::io::stdout().lock();
+    ///     let label = "http://www.w3.org/2000/01/rdf-schema#label";
+    ///     hdt.export_predicate(label, &mut out, ExportFormat::NTriples, XsdString::Implicit)
+    /// }
+    /// ```
+    pub fn export_predicate<W: std::io::Write>(
+        &self, p: &str, writer: &mut W, format: ExportFormat, xsd_string: XsdString,
+    ) -> std::io::Result<usize> {
+        let pid = self.dict.string_to_id(p, &IdKind::Predicate);
+        if pid == 0 {
+            return Ok(0);
+        }
+        let p = nt_term(p);
+        let mut cache = TripleCache::new(self);
+        let mut last_subject = 0;
+        let mut written = 0;
+        for t in PredicateIter::new(&self.triples, pid) {
+            let (s, o) = match (cache.get_s_string(t.subject_id), cache.get_o_string(t.object_id)) {
+                (Ok(s), Ok(o)) => (nt_term(&s), nt_term_as(&o, xsd_string)),
+                (Err(e), _) | (_, Err(e)) => {
+                    error!("{}", TranslateErr { e, t });
+                    continue;
+                }
+            };
+            match format {
+                ExportFormat::NTriples => writeln!(writer, "{s} {p} {o} .")?,
+                // the results are grouped by subject, so their objects can be written as object lists
+                ExportFormat::Turtle if t.subject_id == last_subject => write!(writer, ", {o}")?,
+                ExportFormat::Turtle => {
+                    if last_subject != 0 {
+                        writeln!(writer, " .")?;
+                    }
+                    write!(writer, "{s} {p} {o}")?;
+                }
+            }
+            last_subject = t.subject_id;
+            written += 1;
+        }
+        if format == ExportFormat::Turtle && last_subject != 0 {
+            writeln!(writer, " .")?;
+        }
+        Ok(written)
+    }
+
+    /// Metadata in N-Triples with the same structure as the headers generated by hdt-cpp.
+    /// The given HDT size is the number of bytes of the dictionary and triples sections.
+    fn header_ntriples(&self, base_iri: &str, metadata: &HeaderMetadata, hdt_size: usize) -> String {
+        const HDT: &str = "http://purl.org/HDT/hdt#";
+        const VOID: &str = "http://rdfs.org/ns/void#";
+        const FORMAT: &str = "http://purl.org/dc/terms/format";
+        const TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+        const ISSUED: &str = "http://purl.org/dc/terms/issued";
+        let dict = &self.dict;
+        let shared = dict.shared.num_strings();
+        let size_strings: usize = [&dict.shared, &dict.subjects, &dict.predicates, &dict.objects]
+            .into_iter()
+            .chain(&dict.graphs)
+            .map(DictSectPFC::packed_len)
+            .sum();
+        let order = format!("{:?}", self.triples.order());
+        let base = format!("<{base_iri}>");
+        let line = |s: &str, p: &str, o: &str| format!("{s} <{p}> {o} .\n");
+        let mut lines = vec![
+            line(&base, TYPE, &format!("<{HDT}Dataset>")),
+            line(&base, TYPE, &format!("<{VOID}Dataset>")),
+            line(&base, &format!("{VOID}triples"), &format!("\"{}\"", self.triples.len())),
+            line(&base, &format!("{VOID}properties"), &format!("\"{}\"", dict.predicates.num_strings())),
+            line(
+                &base,
+                &format!("{VOID}distinctSubjects"),
+                &format!("\"{}\"", shared + dict.subjects.num_strings()),
+            ),
+            line(
+                &base,
+                &format!("{VOID}distinctObjects"),
+                &format!("\"{}\"", shared + dict.objects.num_strings()),
+            ),
+            line(&base, &format!("{HDT}statisticalInformation"), "_:statistics"),
+        ];
+        if metadata.issued().is_some() {
+            lines.push(line(&base, &format!("{HDT}publicationInformation"), "_:publicationInformation"));
+        }
+        lines.extend([
+            line(&base, &format!("{HDT}formatInformation"), "_:format"),
+            line("_:format", &format!("{HDT}dictionary"), "_:dictionary"),
+            line("_:format", &format!("{HDT}triples"), "_:triples"),
+            line("_:dictionary", FORMAT, dict.format()),
+            line("_:dictionary", &format!("{HDT}dictionarynumSharedSubjectObject"), &format!("\"{shared}\"")),
+            line("_:dictionary", &format!("{HDT}dictionarymapping"), "\"1\""),
+            line("_:dictionary", &format!("{HDT}dictionarysizeStrings"), &format!("\"{size_strings}\"")),
+            line(
+                "_:dictionary",
+                &format!("{HDT}dictionaryblockSize"),
+                &format!("\"{}\"", dict.shared.block_size()),
+            ),
+            line("_:triples", FORMAT, self.triples.format()),
+            line("_:triples", &format!("{HDT}triplesnumTriples"), &format!("\"{}\"", self.triples.len())),
+            line("_:triples", &format!("{HDT}triplesOrder"), &format!("\"{order}\"")),
+        ]);
+        if let Some(original_size) = metadata.original_size() {
+            lines.push(line("_:statistics", &format!("{HDT}originalSize"), &format!("\"{original_size}\"")));
+        }
+        lines.push(line("_:statistics", &format!("{HDT}hdtSize"), &format!("\"{hdt_size}\"")));
+        if let Some(issued) = metadata.issued() {
+            lines.push(line("_:publicationInformation", ISSUED, &format!("\"{issued}\"")));
+        }
+        lines.push(metadata.ntriples());
+        lines.concat()
+    }
+
     /// An iterator visiting *all* triples as strings in order.
     /// Using this method with a filter can be inefficient for large graphs,
     /// because the strings are stored in compressed form and must be decompressed and allocated.
@@ -77,6 +642,29 @@ impl Hdt {
         self.triples.into_iter().map(move |ids| triple_cache.translate(ids).unwrap())
     }
 
+    /// Like [`Self::triples`] but decompressed on the threads of the rayon thread pool, each of which handles chunks of consecutive subjects.
+    /// Collecting the triples preserves the order of [`Self::triples`].
+    /// # Example
+    /// ```
+    /// use hdt::rayon::prelude::*;
+    /// fn count_literals(hdt: &hdt::Hdt) -> usize {
+    ///     hdt.par_triples().filter(|(_, _, o)| o.starts_with('"')).count()
+    /// }
+    /// ```
+    #[cfg(feature = "rayon")]
+    pub fn par_triples(&self) -> impl rayon::iter::ParallelIterator<Item = StringTriple> + '_ {
+        use rayon::prelude::*;
+        let subjects = self.triples.bitmap_y.rank(self.triples.bitmap_y.len());
+        // more chunks than threads, so that threads with subjects that have few triples take over more chunks
+        let chunk = subjects.div_ceil(rayon::current_num_threads() * 16).max(1);
+        (0..subjects.div_ceil(chunk)).into_par_iter().flat_map_iter(move |i| {
+            let start = i * chunk + 1;
+            let mut triple_cache = TripleCache::new(self);
+            SubjectIter::with_subjects(&self.triples, start..start + chunk)
+                .map(move |ids| triple_cache.translate(ids).unwrap())
+        })
+    }
+
     /// Get all subjects with the given property and object (?PO pattern).
     /// Use this over `triples_with_pattern(None,Some(p),Some(o))` if you don't need whole triples.
     /// # Example
@@ -187,20 +775,447 @@ impl Hdt {
             (None, None, None) => Box::new(self.triples()),
         }
     }
+
+    /// Like [`Self::triples_with_pattern`] but the iterator holds a reference count of the HDT instead of borrowing it.
+    /// # Example
+    /// ```
+    /// use std::sync::Arc;
+    /// fn labels(hdt: Arc<hdt::Hdt>) -> hdt::hdt::SharedTripleIter {
+    ///     hdt::Hdt::shared_triples_with_pattern(&hdt, None, Some("http://www.w3.org/2000/01/rdf-schema#label"), None)
+    /// }
+    /// ```
+    pub fn shared_triples_with_pattern(
+        hdt: &Arc<Self>, sp: Option<&str>, pp: Option<&str>, op: Option<&str>,
+    ) -> SharedTripleIter {
+        let ids: Box<dyn Iterator<Item = TripleId> + Send> = match hdt.pattern_ids(sp, pp, op) {
+            Some(pat) => pattern_iter(SharedTriples(Arc::clone(hdt)), &pat),
+            None => Box::new(iter::empty()),
+        };
+        SharedTripleIter { hdt: Arc::clone(hdt), ids: ids.fuse(), last: None }
+    }
+
+    /// Like [`Self::triples_with_pattern`] but with a configurable string type for the terms, such as `Box<str>`, `String` or a small string type from another crate.
+    /// `Arc<str>` lets equal terms of adjacent triples share one allocation but has two words of reference counts per allocation.
+    /// `Box<str>` is smaller when collecting many results but each term is a separate allocation.
+    /// # Example
+    /// ```
+    /// fn labels(hdt: &hdt::Hdt) -> Vec<(Box<str>, Box<str>, Box<str>)> {
+    ///     hdt.triples_with_pattern_as(None, Some("http://www.w3.org/2000/01/rdf-schema#label"), None).collect()
+    /// }
+    /// ```
+    pub fn triples_with_pattern_as<'a, S: From<String> + Clone + 'a>(
+        &'a self, sp: Option<&str>, pp: Option<&str>, op: Option<&str>,
+    ) -> Box<dyn Iterator<Item = (S, S, S)> + 'a> {
+        let Some(pat) = self.pattern_ids(sp, pp, op) else {
+            return Box::new(iter::empty());
+        };
+        // reuse the terms of the previous triple for components with the same ID
+        let mut last: Option<(TripleId, (S, S, S))> = None;
+        Box::new(self.triples.triples_with_pattern(&pat).filter_map(move |t| {
+            let term = |id: Id, last_term: Option<(Id, &S)>, kind| match last_term {
+                Some((last_id, term)) if last_id == id => Ok(term.clone()),
+                _ => self.dict.id_to_string(id, kind).map(S::from).map_err(|e| TranslateErr { e, t }),
+            };
+            let l = last.as_ref();
+            let translate = || -> Result<(S, S, S), TranslateErr> {
+                Ok((
+                    term(t.subject_id, l.map(|l| (l.0.subject_id, &l.1 .0)), &IdKind::Subject)?,
+                    term(t.predicate_id, l.map(|l| (l.0.predicate_id, &l.1 .1)), &IdKind::Predicate)?,
+                    term(t.object_id, l.map(|l| (l.0.object_id, &l.1 .2)), &IdKind::Object)?,
+                ))
+            };
+            let triple = translate().map_err(|e| error!("{e}")).ok()?;
+            last = Some((t, triple.clone()));
+            Some(triple)
+        }))
+    }
+
+    /// Translate a triple pattern of strings into a triple pattern of IDs, where `None` and 0 stand for a variable.
+    /// Returns `None` if at least one of the terms does not exist in the graph.
+    pub fn pattern_ids(&self, sp: Option<&str>, pp: Option<&str>, op: Option<&str>) -> Option<TripleId> {
+        let id = |x: Option<&str>, kind| match x {
+            None => Some(0),
+            Some(s) => Some(self.dict.string_to_id(s, kind)).filter(|&id| id != 0),
+        };
+        Some(TripleId::new(id(sp, &IdKind::Subject)?, id(pp, &IdKind::Predicate)?, id(op, &IdKind::Object)?))
+    }
+
+    /// Number of triples matching the given pattern, exact if it can be determined within the time budget and otherwise a lower bound,
+    /// see [`TriplesBitmap::count_with_pattern_bounded`]. Terms that do not exist in the graph result in an exact count of 0.
+    /// # Example
+    /// ```
+    /// fn estimate(hdt: hdt::Hdt) {
+    ///     let p = Some("http://www.w3.org/2000/01/rdf-schema#label");
+    ///     let count = hdt.count_with_pattern_bounded(None, p, None, std::time::Duration::from_millis(100));
+    ///     println!("{}{}", if count.at_least { ">=" } else { "" }, count.count);
+    /// }
+    /// ```
+    pub fn count_with_pattern_bounded(
+        &self, sp: Option<&str>, pp: Option<&str>, op: Option<&str>, budget: Duration,
+    ) -> BoundedCount {
+        match self.pattern_ids(sp, pp, op) {
+            Some(pat) => self.triples.count_with_pattern_bounded(&pat, budget),
+            None => BoundedCount { count: 0, at_least: false },
+        }
+    }
+
+    /// Number of results of the given ID pattern without iterating over them, see [`TriplesBitmap::count_pattern`].
+    /// Use [`Self::pattern_ids`] to translate a pattern of strings.
+    /// # Example
+    /// ```
+    /// fn selectivity(hdt: &hdt::Hdt, s: Option<&str>, p: Option<&str>, o: Option<&str>) -> usize {
+    ///     hdt.pattern_ids(s, p, o).map_or(0, |pat| hdt.count_pattern(&pat).count)
+    /// }
+    /// ```
+    pub fn count_pattern(&self, pat: &TripleId) -> Cardinality {
+        self.triples.count_pattern(pat)
+    }
+
+    /// Build a literal in the string format of the HDT dictionary, for use as an object in a triple pattern.
+    /// A language tag takes precedence over the datatype, `xsd:string` is implicit and thus omitted.
+    /// The datatype IRI may be given with or without angle brackets.
+    /// # Example
+    /// ```
+    /// let lit = hdt::Hdt::literal_matcher("2022-10-20", Some("http://www.w3.org/2001/XMLSchema#date"), None);
+    /// assert_eq!("\"2022-10-20\"^^<http://www.w3.org/2001/XMLSchema#date>", lit);
+    /// assert_eq!("\"top class\"@en", hdt::Hdt::literal_matcher("top class", None, Some("en")));
+    /// ```
+    pub fn literal_matcher(lexical: &str, datatype: Option<&str>, lang: Option<&str>) -> String {
+        if let Some(lang) = lang {
+            return format!("\"{lexical}\"@{lang}");
+        }
+        match datatype.map(|dt| dt.strip_prefix('<').and_then(|dt| dt.strip_suffix('>')).unwrap_or(dt)) {
+            None | Some(XSD_STRING) => format!("\"{lexical}\""),
+            Some(dt) => format!("\"{lexical}\"^^<{dt}>"),
+        }
+    }
+
+    /// IDs of the literal objects whose lexical form matches the given regular expression, in ascending order.
+    /// Only the literals of the object section are decoded, each of them once, without iterating over any triples.
+    /// Literals never occur in the shared section and are contiguous in the object section, because they all start with a quotation mark,
+    /// so they are located with [`DictSectPFC::prefix_range`].
+    /// # Example
+    /// ```
+    /// fn years(hdt: &hdt::Hdt) -> usize {
+    ///     let re = hdt::regex::Regex::new(r"^\d{4}$").unwrap();
+    ///     hdt.literals_matching(&re).count()
+    /// }
+    /// ```
+    #[cfg(feature = "regex")]
+    pub fn literals_matching<'a>(&'a self, re: &'a regex::Regex) -> impl Iterator<Item = Id> + 'a {
+        let offset = self.dict.shared.num_strings();
+        let range = self.dict.objects.prefix_range("\"");
+        self.dict.objects.iter_from(range.start).take(range.len()).zip(range).filter_map(move |(literal, id)| {
+            let literal = literal.map_err(|e| error!("Error extracting literal {id}: {e}")).ok()?;
+            // strip the quotation marks and the language tag or datatype
+            let lexical = literal.get(1..literal.rfind('"')?)?;
+            re.is_match(lexical).then_some(id + offset)
+        })
+    }
+
+    /// Get all triples that fit the given subject and predicate and whose object is a literal whose lexical form matches the given regular expression,
+    /// see [`Self::literals_matching`]. The triples are grouped by object in the order of the object IDs.
+    /// # Example
+    /// ```
+    /// fn labels_containing_system(hdt: &hdt::Hdt) -> Vec<hdt::hdt::StringTriple> {
+    ///     let re = hdt::regex::Regex::new(r"(?i)\bsystem\b").unwrap();
+    ///     let label = Some("http://www.w3.org/2000/01/rdf-schema#label");
+    ///     hdt.triples_with_literal_matching(None, label, &re).collect()
+    /// }
+    /// ```
+    #[cfg(feature = "regex")]
+    pub fn triples_with_literal_matching<'a>(
+        &'a self, sp: Option<&str>, pp: Option<&str>, re: &'a regex::Regex,
+    ) -> Box<dyn Iterator<Item = StringTriple> + 'a> {
+        let Some(pat) = self.pattern_ids(sp, pp, None) else {
+            return Box::new(iter::empty());
+        };
+        let mut cache = TripleCache::new(self);
+        Box::new(
+            self.literals_matching(re)
+                .flat_map(move |o| {
+                    self.triples.triples_with_pattern(&TripleId::new(pat.subject_id, pat.predicate_id, o))
+                })
+                .filter_map(move |t| cache.translate(t).map_err(|e| error!("{e}")).ok()),
+        )
+    }
+
+    /// Get all triples with a literal object of the given lexical form whose language is the first match in the given fallback chain.
+    /// Each entry of the chain is a language tag, or `None` for the plain literal without language tag (`xsd:string`).
+    /// Language tags are compared as stored in the dictionary, see [`canonical_language_tag`](crate::containers::rdf::canonical_language_tag).
+    /// Triples are returned in the order of the chain and a subject-predicate pair that matches an earlier entry is skipped for later entries,
+    /// so that each resource contributes its literals in the most preferred available language only.
+    /// # Example
+    /// Resources labelled "Berlin" in German, or in English or without language tag if there is no German label:
+    /// ```
+    /// fn berlin(hdt: &hdt::Hdt) -> Vec<hdt::hdt::StringTriple> {
+    ///     let label = Some("http://www.w3.org/2000/01/rdf-schema#label");
+    ///     hdt.triples_with_lang_fallback(None, label, "Berlin", &[Some("de"), Some("en"), None]).collect()
+    /// }
+    /// ```
+    pub fn triples_with_lang_fallback<'a>(
+        &'a self, sp: Option<&str>, pp: Option<&str>, lexical: &str, chain: &[Option<&str>],
+    ) -> Box<dyn Iterator<Item = StringTriple> + 'a> {
+        let patterns: Vec<TripleId> = chain
+            .iter()
+            .filter_map(|lang| self.pattern_ids(sp, pp, Some(&Self::literal_matcher(lexical, None, *lang))))
+            .collect();
+        let mut matched: HashSet<(Id, Id)> = HashSet::new();
+        let mut cache = TripleCache::new(self);
+        Box::new(
+            patterns
+                .into_iter()
+                .flat_map(|pat| self.triples.triples_with_pattern(&pat))
+                // the object is fixed per entry, so a pair can only be matched again by a later entry
+                .filter(move |t| matched.insert((t.subject_id, t.predicate_id)))
+                .filter_map(move |t| cache.translate(t).map_err(|e| error!("{e}")).ok()),
+        )
+    }
+
+    /// Group the triples that fit the given pattern by their term in the given position and count them.
+    /// Counting is done on IDs, so that each distinct term is only translated once.
+    /// Returns pairs of terms and triple counts, sorted by descending count.
+    /// # Example
+    /// Properties of a resource with the number of values for each of them:
+    /// ```
+    /// fn properties(hdt: hdt::Hdt) {
+    ///     let s = Some("http://dbpedia.org/resource/Leipzig");
+    ///     for (p, count) in hdt.facet(s, None, None, &hdt::IdKind::Predicate) {
+    ///         println!("{p} {count}");
+    ///     }
+    /// }
+    /// ```
+    pub fn facet(
+        &self, sp: Option<&str>, pp: Option<&str>, op: Option<&str>, kind: &'static IdKind,
+    ) -> Vec<(String, usize)> {
+        let Some(pat) = self.pattern_ids(sp, pp, op) else {
+            return Vec::new();
+        };
+        let component = match kind {
+            IdKind::Subject => |t: TripleId| t.subject_id,
+            IdKind::Predicate => |t: TripleId| t.predicate_id,
+            IdKind::Object => |t: TripleId| t.object_id,
+        };
+        let mut counts = BTreeMap::<Id, usize>::new();
+        for t in self.triples.triples_with_pattern(&pat) {
+            *counts.entry(component(t)).or_default() += 1;
+        }
+        let mut facets: Vec<(String, usize)> = counts
+            .into_iter()
+            .filter_map(|(id, count)| {
+                self.dict
+                    .id_to_string(id, kind)
+                    .map_err(|e| error!("Error creating facet: {e}"))
+                    .ok()
+                    .map(|s| (s, count))
+            })
+            .collect();
+        // stable sort keeps the ID order for equal counts
+        facets.sort_by_key(|f| Reverse(f.1));
+        facets
+    }
+
+    /// Number of triples in which the term with the given ID of the given kind occurs, computed with select queries on the bitmaps.
+    /// Subjects and objects of the shared section are the same node, so their degree is the sum of their triples as subject and as object.
+    /// The degree of a predicate is its number of triples, which is estimated like in [`TriplesBitmap::count_pattern`].
+    /// Returns 0 for IDs that do not exist.
+    /// # Example
+    /// ```
+    /// fn hub(hdt: &hdt::Hdt) -> Option<hdt::triples::Id> {
+    ///     let subjects = hdt.dict.shared.num_strings() + hdt.dict.subjects.num_strings();
+    ///     (1..=subjects).max_by_key(|&s| hdt.degree(s, &hdt::IdKind::Subject))
+    /// }
+    /// ```
+    pub fn degree(&self, id: Id, kind: &IdKind) -> usize {
+        let shared = id <= self.dict.shared.num_strings();
+        match kind {
+            IdKind::Predicate => self.triples.count_pattern(&TripleId::new(0, id, 0)).count,
+            IdKind::Subject | IdKind::Object if shared => {
+                self.triples.subject_degree(id) + self.triples.object_degree(id)
+            }
+            IdKind::Subject => self.triples.subject_degree(id),
+            IdKind::Object => self.triples.object_degree(id),
+        }
+    }
+
+    /// Get the triples that fit the given pattern ordered by the given score of their term in the given position in descending order,
+    /// for example to show the most connected entities first.
+    /// Triples with the same score keep the order of [`Self::triples_with_pattern`], so ranking by a bound position changes nothing.
+    /// Only the IDs are scored and sorted, each distinct term is scored once and only the first `limit` triples are translated into strings.
+    /// # Example
+    /// The ten classes with the most triples with their instances first:
+    /// ```
+    /// use hdt::hdt::Rank;
+    /// fn popular_classes(hdt: &hdt::Hdt) -> Vec<hdt::hdt::StringTriple> {
+    ///     let rdf_type = Some("http://www.w3.org/1999/02/22-rdf-syntax-ns#type");
+    ///     hdt.triples_with_pattern_ranked(None, rdf_type, None, &hdt::IdKind::Object, Rank::Degree, 10)
+    /// }
+    /// ```
+    pub fn triples_with_pattern_ranked(
+        &self, sp: Option<&str>, pp: Option<&str>, op: Option<&str>, kind: &'static IdKind, rank: Rank,
+        limit: usize,
+    ) -> Vec<StringTriple> {
+        let Some(pat) = self.pattern_ids(sp, pp, op) else {
+            return Vec::new();
+        };
+        let component = match kind {
+            IdKind::Subject => |t: &TripleId| t.subject_id,
+            IdKind::Predicate => |t: &TripleId| t.predicate_id,
+            IdKind::Object => |t: &TripleId| t.object_id,
+        };
+        let ids: Vec<TripleId> = self.triples.triples_with_pattern(&pat).collect();
+        let mut scores = HashMap::<Id, usize>::new();
+        match rank {
+            Rank::Degree => {
+                for t in &ids {
+                    scores.entry(component(t)).or_insert_with(|| self.degree(component(t), kind));
+                }
+            }
+            Rank::Frequency => {
+                for t in &ids {
+                    *scores.entry(component(t)).or_default() += 1;
+                }
+            }
+        }
+        // keep the best results in a min-heap of the given size, the position keeps the pattern order for equal scores
+        let mut best = BinaryHeap::with_capacity(limit.min(ids.len()) + 1);
+        for (pos, t) in ids.iter().enumerate() {
+            best.push(Reverse((scores[&component(t)], Reverse(pos))));
+            if best.len() > limit {
+                best.pop();
+            }
+        }
+        let mut cache = TripleCache::new(self);
+        best.into_sorted_vec()
+            .into_iter()
+            .filter_map(|Reverse((_, Reverse(pos)))| cache.translate(ids[pos]).map_err(|e| error!("{e}")).ok())
+            .collect()
+    }
+
+    /// Random walks along the outgoing triples of subject IDs, for example for node2vec-style embeddings or for sampling a graph for visualization.
+    /// Performs `n_walks` walks of up to `walk_length` steps from each of the start nodes and returns the traversed triples of each walk.
+    /// Each step follows one of the triples of the current node with uniform probability, so that neighbours connected by multiple predicates are more likely.
+    /// A walk continues with the object of the chosen triple and ends early at objects that are not subjects, such as literals.
+    /// The same seed always yields the same walks.
+    /// # Example
+    /// ```
+    /// fn node_sequences(hdt: &hdt::Hdt) -> Vec<Vec<hdt::triples::Id>> {
+    ///     hdt.random_walks(&[1, 2, 3], 10, 5, 42)
+    ///         .into_iter()
+    ///         .map(|walk| walk.iter().map(|t| t.subject_id).chain(walk.last().map(|t| t.object_id)).collect())
+    ///         .collect()
+    /// }
+    /// ```
+    pub fn random_walks(
+        &self, start_nodes: &[Id], walk_length: usize, n_walks: usize, seed: u64,
+    ) -> Vec<Vec<TripleId>> {
+        // object IDs in the shared section are also subject IDs
+        let shared_size = self.dict.shared.num_strings() as Id;
+        let mut rng = SplitMix64::new(seed);
+        let mut walks = Vec::with_capacity(start_nodes.len() * n_walks);
+        for &start in start_nodes {
+            for _ in 0..n_walks {
+                let mut walk = Vec::with_capacity(walk_length);
+                let mut node = start;
+                while walk.len() < walk_length {
+                    let range = self.triples.subject_range_z(node);
+                    if range.is_empty() {
+                        break;
+                    }
+                    let t = self.triples.triple_at(range.start + rng.below(range.len()));
+                    walk.push(t);
+                    if t.object_id > shared_size {
+                        break;
+                    }
+                    node = t.object_id;
+                }
+                walks.push(walk);
+            }
+        }
+        walks
+    }
+}
+
+/// Shared ownership of an [`Hdt`] that gives access to its triples, so that iterators like [`SubjectIterOf`](crate::triples::SubjectIterOf) can own their data.
+/// Such iterators do not borrow, so they can be returned from functions, sent to other threads and held across await points.
+/// # Example
+/// ```
+/// use hdt::hdt::SharedTriples;
+/// use hdt::triples::SubjectIterOf;
+/// use std::sync::Arc;
+/// fn all_ids(hdt: Arc<hdt::Hdt>) -> std::thread::JoinHandle<usize> {
+///     let iter = SubjectIterOf::new(SharedTriples(hdt));
+///     std::thread::spawn(move || iter.count())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SharedTriples(pub Arc<Hdt>);
+
+impl Borrow<TriplesBitmap> for SharedTriples {
+    fn borrow(&self) -> &TriplesBitmap {
+        &self.0.triples
+    }
 }
 
-/// A TripleCache stores the `Arc<str>` of the last returned triple
+/// Iterator over string triples that owns a shared [`Hdt`], see [`Hdt::shared_triples_with_pattern`].
+/// Reuses the strings of the previous triple for components with the same ID.
+pub struct SharedTripleIter {
+    hdt: Arc<Hdt>,
+    ids: iter::Fuse<Box<dyn Iterator<Item = TripleId> + Send>>,
+    last: Option<(TripleId, StringTriple)>,
+}
+
+impl SharedTripleIter {
+    fn translate(&self, t: TripleId) -> Result<StringTriple, TranslateErr> {
+        let last = self.last.as_ref();
+        let term = |id: Id, last_id: Option<Id>, last_term: Option<&Arc<str>>, kind| match last_term {
+            Some(term) if last_id == Some(id) => Ok(term.clone()),
+            _ => self.hdt.dict.id_to_string(id, kind).map(Arc::from).map_err(|e| TranslateErr { e, t }),
+        };
+        Ok((
+            term(t.subject_id, last.map(|l| l.0.subject_id), last.map(|l| &l.1 .0), &IdKind::Subject)?,
+            term(t.predicate_id, last.map(|l| l.0.predicate_id), last.map(|l| &l.1 .1), &IdKind::Predicate)?,
+            term(t.object_id, last.map(|l| l.0.object_id), last.map(|l| &l.1 .2), &IdKind::Object)?,
+        ))
+    }
+}
+
+impl Iterator for SharedTripleIter {
+    type Item = StringTriple;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let t = self.ids.next()?;
+            match self.translate(t) {
+                Ok(triple) => {
+                    self.last = Some((t, triple.clone()));
+                    return Some(triple);
+                }
+                Err(e) => error!("{e}"),
+            }
+        }
+    }
+}
+
+impl iter::FusedIterator for SharedTripleIter {}
+
+/// A TripleCache stores the `Arc<str>` of the last returned triple and of all predicates returned so far.
+/// Extraction reuses one buffer, so that a new term costs a single allocation for its `Arc<str>`
+/// and a full scan in SPO order mostly allocates only the objects.
 #[derive(Clone, Debug)]
 pub struct TripleCache<'a> {
     hdt: &'a super::Hdt,
     idx: [usize; 3],
     arc: [Option<Arc<str>>; 3],
+    // predicates by ID - 1, there are usually few of them but they alternate within each subject
+    predicates: Vec<Option<Arc<str>>>,
+    buf: String,
 }
 
 impl<'a> TripleCache<'a> {
     /// Build a new [`TripleCache`] for the given [`Hdt`]
     pub const fn new(hdt: &'a super::Hdt) -> Self {
-        TripleCache { hdt, idx: [0; 3], arc: [None, None, None] }
+        TripleCache { hdt, idx: [0; 3], arc: [None, None, None], predicates: Vec::new(), buf: String::new() }
     }
 
     /// Get the string representation of the subject `sid`.
@@ -230,22 +1245,525 @@ impl<'a> TripleCache<'a> {
     fn get_x_string(&mut self, i: usize, pos: usize, kind: &'static IdKind) -> Result<Arc<str>, DictErr> {
         debug_assert!(i != 0);
         if self.idx[pos] == i {
-            Ok(self.arc[pos].as_ref().unwrap().clone())
-        } else {
-            let ret: Arc<str> = self.hdt.dict.id_to_string(i, kind)?.into();
-            self.arc[pos] = Some(ret.clone());
-            self.idx[pos] = i;
-            Ok(ret)
+            return Ok(self.arc[pos].as_ref().unwrap().clone());
+        }
+        let ret = match pos {
+            1 => {
+                if self.predicates.len() < i {
+                    self.predicates.resize(i, None);
+                }
+                if let Some(p) = &self.predicates[i - 1] {
+                    p.clone()
+                } else {
+                    let p = self.extract(i, kind)?;
+                    self.predicates[i - 1] = Some(p.clone());
+                    p
+                }
+            }
+            // an object from the shared section may be the current subject
+            2 if self.idx[0] == i && i <= self.hdt.dict.shared.num_strings() => self.arc[0].clone().unwrap(),
+            _ => self.extract(i, kind)?,
+        };
+        self.arc[pos] = Some(ret.clone());
+        self.idx[pos] = i;
+        Ok(ret)
+    }
+
+    fn extract(&mut self, i: usize, kind: &'static IdKind) -> Result<Arc<str>, DictErr> {
+        self.hdt.dict.id_to_string_into(i, kind, &mut self.buf)?;
+        Ok(Arc::from(self.buf.as_str()))
+    }
+}
+
+/// A TermPool interns the terms it materializes, so that the same term returns the same `Arc<str>` across queries.
+/// Terms are only held weakly and are freed once no result references them anymore.
+/// Subjects and objects in the shared dictionary section are the same term and share the same `Arc<str>`.
+/// # Example
+/// ```
+/// fn labels(hdt: &hdt::Hdt) {
+///     let pool = hdt::hdt::TermPool::new(hdt);
+///     let label = Some("http://www.w3.org/2000/01/rdf-schema#label");
+///     let first: Vec<_> = pool.triples_with_pattern(None, label, None).collect();
+///     // the predicate of both queries points to the same string in memory
+///     let second: Vec<_> = pool.triples_with_pattern(None, label, None).collect();
+/// }
+/// ```
+#[derive(Debug)]
+pub struct TermPool<'a> {
+    hdt: &'a Hdt,
+    // number of entries at which dropped terms are removed
+    terms: Mutex<(TermMap, usize)>,
+}
+
+impl<'a> TermPool<'a> {
+    const MIN_PURGE_AT: usize = 1024;
+
+    /// Create an empty pool for the given [`Hdt`].
+    pub fn new(hdt: &'a Hdt) -> Self {
+        TermPool { hdt, terms: Mutex::new((HashMap::new(), Self::MIN_PURGE_AT)) }
+    }
+
+    /// Get the interned string representation of the given ID.
+    pub fn term(&self, id: Id, kind: &'static IdKind) -> Result<Arc<str>, DictErr> {
+        let shared_size = self.hdt.dict.shared.num_strings() as Id;
+        let section = match kind {
+            IdKind::Subject | IdKind::Object if id <= shared_size => 0,
+            IdKind::Subject => 1,
+            IdKind::Predicate => 2,
+            IdKind::Object => 3,
+        };
+        let (terms, purge_at) = &mut *self.terms.lock().unwrap();
+        if let Some(term) = terms.get(&(section, id)).and_then(Weak::upgrade) {
+            return Ok(term);
+        }
+        let term: Arc<str> = self.hdt.dict.id_to_string(id, kind)?.into();
+        terms.insert((section, id), Arc::downgrade(&term));
+        if terms.len() >= *purge_at {
+            terms.retain(|_, t| t.strong_count() > 0);
+            *purge_at = Self::MIN_PURGE_AT.max(terms.len() * 2);
         }
+        Ok(term)
+    }
+
+    /// Translate a triple of indexes into a triple of interned strings.
+    pub fn translate(&self, t: TripleId) -> Result<StringTriple, TranslateErr> {
+        Ok((
+            self.term(t.subject_id, &IdKind::Subject).map_err(|e| TranslateErr { e, t })?,
+            self.term(t.predicate_id, &IdKind::Predicate).map_err(|e| TranslateErr { e, t })?,
+            self.term(t.object_id, &IdKind::Object).map_err(|e| TranslateErr { e, t })?,
+        ))
+    }
+
+    /// Like [`Hdt::triples_with_pattern`] but with interned strings.
+    pub fn triples_with_pattern(
+        &self, sp: Option<&str>, pp: Option<&str>, op: Option<&str>,
+    ) -> Box<dyn Iterator<Item = StringTriple> + '_> {
+        let Some(pat) = self.hdt.pattern_ids(sp, pp, op) else {
+            return Box::new(iter::empty());
+        };
+        Box::new(
+            self.hdt
+                .triples
+                .triples_with_pattern(&pat)
+                .filter_map(|t| self.translate(t).map_err(|e| error!("{e}")).ok()),
+        )
+    }
+
+    /// Number of terms in the pool that are still referenced.
+    pub fn len(&self) -> usize {
+        self.terms.lock().unwrap().0.values().filter(|t| t.strong_count() > 0).count()
+    }
+}
+
+/// Counts the bytes written to it without storing them.
+struct ByteCounter(usize);
+
+impl std::io::Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::containers::rdf::{Literal, Term};
     use crate::tests::init;
+    use crate::triples::SubjectIterOf;
     use pretty_assertions::{assert_eq, assert_ne};
     use std::fs::File;
+    use sucds::Serializable;
+
+    #[test]
+    fn capabilities() {
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let hdt = Hdt::new(std::io::BufReader::new(file)).unwrap();
+        let capabilities = hdt.capabilities();
+        assert_eq!("<http://purl.org/HDT/hdt#HDTv1>", capabilities.format);
+        assert_eq!("<http://purl.org/HDT/hdt#dictionaryFour>", capabilities.dictionary);
+        assert_eq!(Order::SPO, capabilities.order);
+        assert!(capabilities.object_index && !capabilities.predicate_index && !capabilities.damaged_sections);
+        assert_eq!(cfg!(feature = "sophia"), capabilities.features.contains(&"sophia"));
+        assert_eq!(cfg!(feature = "http"), capabilities.features.contains(&"http"));
+        // every feature of the manifest is listed
+        let manifest = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml")).unwrap();
+        let section = manifest.split("[features]").nth(1).unwrap().split("\n[").next().unwrap();
+        let mut declared: Vec<&str> = section
+            .lines()
+            .filter_map(|line| line.split_once('=').map(|(name, _)| name.trim()))
+            .filter(|&name| name != "default")
+            .collect();
+        let mut listed: Vec<&str> = FEATURES.iter().map(|(feature, _)| *feature).collect();
+        declared.sort_unstable();
+        listed.sort_unstable();
+        assert_eq!(declared, listed);
+    }
+
+    #[test]
+    fn estimate_index_memory() {
+        init();
+        // large enough that the constant overhead of the index structures is negligible
+        let mut builder = crate::builder::HdtBuilder::new();
+        for i in 0..20_000 {
+            let (s, p, o) = (i / 3, i * 7 % 100, i * 13 % 5000);
+            builder.add_triple(
+                &format!("http://e.org/s{s}"),
+                &format!("http://e.org/p{p}"),
+                &format!("http://e.org/o{o}"),
+            );
+        }
+        let mut hdt = builder.build().unwrap();
+        let predicate = hdt.estimate_index_memory(IndexKind::Predicate);
+        hdt.triples.build_predicate_index().unwrap();
+        assert_eq!(predicate, hdt.estimate_index_memory(IndexKind::Predicate));
+        let actual = [
+            (IndexKind::Object, hdt.triples.op_index.size_in_bytes()),
+            (IndexKind::Wavelet, hdt.triples.wavelet_y.size_in_bytes()),
+            (IndexKind::Predicate, hdt.triples.predicate_index.as_ref().unwrap().size_in_bytes()),
+        ];
+        for (kind, size) in actual {
+            let estimate = hdt.estimate_index_memory(kind);
+            assert!(
+                size * 9 < estimate.size * 10 && estimate.size * 9 < size * 10,
+                "{kind:?} {size} {estimate:?}"
+            );
+            assert!(estimate.size < estimate.peak);
+        }
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn literals_matching() {
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let hdt = Hdt::new(std::io::BufReader::new(file)).unwrap();
+        let re = regex::Regex::new(r"(?i)\bchapter\b").unwrap();
+        let expected: Vec<Id> = hdt
+            .dict
+            .terms(&IdKind::Object)
+            .filter(|(_, o)| o.starts_with('"') && re.is_match(&o[1..o.rfind('"').unwrap()]))
+            .map(|(id, _)| id)
+            .collect();
+        assert!(expected.len() > 1);
+        assert_eq!(expected, hdt.literals_matching(&re).collect::<Vec<_>>());
+        let triples: Vec<_> = hdt.triples_with_literal_matching(None, None, &re).collect();
+        let count: usize =
+            expected.iter().map(|&o| hdt.triples.triples_with_pattern(&TripleId::new(0, 0, o)).count()).sum();
+        assert_eq!(count, triples.len());
+        assert!(triples.iter().all(|(_, _, o)| re.is_match(o)));
+        assert_eq!(0, hdt.triples_with_literal_matching(Some("http://example.org/missing"), None, &re).count());
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn map() {
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let hdt = Hdt::new(std::io::BufReader::new(file)).unwrap();
+        let mapped = Hdt::map("tests/resources/snikmeta.hdt").unwrap();
+        assert!(mapped.size_in_bytes() < hdt.size_in_bytes());
+        assert_eq!(hdt.triples().collect::<Vec<_>>(), mapped.triples().collect::<Vec<_>>());
+        let label = Some("http://www.w3.org/2000/01/rdf-schema#label");
+        assert_eq!(
+            hdt.triples_with_pattern(None, label, None).collect::<Vec<_>>(),
+            mapped.triples_with_pattern(None, label, None).collect::<Vec<_>>()
+        );
+        let mut bytes = Vec::new();
+        mapped.write_to(&mut bytes, "file://snikmeta.nt").unwrap();
+        assert_eq!(hdt.triples().count(), Hdt::new(bytes.as_slice()).unwrap().triples().count());
+        assert!(Hdt::map("tests/resources/doesnotexist.hdt").is_err());
+        assert_eq!(hdt.triples.len(), Hdt::map_verified("tests/resources/snikmeta.hdt").unwrap().triples.len());
+
+        // a damaged object section is only detected by map_verified, but decoding it does not panic
+        let dir = std::env::temp_dir().join(format!("hdt-map-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("snikmeta.hdt");
+        let mut data = std::fs::read("tests/resources/snikmeta.hdt").unwrap();
+        let packed = hdt.dict.objects.packed_data().unwrap();
+        let offset = data.windows(packed.len()).position(|w| w == packed).unwrap();
+        for i in (0..packed.len()).step_by(7) {
+            data[offset + i] ^= 0x80;
+        }
+        std::fs::write(&path, &data).unwrap();
+        let damaged = Hdt::map(&path).unwrap();
+        assert!(damaged.check_mapping().is_ok());
+        damaged.dict.objects.iter().for_each(drop);
+        for id in 1..=damaged.dict.objects.num_strings() {
+            let _ = damaged.dict.objects.extract(id);
+        }
+        damaged.dict.objects.string_to_id("http://www.snik.eu/ontology/meta/Top");
+        damaged.dict.objects.prefix_range("http://www.snik.eu/");
+        let e = Hdt::map_verified(&path).unwrap_err();
+        assert!(
+            matches!(HdtError::find(e.as_ref()), Some(HdtError::Checksum { part: Part::Dictionary, .. })),
+            "{e}"
+        );
+        // extending the file in place is detected, unlike replacing it
+        let mut file = std::fs::OpenOptions::new().append(true).open(&path).unwrap();
+        std::io::Write::write_all(&mut file, b"appended").unwrap();
+        let e = damaged.check_mapping().unwrap_err();
+        assert!(matches!(&e, HdtError::Modified { what, .. } if what.contains("length")), "{e}");
+        let replaced = dir.join("replaced.hdt");
+        std::fs::copy("tests/resources/snikmeta.hdt", &replaced).unwrap();
+        let current = Hdt::map(&replaced).unwrap();
+        std::fs::rename(&path, &replaced).unwrap();
+        assert!(current.check_mapping().is_ok());
+        assert!(hdt.check_mapping().is_ok());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn map_with_index() {
+        init();
+        let dir = std::env::temp_dir().join(format!("hdt-index-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("snikmeta.hdt");
+        std::fs::copy("tests/resources/snikmeta.hdt", &path).unwrap();
+        let index_path = dir.join(format!("snikmeta.hdt{INDEX_SUFFIX}"));
+        let hdt = Hdt::map(&path).unwrap();
+        // the first call writes the index file and the second one maps it
+        let built = Hdt::map_with_index(&path).unwrap();
+        assert!(index_path.exists());
+        #[cfg(unix)]
+        let inode = || std::os::unix::fs::MetadataExt::ino(&std::fs::metadata(&index_path).unwrap());
+        #[cfg(unix)]
+        let written = inode();
+        let mapped = Hdt::map_with_index(&path).unwrap();
+        // a valid index file is not written again
+        #[cfg(unix)]
+        assert_eq!(written, inode());
+        assert!(mapped.size_in_bytes() < hdt.size_in_bytes());
+        let o = Some("http://www.snik.eu/ontology/meta/Top");
+        let expected: Vec<_> = hdt.triples_with_pattern(None, None, o).collect();
+        assert!(!expected.is_empty());
+        for h in [&built, &mapped] {
+            assert_eq!(expected, h.triples_with_pattern(None, None, o).collect::<Vec<_>>());
+            for t in hdt.triples.into_iter().step_by(5) {
+                for pat in [
+                    TripleId::new(0, t.predicate_id, t.object_id),
+                    TripleId::new(0, t.predicate_id, 0),
+                    TripleId::new(t.subject_id, t.predicate_id, 0),
+                ] {
+                    assert_eq!(
+                        hdt.triples.triples_with_pattern(&pat).collect::<Vec<_>>(),
+                        h.triples.triples_with_pattern(&pat).collect::<Vec<_>>()
+                    );
+                }
+            }
+        }
+        // a changed modification time changes the fingerprint, so that the index file is written again
+        let written = std::fs::read(&index_path).unwrap();
+        let modified = std::fs::metadata(&path).unwrap().modified().unwrap() + Duration::from_secs(10);
+        std::fs::File::options().write(true).open(&path).unwrap().set_modified(modified).unwrap();
+        Hdt::map_with_index(&path).unwrap();
+        assert_ne!(written, std::fs::read(&index_path).unwrap());
+        // an index file that does not match is rebuilt and mapped
+        std::fs::write(&index_path, b"garbage").unwrap();
+        let rebuilt = Hdt::map_with_index(&path).unwrap();
+        assert_eq!(expected, rebuilt.triples_with_pattern(None, None, o).collect::<Vec<_>>());
+        assert!(rebuilt.size_in_bytes() < hdt.size_in_bytes());
+        // a stale index file of another HDT file with the same number of triples is rejected
+        let other_path = dir.join("other.hdt");
+        let other = crate::synthetic::Generator::new(hdt.triples.len()).with_seed(7).build().unwrap();
+        other
+            .write_to(
+                std::io::BufWriter::new(std::fs::File::create(&other_path).unwrap()),
+                "http://example.org/other",
+            )
+            .unwrap();
+        std::fs::copy(&index_path, dir.join(format!("other.hdt{INDEX_SUFFIX}"))).unwrap();
+        let other = Hdt::map(&other_path).unwrap();
+        let indexed = Hdt::map_with_index(&other_path).unwrap();
+        for t in other.triples.into_iter().step_by(5) {
+            let pat = TripleId::new(0, 0, t.object_id);
+            assert_eq!(
+                other.triples.triples_with_pattern(&pat).collect::<Vec<_>>(),
+                indexed.triples.triples_with_pattern(&pat).collect::<Vec<_>>()
+            );
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn empty() {
+        init();
+        let mut bytes = Vec::new();
+        crate::builder::HdtBuilder::new()
+            .build()
+            .unwrap()
+            .write_to(&mut bytes, "http://example.org/empty")
+            .unwrap();
+        let hdt = Hdt::new(bytes.as_slice()).unwrap();
+        for sect in [&hdt.dict.shared, &hdt.dict.subjects, &hdt.dict.predicates, &hdt.dict.objects] {
+            assert_eq!(0, sect.num_strings());
+        }
+        assert_eq!(0, hdt.triples.len());
+        assert_eq!(0, hdt.triples().count());
+        assert_eq!(0, hdt.dict.string_to_id("http://example.org/a", &IdKind::Subject));
+        assert!(hdt.dict.id_to_string(0, &IdKind::Object).is_err());
+        assert!(hdt.dict.id_to_string(1, &IdKind::Predicate).is_err());
+        let a = Some("http://example.org/a");
+        for (sp, pp, op) in [(None, None, None), (a, None, None), (None, a, None), (None, None, a), (a, a, a)] {
+            assert_eq!(0, hdt.triples_with_pattern(sp, pp, op).count());
+        }
+        assert_eq!(0, hdt.triples.triples_with_pattern(&TripleId::new(0, 0, 0)).count());
+        assert_eq!(0, hdt.triples.sample(10, 1).count());
+        assert!(hdt.facet(None, None, None, &IdKind::Predicate).is_empty());
+        #[cfg(feature = "mmap")]
+        {
+            let path = std::env::temp_dir().join(format!("hdt-empty-{}.hdt", std::process::id()));
+            std::fs::write(&path, &bytes).unwrap();
+            let mapped = Hdt::map(&path).unwrap();
+            std::fs::remove_file(&path).unwrap();
+            assert_eq!(0, mapped.triples().count());
+        }
+
+        // empty shared and subject sections
+        let mut builder = crate::builder::HdtBuilder::new();
+        builder.add_triple("http://example.org/a", "http://example.org/p", "\"b\"");
+        builder.add_triple("http://example.org/a", "http://example.org/p", "http://example.org/a");
+        let hdt = builder.build().unwrap();
+        assert_eq!(
+            (1, 0, 1),
+            (hdt.dict.shared.num_strings(), hdt.dict.subjects.num_strings(), hdt.dict.objects.num_strings())
+        );
+        assert_eq!(0, hdt.dict.string_to_id("http://example.org/b", &IdKind::Subject));
+        assert_eq!(2, hdt.triples_with_pattern(a, None, None).count());
+        let mut builder = crate::builder::HdtBuilder::new();
+        builder.add_triple("http://example.org/a", "http://example.org/p", "\"b\"");
+        let hdt = builder.build().unwrap();
+        assert_eq!(0, hdt.dict.shared.num_strings());
+        assert_eq!(1, hdt.triples_with_pattern(None, None, Some("\"b\"")).count());
+        assert_eq!(0, hdt.triples_with_pattern(None, None, a).count());
+    }
+
+    #[test]
+    fn export_predicate() {
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let hdt = Hdt::new(std::io::BufReader::new(file)).unwrap();
+        let label = "http://www.w3.org/2000/01/rdf-schema#label";
+        let expected: Vec<_> = hdt
+            .triples_with_pattern(None, Some(label), None)
+            .map(|(s, p, o)| format!("{} {} {} .", nt_term(&s), nt_term(&p), nt_term(&o)))
+            .collect();
+        assert!(!expected.is_empty());
+        let mut out = Vec::new();
+        let written = hdt.export_predicate(label, &mut out, ExportFormat::NTriples, XsdString::Implicit).unwrap();
+        assert_eq!(expected.len(), written);
+        assert_eq!(expected, String::from_utf8(out).unwrap().lines().collect::<Vec<_>>());
+
+        // explicit datatype for the literals without language tag and datatype
+        let (_, p, _) = hdt.triples().find(|(_, _, o)| o.starts_with('"') && o.ends_with('"')).unwrap();
+        let plain = hdt.triples_with_pattern(None, Some(&p), None).filter(|(_, _, o)| o.ends_with('"')).count();
+        let mut out = Vec::new();
+        hdt.export_predicate(&p, &mut out, ExportFormat::NTriples, XsdString::Explicit).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(plain, out.matches("\"^^<http://www.w3.org/2001/XMLSchema#string> .").count());
+
+        let mut out = Vec::new();
+        assert_eq!(
+            expected.len(),
+            hdt.export_predicate(label, &mut out, ExportFormat::Turtle, XsdString::Implicit).unwrap()
+        );
+        let out = String::from_utf8(out).unwrap();
+        let subjects: HashSet<_> = hdt.triples_with_pattern(None, Some(label), None).map(|(s, _, _)| s).collect();
+        assert_eq!(subjects.len(), out.lines().count());
+        assert!(out.lines().all(|line| line.ends_with(" .")));
+        // one separator per additional object of a subject, apart from those inside of literals
+        let in_terms: usize = expected.iter().map(|line| line.matches(", ").count()).sum();
+        assert_eq!(expected.len() - subjects.len() + in_terms, out.matches(", ").count());
+
+        let mut out = Vec::new();
+        let unknown = "http://example.org/doesnotexist";
+        assert_eq!(0, hdt.export_predicate(unknown, &mut out, ExportFormat::Turtle, XsdString::Implicit).unwrap());
+        assert!(out.is_empty());
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn par_triples() {
+        use rayon::prelude::*;
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let hdt = Hdt::new(std::io::BufReader::new(file)).unwrap();
+        assert_eq!(hdt.triples().collect::<Vec<_>>(), hdt.par_triples().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn write_to() {
+        init();
+        let original = std::fs::read("tests/resources/snikmeta.hdt").expect("error reading file");
+        let hdt = Hdt::new(original.as_slice()).unwrap();
+        let mut bytes = Vec::new();
+        hdt.write_to(&mut bytes, "file://snikmeta.nt").unwrap();
+        let copy = Hdt::new(bytes.as_slice()).unwrap();
+        assert_eq!(hdt.triples().collect::<Vec<_>>(), copy.triples().collect::<Vec<_>>());
+        assert_eq!(hdt.capabilities(), copy.capabilities());
+        // the dictionary sections are identical to the ones written by hdt-cpp
+        let dict_sections = |data: &[u8]| {
+            let mut reader = &data[data.windows(5).position(|w| w == b"$HDT\x03").unwrap()..];
+            ControlInfo::read(&mut reader).unwrap();
+            reader[..reader.windows(5).position(|w| w == b"$HDT\x04").unwrap()].to_vec()
+        };
+        assert_eq!(dict_sections(&original), dict_sections(&bytes));
+        // the triples section as well, from its control information to the end of the file
+        let triples_section =
+            |data: &[u8]| data[data.windows(5).position(|w| w == b"$HDT\x04").unwrap()..].to_vec();
+        assert_eq!(triples_section(&original), triples_section(&bytes));
+        // the header has the same statistics as the one written by hdt-cpp
+        let mut reader = bytes.as_slice();
+        ControlInfo::read(&mut reader).unwrap();
+        let header = Header::read(&mut reader).unwrap();
+        let mut reader = original.as_slice();
+        ControlInfo::read(&mut reader).unwrap();
+        let original_header = Header::read(&mut reader).unwrap();
+        // only the sizes of the strings and sections differ, as they are computed differently
+        let common: Vec<_> = header.body.intersection(&original_header.body).collect();
+        assert_eq!(header.body.len() - 2, common.len(), "{:?}", header.body.difference(&original_header.body));
+        // writing is deterministic
+        let mut again = Vec::new();
+        copy.write_to(&mut again, "file://snikmeta.nt").unwrap();
+        assert_eq!(bytes, again);
+    }
+
+    #[test]
+    fn write_with_metadata() {
+        init();
+        let original = std::fs::read("tests/resources/snikmeta.hdt").expect("error reading file");
+        let hdt = Hdt::new(original.as_slice()).unwrap();
+        let source = "http://purl.org/dc/terms/source";
+        let metadata = HeaderMetadata::new()
+            .with_original_size(46813)
+            .with_issued("2023-12-06T17:34:08+01:00")
+            .with_triple("file://snikmeta.nt", source, "\"SNIK \\ \"meta\"\"@en");
+        let mut bytes = Vec::new();
+        hdt.write_with_metadata(&mut bytes, "file://snikmeta.nt", &metadata).unwrap();
+        let header = |data: &[u8]| {
+            let mut reader = data;
+            ControlInfo::read(&mut reader).unwrap();
+            Header::read(&mut reader).unwrap().body
+        };
+        let (header, original_header) = (header(&bytes), header(&original));
+        assert_eq!(original_header.len() + 1, header.len());
+        assert_eq!(original_header.len() - 2, header.intersection(&original_header).count());
+        assert!(header.iter().any(|t| t.predicate == source));
+        // the sections are written after the header and the recorded size is theirs
+        let sections = &bytes[bytes.windows(5).position(|w| w == b"$HDT\x03").unwrap()..];
+        let hdt_size = Term::Literal(Literal::new(sections.len().to_string()));
+        assert!(header.iter().any(|t| t.predicate.ends_with("hdtSize") && t.object == hdt_size));
+        assert_eq!(
+            hdt.triples().collect::<Vec<_>>(),
+            Hdt::new(bytes.as_slice()).unwrap().triples().collect::<Vec<_>>()
+        );
+    }
 
     #[test]
     fn triples() {
@@ -258,6 +1776,30 @@ mod tests {
         assert_eq!(v.len(), 328);
         assert_eq!(v, hdt.triples_with_pattern(None, None, None).collect::<Vec<_>>(), "all triples not equal ???");
         assert_ne!(0, hdt.dict.string_to_id("http://www.snik.eu/ontology/meta", &IdKind::Subject));
+        for (lexical, dt, lang) in [
+            ("top class", None, Some("en")),
+            ("2022-10-20", Some("<http://www.w3.org/2001/XMLSchema#date>"), None),
+            ("2022-10-20", Some("http://www.w3.org/2001/XMLSchema#date"), None),
+        ] {
+            let o = Hdt::literal_matcher(lexical, dt, lang);
+            assert_ne!(0, hdt.dict.string_to_id(&o, &IdKind::Object), "{o}");
+        }
+        assert_eq!("\"x\"", Hdt::literal_matcher("x", Some("http://www.w3.org/2001/XMLSchema#string"), None));
+        let label = Some("http://www.w3.org/2000/01/rdf-schema#label");
+        let kapitel: Vec<_> =
+            hdt.triples_with_lang_fallback(None, label, "Kapitel", &[Some("en"), Some("de")]).collect();
+        assert_eq!(hdt.triples_with_pattern(None, label, Some("\"Kapitel\"@de")).collect::<Vec<_>>(), kapitel);
+        assert_eq!(2, kapitel.len());
+        let approves = hdt.triples_with_pattern(None, label, Some("\"approves\"@en")).collect::<Vec<_>>();
+        for chain in
+            [&[Some("de"), None, Some("en")][..], &[Some("en"), Some("en")], &[Some("en"), Some("doesnotexist")]]
+        {
+            assert_eq!(
+                approves,
+                hdt.triples_with_lang_fallback(None, label, "approves", chain).collect::<Vec<_>>()
+            );
+        }
+        assert_eq!(0, hdt.triples_with_lang_fallback(None, label, "approves", &[Some("de"), None]).count());
         for uri in ["http://www.snik.eu/ontology/meta/Top", "http://www.snik.eu/ontology/meta", "doesnotexist"] {
             let filtered: Vec<_> = v.clone().into_iter().filter(|triple| triple.0.as_ref() == uri).collect();
             let with_s: Vec<_> = hdt.triples_with_pattern(Some(uri), None, None).collect();
@@ -303,5 +1845,239 @@ mod tests {
         let o = "\"ХОББИ\"@ru";
         let triple_vec = vec![(Arc::from(s), Arc::from(p), Arc::from(o))];
         assert_eq!(triple_vec, hdt.triples_with_pattern(Some(s), Some(p), None).collect::<Vec<_>>(),);
+
+        // facets
+        assert_eq!(None, hdt.pattern_ids(Some("doesnotexist"), None, None));
+        assert!(hdt.facet(Some("doesnotexist"), None, None, &IdKind::Predicate).is_empty());
+        let facets = hdt.facet(Some(meta), None, None, &IdKind::Predicate);
+        assert_eq!(hdt.triples_with_pattern(Some(meta), None, None).count(), facets.iter().map(|f| f.1).sum());
+        assert!(facets.windows(2).all(|w| w[0].1 >= w[1].1), "facets not sorted by count");
+        for (p, count) in &facets {
+            assert_eq!(*count, hdt.triples_with_pattern(Some(meta), Some(p), None).count(), "facet count for {p}");
+        }
+        let facets =
+            hdt.facet(None, Some("http://www.w3.org/2000/01/rdf-schema#subClassOf"), None, &IdKind::Object);
+        assert_eq!(vec![(et.to_owned(), 4)], facets.into_iter().filter(|f| f.0 == et).collect::<Vec<_>>());
+
+        // term pool
+        let pool = TermPool::new(&hdt);
+        let sub_class_of = Some("http://www.w3.org/2000/01/rdf-schema#subClassOf");
+        let first: Vec<_> = pool.triples_with_pattern(None, sub_class_of, None).collect();
+        let second: Vec<_> = pool.triples_with_pattern(None, sub_class_of, None).collect();
+        assert_eq!(hdt.triples_with_pattern(None, sub_class_of, None).collect::<Vec<_>>(), first);
+        assert!(first.iter().zip(&second).all(|(a, b)| Arc::ptr_eq(&a.0, &b.0) && Arc::ptr_eq(&a.2, &b.2)));
+        let as_object = &first.iter().find(|t| t.2.as_ref() == et).unwrap().2;
+        let as_subject = pool.triples_with_pattern(Some(et), None, None).next().unwrap().0;
+        assert!(Arc::ptr_eq(as_object, &as_subject), "shared term not interned");
+        assert!(pool.len() > 0);
+        drop((first, second, as_subject));
+        assert_eq!(0, pool.len());
+    }
+
+    #[test]
+    fn triple_cache() {
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let hdt = Hdt::new(std::io::BufReader::new(file)).unwrap();
+        let v: Vec<StringTriple> = hdt.triples().collect();
+        for (t, (s, p, o)) in hdt.triples.into_iter().zip(&v) {
+            assert_eq!(hdt.dict.id_to_string(t.subject_id, &IdKind::Subject).unwrap(), s.as_ref());
+            assert_eq!(hdt.dict.id_to_string(t.predicate_id, &IdKind::Predicate).unwrap(), p.as_ref());
+            assert_eq!(hdt.dict.id_to_string(t.object_id, &IdKind::Object).unwrap(), o.as_ref());
+        }
+        // each predicate is only extracted once
+        let mut predicates: Vec<_> = v.iter().map(|(_, p, _)| Arc::as_ptr(p)).collect();
+        predicates.sort_unstable();
+        predicates.dedup();
+        assert_eq!(hdt.dict.predicates.num_strings(), predicates.len());
+    }
+
+    #[test]
+    fn triples_as() {
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let hdt = Hdt::new(std::io::BufReader::new(file)).unwrap();
+        let top = Some("http://www.snik.eu/ontology/meta/Top");
+        for (sp, pp, op) in [(top, None, None), (None, None, top), (None, None, None), (Some("x"), None, None)] {
+            let expected: Vec<(String, String, String)> = hdt
+                .triples_with_pattern(sp, pp, op)
+                .map(|(s, p, o)| (s.to_string(), p.to_string(), o.to_string()))
+                .collect();
+            assert_eq!(expected, hdt.triples_with_pattern_as::<String>(sp, pp, op).collect::<Vec<_>>());
+            let boxed: Vec<(Box<str>, Box<str>, Box<str>)> = hdt.triples_with_pattern_as(sp, pp, op).collect();
+            assert_eq!(expected.len(), boxed.len());
+            assert!(expected.iter().zip(&boxed).all(|(e, b)| e.0 == *b.0 && e.1 == *b.1 && e.2 == *b.2));
+        }
+    }
+
+    #[test]
+    fn shared_triples() {
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let hdt = Arc::new(Hdt::new(std::io::BufReader::new(file)).unwrap());
+        let label = Some("http://www.w3.org/2000/01/rdf-schema#label");
+        let top = Some("http://www.snik.eu/ontology/meta/Top");
+        for (sp, pp, op) in [(top, None, None), (None, label, None), (None, None, top), (None, None, None)] {
+            let expected: Vec<_> = hdt.triples_with_pattern(sp, pp, op).collect();
+            let shared = Hdt::shared_triples_with_pattern(&hdt, sp, pp, op);
+            // the iterator does not borrow, so it can be moved to another thread
+            let actual = std::thread::spawn(move || shared.collect::<Vec<_>>()).join().unwrap();
+            assert_eq!(expected, actual);
+        }
+        assert_eq!(0, Hdt::shared_triples_with_pattern(&hdt, Some("doesnotexist"), None, None).count());
+        assert_eq!(hdt.triples.len(), SubjectIterOf::new(SharedTriples(hdt.clone())).count());
+    }
+
+    #[test]
+    fn random_walks() {
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let hdt = Hdt::new(std::io::BufReader::new(file)).unwrap();
+        let top = hdt.dict.string_to_id("http://www.snik.eu/ontology/meta/Top", &IdKind::Subject);
+        let walks = hdt.random_walks(&[top, 0, usize::MAX], 5, 20, 42);
+        assert_eq!(60, walks.len());
+        assert_eq!(walks, hdt.random_walks(&[top, 0, usize::MAX], 5, 20, 42), "same seed, different walks");
+        assert!(walks[20..].iter().all(Vec::is_empty), "walks from invalid subjects");
+        let shared = hdt.dict.shared.num_strings();
+        for walk in &walks[..20] {
+            assert!(!walk.is_empty() && walk.len() <= 5);
+            assert_eq!(top, walk[0].subject_id);
+            for t in walk {
+                assert!(hdt.triples.triples_with_pattern(t).next().is_some(), "walked non-existing triple {t:?}");
+            }
+            for w in walk.windows(2) {
+                assert_eq!(w[0].object_id, w[1].subject_id);
+            }
+            let last = walk.last().unwrap();
+            assert!(
+                walk.len() == 5
+                    || last.object_id > shared
+                    || hdt.triples.subject_range_z(last.object_id).is_empty()
+            );
+        }
+    }
+
+    #[test]
+    fn triples_with_pattern_ranked() {
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let hdt = Hdt::new(std::io::BufReader::new(file)).unwrap();
+        let count = |sp: Option<&str>, op: Option<&str>| hdt.triples_with_pattern(sp, None, op).count();
+        let shared = hdt.dict.shared.num_strings();
+        for kind in [&IdKind::Subject, &IdKind::Object] {
+            for id in 1..=shared + 3 {
+                let term = hdt.dict.id_to_string(id, kind).unwrap();
+                let (as_subject, as_object) = (count(Some(&term), None), count(None, Some(&term)));
+                let expected = match kind {
+                    _ if id <= shared => as_subject + as_object,
+                    IdKind::Subject => as_subject,
+                    _ => as_object,
+                };
+                assert_eq!(expected, hdt.degree(id, kind), "{kind:?} {term}");
+            }
+        }
+        assert_eq!(0, hdt.degree(usize::MAX, &IdKind::Object));
+
+        let rdf_type = Some("http://www.w3.org/1999/02/22-rdf-syntax-ns#type");
+        let all: Vec<_> = hdt.triples_with_pattern(None, rdf_type, None).collect();
+        let ranked =
+            hdt.triples_with_pattern_ranked(None, rdf_type, None, &IdKind::Object, Rank::Frequency, usize::MAX);
+        assert_eq!(all.len(), ranked.len());
+        let frequency = |o: &str| all.iter().filter(|t| &*t.2 == o).count();
+        assert!(ranked.windows(2).all(|w| frequency(&w[0].2) >= frequency(&w[1].2)));
+        assert!(frequency(&ranked[0].2) > frequency(&ranked[ranked.len() - 1].2));
+        let top = hdt.triples_with_pattern_ranked(None, rdf_type, None, &IdKind::Object, Rank::Frequency, 3);
+        assert_eq!(ranked[..3], top);
+
+        let ranked = hdt.triples_with_pattern_ranked(None, None, None, &IdKind::Subject, Rank::Degree, 10);
+        assert_eq!(10, ranked.len());
+        let degree = |s: &str| hdt.degree(hdt.dict.string_to_id(s, &IdKind::Subject), &IdKind::Subject);
+        assert!(ranked.windows(2).all(|w| degree(&w[0].0) >= degree(&w[1].0)));
+        // bound positions keep the pattern order
+        let label = Some("http://www.w3.org/2000/01/rdf-schema#label");
+        let labels: Vec<_> = hdt.triples_with_pattern(None, label, None).collect();
+        assert_eq!(
+            labels,
+            hdt.triples_with_pattern_ranked(None, label, None, &IdKind::Predicate, Rank::Degree, usize::MAX)
+        );
+        assert_eq!(
+            labels[..5],
+            hdt.triples_with_pattern_ranked(None, label, None, &IdKind::Predicate, Rank::Degree, 5)
+        );
+        assert!(hdt
+            .triples_with_pattern_ranked(Some("doesnotexist"), None, None, &IdKind::Object, Rank::Degree, 5)
+            .is_empty());
+    }
+
+    #[test]
+    fn compressed() {
+        init();
+        let data = std::fs::read("tests/resources/snikmeta.hdt").unwrap();
+        assert_eq!(None, Compression::detect(&data));
+        let expected: Vec<_> = Hdt::new(data.as_slice()).unwrap().triples().collect();
+        assert!(!expected.is_empty());
+        #[cfg(feature = "gzip")]
+        {
+            use std::io::Write;
+            let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(&data).unwrap();
+            let gzip = encoder.finish().unwrap();
+            assert_eq!(Some(Compression::Gzip), Compression::detect(&gzip));
+            let decompressed = Hdt::new(gzip.as_slice()).unwrap();
+            assert_eq!(expected, decompressed.triples().collect::<Vec<_>>());
+        }
+        #[cfg(feature = "zstd")]
+        {
+            let zstd = zstd::encode_all(data.as_slice(), 0).unwrap();
+            assert_eq!(Some(Compression::Zstd), Compression::detect(&zstd));
+            let decompressed = Hdt::recover(zstd.as_slice()).unwrap();
+            assert_eq!(expected, decompressed.triples().collect::<Vec<_>>());
+        }
+        #[cfg(not(feature = "zstd"))]
+        {
+            let e = Hdt::new([0x28, 0xb5, 0x2f, 0xfd, 0].as_slice()).unwrap_err();
+            assert!(e.to_string().contains("zstd feature"), "{e}");
+        }
+        // truncated compressed data is an error, not a partial graph
+        #[cfg(feature = "gzip")]
+        assert!(Hdt::new(&[0x1f, 0x8b, 8, 0][..]).is_err());
+    }
+
+    #[test]
+    fn recover() {
+        init();
+        let mut data = std::fs::read("tests/resources/snikmeta.hdt").unwrap();
+        assert!(Hdt::recover(data.as_slice()).unwrap().dict.damaged_ids(&IdKind::Object).is_empty());
+        // corrupt a literal, which is stored in the object section
+        let pos = data.windows(9).position(|w| w == b"top class").unwrap();
+        data[pos] = b'T';
+        assert!(Hdt::new(data.as_slice()).is_err());
+        let hdt = Hdt::recover(data.as_slice()).unwrap();
+        let shared = hdt.dict.shared.num_strings();
+        assert_eq!(
+            vec![shared + 1..=shared + hdt.dict.objects.num_strings()],
+            hdt.dict.damaged_ids(&IdKind::Object)
+        );
+        assert!(hdt.dict.damaged_ids(&IdKind::Subject).is_empty());
+        assert!(hdt.dict.id_to_string(shared + 1, &IdKind::Object).is_err());
+        assert!(hdt.dict.id_to_string(shared + 1, &IdKind::Subject).is_ok());
+        assert!(hdt.dict.id_to_string(1, &IdKind::Object).is_ok());
+        // writing keeps the damaged section detectable
+        let mut copy = Vec::new();
+        hdt.write_to(&mut copy, "http://example.org/copy").unwrap();
+        assert!(Hdt::new(copy.as_slice()).is_err());
+        assert!(Hdt::recover(copy.as_slice()).unwrap().dict.objects.is_damaged());
+        // triples with damaged objects are skipped
+        let pool = TermPool::new(&hdt);
+        let label = Some("http://www.w3.org/2000/01/rdf-schema#label");
+        assert_eq!(
+            0,
+            pool.triples_with_pattern(Some("http://www.snik.eu/ontology/meta/Top"), label, None).count()
+        );
+        let sub_class_of = Some("http://www.w3.org/2000/01/rdf-schema#subClassOf");
+        let pat = hdt.pattern_ids(None, sub_class_of, None).unwrap();
+        let intact = hdt.triples.triples_with_pattern(&pat).filter(|t| t.object_id <= shared).count();
+        assert!(intact > 0 && intact < 12);
+        assert_eq!(intact, pool.triples_with_pattern(None, sub_class_of, None).count());
     }
 }
@@ -1,23 +1,85 @@
 // //! *This module is available only if HDT is built with the `"sophia"` feature.*
+use crate::compare_terms;
+use crate::containers::rdf::{canonical_language_tag, XsdString};
 #[cfg(feature = "sophia")]
 use crate::four_sect_dict::IdKind;
 use crate::hdt::Hdt;
 use crate::triples::{Id, ObjectIter, PredicateIter, PredicateObjectIter, SubjectIter, TripleId};
-use log::debug;
+use log::{debug, warn};
 use sophia::api::graph::Graph;
-use sophia::api::term::{matcher::TermMatcher, BnodeId, IriRef, LanguageTag, Term};
-use std::convert::Infallible;
-use std::io::{self, Error, ErrorKind};
-use std::iter;
+use sophia::api::term::{
+    matcher::{Any, TermMatcher},
+    BnodeId, IriRef, LanguageTag, Term,
+};
+use std::iter::{self, FusedIterator};
 use std::sync::Arc;
+use thiserror::Error;
 
+mod dataset;
+pub use dataset::HdtDataset;
+#[cfg(feature = "oxrdf")]
+mod oxigraph;
 mod term;
-pub use term::HdtTerm;
+pub use term::{
+    well_known_datatype, HdtTerm, RDF_LANG_STRING, XSD_BOOLEAN, XSD_DATE, XSD_DATE_TIME, XSD_DECIMAL, XSD_DOUBLE,
+    XSD_INTEGER, XSD_STRING,
+};
 
 /// Adapter to use HDT as a Sophia graph.
 pub struct HdtGraph {
     /// Wrapped HDT instance
     pub hdt: Hdt,
+    validation: Validation,
+    canonical_tags: bool,
+    xsd_string: XsdString,
+}
+
+/// How strictly IRIs, including datatypes, and language tags are validated when terms are extracted from the dictionary.
+/// Validation is skipped by default, because it is costly and HDT files are usually created from valid RDF.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Validation {
+    /// Create terms without validation.
+    #[default]
+    Unchecked,
+    /// Log a warning for each triple with an invalid term but still return it.
+    Warn,
+    /// Return an error instead of each triple with an invalid term.
+    Strict,
+}
+
+/// The error type of [`HdtGraph`] and [`HdtDataset`], which is part of the iterator items so that a damaged term only fails its own triples.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum GraphError {
+    /// A term could not be extracted from the dictionary, such as because of a corrupted section.
+    #[error("cannot extract term {id} from the dictionary: {message}")]
+    Extract {
+        /// ID of the term.
+        id: Id,
+        /// Description of the dictionary error.
+        message: String,
+    },
+    /// A dictionary string is not a term in the HDT string format.
+    #[error("invalid term string {term}: {message}")]
+    Decode {
+        /// The string extracted from the dictionary.
+        term: String,
+        /// What is wrong with it.
+        message: String,
+    },
+    /// An IRI or language tag is invalid, only occurs with [`Validation::Strict`].
+    #[error(transparent)]
+    Invalid(#[from] InvalidTerm),
+}
+
+/// Invalid IRI or language tag of a term that was extracted with [`Validation::Strict`], see [`GraphError::Invalid`].
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum InvalidTerm {
+    /// An IRI or datatype IRI does not conform to RFC 3987.
+    #[error("invalid IRI <{0}>")]
+    Iri(String),
+    /// A language tag does not conform to BCP 47.
+    #[error("invalid language tag @{0}")]
+    LanguageTag(String),
 }
 
 /// HdtGraph does not support all of the Sophia TermMatcher functionality.
@@ -29,54 +91,249 @@ enum HdtMatcher {
 impl HdtGraph {
     /// Wrapper around Hdt.
     pub const fn new(hdt: Hdt) -> Self {
-        HdtGraph { hdt }
+        HdtGraph { hdt, validation: Validation::Unchecked, canonical_tags: false, xsd_string: XsdString::Implicit }
+    }
+
+    /// Validate the extracted terms with the given level, see [`Validation`].
+    /// # Example
+    /// ```
+    /// use hdt::hdt_graph::Validation;
+    /// fn strict(hdt: hdt::Hdt) -> hdt::HdtGraph {
+    ///     hdt::HdtGraph::new(hdt).with_validation(Validation::Strict)
+    /// }
+    /// ```
+    #[must_use]
+    pub const fn with_validation(mut self, validation: Validation) -> Self {
+        self.validation = validation;
+        self
+    }
+
+    /// Convert language tags to the canonical BCP 47 case, such as `en-US` instead of `en-us`, see [`canonical_language_tag`].
+    /// Constant language tagged literals in patterns are also found if the dictionary contains them in canonical or lowercase form.
+    /// Use this to match terms produced by other parsers, because language tags are case insensitive but stored as given in the source data.
+    #[must_use]
+    pub const fn with_canonical_language_tags(mut self, canonical_tags: bool) -> Self {
+        self.canonical_tags = canonical_tags;
+        self
+    }
+
+    /// Form in which literals with the datatype `xsd:string` are stored in the dictionary, see [`XsdString`].
+    /// Constant literals in patterns are looked up in this form first and then in the other one,
+    /// so that files whose creator did not omit the datatype can be queried efficiently as well.
+    /// The extracted terms always have the datatype, as required by Sophia.
+    /// # Example
+    /// ```
+    /// use hdt::containers::rdf::XsdString;
+    /// fn explicit(hdt: hdt::Hdt) -> hdt::HdtGraph {
+    ///     hdt::HdtGraph::new(hdt).with_xsd_string(XsdString::Explicit)
+    /// }
+    /// ```
+    #[must_use]
+    pub const fn with_xsd_string(mut self, xsd_string: XsdString) -> Self {
+        self.xsd_string = xsd_string;
+        self
+    }
+
+    /// Whether the results need to be processed by [`Self::check`].
+    fn needs_check(&self) -> bool {
+        self.validation != Validation::Unchecked || self.canonical_tags
+    }
+
+    /// Apply the language tag canonicalization and the validation level of the graph to the given triple.
+    fn check(&self, mut triple: [HdtTerm; 3]) -> Result<[HdtTerm; 3], GraphError> {
+        if self.canonical_tags {
+            triple[2] = canonical(triple[2].clone());
+        }
+        if self.validation == Validation::Unchecked {
+            return Ok(triple);
+        }
+        match triple.iter().try_for_each(validate) {
+            Err(e) if self.validation == Validation::Strict => Err(e.into()),
+            Err(e) => {
+                warn!("{e} in triple {triple:?}");
+                Ok(triple)
+            }
+            Ok(()) => Ok(triple),
+        }
     }
     /// Size in bytes on the heap.
     pub fn size_in_bytes(&self) -> usize {
         self.hdt.size_in_bytes()
     }
 
-    fn id_term(&self, id: Id, kind: &'static IdKind) -> HdtTerm {
-        auto_term(&self.hdt.dict.id_to_string(id, kind).unwrap()).unwrap()
+    fn id_term(&self, id: Id, kind: &'static IdKind) -> Result<HdtTerm, GraphError> {
+        let s = self
+            .hdt
+            .dict
+            .id_to_string(id, kind)
+            .map_err(|e| GraphError::Extract { id, message: e.to_string() })?;
+        auto_term(&s)
         // TODO: optimize by excluding cases depending on the id kind
         //IriRef::new_unchecked(MownStr::from(s)).into_term()
     }
 
+    /// Translates the given triple IDs into Sophia terms while keeping the IDs.
+    /// Useful for caching or joining on IDs while still displaying the terms.
+    /// Triples with a term that cannot be extracted result in an error, the iteration can continue afterwards.
+    /// # Example
+    /// ```
+    /// use hdt::triples::TripleId;
+    /// fn print_with_ids(graph: &hdt::HdtGraph) -> Result<(), hdt::hdt_graph::GraphError> {
+    ///     let ids = graph.hdt.triples.triples_with_pattern(&TripleId::new(1, 0, 0));
+    ///     for r in graph.with_terms(ids) {
+    ///         let (tid, [s, p, o]) = r?;
+    ///         println!("{tid:?} {s:?} {p:?} {o:?}");
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub const fn with_terms<I: Iterator<Item = TripleId>>(&self, ids: I) -> IdTermIter<'_, I> {
+        IdTermIter { graph: self, ids, last: None }
+    }
+
+    /// All triples that fit the given ID pattern as pairs of IDs and terms, where 0 stands for a variable.
+    /// See [`TriplesBitmap::triples_with_pattern`](crate::triples::TriplesBitmap::triples_with_pattern).
+    pub fn triples_with_ids(&self, pat: &TripleId) -> IdTermIter<'_, Box<dyn Iterator<Item = TripleId> + '_>> {
+        self.with_terms(self.hdt.triples.triples_with_pattern(pat))
+    }
+
+    /// Resolves many constant terms to the dictionary section and ID they occur with, such as for preparing the patterns of a query in advance.
+    /// Terms are looked up in lexicographical order, so that neighbouring terms share the dictionary blocks while they are still cached.
+    /// IRIs and blank nodes are tried as subject, then as object and then as predicate, so that the ID of a term in the shared section is the same for both subject and object.
+    /// Literals are only tried as objects.
+    /// The result has the same order as the input, with `None` for terms that do not occur in the graph.
+    /// # Example
+    /// ```
+    /// use hdt::sophia::api::term::{IriRef, SimpleTerm};
+    /// fn resolve(graph: &hdt::HdtGraph) {
+    ///     let terms = [SimpleTerm::Iri(IriRef::new_unchecked("http://www.w3.org/1999/02/22-rdf-syntax-ns#type".into()))];
+    ///     println!("{:?}", graph.resolve_terms(&terms));
+    /// }
+    /// ```
+    pub fn resolve_terms<T: Term>(&self, terms: &[T]) -> Vec<Option<(IdKind, Id)>> {
+        let mut keyed: Vec<(usize, HdtTerm, String)> = terms
+            .iter()
+            .enumerate()
+            .filter_map(|(i, t)| {
+                let t = HdtTerm::try_from(t.borrow_term())?;
+                let key = term_string(&t, self.xsd_string);
+                Some((i, t, key))
+            })
+            .collect();
+        keyed.sort_unstable_by(|a, b| compare_terms(&a.2, &b.2));
+        let mut resolved = vec![None; terms.len()];
+        for (i, t, _) in keyed {
+            let kinds: &[IdKind] = match t {
+                HdtTerm::LiteralDatatype(..) | HdtTerm::LiteralLanguage(..) => &[IdKind::Object],
+                _ => &[IdKind::Subject, IdKind::Object, IdKind::Predicate],
+            };
+            resolved[i] =
+                kinds.iter().find_map(|kind| self.term_id(t.clone(), kind).map(|(_, id)| (kind.clone(), id)));
+        }
+        resolved
+    }
+
     /// Transforms a Sophia TermMatcher to a constant HdtTerm and Id if possible.
     /// Returns none if it matches a constant term that cannot be found.
     fn unpack_matcher<T: TermMatcher>(&self, tm: &T, kind: &IdKind) -> Option<HdtMatcher> {
         match tm.constant() {
-            Some(t) => match HdtTerm::try_from(t.borrow_term()) {
-                Some(t) => {
-                    let id = self.hdt.dict.string_to_id(&term_string(&t), kind);
-                    if id == 0 {
-                        return None;
-                    }
-                    Some(HdtMatcher::Constant((t, id)))
-                }
-                None => None,
-            },
+            Some(t) => {
+                let t = HdtTerm::try_from(t.borrow_term())?;
+                self.term_id(t, kind).map(HdtMatcher::Constant)
+            }
             None => Some(HdtMatcher::Other),
         }
     }
+
+    /// Looks up the ID of the given term in the given position, returns none if it cannot be found.
+    fn term_id(&self, t: HdtTerm, kind: &IdKind) -> Option<(HdtTerm, Id)> {
+        match t {
+            HdtTerm::LiteralLanguage(lex, tag) if self.canonical_tags => {
+                // language tags are usually stored in either canonical or lowercase form
+                let canonical_tag = canonical_language_tag(tag.as_str());
+                [tag.as_str().to_owned(), canonical_tag.clone(), tag.as_str().to_ascii_lowercase()]
+                    .into_iter()
+                    .map(|tag| self.hdt.dict.string_to_id(&format!("\"{lex}\"@{tag}"), kind))
+                    .find(|&id| id != 0)
+                    .map(|id| {
+                        let tag = LanguageTag::new_unchecked(Arc::from(canonical_tag));
+                        (HdtTerm::LiteralLanguage(lex, tag), id)
+                    })
+            }
+            HdtTerm::LiteralDatatype(_, ref dt) if dt.as_str() == XSD_STRING.as_str() => {
+                let s = term_string(&t, self.xsd_string);
+                let other = match self.xsd_string {
+                    XsdString::Implicit => XsdString::Explicit,
+                    XsdString::Explicit => XsdString::Implicit,
+                };
+                [self.xsd_string, other]
+                    .into_iter()
+                    .map(|xsd_string| self.hdt.dict.string_to_id(&xsd_string.apply(&s), kind))
+                    .find(|&id| id != 0)
+                    .map(|id| (t, id))
+            }
+            t => {
+                let id = self.hdt.dict.string_to_id(&term_string(&t, self.xsd_string), kind);
+                if id == 0 {
+                    return None;
+                }
+                Some((t, id))
+            }
+        }
+    }
+}
+
+/// Adapter that yields each triple ID together with its materialized terms, see [`HdtGraph::with_terms`].
+/// Reuses the terms of the previous triple for components with the same ID.
+pub struct IdTermIter<'a, I> {
+    graph: &'a HdtGraph,
+    ids: I,
+    last: Option<(TripleId, [HdtTerm; 3])>,
 }
 
+impl<I> IdTermIter<'_, I> {
+    /// Terms of the given triple, with those of the previous triple for components with the same ID.
+    fn terms(&self, t: &TripleId) -> Result<[HdtTerm; 3], GraphError> {
+        let ids = [t.subject_id, t.predicate_id, t.object_id];
+        let kinds = [&IdKind::Subject, &IdKind::Predicate, &IdKind::Object];
+        let term = |i: usize| match &self.last {
+            Some((l, terms)) if [l.subject_id, l.predicate_id, l.object_id][i] == ids[i] => Ok(terms[i].clone()),
+            _ => self.graph.id_term(ids[i], kinds[i]),
+        };
+        let [s, p, o] = [term(0)?, term(1)?, term(2)?];
+        let o = if self.graph.canonical_tags { canonical(o) } else { o };
+        Ok([s, p, o])
+    }
+}
+
+impl<I: Iterator<Item = TripleId>> Iterator for IdTermIter<'_, I> {
+    type Item = Result<(TripleId, [HdtTerm; 3]), GraphError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let t = self.ids.next()?;
+        let terms = self.terms(&t);
+        self.last = terms.as_ref().ok().map(|terms| (t, terms.clone()));
+        Some(terms.map(|terms| (t, terms)))
+    }
+}
+
+impl<I: FusedIterator<Item = TripleId>> FusedIterator for IdTermIter<'_, I> {}
+
 /// Create the correct Sophia term for a given resource string.
 /// Slow, use the appropriate method if you know which type (Literal, URI, or blank node) the string has.
-fn auto_term(s: &str) -> io::Result<HdtTerm> {
+/// Literals with `xsd:string` result in the same term in both forms of [`XsdString`], as in RDF 1.1.
+fn auto_term(s: &str) -> Result<HdtTerm, GraphError> {
+    let invalid = |message: &str| GraphError::Decode { term: s.to_owned(), message: message.to_owned() };
+    let s = &*XsdString::Implicit.apply(s);
     match s.chars().next() {
-        None => Err(Error::new(ErrorKind::InvalidData, "empty input")),
+        None => Err(invalid("empty input")),
         Some('"') => match s.rfind('"') {
-            None => Err(Error::new(
-                ErrorKind::InvalidData,
-                format!("missing right quotation mark in literal string {s}"),
-            )),
-            Some(index) => {
+            Some(index) if index > 0 => {
                 let lex = Arc::from(&s[1..index]);
                 let rest = &s[index + 1..];
                 // literal with no language tag and no datatype
                 if rest.is_empty() {
-                    return Ok(HdtTerm::LiteralDatatype(lex, term::XSD_STRING.clone()));
+                    return Ok(HdtTerm::LiteralDatatype(lex, XSD_STRING.clone()));
                 }
                 // either language tag or datatype
                 if let Some(tag_index) = rest.find('@') {
@@ -84,48 +341,67 @@ fn auto_term(s: &str) -> io::Result<HdtTerm> {
                     return Ok(HdtTerm::LiteralLanguage(lex, tag));
                 }
                 // datatype
-                let mut dt_split = rest.split("^^");
-                dt_split.next(); // empty
-                match dt_split.next() {
-                    Some(dt) => {
-                        let unquoted = &dt[1..dt.len() - 1];
-                        let dt = IriRef::new_unchecked(Arc::from(unquoted));
+                match rest.strip_prefix("^^<").and_then(|dt| dt.strip_suffix('>')) {
+                    Some(unquoted) => {
+                        let dt = well_known_datatype(unquoted)
+                            .cloned()
+                            .unwrap_or_else(|| IriRef::new_unchecked(Arc::from(unquoted)));
                         Ok(HdtTerm::LiteralDatatype(lex, dt))
                     }
-                    None => Err(Error::new(ErrorKind::InvalidData, format!("empty datatype in {s}"))),
+                    None => Err(invalid("invalid datatype")),
                 }
             }
+            _ => Err(invalid("missing right quotation mark in literal")),
+        },
+        Some('_') => match s.strip_prefix("_:") {
+            Some(id) => Ok(HdtTerm::BlankNode(BnodeId::new_unchecked(Arc::from(id)))),
+            None => Err(invalid("blank node without \"_:\" prefix")),
         },
-        Some('_') => Ok(HdtTerm::BlankNode(BnodeId::new_unchecked(Arc::from(&s[2..])))),
         _ => Ok(HdtTerm::Iri(IriRef::new_unchecked(Arc::from(s)))),
     }
 }
 
-// Convert a SimpleTerm into the HDT String format.
+/// Convert the language tag of the given term to the canonical BCP 47 case.
+fn canonical(t: HdtTerm) -> HdtTerm {
+    match t {
+        HdtTerm::LiteralLanguage(lex, tag) => HdtTerm::LiteralLanguage(
+            lex,
+            LanguageTag::new_unchecked(Arc::from(canonical_language_tag(tag.as_str()))),
+        ),
+        t => t,
+    }
+}
+
+/// Validate the IRIs, including datatypes, and language tags of the given term.
+fn validate(t: &HdtTerm) -> Result<(), InvalidTerm> {
+    match t {
+        HdtTerm::Iri(iri) | HdtTerm::LiteralDatatype(_, iri) => {
+            IriRef::new(iri.as_str()).map(|_| ()).map_err(|_| InvalidTerm::Iri(iri.as_str().to_owned()))
+        }
+        HdtTerm::LiteralLanguage(_, tag) => LanguageTag::new(tag.as_str())
+            .map(|_| ())
+            .map_err(|_| InvalidTerm::LanguageTag(tag.as_str().to_owned())),
+        HdtTerm::BlankNode(_) => Ok(()),
+    }
+}
+
+// Convert a SimpleTerm into the HDT String format with xsd:string literals in the given form.
 // Sophia doesn't include the _: prefix for blank node strings but HDT expects it
 // not needed for property terms, as they can't be blank nodes
-fn term_string(t: &HdtTerm) -> String {
+fn term_string(t: &HdtTerm, xsd_string: XsdString) -> String {
     match t {
         HdtTerm::BlankNode(b) => "_:".to_owned() + b.as_str(),
         HdtTerm::Iri(i) => i.as_str().to_owned(),
-        HdtTerm::LiteralLanguage(l, lang) => {
-            format!("\"{l}\"@{}", lang.as_str())
-        }
+        HdtTerm::LiteralLanguage(l, lang) => Hdt::literal_matcher(l, None, Some(lang.as_str())),
         HdtTerm::LiteralDatatype(l, dt) => {
-            let xsd_string: &str = "http://www.w3.org/2001/XMLSchema#string";
-            let dts = dt.as_str();
-            if dts == xsd_string {
-                format!("\"{l}\"")
-            } else {
-                format!("\"{l}\"^^<{dts}>")
-            }
+            xsd_string.apply(&Hdt::literal_matcher(l, Some(dt.as_str()), None)).into_owned()
         }
     }
 }
 
 impl Graph for HdtGraph {
     type Triple<'a> = [HdtTerm; 3];
-    type Error = Infallible; // infallible for now, figure out what to put here later
+    type Error = GraphError;
 
     /// # Example
     /// ```
@@ -136,9 +412,7 @@ impl Graph for HdtGraph {
     /// ```
     fn triples(&self) -> impl Iterator<Item = Result<Self::Triple<'_>, Self::Error>> {
         debug!("Iterating through ALL triples in the HDT Graph. This can be inefficient for large graphs.");
-        self.hdt.triples().map(move |(s, p, o)| {
-            Ok([auto_term(&s).unwrap(), HdtTerm::Iri(IriRef::new_unchecked(p)), auto_term(&o).unwrap()])
-        })
+        self.triples_matching(Any, Any, Any)
     }
 
     /// Only supports constant and "any" matchers.
@@ -177,67 +451,59 @@ impl Graph for HdtGraph {
             None => return Box::new(iter::empty()),
             Some(x) => x,
         };
-        // TODO: improve error handling
-        match (xso, xpo, xoo) {
+        let triples: Box<dyn Iterator<Item = Result<Self::Triple<'s>, Self::Error>> + 's> = match (xso, xpo, xoo) {
             //if SubjectIter::with_pattern(&self.hdt.triples, &TripleId::new(s.1, p.1, o.1)).next().is_some() { // always true
             (Constant(s), Constant(p), Constant(o)) => Box::new(iter::once(Ok([s.0, p.0, o.0]))),
             (Constant(s), Constant(p), Other) => Box::new(
                 SubjectIter::with_pattern(&self.hdt.triples, &TripleId::new(s.1, p.1, 0))
-                    .map(|tid| {
-                        auto_term(&self.hdt.dict.id_to_string(tid.object_id, &IdKind::Object).unwrap()).unwrap()
-                    })
-                    .filter(move |term| om.matches(term))
-                    .map(move |term| Ok([s.0.clone(), p.0.clone(), term])),
+                    .map(|t| self.id_term(t.object_id, &IdKind::Object))
+                    .filter(move |r| r.as_ref().map_or(true, |term| om.matches(term)))
+                    .map(move |r| r.map(|term| [s.0.clone(), p.0.clone(), term])),
             ),
             (Constant(s), Other, Constant(o)) => Box::new(
                 SubjectIter::with_pattern(&self.hdt.triples, &TripleId::new(s.1, 0, o.1))
                     .map(|t| self.id_term(t.predicate_id, &IdKind::Predicate))
-                    .filter(move |term| pm.matches(term))
-                    .map(move |term| Ok([s.0.clone(), term, o.0.clone()])),
+                    .filter(move |r| r.as_ref().map_or(true, |term| pm.matches(term)))
+                    .map(move |r| r.map(|term| [s.0.clone(), term, o.0.clone()])),
             ),
             (Constant(s), Other, Other) => Box::new(
                 SubjectIter::with_pattern(&self.hdt.triples, &TripleId::new(s.1, 0, 0))
                     .map(move |t| {
-                        [
-                            self.id_term(t.predicate_id, &IdKind::Predicate),
-                            self.id_term(t.object_id, &IdKind::Object),
-                        ]
+                        self.id_term(t.predicate_id, &IdKind::Predicate)
+                            .and_then(|pt| Ok([pt, self.id_term(t.object_id, &IdKind::Object)?]))
                     })
-                    .filter(move |[pt, ot]| pm.matches(pt) && om.matches(ot))
-                    .map(move |[pt, ot]| Ok([s.0.clone(), pt, ot])),
+                    .filter(move |r| r.as_ref().map_or(true, |[pt, ot]| pm.matches(pt) && om.matches(ot)))
+                    .map(move |r| r.map(|[pt, ot]| [s.0.clone(), pt, ot])),
             ),
             (Other, Constant(p), Constant(o)) => Box::new(
                 PredicateObjectIter::new(&self.hdt.triples, p.1, o.1)
                     .map(|sid| self.id_term(sid, &IdKind::Subject))
-                    .filter(move |term| sm.matches(term))
-                    .map(move |term| Ok([term, p.0.clone(), o.0.clone()])),
+                    .filter(move |r| r.as_ref().map_or(true, |term| sm.matches(term)))
+                    .map(move |r| r.map(|term| [term, p.0.clone(), o.0.clone()])),
             ),
             (Other, Constant(p), Other) => Box::new(
                 PredicateIter::new(&self.hdt.triples, p.1)
                     .map(move |t| {
-                        [self.id_term(t.subject_id, &IdKind::Subject), self.id_term(t.object_id, &IdKind::Object)]
+                        self.id_term(t.subject_id, &IdKind::Subject)
+                            .and_then(|st| Ok([st, self.id_term(t.object_id, &IdKind::Object)?]))
                     })
-                    .filter(move |[st, ot]| sm.matches(st) && om.matches(ot))
-                    .map(move |[st, ot]| Ok([st, p.0.clone(), ot])),
+                    .filter(move |r| r.as_ref().map_or(true, |[st, ot]| sm.matches(st) && om.matches(ot)))
+                    .map(move |r| r.map(|[st, ot]| [st, p.0.clone(), ot])),
             ),
             (Other, Other, Constant(o)) => Box::new(ObjectIter::new(&self.hdt.triples, o.1).map(move |t| {
-                Ok([
-                    auto_term(&Arc::from(self.hdt.dict.id_to_string(t.subject_id, &IdKind::Subject).unwrap()))
-                        .unwrap(),
-                    self.id_term(t.predicate_id, &IdKind::Predicate),
-                    o.0.clone(),
-                ])
+                self.id_term(t.subject_id, &IdKind::Subject)
+                    .and_then(|st| Ok([st, self.id_term(t.predicate_id, &IdKind::Predicate)?, o.0.clone()]))
             })),
             (Other, Other, Other) => Box::new(
-                self.hdt
-                    .triples()
-                    .map(move |(s, p, o)| {
-                        [auto_term(&s).unwrap(), HdtTerm::Iri(IriRef::new_unchecked(p)), auto_term(&o).unwrap()]
-                    })
-                    .filter(move |[st, pt, ot]| sm.matches(st) && pm.matches(pt) && om.matches(ot))
-                    .map(Result::Ok),
+                self.triples_with_ids(&TripleId::new(0, 0, 0)).map(|r| r.map(|(_, t)| t)).filter(move |r| {
+                    r.as_ref().map_or(true, |[st, pt, ot]| sm.matches(st) && pm.matches(pt) && om.matches(ot))
+                }),
             ),
+        };
+        if !self.needs_check() {
+            return triples;
         }
+        Box::new(triples.map(move |r| r.and_then(|t| self.check(t))))
     }
 }
 
@@ -247,6 +513,7 @@ mod tests {
     use crate::tests::init;
     use sophia::api::prelude::Triple;
     use sophia::api::term::matcher::Any;
+    use sophia::api::term::SimpleTerm;
     use std::fs::File;
 
     #[test]
@@ -255,7 +522,7 @@ mod tests {
         let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
         let hdt = Hdt::new(std::io::BufReader::new(file)).unwrap();
         let graph = HdtGraph::new(hdt);
-        let triples: Vec<Result<[HdtTerm; 3], Infallible>> = graph.triples().collect();
+        let triples: Vec<Result<[HdtTerm; 3], GraphError>> = graph.triples().collect();
         assert_eq!(triples.len(), 328);
         let meta_top = "http://www.snik.eu/ontology/meta/Top";
         assert!(graph
@@ -362,6 +629,13 @@ mod tests {
                 .map(Result::unwrap)
                 .collect::<Vec<_>>()
         );
+        // *** IDs together with terms *********************************************************
+        let with_ids: Vec<_> = graph.triples_with_ids(&TripleId::new(0, 0, 0)).map(Result::unwrap).collect();
+        assert_eq!(328, with_ids.len());
+        for ((tid, terms), triple) in with_ids.iter().zip(&triples) {
+            assert_eq!(triple.as_ref().unwrap(), terms);
+            assert_eq!(graph.id_term(tid.object_id, &IdKind::Object).unwrap(), terms[2]);
+        }
         /*
         let testo = &SimpleTerm::from(LiteralDatatype(
             "testo",
@@ -370,4 +644,218 @@ mod tests {
         assert!(graph.triples_matching(testo).count() == 1);
         */
     }
+
+    #[test]
+    fn test_validation() {
+        init();
+        let iri = |s: &str| HdtTerm::Iri(IriRef::new_unchecked(s.into()));
+        assert_eq!(Ok(()), validate(&iri("http://www.snik.eu/ontology/meta")));
+        assert_eq!(Err(InvalidTerm::Iri("not an iri".to_owned())), validate(&iri("not an iri")));
+        let tag = |t: &str| HdtTerm::LiteralLanguage("x".into(), LanguageTag::new_unchecked(t.into()));
+        assert_eq!(Ok(()), validate(&tag("en-US")));
+        assert_eq!(Err(InvalidTerm::LanguageTag("en_US!".to_owned())), validate(&tag("en_US!")));
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let hdt = Hdt::new(std::io::BufReader::new(file)).unwrap();
+        let graph = HdtGraph::new(hdt).with_validation(Validation::Strict);
+        assert!(graph.triples().all(|t| t.is_ok()));
+        assert!(graph.triples_matching(Any, Any, Any).all(|t| t.is_ok()));
+        let invalid = [iri("not an iri"), iri("http://example.org"), iri("http://example.org")];
+        assert_eq!(
+            Err(GraphError::Invalid(InvalidTerm::Iri("not an iri".to_owned()))),
+            graph.check(invalid.clone())
+        );
+        assert!(graph.with_validation(Validation::Warn).check(invalid).is_ok());
+    }
+
+    #[test]
+    fn test_graph_errors() {
+        init();
+        for s in ["", "\"", "\"unterminated", "\"a\"^^http://example.org/dt", "_b1"] {
+            assert!(matches!(auto_term(s), Err(GraphError::Decode { .. })), "{s}");
+        }
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let graph = HdtGraph::new(Hdt::new(std::io::BufReader::new(file)).unwrap());
+        let missing = 1_000_000;
+        assert!(
+            matches!(graph.id_term(missing, &IdKind::Object), Err(GraphError::Extract { id, .. }) if id == missing)
+        );
+        // an error only fails its own triple
+        let ids = [TripleId::new(1, 1, missing), TripleId::new(1, 1, 1)];
+        let results: Vec<_> = graph.with_terms(ids.into_iter()).collect();
+        assert!(results[0].is_err());
+        assert!(results[1].is_ok());
+    }
+
+    #[test]
+    fn test_empty_graph() {
+        init();
+        let mut bytes = Vec::new();
+        crate::builder::HdtBuilder::new()
+            .build()
+            .unwrap()
+            .write_to(&mut bytes, "http://example.org/empty")
+            .unwrap();
+        let graph = HdtGraph::new(Hdt::new(bytes.as_slice()).unwrap()).with_validation(Validation::Strict);
+        assert_eq!(0, graph.triples().count());
+        assert_eq!(0, graph.triples_matching(Any, Any, Any).count());
+        let a = HdtTerm::Iri(IriRef::new_unchecked("http://example.org/a".into()));
+        assert_eq!(0, graph.triples_matching(Some(a.clone()), Any, Any).count());
+        assert_eq!(0, graph.triples_matching(Any, Some(a.clone()), Any).count());
+        assert_eq!(0, graph.triples_matching(Any, Any, Some(a.clone())).count());
+        assert_eq!(0, graph.triples_matching(Some(a.clone()), Some(a.clone()), Some(a)).count());
+    }
+
+    #[test]
+    fn test_resolve_terms() {
+        init();
+        let file = std::fs::File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let hdt = Hdt::new(std::io::BufReader::new(file)).unwrap();
+        let graph = HdtGraph::new(hdt);
+        let iri = |s: &'static str| SimpleTerm::Iri(IriRef::new_unchecked(s.into()));
+        let terms = [
+            iri("http://www.w3.org/2000/01/rdf-schema#label"),
+            iri("http://www.snik.eu/ontology/meta/Top"),
+            iri("http://example.org/doesnotexist"),
+            SimpleTerm::LiteralLanguage("top class".into(), LanguageTag::new_unchecked("en".into())),
+        ];
+        let resolved = graph.resolve_terms(&terms);
+        let id = |s: &str, kind: &IdKind| graph.hdt.dict.string_to_id(s, kind);
+        let label = id("http://www.w3.org/2000/01/rdf-schema#label", &IdKind::Predicate);
+        assert!(matches!(resolved[0], Some((IdKind::Predicate, i)) if i == label));
+        let top = id("http://www.snik.eu/ontology/meta/Top", &IdKind::Subject);
+        assert!(matches!(resolved[1], Some((IdKind::Subject, i)) if i == top));
+        assert!(resolved[2].is_none());
+        let top_class = id("\"top class\"@en", &IdKind::Object);
+        assert!(matches!(resolved[3], Some((IdKind::Object, i)) if i == top_class));
+    }
+
+    #[test]
+    fn test_well_known_datatypes() {
+        init();
+        let a = auto_term("\"1\"^^<http://www.w3.org/2001/XMLSchema#integer>").unwrap();
+        let b = auto_term("\"2\"^^<http://www.w3.org/2001/XMLSchema#integer>").unwrap();
+        let (HdtTerm::LiteralDatatype(_, a), HdtTerm::LiteralDatatype(_, b)) = (a, b) else {
+            panic!("not a typed literal");
+        };
+        assert_eq!(XSD_INTEGER.as_str(), a.as_str());
+        // the same Arc is shared instead of allocating a new one
+        assert!(std::ptr::eq(a.as_str(), b.as_str()));
+        assert!(well_known_datatype("http://example.org/custom").is_none());
+    }
+
+    #[test]
+    fn test_canonical_language_tags() {
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let hdt = Hdt::new(std::io::BufReader::new(file)).unwrap();
+        let graph = HdtGraph::new(hdt);
+        let lit = |t: &str| HdtTerm::LiteralLanguage("top class".into(), LanguageTag::new_unchecked(t.into()));
+        assert_eq!(0, graph.triples_matching(Any, Any, [lit("EN")]).count());
+        let graph = graph.with_canonical_language_tags(true);
+        let triples: Vec<_> = graph.triples_matching(Any, Any, [lit("EN")]).map(Result::unwrap).collect();
+        assert_eq!(1, triples.len());
+        assert_eq!(lit("en"), triples[0][2]);
+        let o = canonical(HdtTerm::LiteralLanguage("x".into(), LanguageTag::new_unchecked("en-us".into())));
+        assert_eq!(HdtTerm::LiteralLanguage("x".into(), LanguageTag::new_unchecked("en-US".into())), o);
+    }
+
+    #[test]
+    fn test_xsd_string() {
+        init();
+        let build = || {
+            let mut builder = crate::builder::HdtBuilder::new();
+            let p = "http://example.org/p";
+            builder.add_triple("http://example.org/a", p, "\"abc\"^^<http://www.w3.org/2001/XMLSchema#string>");
+            builder.add_triple("http://example.org/b", p, "\"def\"");
+            builder.build().unwrap()
+        };
+        let lit = |lex: &str| HdtTerm::LiteralDatatype(lex.into(), XSD_STRING.clone());
+        for xsd_string in [XsdString::Implicit, XsdString::Explicit] {
+            // found regardless of the form in the dictionary
+            let graph = HdtGraph::new(build()).with_xsd_string(xsd_string);
+            for lex in ["abc", "def"] {
+                let triples: Vec<_> = graph.triples_matching(Any, Any, [lit(lex)]).map(Result::unwrap).collect();
+                assert_eq!(1, triples.len(), "{xsd_string:?} {lex}");
+                assert_eq!(lit(lex), triples[0][2]);
+            }
+            let abc = "\"abc\"^^<http://www.w3.org/2001/XMLSchema#string>";
+            let expected = if xsd_string == XsdString::Explicit { abc } else { "\"abc\"" };
+            assert_eq!(expected, term_string(&lit("abc"), xsd_string));
+            assert_eq!(
+                "\"1\"^^<http://www.w3.org/2001/XMLSchema#integer>",
+                term_string(&auto_term("\"1\"^^<http://www.w3.org/2001/XMLSchema#integer>").unwrap(), xsd_string)
+            );
+            assert_eq!(lit("abc"), auto_term(&term_string(&lit("abc"), xsd_string)).unwrap());
+        }
+        assert_eq!(
+            auto_term("\"abc\"").unwrap(),
+            auto_term("\"abc\"^^<http://www.w3.org/2001/XMLSchema#string>").unwrap()
+        );
+    }
+
+    #[cfg(feature = "oxrdf")]
+    #[test]
+    fn test_oxrdf() {
+        use oxrdf::{NamedNode, Term};
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let hdt = Hdt::new(std::io::BufReader::new(file)).unwrap();
+        let graph = HdtGraph::new(hdt);
+        for triple in graph.triples() {
+            for t in triple.unwrap() {
+                let back: HdtTerm = Term::from(t.clone()).try_into().unwrap();
+                assert_eq!(t, back);
+            }
+        }
+        let label = NamedNode::new_unchecked("http://www.w3.org/2000/01/rdf-schema#label");
+        let triples: Vec<_> = graph.triples_matching_oxrdf(None, Some(&label), None).map(Result::unwrap).collect();
+        let label_term = HdtTerm::Iri(IriRef::new_unchecked(label.as_str().into()));
+        let expected: Vec<_> = graph.triples_matching(Any, [label_term], Any).map(Result::unwrap).collect();
+        assert_eq!(expected.len(), triples.len());
+        for (t, [s, _, o]) in triples.iter().zip(expected) {
+            assert_eq!(Term::from(s), Term::from(t.subject.clone()));
+            assert_eq!(Term::from(o), t.object);
+        }
+        let s = Term::from(triples[0].subject.clone());
+        assert_eq!(1, graph.triples_matching_oxrdf(Some(&s), Some(&label), Some(&triples[0].object)).count());
+        let unknown = Term::from(NamedNode::new_unchecked("http://example.org/unknown"));
+        assert_eq!(0, graph.triples_matching_oxrdf(Some(&unknown), None, None).count());
+    }
+
+    #[test]
+    fn test_dataset() {
+        use sophia::api::dataset::Dataset;
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let hdt = Hdt::new(std::io::BufReader::new(file)).unwrap();
+        let dataset = HdtDataset::new(HdtGraph::new(hdt));
+        let quads: Vec<_> = dataset.quads().map(Result::unwrap).collect();
+        assert_eq!(328, quads.len());
+        assert!(quads.iter().all(|(_, g)| g.is_none()));
+        let label = HdtTerm::Iri(IriRef::new_unchecked("http://www.w3.org/2000/01/rdf-schema#label".into()));
+        assert_eq!(
+            dataset.graph.triples_matching(Any, [label.clone()], Any).count(),
+            dataset.quads_matching(Any, [label], Any, Any).count()
+        );
+
+        // quad file with the first triples in graph 1 and all triples in graph 2
+        let mut hdt = dataset.graph.hdt;
+        let len = hdt.triples.len();
+        let first = crate::containers::Bitmap::from_bits((0..len).map(|pos_z| pos_z < 10));
+        let all = crate::containers::Bitmap::from_bits((0..len).map(|_| true));
+        hdt.dict.graphs = Some(crate::DictSectPFC::new(&["http://example.org/g1", "http://example.org/g2"], 16));
+        hdt.triples.quad_info = Some(crate::triples::QuadInfo::new(vec![first, all]).unwrap());
+        let dataset = HdtDataset::new(HdtGraph::new(hdt));
+        let quads: Vec<_> = dataset.quads().map(Result::unwrap).collect();
+        assert_eq!(len + 10, quads.len());
+        let g1 = HdtTerm::Iri(IriRef::new_unchecked("http://example.org/g1".into()));
+        assert_eq!(10, quads.iter().filter(|(_, g)| g.as_ref() == Some(&g1)).count());
+        let meta_top = HdtTerm::Iri(IriRef::new_unchecked("http://www.snik.eu/ontology/meta/Top".into()));
+        let expected = dataset.graph.triples_matching([meta_top.clone()], Any, Any).count();
+        assert_ne!(0, expected);
+        let with_top: Vec<_> =
+            dataset.quads_matching([meta_top.clone()], Any, Any, Any).map(Result::unwrap).collect();
+        assert!(with_top.len() >= expected);
+        assert!(with_top.iter().all(|([s, _, _], g)| *s == meta_top && g.is_some()));
+    }
 }
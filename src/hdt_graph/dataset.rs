@@ -0,0 +1,118 @@
+//! I define [`HdtDataset`], an implementation of [`sophia::api::dataset::Dataset`].
+use super::{auto_term, GraphError, HdtGraph, HdtMatcher, HdtTerm};
+use crate::four_sect_dict::IdKind;
+use crate::triples::TripleId;
+use log::error;
+use sophia::api::dataset::{DResult, Dataset};
+use sophia::api::graph::Graph;
+use sophia::api::quad::Spog;
+use sophia::api::term::matcher::{Any, GraphNameMatcher, TermMatcher};
+use std::iter;
+
+/// Adapter to use HDT as a Sophia dataset.
+/// The triples of files without graphs are in the default graph, those of quad files in their named graphs,
+/// see [`QuadId`](crate::triples::QuadId).
+/// Patterns are evaluated like with the wrapped [`HdtGraph`], including its validation and language tag settings.
+pub struct HdtDataset {
+    /// Wrapped graph
+    pub graph: HdtGraph,
+}
+
+impl HdtDataset {
+    /// Wrapper around HdtGraph.
+    /// # Example
+    /// ```
+    /// use hdt::hdt_graph::HdtDataset;
+    /// fn dataset(hdt: hdt::Hdt) -> HdtDataset {
+    ///     HdtDataset::new(hdt::HdtGraph::new(hdt))
+    /// }
+    /// ```
+    pub const fn new(graph: HdtGraph) -> Self {
+        HdtDataset { graph }
+    }
+
+    /// Terms of the named graphs, with the graph ID - 1 as index and `None` for names that cannot be extracted.
+    fn graph_names(&self) -> Vec<Option<HdtTerm>> {
+        let Some(graphs) = &self.graph.hdt.dict.graphs else {
+            return Vec::new();
+        };
+        graphs
+            .iter()
+            .map(|name| match name {
+                Ok(name) => auto_term(&name).map_err(|e| error!("Invalid graph name {name}: {e}")).ok(),
+                Err(e) => {
+                    error!("{e}");
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+impl Dataset for HdtDataset {
+    type Quad<'a> = Spog<HdtTerm>;
+    type Error = GraphError;
+
+    /// # Example
+    /// ```
+    /// use hdt::sophia::api::dataset::Dataset;
+    /// fn print_first_quad(dataset: hdt::hdt_graph::HdtDataset) {
+    ///     println!("{:?}", dataset.quads().next().expect("no quad in the dataset"));
+    /// }
+    /// ```
+    fn quads(&self) -> impl Iterator<Item = DResult<Self, Self::Quad<'_>>> + '_ {
+        self.quads_matching(Any, Any, Any, Any)
+    }
+
+    /// Only supports constant and "any" matchers for subject, predicate and object, like [`HdtGraph::triples_matching`].
+    /// Graph name matchers are evaluated once for each named graph.
+    fn quads_matching<'s, S, P, O, G>(
+        &'s self, sm: S, pm: P, om: O, gm: G,
+    ) -> impl Iterator<Item = DResult<Self, Self::Quad<'s>>> + 's
+    where
+        S: TermMatcher + 's,
+        P: TermMatcher + 's,
+        O: TermMatcher + 's,
+        G: GraphNameMatcher + 's,
+    {
+        let graph = &self.graph;
+        if graph.hdt.triples.quad_info.is_none() {
+            if !gm.matches(None::<&HdtTerm>) {
+                return Box::new(iter::empty()) as Box<dyn Iterator<Item = DResult<Self, Self::Quad<'s>>> + 's>;
+            }
+            return Box::new(graph.triples_matching(sm, pm, om).map(|r| r.map(|t| (t, None))));
+        }
+        let names: Vec<_> =
+            self.graph_names().into_iter().map(|name| name.filter(|name| gm.matches(Some(name)))).collect();
+        let (Some(xs), Some(xp), Some(xo)) = (
+            graph.unpack_matcher(&sm, &IdKind::Subject),
+            graph.unpack_matcher(&pm, &IdKind::Predicate),
+            graph.unpack_matcher(&om, &IdKind::Object),
+        ) else {
+            return Box::new(iter::empty());
+        };
+        let id = |x: &HdtMatcher| match x {
+            HdtMatcher::Constant((_, id)) => *id,
+            HdtMatcher::Other => 0,
+        };
+        let pat = TripleId::new(id(&xs), id(&xp), id(&xo));
+        let quads = graph.hdt.triples.quads_with_pattern(&pat, 0).filter_map(move |q| {
+            let name = names.get(q.graph_id - 1)?.clone()?;
+            let t = graph.id_term(q.subject_id, &IdKind::Subject).and_then(|s| {
+                Ok([
+                    s,
+                    graph.id_term(q.predicate_id, &IdKind::Predicate)?,
+                    graph.id_term(q.object_id, &IdKind::Object)?,
+                ])
+            });
+            match t {
+                Ok(t) => (sm.matches(&t[0]) && pm.matches(&t[1]) && om.matches(&t[2])).then_some(Ok((t, name))),
+                Err(e) => Some(Err(e)),
+            }
+        });
+        if !graph.needs_check() {
+            return Box::new(quads.map(|r| r.map(|(t, name)| (t, Some(name)))));
+        }
+        Box::new(quads.map(move |r| r.and_then(|(t, name)| graph.check(t).map(|t| (t, Some(name))))))
+    }
+}
@@ -0,0 +1,113 @@
+//! I define conversions between [`HdtTerm`] and [`oxrdf::Term`] and a pattern API of [`HdtGraph`] for oxrdf terms, which are used by Oxigraph.
+use super::{well_known_datatype, GraphError, HdtGraph, HdtTerm};
+use crate::four_sect_dict::IdKind;
+use crate::triples::TripleId;
+use oxrdf::{BlankNode, Literal, NamedNode, Subject, Term, Triple};
+use sophia::api::term::{BnodeId, IriRef, LanguageTag};
+use std::iter;
+use std::sync::Arc;
+
+impl From<HdtTerm> for Term {
+    fn from(t: HdtTerm) -> Self {
+        match t {
+            HdtTerm::Iri(iri) => NamedNode::new_unchecked(iri.as_str()).into(),
+            HdtTerm::BlankNode(bnid) => BlankNode::new_unchecked(bnid.as_str()).into(),
+            HdtTerm::LiteralDatatype(lex, dt) => {
+                Literal::new_typed_literal(&*lex, NamedNode::new_unchecked(dt.as_str())).into()
+            }
+            HdtTerm::LiteralLanguage(lex, tag) => {
+                Literal::new_language_tagged_literal_unchecked(&*lex, tag.as_str()).into()
+            }
+        }
+    }
+}
+
+/// Fails for quoted triples, which HDT does not support, and returns them unchanged.
+/// # Example
+/// ```
+/// use hdt::hdt_graph::HdtTerm;
+/// use hdt::oxrdf::{NamedNode, Term};
+/// let t = Term::from(NamedNode::new_unchecked("http://example.org/a"));
+/// let hdt_term: HdtTerm = t.clone().try_into().unwrap();
+/// assert_eq!(t, Term::from(hdt_term));
+/// ```
+impl TryFrom<Term> for HdtTerm {
+    type Error = Term;
+
+    fn try_from(t: Term) -> Result<Self, Self::Error> {
+        hdt_term(&t).ok_or(t)
+    }
+}
+
+fn hdt_term(t: &Term) -> Option<HdtTerm> {
+    match t {
+        Term::NamedNode(n) => Some(HdtTerm::Iri(IriRef::new_unchecked(Arc::from(n.as_str())))),
+        Term::BlankNode(b) => Some(HdtTerm::BlankNode(BnodeId::new_unchecked(Arc::from(b.as_str())))),
+        Term::Literal(l) => Some(match l.language() {
+            Some(tag) => {
+                HdtTerm::LiteralLanguage(Arc::from(l.value()), LanguageTag::new_unchecked(Arc::from(tag)))
+            }
+            None => {
+                let dt = l.datatype().as_str();
+                let dt = well_known_datatype(dt).cloned().unwrap_or_else(|| IriRef::new_unchecked(Arc::from(dt)));
+                HdtTerm::LiteralDatatype(Arc::from(l.value()), dt)
+            }
+        }),
+        // quoted triples with the rdf-star feature of oxrdf
+        #[allow(unreachable_patterns)]
+        _ => None,
+    }
+}
+
+/// Fails for literal subjects and predicates that are not IRIs, which only occur in damaged dictionaries.
+fn triple([s, p, o]: [HdtTerm; 3]) -> Result<Triple, GraphError> {
+    let invalid = |t: Term, message: &str| GraphError::Decode { term: t.to_string(), message: message.to_owned() };
+    let subject: Subject = match Term::from(s) {
+        Term::NamedNode(n) => n.into(),
+        Term::BlankNode(b) => b.into(),
+        t => return Err(invalid(t, "subject is neither an IRI nor a blank node")),
+    };
+    let predicate = match Term::from(p) {
+        Term::NamedNode(n) => n,
+        t => return Err(invalid(t, "predicate is not an IRI")),
+    };
+    Ok(Triple::new(subject, predicate, o))
+}
+
+impl HdtGraph {
+    /// All triples that fit the given pattern of oxrdf terms, where `None` stands for a variable,
+    /// for applications based on Oxigraph that do not use Sophia.
+    /// Terms are looked up and extracted like with [`sophia::api::graph::Graph::triples_matching`],
+    /// including the validation, language tag and `xsd:string` settings of the graph.
+    /// # Example
+    /// Who was born in Leipzig?
+    /// ```
+    /// use hdt::oxrdf::{NamedNode, Term};
+    /// fn query(dbpedia: hdt::HdtGraph) {
+    ///     let birth_place = NamedNode::new_unchecked("http://www.snik.eu/ontology/birthPlace");
+    ///     let leipzig = Term::from(NamedNode::new_unchecked("http://www.snik.eu/resource/Leipzig"));
+    ///     for t in dbpedia.triples_matching_oxrdf(None, Some(&birth_place), Some(&leipzig)) {
+    ///         println!("{}", t.unwrap().subject);
+    ///     }
+    /// }
+    /// ```
+    pub fn triples_matching_oxrdf<'a>(
+        &'a self, s: Option<&Term>, p: Option<&NamedNode>, o: Option<&Term>,
+    ) -> Box<dyn Iterator<Item = Result<Triple, GraphError>> + 'a> {
+        let id = |t: Option<&Term>, kind| match t {
+            None => Some(0),
+            Some(t) => self.term_id(hdt_term(t)?, kind).map(|(_, id)| id),
+        };
+        let p = p.map(|p| Term::from(p.clone()));
+        let (Some(s), Some(p), Some(o)) =
+            (id(s, &IdKind::Subject), id(p.as_ref(), &IdKind::Predicate), id(o, &IdKind::Object))
+        else {
+            return Box::new(iter::empty());
+        };
+        let triples = self.triples_with_ids(&TripleId::new(s, p, o)).map(|r| r.map(|(_, t)| t));
+        if !self.needs_check() {
+            return Box::new(triples.map(|r| r.and_then(triple)));
+        }
+        Box::new(triples.map(move |r| r.and_then(|t| self.check(t)).and_then(triple)))
+    }
+}
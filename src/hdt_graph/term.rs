@@ -6,8 +6,33 @@ use sophia::iri::IriRef;
 use std::sync::Arc;
 
 lazy_static::lazy_static! {
+    /// Datatype of literals without language tag or explicit datatype.
     pub static ref XSD_STRING: IriRef<Arc<str>> = xsd::string.iri().unwrap().map_unchecked(|m| Arc::from(m.as_ref()));
+    /// Datatype of literals with a language tag.
     pub static ref RDF_LANG_STRING: IriRef<Arc<str>> = rdf::langString.iri().unwrap().map_unchecked(|m| Arc::from(m.as_ref()));
+    /// `xsd:integer`
+    pub static ref XSD_INTEGER: IriRef<Arc<str>> = xsd::integer.iri().unwrap().map_unchecked(|m| Arc::from(m.as_ref()));
+    /// `xsd:decimal`
+    pub static ref XSD_DECIMAL: IriRef<Arc<str>> = xsd::decimal.iri().unwrap().map_unchecked(|m| Arc::from(m.as_ref()));
+    /// `xsd:double`
+    pub static ref XSD_DOUBLE: IriRef<Arc<str>> = xsd::double.iri().unwrap().map_unchecked(|m| Arc::from(m.as_ref()));
+    /// `xsd:boolean`
+    pub static ref XSD_BOOLEAN: IriRef<Arc<str>> = xsd::boolean.iri().unwrap().map_unchecked(|m| Arc::from(m.as_ref()));
+    /// `xsd:date`
+    pub static ref XSD_DATE: IriRef<Arc<str>> = xsd::date.iri().unwrap().map_unchecked(|m| Arc::from(m.as_ref()));
+    /// `xsd:dateTime`
+    pub static ref XSD_DATE_TIME: IriRef<Arc<str>> = xsd::dateTime.iri().unwrap().map_unchecked(|m| Arc::from(m.as_ref()));
+}
+
+/// The shared constant for the given datatype IRI if it is one of the well-known datatypes defined in this module.
+/// Literals with these datatypes then share the same `Arc` instead of allocating a new one for each term.
+pub fn well_known_datatype(iri: &str) -> Option<&'static IriRef<Arc<str>>> {
+    [
+        &*XSD_STRING, &*XSD_INTEGER, &*XSD_DATE_TIME, &*XSD_DATE, &*XSD_DECIMAL, &*XSD_DOUBLE, &*XSD_BOOLEAN,
+        &*RDF_LANG_STRING,
+    ]
+    .into_iter()
+    .find(|dt| dt.as_str() == iri)
 }
 
 /// An implementation of [`sophia::api::term::Term`] for [`HdtGraph`](super::HdtGraph).
@@ -1,9 +1,10 @@
-use crate::containers::rdf::{Id, Literal, Term, Triple};
-use crate::containers::ControlInfo;
-use eyre::{eyre, Result, WrapErr};
+use crate::containers::rdf::{nt_term, Id, Literal, Term, Triple};
+use crate::containers::{ControlInfo, ControlType};
+use crate::error::{HdtError, Part};
+use eyre::{Result, WrapErr};
 use ntriple::parser::triple_line;
 use std::collections::BTreeSet;
-use std::io::BufRead;
+use std::io::{self, BufRead, Write};
 use std::str;
 
 /// Metadata about the dataset, see <https://www.rdfhdt.org/hdt-binary-format/#header>.
@@ -22,11 +23,15 @@ impl Header {
     pub fn read<R: BufRead>(reader: &mut R) -> Result<Self> {
         let header_ci = ControlInfo::read(reader)?;
         if header_ci.format != "ntriples" {
-            return Err(eyre!("Invalid header format {}, only 'ntriples' is supported", header_ci.format));
+            let (format, supported) = (header_ci.format, "only ntriples is supported");
+            return Err(HdtError::UnsupportedFormat { part: Part::Header, format, supported }.into());
         }
 
-        let ls = header_ci.get("length").ok_or_else(|| eyre!("missing header length"))?;
-        let length = ls.parse::<usize>().wrap_err_with(|| format!("invalid header length '{ls}'"))?;
+        let ls =
+            header_ci.get("length").ok_or_else(|| HdtError::invalid(Part::Header, "missing header length"))?;
+        let length = ls
+            .parse::<usize>()
+            .map_err(|e| HdtError::invalid(Part::Header, format!("invalid header length '{ls}': {e}")))?;
 
         let mut body_buffer: Vec<u8> = vec![0; length];
         reader.read_exact(&mut body_buffer)?;
@@ -62,6 +67,86 @@ impl Header {
         }
         Ok(Header { format: header_ci.format, length, body })
     }
+
+    /// Number of triples as stated by `void:triples` or `hdt:triplesnumTriples`, if the header contains it.
+    pub fn num_triples(&self) -> Option<usize> {
+        const PREDICATES: [&str; 2] =
+            ["http://rdfs.org/ns/void#triples", "http://purl.org/HDT/hdt#triplesnumTriples"];
+        self.body.iter().filter(|t| PREDICATES.contains(&t.predicate.as_str())).find_map(|t| match &t.object {
+            Term::Literal(literal) => literal.form().parse().ok(),
+            Term::Id(_) => None,
+        })
+    }
+
+    /// Write a header section with the given N-Triples data including its control information, in the format expected by [`Self::read`].
+    pub fn write<W: Write>(writer: &mut W, ntriples: &str) -> io::Result<()> {
+        let mut header_ci = ControlInfo::new(ControlType::Header, "ntriples");
+        header_ci.set("length", &ntriples.len().to_string());
+        header_ci.write(writer)?;
+        writer.write_all(ntriples.as_bytes())
+    }
+}
+
+/// Metadata for the header written by [`Hdt::write_with_metadata`](crate::Hdt::write_with_metadata) that cannot be computed from the dictionary and triples.
+/// The counts of triples, properties, distinct subjects and objects and the size of the written sections are always included.
+/// # Example
+/// ```
+/// use hdt::header::HeaderMetadata;
+/// let metadata = HeaderMetadata::new()
+///     .with_original_size(46813)
+///     .with_issued("2023-12-06T17:34:08+01:00")
+///     .with_triple("http://example.org/dataset", "http://purl.org/dc/terms/source", "http://example.org/data.nt");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HeaderMetadata {
+    original_size: Option<u64>,
+    issued: Option<String>,
+    triples: Vec<[String; 3]>,
+}
+
+impl HeaderMetadata {
+    /// No additional metadata.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Size in bytes of the RDF data the HDT was created from, written as `hdt:originalSize`,
+    /// see [`HdtBuilder::original_size`](crate::builder::HdtBuilder::original_size).
+    #[must_use]
+    pub const fn with_original_size(mut self, original_size: u64) -> Self {
+        self.original_size = Some(original_size);
+        self
+    }
+
+    /// Creation date as `xsd:dateTime`, such as `2023-12-06T17:34:08+01:00`, written as `dcterms:issued`.
+    /// There is no default, so that writing the same HDT twice results in the same bytes.
+    #[must_use]
+    pub fn with_issued(mut self, date_time: &str) -> Self {
+        self.issued = Some(date_time.to_owned());
+        self
+    }
+
+    /// Add a triple of terms in the string format of the HDT dictionary, such as the provenance of the dataset.
+    #[must_use]
+    pub fn with_triple(mut self, s: &str, p: &str, o: &str) -> Self {
+        self.triples.push([s.to_owned(), p.to_owned(), o.to_owned()]);
+        self
+    }
+
+    /// Size in bytes of the source data, if known.
+    pub const fn original_size(&self) -> Option<u64> {
+        self.original_size
+    }
+
+    /// Creation date, if known.
+    pub fn issued(&self) -> Option<&str> {
+        self.issued.as_deref()
+    }
+
+    /// The added triples as N-Triples.
+    pub fn ntriples(&self) -> String {
+        self.triples.iter().map(|[s, p, o]| [nt_term(s), nt_term(p), nt_term(o)].join(" ") + " .\n").collect()
+    }
 }
 
 #[cfg(test)]
@@ -82,6 +167,7 @@ mod tests {
             assert_eq!(header.format, "ntriples");
             assert_eq!(header.length, 1891);
             assert_eq!(header.body.len(), 22);
+            assert_eq!(header.num_triples(), Some(158_991_568));
         } else {
             panic!("Failed to read header");
         }
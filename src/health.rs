@@ -0,0 +1,180 @@
+//! Cheap canary operations on a loaded HDT, for example as a readiness probe of a service running in an orchestrated deployment.
+//! Each probe samples different triples, so that repeated probes gradually cover more of the data.
+use crate::four_sect_dict::IdKind;
+use crate::hdt::Hdt;
+use crate::triples::TripleId;
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Number of triples sampled by each canary operation.
+const SAMPLE_SIZE: usize = 8;
+
+/// Outcome of a single canary operation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthCheck {
+    /// Short name of the operation, one of `mapping`, `extract`, `pattern` and `crc`.
+    pub name: &'static str,
+    /// Description of the first problem found, `None` if the operation succeeded.
+    pub error: Option<String>,
+    /// Time the operation took.
+    pub duration: Duration,
+}
+
+impl HealthCheck {
+    /// Whether the operation succeeded.
+    pub const fn passed(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Outcomes of all canary operations of a [`Hdt::health_check`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthReport {
+    /// Seed of the random sample, pass it to [`Hdt::health_check_with_seed`] to repeat a failed probe.
+    pub seed: u64,
+    /// One entry per canary operation, in the order they were performed.
+    pub checks: Vec<HealthCheck>,
+}
+
+impl HealthReport {
+    /// Whether all canary operations succeeded.
+    pub fn is_healthy(&self) -> bool {
+        self.checks.iter().all(HealthCheck::passed)
+    }
+
+    /// Time all canary operations took together.
+    pub fn duration(&self) -> Duration {
+        self.checks.iter().map(|check| check.duration).sum()
+    }
+}
+
+impl Hdt {
+    /// Perform a handful of canary operations on randomly sampled triples, which complete in milliseconds even for huge files:
+    /// - `mapping`: with the `mmap` feature, check that a memory mapped file was not changed, see [`Hdt::check_mapping`].
+    ///   If it was, the other operations are skipped, because accessing a truncated file kills the process with SIGBUS.
+    /// - `extract`: translate the IDs of the sampled triples to strings and back.
+    /// - `pattern`: find the sampled triples with subject, predicate and object patterns, which uses all indexes.
+    /// - `crc`: compare the predicate section, which is small, with its checksum from loading time.
+    ///
+    /// Panics inside the operations, for example caused by a memory mapped file that was modified after loading,
+    /// are caught and reported as failures.
+    /// # Example
+    /// ```
+    /// fn ready(hdt: &hdt::Hdt) -> bool {
+    ///     let report = hdt.health_check();
+    ///     if !report.is_healthy() {
+    ///         eprintln!("{report:?}");
+    ///     }
+    ///     report.is_healthy()
+    /// }
+    /// ```
+    pub fn health_check(&self) -> HealthReport {
+        let seed = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_nanos() as u64);
+        self.health_check_with_seed(seed)
+    }
+
+    /// Like [`Self::health_check`] but with a fixed seed for the random sample.
+    pub fn health_check_with_seed(&self, seed: u64) -> HealthReport {
+        let mut checks = Vec::new();
+        #[cfg(feature = "mmap")]
+        {
+            let mapping = run("mapping", || self.check_mapping().map_err(|e| e.to_string()));
+            let changed = !mapping.passed();
+            checks.push(mapping);
+            if changed {
+                return HealthReport { seed, checks };
+            }
+        }
+        let sample: Vec<TripleId> = self.triples.sample(SAMPLE_SIZE, seed).collect();
+        checks.extend([
+            run("extract", || self.check_extract(&sample)),
+            run("pattern", || self.check_pattern(&sample)),
+            run("crc", || {
+                if self.dict.predicates.verify_crc() {
+                    Ok(())
+                } else {
+                    Err("predicate section changed".into())
+                }
+            }),
+        ]);
+        HealthReport { seed, checks }
+    }
+
+    /// Translate each ID of the sample to a string and back to the same ID.
+    fn check_extract(&self, sample: &[TripleId]) -> Result<(), String> {
+        for t in sample {
+            let ids = [t.subject_id, t.predicate_id, t.object_id];
+            for (id, kind) in ids.into_iter().zip([&IdKind::Subject, &IdKind::Predicate, &IdKind::Object]) {
+                let s = self.dict.id_to_string(id, kind).map_err(|e| format!("{kind:?} {id}: {e}"))?;
+                let located = self.dict.string_to_id(&s, kind);
+                if located != id {
+                    return Err(format!("{kind:?} {id} extracts to {s} which is located at {located}"));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Find each triple of the sample with patterns answered by the subject, predicate and object index, respectively.
+    fn check_pattern(&self, sample: &[TripleId]) -> Result<(), String> {
+        for t in sample {
+            let pats = [
+                TripleId::new(t.subject_id, t.predicate_id, 0),
+                TripleId::new(0, t.predicate_id, t.object_id),
+                TripleId::new(0, 0, t.object_id),
+            ];
+            for pat in pats {
+                if !self.triples.triples_with_pattern(&pat).any(|found| found == *t) {
+                    return Err(format!("{t:?} not found with pattern {pat:?}"));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Time the given operation and turn a panic into an error.
+fn run(name: &'static str, op: impl FnOnce() -> Result<(), String>) -> HealthCheck {
+    let start = Instant::now();
+    let error = match panic::catch_unwind(AssertUnwindSafe(op)) {
+        Ok(result) => result.err(),
+        Err(payload) => Some(format!("panic: {}", panic_message(payload.as_ref()))),
+    };
+    HealthCheck { name, error, duration: start.elapsed() }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::init;
+    use pretty_assertions::assert_eq;
+    use std::fs::File;
+
+    #[test]
+    fn health_check() {
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let hdt = Hdt::new(std::io::BufReader::new(file)).unwrap();
+        let report = hdt.health_check();
+        assert!(report.is_healthy(), "{report:?}");
+        let names = if cfg!(feature = "mmap") { vec!["mapping"] } else { Vec::new() };
+        assert_eq!(
+            [names, vec!["extract", "pattern", "crc"]].concat(),
+            report.checks.iter().map(|c| c.name).collect::<Vec<_>>()
+        );
+        let errors = |r: &HealthReport| r.checks.iter().map(|c| c.error.clone()).collect::<Vec<_>>();
+        assert_eq!(errors(&report), errors(&hdt.health_check_with_seed(report.seed)));
+
+        let failed = run("panic", || panic!("canary"));
+        assert_eq!(Some("panic: canary".to_owned()), failed.error);
+    }
+}
@@ -0,0 +1,273 @@
+//! Labels of subjects by language for multilingual display and autocomplete, see [`LabelIndex`].
+//! Looking up the label of a subject in a given language with a triple pattern needs to extract and compare the language tags of all labels of the subject,
+//! while the index answers it with a single hash map lookup on IDs.
+use crate::containers::rdf::canonical_language_tag;
+use crate::four_sect_dict::IdKind;
+use crate::hdt::Hdt;
+use crate::triples::{Id, PredicateIter, TripleId};
+use crate::vocab::Vocab;
+use log::error;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+/// Labeling predicates used by [`LabelIndex::with_default_predicates`] in the order of their priority.
+pub const DEFAULT_PREDICATES: [Vocab; 2] = [Vocab::SkosPrefLabel, Vocab::RdfsLabel];
+
+/// Map from a subject ID and a language to the object ID of the label of the subject in that language, for a configurable list of labeling predicates.
+/// Only plain literals, which have the empty language, and language-tagged literals are indexed.
+/// If a subject has several labels in the same language, the label of the first predicate in the list wins and for the same predicate the label with the lowest object ID.
+/// Language tags are compared in their canonical form, see [`canonical_language_tag`], so that `en-us` finds labels tagged with `en-US`.
+/// # Example
+/// ```
+/// fn display(hdt: &hdt::Hdt, subject: &str) -> String {
+///     let index = hdt::labels::LabelIndex::with_default_predicates(hdt);
+///     index.label(hdt, subject, &["de", "en", ""]).unwrap_or_else(|| subject.to_owned())
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct LabelIndex {
+    /// IDs of the labeling predicates that exist in the dictionary, in the order of their priority.
+    predicates: Vec<Id>,
+    /// Number of each canonical language tag occurring in the labels.
+    languages: HashMap<String, u32>,
+    /// Object ID of the label of each pair of a subject ID and a language number.
+    labels: HashMap<(Id, u32), Id>,
+}
+
+/// Language tag of the given literal in the HDT dictionary string format, empty for plain literals and `None` for other terms.
+fn language(object: &str) -> Option<&str> {
+    let end = object.strip_prefix('"')?.rfind('"')? + 1;
+    match &object[end + 1..] {
+        "" | "^^<http://www.w3.org/2001/XMLSchema#string>" => Some(""),
+        suffix => suffix.strip_prefix('@'),
+    }
+}
+
+impl LabelIndex {
+    /// Index the labels of the given predicates, in the order of their priority, of all subjects of the given HDT.
+    /// Predicates that do not occur in the HDT are ignored.
+    pub fn new(hdt: &Hdt, predicates: &[&str]) -> Self {
+        let ids = predicates.iter().map(|p| hdt.dict.string_to_id(p, &IdKind::Predicate));
+        Self::from_ids(hdt, ids.collect())
+    }
+
+    /// Index the labels of the [`DEFAULT_PREDICATES`], whose IDs are already resolved in [`Hdt::vocab`].
+    pub fn with_default_predicates(hdt: &Hdt) -> Self {
+        Self::from_ids(hdt, DEFAULT_PREDICATES.iter().map(|&p| hdt.vocab.predicate(p)).collect())
+    }
+
+    fn from_ids(hdt: &Hdt, mut predicates: Vec<Id>) -> Self {
+        predicates.retain(|&p| p != 0);
+        let mut index = LabelIndex { predicates, ..Default::default() };
+        // language number of each object, as the same literal is often the label of several subjects
+        let mut object_languages = HashMap::<Id, Option<u32>>::new();
+        for &p in &index.predicates {
+            for t in PredicateIter::new(&hdt.triples, p) {
+                let lang = *object_languages.entry(t.object_id).or_insert_with(|| {
+                    let object = hdt
+                        .dict
+                        .id_to_string(t.object_id, &IdKind::Object)
+                        .map_err(|e| error!("Error extracting label {}: {e}", t.object_id))
+                        .ok()?;
+                    let lang = canonical_language_tag(language(&object)?);
+                    let next = u32::try_from(index.languages.len()).expect("too many languages");
+                    Some(*index.languages.entry(lang).or_insert(next))
+                });
+                if let Some(lang) = lang {
+                    // triples of a predicate are visited in ascending order of subject and object
+                    index.labels.entry((t.subject_id, lang)).or_insert(t.object_id);
+                }
+            }
+        }
+        index
+    }
+
+    /// Object ID of the label of the given subject ID in the given language, use the empty language for plain literals.
+    pub fn get(&self, subject_id: Id, lang: &str) -> Option<Id> {
+        let lang = self.languages.get(&canonical_language_tag(lang))?;
+        self.labels.get(&(subject_id, *lang)).copied()
+    }
+
+    /// Object ID of the label of the given subject ID in the first language of the given fallback chain for which it has one.
+    pub fn get_with_fallback(&self, subject_id: Id, chain: &[&str]) -> Option<Id> {
+        chain.iter().find_map(|lang| self.get(subject_id, lang))
+    }
+
+    /// Lexical form of the label of the given subject in the first language of the given fallback chain for which it has one.
+    pub fn label(&self, hdt: &Hdt, subject: &str, chain: &[&str]) -> Option<String> {
+        let id = self.get_with_fallback(hdt.dict.string_to_id(subject, &IdKind::Subject), chain)?;
+        let object = hdt.dict.id_to_string(id, &IdKind::Object).map_err(|e| error!("{e}")).ok()?;
+        Some(object[1..object.rfind('"')?].to_owned())
+    }
+
+    /// IDs of the labeling predicates that occur in the HDT, in the order of their priority.
+    pub fn predicates(&self) -> &[Id] {
+        &self.predicates
+    }
+
+    /// Canonical language tags of the indexed labels in ascending order, including the empty language if there are plain literals.
+    pub fn languages(&self) -> Vec<&str> {
+        let mut languages: Vec<&str> = self.languages.keys().map(String::as_str).collect();
+        languages.sort_unstable();
+        languages
+    }
+
+    /// Number of indexed pairs of a subject and a language.
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    /// Whether no label is indexed.
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+}
+
+impl Hdt {
+    /// Labels starting with the given prefix and their subjects, for suggestions while typing.
+    /// Labels are the plain and language-tagged literals of the [`DEFAULT_PREDICATES`] and, if a language is given,
+    /// only those with that language, compared like in [`LabelIndex`], where the empty language stands for plain literals.
+    /// The prefix is compared with the lexical form as stored, except that the case of its first character is ignored,
+    /// so that both `leip` and `Leip` find `Leipzig`.
+    /// Returns at most the given number of pairs of the lexical form of a label and its subject,
+    /// ranked by the [degree](Self::degree) of the subject in descending order, so that well connected resources come first.
+    /// A subject with several matching labels is only returned once with the label with the lowest ID.
+    ///
+    /// As the object section is sorted, the candidate literals are located with [`DictSectPFC::prefix_range`](crate::DictSectPFC::prefix_range),
+    /// so that only the literals starting with the prefix are decoded, which are still many for short prefixes in large datasets.
+    /// # Example
+    /// ```
+    /// fn suggest(hdt: &hdt::Hdt, typed: &str) -> Vec<String> {
+    ///     hdt.autocomplete(typed, Some("en"), 10).into_iter().map(|(label, subject)| format!("{label} ({subject})")).collect()
+    /// }
+    /// ```
+    pub fn autocomplete(&self, prefix: &str, lang: Option<&str>, limit: usize) -> Vec<(String, String)> {
+        let predicates: Vec<Id> =
+            DEFAULT_PREDICATES.iter().map(|&p| self.vocab.predicate(p)).filter(|&p| p != 0).collect();
+        let lang = lang.map(canonical_language_tag);
+        let mut chars = prefix.chars();
+        let first = chars.next();
+        let rest = chars.as_str();
+        let mut prefixes: Vec<String> = match first {
+            None => vec![String::new()],
+            Some(c) => [c.to_lowercase().collect::<String>(), c.to_uppercase().collect()]
+                .into_iter()
+                .map(|c| c + rest)
+                .collect(),
+        };
+        prefixes.dedup();
+        let offset = self.dict.shared.num_strings();
+        // subjects with the object ID of their first matching label
+        let mut subjects = HashMap::<Id, Id>::new();
+        for prefix in prefixes {
+            let range = self.dict.objects.prefix_range(&format!("\"{prefix}"));
+            for (literal, id) in self.dict.objects.iter_from(range.start).take(range.len()).zip(range) {
+                let Ok(literal) = literal.map_err(|e| error!("Error extracting literal {id}: {e}")) else {
+                    continue;
+                };
+                let Some(literal_lang) = language(&literal) else { continue };
+                if lang.as_ref().is_some_and(|lang| *lang != canonical_language_tag(literal_lang)) {
+                    continue;
+                }
+                let o = id + offset;
+                for &p in &predicates {
+                    for t in self.triples.triples_with_pattern(&TripleId::new(0, p, o)) {
+                        let label = subjects.entry(t.subject_id).or_insert(o);
+                        *label = (*label).min(o);
+                    }
+                }
+            }
+        }
+        // keep the best results in a min-heap of the given size
+        let mut best = BinaryHeap::with_capacity(limit + 1);
+        for (s, o) in subjects {
+            best.push(Reverse((self.degree(s, &IdKind::Subject), Reverse(o), Reverse(s))));
+            if best.len() > limit {
+                best.pop();
+            }
+        }
+        best.into_sorted_vec()
+            .into_iter()
+            .filter_map(|Reverse((_, Reverse(o), Reverse(s)))| {
+                let label = self.dict.id_to_string(o, &IdKind::Object).map_err(|e| error!("{e}")).ok()?;
+                let subject = self.dict.id_to_string(s, &IdKind::Subject).map_err(|e| error!("{e}")).ok()?;
+                Some((label[1..label.rfind('"')?].to_owned(), subject))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::init;
+    use pretty_assertions::assert_eq;
+    use std::fs::File;
+
+    #[test]
+    fn labels() {
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let hdt = Hdt::new(std::io::BufReader::new(file)).unwrap();
+        let label = "http://www.w3.org/2000/01/rdf-schema#label";
+        let index = LabelIndex::new(&hdt, &[label, "http://example.org/missing"]);
+        assert_eq!(vec![hdt.dict.string_to_id(label, &IdKind::Predicate)], index.predicates());
+        assert!(!index.is_empty());
+        assert_eq!(index.len(), LabelIndex::with_default_predicates(&hdt).len());
+
+        // compare with the first label of each subject and language from the triples
+        let mut expected = HashMap::<(String, String), String>::new();
+        for (s, _, o) in hdt.triples_with_pattern(None, Some(label), None) {
+            if let Some(lang) = language(&o) {
+                expected.entry((s.to_string(), canonical_language_tag(lang))).or_insert_with(|| o.to_string());
+            }
+        }
+        assert_eq!(expected.len(), index.len());
+        let mut languages: Vec<&str> = expected.keys().map(|(_, lang)| lang.as_str()).collect();
+        languages.sort_unstable();
+        languages.dedup();
+        assert_eq!(languages, index.languages());
+        for ((s, lang), o) in &expected {
+            let id = index.get(hdt.dict.string_to_id(s, &IdKind::Subject), lang);
+            assert_eq!(Some(hdt.dict.string_to_id(o, &IdKind::Object)), id);
+            assert_eq!(Some(&o[1..o.rfind('"').unwrap()]), index.label(&hdt, s, &["xx", lang]).as_deref());
+        }
+        let ((s, lang), _) = expected.iter().next().unwrap();
+        assert_eq!(index.get(1, lang), index.get(1, &lang.to_uppercase()));
+        assert_eq!(None, index.label(&hdt, s, &["xx"]));
+        assert_eq!(None, index.label(&hdt, "http://example.org/missing", &[lang]));
+
+        assert_eq!(Some("en"), language("\"a\"@en"));
+        assert_eq!(Some(""), language("\"a \\\"b\\\"\""));
+        assert_eq!(Some(""), language("\"1\"^^<http://www.w3.org/2001/XMLSchema#string>"));
+        assert_eq!(None, language("\"1\"^^<http://www.w3.org/2001/XMLSchema#integer>"));
+        assert_eq!(None, language("http://example.org/a"));
+    }
+
+    #[test]
+    fn autocomplete() {
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let hdt = Hdt::new(std::io::BufReader::new(file)).unwrap();
+        let chapters = hdt.autocomplete("ch", None, 10);
+        assert_eq!(4, chapters.len());
+        assert!(chapters.contains(&(
+            "chapter number".to_owned(),
+            "http://www.snik.eu/ontology/meta/chapterNumber".to_owned()
+        )));
+        assert_eq!(chapters, hdt.autocomplete("Ch", None, 10));
+        assert_eq!(chapters[..2], hdt.autocomplete("ch", None, 2));
+        let degree = |s: &str| {
+            hdt.triples_with_pattern(Some(s), None, None).count()
+                + hdt.triples_with_pattern(None, None, Some(s)).count()
+        };
+        assert!(chapters.windows(2).all(|w| degree(&w[0].1) >= degree(&w[1].1)));
+
+        let english = hdt.autocomplete("e", Some("EN"), 10);
+        assert!(hdt.autocomplete("e", None, 10).iter().any(|(label, _)| label == "erhöht"));
+        assert!(!english.is_empty() && english.iter().all(|(label, _)| label.starts_with("entity type")));
+        assert_eq!(Vec::<(String, String)>::new(), hdt.autocomplete("e", Some(""), 10));
+        assert_eq!(Vec::<(String, String)>::new(), hdt.autocomplete("nonexisting", None, 10));
+        assert_eq!(Vec::<(String, String)>::new(), hdt.autocomplete("ch", None, 0));
+    }
+}
@@ -0,0 +1,477 @@
+//! Opening HDT files without loading all sections, for tools that only need the metadata or look up a few subjects, see [`LazyHdt`].
+use crate::containers::{Bitmap, ControlInfo, SequenceLayout};
+use crate::error::{HdtError, Part};
+use crate::four_sect_dict::{
+    shared_or_own, FourSectDict, IdKind, SectKind, DICTIONARY_FOUR, DICTIONARY_FOUR_QUAD,
+};
+use crate::hdt::{Compression, Hdt, StringTriple};
+use crate::header::Header;
+use crate::triples::{require_hdtq, Id, Order, TripleId, TriplesBitmap, TRIPLES_BITMAP, TRIPLES_BITMAP_QUAD};
+use crate::vocab::Vocabulary;
+use crate::DictSectPFC;
+use eyre::{eyre, Result, WrapErr};
+use std::collections::hash_map::{Entry, HashMap};
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+
+/// Reader that can seek, see [`Source`].
+pub trait SeekRead: Read + Seek + Send {}
+
+impl<T: Read + Seek + Send> SeekRead for T {}
+
+/// Location of HDT data from which a [`LazyHdt`] reads its sections, such as a file path.
+/// Each reader is used for reading one or more sections sequentially after seeking to the start of the first one.
+pub trait Source: fmt::Debug + Send + Sync {
+    /// A new reader positioned at the beginning of the data.
+    fn reader(&self) -> io::Result<Box<dyn SeekRead>>;
+
+    /// The bytes of the given range, for reading a few entries or strings of a section without the rest.
+    /// By default, a new reader seeks to the start of the range.
+    fn read_range(&self, range: Range<u64>) -> io::Result<Vec<u8>> {
+        let len = usize::try_from(range.end.saturating_sub(range.start)).map_err(io::Error::other)?;
+        let mut reader = self.reader()?;
+        reader.seek(SeekFrom::Start(range.start))?;
+        let mut bytes = vec![0; len];
+        reader.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+impl Source for PathBuf {
+    fn reader(&self) -> io::Result<Box<dyn SeekRead>> {
+        Ok(Box::new(File::open(self)?))
+    }
+}
+
+/// Bitmaps of the triples section and the positions of its sequences, which are enough to find the triples of a subject
+/// by reading only the entries of that subject.
+#[derive(Debug)]
+struct Layout {
+    /// marks the last predicate of each subject
+    bitmap_y: Bitmap,
+    /// marks the last object of each pair of subject and predicate
+    bitmap_z: Bitmap,
+    /// predicate IDs
+    sequence_y: SequenceLayout,
+    /// object IDs
+    sequence_z: SequenceLayout,
+}
+
+/// HDT file whose object dictionary section and triples section are only read in parts when needed.
+/// The control information, the header and the shared, subject and predicate sections are read when opening,
+/// so that metadata and lookups of subjects and predicates are available right away.
+/// Of the object section only the block offsets are read when opening, each lookup reads just the blocks of its objects.
+/// Of the triples section the bitmaps and the metadata of the sequences are read once by the first method that needs triples,
+/// each subject then reads only its entries of the sequences with [`Source::read_range`].
+/// The wavelet matrix and the object index, which take most of the time and memory of [`Hdt::new`] for large files, are not built.
+/// Partial reads cannot check the CRC32C checksums of the object section and the sequences, which [`Self::objects`] and [`Self::load`] do.
+/// Use [`Self::load`] for the other triple patterns, which reuses the sections that are already read.
+/// The file must not be modified while the value is in use.
+/// Besides files, the sections can be read from any [`Source`] that supports seeking.
+/// # Example
+/// ```
+/// let lazy = hdt::lazy::LazyHdt::open("tests/resources/snikmeta.hdt").unwrap();
+/// println!("{} subjects", lazy.num_subjects());
+/// for (s, p, o) in lazy.triples_with_subject("http://www.snik.eu/ontology/meta").unwrap() {
+///     println!("{s} {p} {o}");
+/// }
+/// ```
+#[derive(Debug)]
+pub struct LazyHdt {
+    source: Box<dyn Source>,
+    /// global control information, whose format identifies the HDT version
+    pub global_ci: ControlInfo,
+    /// metadata about the dataset
+    pub header: Header,
+    /// control information of the dictionary, whose format identifies the dictionary type
+    pub dict_ci: ControlInfo,
+    /// control information of the triples, whose format identifies the triples type and which contains the order
+    pub triples_ci: ControlInfo,
+    /// section of the terms that are both subjects and objects
+    pub shared: DictSectPFC,
+    /// section of the terms that are only subjects
+    pub subjects: DictSectPFC,
+    /// section of the predicates
+    pub predicates: DictSectPFC,
+    /// object section without its packed data, for finding the block of an object
+    object_blocks: DictSectPFC,
+    /// position of the object section
+    objects_offset: u64,
+    /// position of the packed data of the object section
+    objects_data: u64,
+    /// position and number of strings of the graph section of quad files
+    graphs: Option<(u64, usize)>,
+    triples_offset: u64,
+    objects: OnceLock<DictSectPFC>,
+    layout: OnceLock<Layout>,
+}
+
+/// Read a dictionary section and check the checksum of its packed data.
+fn read_sect<R: BufRead>(reader: &mut R, name: &str) -> Result<DictSectPFC> {
+    let (sect, crc_handle) =
+        DictSectPFC::read(reader).wrap_err_with(|| format!("Failed to read {name} section"))?;
+    if !crc_handle.join().unwrap_or(false) {
+        return Err(HdtError::checksum(Part::Dictionary, "CRC32C", format!("{name} section")).into());
+    }
+    Ok(sect)
+}
+
+/// First position for the given ID counting from 1 in a level whose bitmap marks the last position of each ID, see [`crate::containers::AdjList::find`].
+fn find(bitmap: &Bitmap, x: Id) -> Option<usize> {
+    if x == 0 {
+        return Some(0);
+    }
+    bitmap.select1(x - 1).map(|pos| pos + 1)
+}
+
+impl Layout {
+    /// Read the bitmaps of a triples section and skip the data of its sequences, starting with its control information.
+    fn read<R: BufRead + Seek>(reader: &mut R) -> Result<Self> {
+        ControlInfo::read(reader)?;
+        let bitmap_y = Bitmap::read(reader).wrap_err("Failed to read Y level bitmap")?;
+        let bitmap_z = Bitmap::read(reader).wrap_err("Failed to read Z level bitmap")?;
+        let sequence_y = SequenceLayout::skip(reader).wrap_err("Failed to read Y level sequence")?;
+        let sequence_z = SequenceLayout::skip(reader).wrap_err("Failed to read Z level sequence")?;
+        Ok(Layout { bitmap_y, bitmap_z, sequence_y, sequence_z })
+    }
+
+    fn size_in_bytes(&self) -> usize {
+        self.bitmap_y.size_in_bytes() + self.bitmap_z.size_in_bytes()
+    }
+}
+
+impl LazyHdt {
+    /// Read the metadata and the shared, subject and predicate sections of the HDT file at the given path, see [`Hdt::new`] for the supported formats.
+    /// The object section is skipped by its length, so that only its block offsets are read, which is not possible for compressed files.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        Self::from_source(Box::new(path.as_ref().to_owned()))
+    }
+
+    /// Like [`Self::open`] but reads from the given source, which needs to support seeking to skip the sections that are read later.
+    pub fn from_source(source: Box<dyn Source>) -> Result<Self, Box<dyn Error>> {
+        let mut reader = BufReader::new(source.reader()?);
+        if let Some(compression) = Compression::detect(reader.fill_buf()?) {
+            let (format, supported) = (
+                format!("{compression:?} compressed data"),
+                "sections of compressed files cannot be skipped, use Hdt::new",
+            );
+            return Err(HdtError::UnsupportedFormat { part: Part::ControlInfo, format, supported }.into());
+        }
+        let global_ci = ControlInfo::read(&mut reader).wrap_err("Failed to read HDT control info")?;
+        let header = Header::read(&mut reader).wrap_err("Failed to read HDT header")?;
+        let dict_ci = ControlInfo::read(&mut reader).wrap_err("Failed to read dictionary control info")?;
+        let quad = match &dict_ci.format[..] {
+            DICTIONARY_FOUR => false,
+            DICTIONARY_FOUR_QUAD => {
+                require_hdtq(Part::Dictionary, DICTIONARY_FOUR_QUAD)?;
+                true
+            }
+            _ => {
+                return Err(HdtError::UnsupportedFormat {
+                    part: Part::Dictionary,
+                    format: dict_ci.format,
+                    supported: "only four section dictionaries are supported",
+                }
+                .into())
+            }
+        };
+        let shared = read_sect(&mut reader, "shared")?;
+        let subjects = read_sect(&mut reader, "subject")?;
+        let predicates = read_sect(&mut reader, "predicate")?;
+        let objects_offset = reader.stream_position()?;
+        let (object_blocks, objects_data) =
+            DictSectPFC::skip(&mut reader).wrap_err("Failed to read object section")?;
+        let graphs = if quad {
+            let offset = reader.stream_position()?;
+            let (graphs, _) = DictSectPFC::skip(&mut reader).wrap_err("Failed to read graph section")?;
+            Some((offset, graphs.num_strings()))
+        } else {
+            None
+        };
+        let triples_offset = reader.stream_position()?;
+        let triples_ci = ControlInfo::read(&mut reader).wrap_err("Failed to read triples control info")?;
+        if ![TRIPLES_BITMAP, TRIPLES_BITMAP_QUAD].contains(&&triples_ci.format[..]) {
+            let (format, supported) = (triples_ci.format, "only bitmap triples are supported");
+            return Err(HdtError::UnsupportedFormat { part: Part::Triples, format, supported }.into());
+        }
+        if triples_ci.format == TRIPLES_BITMAP_QUAD {
+            require_hdtq(Part::Triples, TRIPLES_BITMAP_QUAD)?;
+        }
+        Ok(LazyHdt {
+            source,
+            global_ci,
+            header,
+            dict_ci,
+            triples_ci,
+            shared,
+            subjects,
+            predicates,
+            object_blocks,
+            objects_offset,
+            objects_data,
+            graphs,
+            triples_offset,
+            objects: OnceLock::new(),
+            layout: OnceLock::new(),
+        })
+    }
+
+    /// Get the value of the given lock or read it from the given position of the file.
+    /// Threads that need it at the same time may both read it, but only one value is kept.
+    fn get_or_read<'a, T>(
+        &self, lock: &'a OnceLock<T>, offset: u64,
+        read: impl FnOnce(&mut BufReader<Box<dyn SeekRead>>) -> Result<T>,
+    ) -> Result<&'a T> {
+        if let Some(value) = lock.get() {
+            return Ok(value);
+        }
+        let mut reader = BufReader::new(self.source.reader()?);
+        reader.seek(SeekFrom::Start(offset))?;
+        let value = read(&mut reader)?;
+        Ok(lock.get_or_init(|| value))
+    }
+
+    /// The whole object section, which is read on the first call and then used by all lookups of objects.
+    pub fn objects(&self) -> Result<&DictSectPFC> {
+        self.get_or_read(&self.objects, self.objects_offset, |r| read_sect(r, "object"))
+    }
+
+    fn layout(&self) -> Result<&Layout> {
+        self.get_or_read(&self.layout, self.triples_offset, Layout::read)
+    }
+
+    /// Number of distinct subjects.
+    pub const fn num_subjects(&self) -> usize {
+        self.shared.num_strings() + self.subjects.num_strings()
+    }
+
+    /// Number of distinct predicates.
+    pub const fn num_predicates(&self) -> usize {
+        self.predicates.num_strings()
+    }
+
+    /// Number of distinct objects, known without reading the object section.
+    pub const fn num_objects(&self) -> usize {
+        self.shared.num_strings() + self.object_blocks.num_strings()
+    }
+
+    /// Number of graphs of quad files, `None` for triple files.
+    pub fn num_graphs(&self) -> Option<usize> {
+        self.graphs.map(|(_, graphs)| graphs)
+    }
+
+    /// Number of triples from the `numTriples` control information or the header, see [`Header::num_triples`].
+    /// Files that state neither read the layout of the triples section on the first call.
+    pub fn num_triples(&self) -> Result<usize> {
+        let declared = self.triples_ci.get("numTriples").and_then(|n| n.parse().ok());
+        if let Some(n) = declared.or_else(|| self.header.num_triples()) {
+            return Ok(n);
+        }
+        Ok(self.layout()?.sequence_z.entries)
+    }
+
+    /// ID of the given subject, 0 if it does not exist, see [`FourSectDict::string_to_id`].
+    pub fn subject_id(&self, s: &str) -> Id {
+        shared_or_own(self.shared.string_to_id(s), || self.subjects.string_to_id(s), self.shared.num_strings())
+    }
+
+    /// ID of the given predicate, 0 if it does not exist.
+    pub fn predicate_id(&self, p: &str) -> Id {
+        self.predicates.string_to_id(p)
+    }
+
+    /// Get the string of the given ID of the given kind, which reads the block of an object if needed, see [`FourSectDict::id_to_string`].
+    pub fn id_to_string(&self, id: Id, id_kind: &IdKind) -> Result<String> {
+        self.extract(id, id_kind, &mut HashMap::new())
+    }
+
+    /// Like [`Self::id_to_string`] but keeps the blocks of the object section that are read by their position,
+    /// so that each block is read only once for several objects.
+    fn extract(&self, id: Id, id_kind: &IdKind, blocks: &mut HashMap<usize, Vec<u8>>) -> Result<String> {
+        let string = match SectKind::locate(id, id_kind, self.shared.num_strings()) {
+            (SectKind::Shared, id) => self.shared.extract(id),
+            (SectKind::Subject, id) => self.subjects.extract(id),
+            (SectKind::Predicate, id) => self.predicates.extract(id),
+            (SectKind::Object, id) => {
+                if let Some(objects) = self.objects.get() {
+                    objects.extract(id)
+                } else {
+                    let range = self.object_blocks.block_range(id)?;
+                    let block = match blocks.entry(range.start) {
+                        Entry::Occupied(entry) => entry.into_mut(),
+                        Entry::Vacant(entry) => {
+                            let start = self.objects_data + range.start as u64;
+                            entry.insert(self.source.read_range(start..self.objects_data + range.end as u64)?)
+                        }
+                    };
+                    self.object_blocks.extract_from_block(id, block)
+                }
+            }
+        };
+        string.wrap_err_with(|| format!("Failed to extract {id_kind:?} {id}"))
+    }
+
+    /// IDs of all triples with the given subject ID in the order of their predicate and object IDs,
+    /// which reads the entries of the subject from the sequences of the triples section but nothing of the object section.
+    pub fn triple_ids_with_subject(&self, subject_id: Id) -> Result<Vec<TripleId>> {
+        if self.triples_ci.get("order") != Some((Order::SPO as u8).to_string()) {
+            return Err(eyre!("Only SPO order is supported without loading, use LazyHdt::load"));
+        }
+        let Layout { bitmap_y, bitmap_z, sequence_y: y, sequence_z: z } = self.layout()?;
+        if subject_id == 0 || subject_id > bitmap_y.rank(y.entries) {
+            return Ok(Vec::new());
+        }
+        let invalid =
+            || HdtError::invalid(Part::Triples, format!("bitmaps do not match sequences at subject {subject_id}"));
+        let ys = find(bitmap_y, subject_id - 1).zip(find(bitmap_y, subject_id)).ok_or_else(invalid)?;
+        let zs = find(bitmap_z, ys.0).zip(find(bitmap_z, ys.1)).ok_or_else(invalid)?;
+        if ys.1 > y.entries || zs.1 > z.entries {
+            return Err(invalid().into());
+        }
+        let bytes_y = self.source.read_range(y.byte_range(ys.0..ys.1))?;
+        let bytes_z = self.source.read_range(z.byte_range(zs.0..zs.1))?;
+        let mut triples = Vec::with_capacity(zs.1 - zs.0);
+        let mut pos_z = zs.0;
+        for pos_y in ys.0..ys.1 {
+            let p = y.get(&bytes_y, ys.0, pos_y);
+            let end = find(bitmap_z, pos_y + 1).filter(|&end| end <= zs.1).ok_or_else(invalid)?;
+            for pos_z in pos_z..end {
+                triples.push(TripleId::new(subject_id, p, z.get(&bytes_z, zs.0, pos_z)));
+            }
+            pos_z = end;
+        }
+        Ok(triples)
+    }
+
+    /// All triples with the given subject, which reads the entries of the subject from the triples section
+    /// and each block of the object section that contains one of its objects.
+    /// # Example
+    /// ```
+    /// let lazy = hdt::lazy::LazyHdt::open("tests/resources/snikmeta.hdt").unwrap();
+    /// assert!(lazy.triples_with_subject("http://example.org/missing").unwrap().is_empty());
+    /// ```
+    pub fn triples_with_subject(&self, s: &str) -> Result<Vec<StringTriple>> {
+        let ids = self.triple_ids_with_subject(self.subject_id(s))?;
+        let subject: Arc<str> = Arc::from(s);
+        let mut predicate: (Id, Arc<str>) = (0, Arc::from(""));
+        let mut blocks = HashMap::new();
+        ids.into_iter()
+            .map(|t| {
+                if t.predicate_id != predicate.0 {
+                    predicate = (t.predicate_id, self.id_to_string(t.predicate_id, &IdKind::Predicate)?.into());
+                }
+                let object = self.extract(t.object_id, &IdKind::Object, &mut blocks)?;
+                Ok((Arc::clone(&subject), Arc::clone(&predicate.1), object.into()))
+            })
+            .collect()
+    }
+
+    /// Size in bytes on the heap of the sections that have been read so far.
+    pub fn size_in_bytes(&self) -> usize {
+        self.shared.size_in_bytes()
+            + self.subjects.size_in_bytes()
+            + self.predicates.size_in_bytes()
+            + self.object_blocks.size_in_bytes()
+            + self.objects.get().map_or(0, DictSectPFC::size_in_bytes)
+            + self.layout.get().map_or(0, Layout::size_in_bytes)
+    }
+
+    /// Read the remaining sections and build the indexes for all triple patterns, like [`Hdt::new`] does.
+    /// The dictionary sections that are already read are reused, the triples section is read again.
+    pub fn load(self) -> Result<Hdt, Box<dyn Error>> {
+        let mut reader = BufReader::new(self.source.reader()?);
+        let objects = if let Some(objects) = self.objects.into_inner() {
+            objects
+        } else {
+            reader.seek(SeekFrom::Start(self.objects_offset))?;
+            read_sect(&mut reader, "object")?
+        };
+        let mut graphs = None;
+        if let Some((offset, _)) = self.graphs {
+            reader.seek(SeekFrom::Start(offset))?;
+            graphs = Some(read_sect(&mut reader, "graph")?);
+        }
+        reader.seek(SeekFrom::Start(self.triples_offset))?;
+        let triples = TriplesBitmap::read_sect(&mut reader).wrap_err("Failed to read HDT triples section")?;
+        let dict = FourSectDict {
+            shared: self.shared,
+            subjects: self.subjects,
+            predicates: self.predicates,
+            objects,
+            graphs,
+        };
+        let vocab = Vocabulary::resolve(&dict);
+        Ok(Hdt {
+            global_ci: self.global_ci,
+            dict,
+            triples,
+            vocab,
+            #[cfg(feature = "mmap")]
+            mapped: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::init;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn lazy() -> Result<(), Box<dyn Error>> {
+        init();
+        let path = "tests/resources/snikmeta.hdt";
+        let hdt = Hdt::new(BufReader::new(File::open(path)?))?;
+        let lazy = LazyHdt::open(path)?;
+        assert!(lazy.objects.get().is_none() && lazy.layout.get().is_none());
+        assert_eq!(hdt.dict.shared.num_strings() + hdt.dict.subjects.num_strings(), lazy.num_subjects());
+        assert_eq!(hdt.dict.predicates.num_strings(), lazy.num_predicates());
+        assert_eq!(hdt.dict.shared.num_strings() + hdt.dict.objects.num_strings(), lazy.num_objects());
+        assert_eq!(None, lazy.num_graphs());
+        let s = "http://www.snik.eu/ontology/meta/Function";
+        let id = hdt.dict.string_to_id(s, &IdKind::Subject);
+        assert_eq!(id, lazy.subject_id(s));
+        assert_eq!(s, lazy.id_to_string(id, &IdKind::Subject)?);
+        assert!(lazy.objects.get().is_none());
+
+        let expected: Vec<TripleId> = hdt.triples.triples_with_pattern(&TripleId::new(id, 0, 0)).collect();
+        assert!(!expected.is_empty());
+        assert_eq!(expected, lazy.triple_ids_with_subject(id)?);
+        assert!(lazy.objects.get().is_none() && lazy.layout.get().is_some());
+        let expected: Vec<StringTriple> = hdt.triples_with_pattern(Some(s), None, None).collect();
+        assert_eq!(expected, lazy.triples_with_subject(s)?);
+        // objects are read by block
+        assert!(lazy.objects.get().is_none());
+        for id in [1, hdt.dict.shared.num_strings() + 1, lazy.num_objects()] {
+            assert_eq!(hdt.dict.id_to_string(id, &IdKind::Object)?, lazy.id_to_string(id, &IdKind::Object)?);
+        }
+        assert!(lazy.id_to_string(lazy.num_objects() + 1, &IdKind::Object).is_err());
+        lazy.objects()?;
+        assert_eq!(expected, lazy.triples_with_subject(s)?);
+        for id in 1..=lazy.num_subjects() {
+            let expected: Vec<TripleId> = hdt.triples.triples_with_pattern(&TripleId::new(id, 0, 0)).collect();
+            assert_eq!(expected, lazy.triple_ids_with_subject(id)?);
+        }
+        assert!(lazy.triple_ids_with_subject(lazy.num_subjects() + 1)?.is_empty());
+        assert!(lazy.size_in_bytes() > 0);
+
+        // from the header and, without it, from the layout of the triples section
+        assert_eq!(hdt.triples.len(), lazy.num_triples()?);
+        let mut lazy = lazy;
+        lazy.header.body.clear();
+        assert_eq!(hdt.triples.len(), lazy.num_triples()?);
+
+        let loaded = lazy.load()?;
+        assert_eq!(hdt.triples().collect::<Vec<_>>(), loaded.triples().collect::<Vec<_>>());
+        let loaded = LazyHdt::open(path)?.load()?;
+        assert_eq!(hdt.triples.len(), loaded.triples.len());
+        assert_eq!(hdt.dict.objects.num_strings(), loaded.dict.objects.num_strings());
+        Ok(())
+    }
+}
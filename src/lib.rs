@@ -8,7 +8,8 @@
 //!
 //! HDT is a loading and triple pattern querying library for the [Header Dictionary Triples](https://www.rdfhdt.org/) compressed binary RDF format.
 //!
-//! Currently this library only supports loading and querying existing HDT files as created by [hdt-cpp](https://github.com/rdfhdt/hdt-cpp).
+//! Currently this library mainly supports loading and querying existing HDT files as created by [hdt-cpp](https://github.com/rdfhdt/hdt-cpp).
+//! Small HDT files can also be built from N-Triples with the [`builder`] module and written with [`Hdt::write_to`].
 //! For reference implementations of HDT in C++ and Java, which support conversion and serialization from and into HDT with different format options,
 //! and acknowledgement of all the original authors, please look at the <https://github.com/rdfhdt> organisation.
 //!
@@ -24,6 +25,8 @@
 //! println!("{:?}", majors.collect::<Vec<_>>());
 //! ```
 //!
+//! The commonly used types are re-exported in the [`prelude`], whose paths stay stable across minor versions.
+//!
 #![cfg_attr(
     feature = "sophia",
     doc = r##"
@@ -50,6 +53,19 @@ fn query(hdt: Hdt)
 //
 // - **`sophia`** *(enabled by default)* — Implements the Graph trait from the [Sophia](https://crates.io/crates/sophia) RDF toolkit.
 // This allows you to drastically reduce the RAM usage of an existing application based on Sophia that loads a large knowledge base but requires an input file in the HDT format.
+// - **`async`** — Provides `futures::Stream` adapters for the triple pattern iterators in the `stream` module, which cooperatively yield to the executor.
+// - **`arena`** — Allocates the strings of query results in a per-query arena in the `arena` module, which reduces allocator pressure.
+// - **`mmap`** — Memory maps HDT files with `Hdt::map` instead of reading them into memory, which speeds up loading large files. `Hdt::map_verified` also checks the checksums of the mapped data and `Hdt::map_with_index` reads the indexes from an index file instead of building them, so that processes on one host share the object index.
+// - **`oxrdf`** — Converts between `HdtTerm` and the terms of the [oxrdf](https://crates.io/crates/oxrdf) crate used by Oxigraph and queries an `HdtGraph` with them, implies `sophia`.
+// - **`rio`** — Builds HDT from the triples of any parser of the [rio](https://crates.io/crates/rio_api) crates, such as Turtle, TriG and RDF/XML, with `HdtBuilder::read_rio`.
+// - **`regex`** — Finds literals and their triples by regular expressions over the object section with `Hdt::literals_matching`.
+// - **`fulltext`** — Searches literals by keywords with the inverted index `fulltext::FullTextIndex`, which is stored in a file next to the HDT file.
+// - **`rayon`** — Decompresses all triples on multiple threads with `Hdt::par_triples`, which splits the subjects into chunks.
+// - **`serde`** — Derives `Serialize` and `Deserialize` for the statistics in the `stats` and `service` modules and the report of `Hdt::export`.
+// - **`gzip`** and **`zstd`** — Decompresses gzip and Zstandard compressed HDT files such as `file.hdt.gz` transparently in `Hdt::new`, which detects the compression by its magic bytes. With `zstd`, `containers::ZstdBytes` keeps dictionary sections compressed in memory.
+// - **`http`** — Opens HDT files on web servers with `LazyHdt::open_url` in the `remote` module, which reads only the needed sections with HTTP range requests, and `remote::HttpBytes` keeps dictionary sections on the server.
+// - **`hdtq`** *(experimental)* — Reads quad files with a graph section and one bitmap per graph after the triples, which `TriplesBitmap::quads_with_pattern` queries. The layout follows the HDTq proposal but is only tested with files written by this crate, so it may change and files of other implementations may be rejected.
+// - **`cli`** — Builds the `hdt` command line tool with the `info`, `query`, `export`, `validate`, `cat`, `subset`, `diff` and `generate` subcommands, implies `mmap`.
 #![warn(missing_docs)]
 #![warn(clippy::pedantic)]
 #![warn(clippy::cargo)]
@@ -72,11 +88,35 @@ fn query(hdt: Hdt)
 #![allow(clippy::len_without_is_empty)]
 // multiple versions of syn crate in transitive dependencies
 #![allow(clippy::multiple_crate_versions)]
+#[cfg(feature = "arena")]
+/// Arena allocation of query results.
+pub mod arena;
+/// Building HDT from RDF data.
+pub mod builder;
+/// Changed subjects between two versions.
+pub mod changes;
+/// Typed columns of predicate values.
+pub mod column;
+/// Collected triple IDs with 32 bit IDs.
+pub mod compact;
+/// Compatibility checks against hdt-cpp with golden outputs.
+pub mod compat;
 /// Types for storing and reading data.
 pub mod containers;
 // Types for representing dictionaries.
 mod dict_sect_pfc;
+/// Added and removed triples between versions.
+pub mod diff;
+/// Structured errors for reading HDT files.
+pub mod error;
+/// Approximate statistics from random samples.
+pub mod estimate;
+/// Export of all triples with error recovery.
+pub mod export;
 mod four_sect_dict;
+#[cfg(feature = "fulltext")]
+/// Keyword search over literals.
+pub mod fulltext;
 /// Types for representing triple sections.
 pub mod hdt;
 #[cfg(feature = "sophia")]
@@ -86,16 +126,68 @@ pub use sophia;
 pub mod hdt_graph;
 /// Types for representing the header.
 pub mod header;
+/// Canary operations for readiness probes.
+pub mod health;
+/// Labels of subjects by language.
+pub mod labels;
+/// Opening HDT files with deferred loading of sections.
+pub mod lazy;
+/// Limit on concurrent expensive operations.
+pub mod limiter;
+/// Merging of several HDT files into one.
+pub mod merge;
+#[cfg(feature = "oxrdf")]
+pub use oxrdf;
+#[cfg(feature = "rayon")]
+pub use rayon;
+#[cfg(feature = "regex")]
+pub use regex;
+/// Stable re-exports of the commonly used types.
+pub mod prelude;
+/// Triple patterns that are resolved once and executed many times.
+pub mod prepared;
+/// Per-phase timings of queries.
+pub mod profile;
+/// Internal coordinates of triples for debugging.
+pub mod provenance;
+/// Evaluation of basic graph patterns with shared variables.
+pub mod query;
+/// Export with RDF-star annotations.
+pub mod rdf_star;
+#[cfg(feature = "http")]
+/// Reading HDT files from web servers with range requests.
+pub mod remote;
+/// Class and property usage summaries.
+pub mod schema;
+/// Dataset statistics for service descriptions.
+pub mod service;
+/// MinHash and HyperLogLog sketches for dataset similarity.
+pub mod sketch;
+/// Degree distributions and term kinds for dataset profiling.
+pub mod stats;
+#[cfg(feature = "async")]
+/// Stream adapters for async consumers.
+pub mod stream;
+/// Extraction of matching triples into a new HDT.
+pub mod subset;
+/// Deterministic synthetic graphs for benchmarks.
+pub mod synthetic;
+/// Filtering by validity intervals.
+pub mod temporal;
 /// Types for representing and querying triples.
 pub mod triples;
+/// Thorough validation of HDT files.
+pub mod validation;
+/// Well-known vocabulary terms.
+pub mod vocab;
 
 pub use crate::hdt::Hdt;
 use containers::ControlInfo;
-use dict_sect_pfc::DictSectPFC;
+pub use dict_sect_pfc::{compare_terms, DictSectIter, DictSectPFC};
 use four_sect_dict::FourSectDict;
-pub use four_sect_dict::IdKind;
+pub use four_sect_dict::{Dictionary, IdKind};
 #[cfg(feature = "sophia")]
-pub use hdt_graph::HdtGraph;
+pub use hdt_graph::{HdtDataset, HdtGraph};
 
 #[cfg(test)]
 mod tests {
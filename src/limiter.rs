@@ -0,0 +1,326 @@
+//! Limit on the number of expensive operations that run at the same time, such as full scans and index builds,
+//! so that a single client of an HDT service embedded in an application cannot occupy all cores.
+//! Cheap operations such as lookups of a given subject are not limited, see [`Hdt::triples_with_pattern_limited`].
+//! Index builds are not limited on their own: Wrap [`TriplesBitmap::build_predicate_index`](crate::triples::TriplesBitmap::build_predicate_index)
+//! in [`Limiter::run`] as in the example of [`Limiter`]. The object index and the wavelet matrix are built while loading,
+//! so loading an HDT file with [`Hdt::new`] needs to be wrapped in the same way if it competes with queries.
+use crate::hdt::{Hdt, StringTriple};
+use std::collections::VecDeque;
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+/// Reason why an operation was not admitted by a [`Limiter`].
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitError {
+    /// The maximum number of operations are already waiting.
+    #[error("rejected because {queued} operations are already waiting")]
+    QueueFull {
+        /// Number of waiting operations.
+        queued: usize,
+    },
+    /// No operation finished within the timeout.
+    #[error("timed out after waiting {waited:?}")]
+    Timeout {
+        /// Time spent waiting.
+        waited: Duration,
+    },
+}
+
+/// Current state and counters of a [`Limiter`] since its creation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LimiterMetrics {
+    /// Number of operations that are running.
+    pub running: usize,
+    /// Number of operations that are waiting for a permit.
+    pub queued: usize,
+    /// Number of operations that were admitted, including the running ones.
+    pub admitted: usize,
+    /// Number of operations that were rejected because the queue was full.
+    pub rejected: usize,
+    /// Number of operations that gave up waiting after the timeout.
+    pub timed_out: usize,
+    /// Total time that admitted operations spent waiting.
+    pub total_wait: Duration,
+    /// Longest time that an admitted operation spent waiting.
+    pub max_wait: Duration,
+}
+
+/// Semaphore that admits a maximum number of expensive operations at the same time and queues the others.
+/// Waiting operations are admitted in the order of their arrival and new operations do not overtake them.
+/// Share one limiter between all threads that query the same HDT, for example in an `Arc` or a static.
+/// # Example
+/// ```
+/// use hdt::limiter::Limiter;
+/// use std::time::Duration;
+/// fn build_index(hdt: &mut hdt::Hdt, limiter: &Limiter) -> eyre::Result<()> {
+///     limiter.run(|| hdt.triples.build_predicate_index())??;
+///     Ok(())
+/// }
+/// let limiter = Limiter::new(2).with_max_queued(16).with_timeout(Duration::from_secs(10));
+/// assert_eq!(0, limiter.metrics().running);
+/// ```
+#[derive(Debug)]
+pub struct Limiter {
+    max_running: usize,
+    max_queued: usize,
+    timeout: Option<Duration>,
+    state: Mutex<State>,
+    released: Condvar,
+}
+
+/// Counters and the tickets of the waiting operations in the order of their arrival.
+#[derive(Debug, Default)]
+struct State {
+    metrics: LimiterMetrics,
+    waiting: VecDeque<u64>,
+    next_ticket: u64,
+}
+
+/// Admission of an operation by a [`Limiter`], which lets the next operation in when dropped.
+#[derive(Debug)]
+pub struct Permit<'a> {
+    limiter: &'a Limiter,
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        self.limiter.lock().metrics.running -= 1;
+        // all waiting threads check whether they are first in line
+        self.limiter.released.notify_all();
+    }
+}
+
+impl Limiter {
+    /// Admit at most the given number of operations at the same time and let the others wait without limits.
+    /// Panics if the number is 0.
+    pub fn new(max_running: usize) -> Self {
+        assert_ne!(0, max_running, "a limiter needs to admit at least one operation");
+        Limiter {
+            max_running,
+            max_queued: usize::MAX,
+            timeout: None,
+            state: Mutex::new(State::default()),
+            released: Condvar::new(),
+        }
+    }
+
+    /// Reject operations right away instead of queueing them when the given number of operations are already waiting.
+    #[must_use]
+    pub const fn with_max_queued(mut self, max_queued: usize) -> Self {
+        self.max_queued = max_queued;
+        self
+    }
+
+    /// Give up waiting for a permit after the given time.
+    #[must_use]
+    pub const fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        // the counters stay consistent even if a thread panicked while holding the lock
+        self.state.lock().unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    /// Wait until an operation may run, after all operations that were already waiting.
+    pub fn acquire(&self) -> Result<Permit<'_>, LimitError> {
+        let start = Instant::now();
+        let mut state = self.lock();
+        if state.metrics.running >= self.max_running || !state.waiting.is_empty() {
+            if state.waiting.len() >= self.max_queued {
+                state.metrics.rejected += 1;
+                return Err(LimitError::QueueFull { queued: state.waiting.len() });
+            }
+            let ticket = state.next_ticket;
+            state.next_ticket += 1;
+            state.waiting.push_back(ticket);
+            state.metrics.queued = state.waiting.len();
+            while state.metrics.running >= self.max_running || state.waiting.front() != Some(&ticket) {
+                state = match self.timeout {
+                    None => self.released.wait(state).unwrap_or_else(std::sync::PoisonError::into_inner),
+                    Some(timeout) => {
+                        let Some(remaining) = timeout.checked_sub(start.elapsed()) else {
+                            state.waiting.retain(|&t| t != ticket);
+                            state.metrics.queued = state.waiting.len();
+                            state.metrics.timed_out += 1;
+                            // the next operation in line may be able to run now
+                            self.released.notify_all();
+                            return Err(LimitError::Timeout { waited: start.elapsed() });
+                        };
+                        self.released
+                            .wait_timeout(state, remaining)
+                            .unwrap_or_else(std::sync::PoisonError::into_inner)
+                            .0
+                    }
+                };
+            }
+            state.waiting.pop_front();
+            state.metrics.queued = state.waiting.len();
+            // with several free permits, the next operation in line may run as well
+            self.released.notify_all();
+        }
+        let waited = start.elapsed();
+        let metrics = &mut state.metrics;
+        metrics.running += 1;
+        metrics.admitted += 1;
+        metrics.total_wait += waited;
+        metrics.max_wait = metrics.max_wait.max(waited);
+        Ok(Permit { limiter: self })
+    }
+
+    /// Admit an operation only if it can run right away without overtaking waiting operations.
+    pub fn try_acquire(&self) -> Option<Permit<'_>> {
+        let mut state = self.lock();
+        if state.metrics.running >= self.max_running || !state.waiting.is_empty() {
+            state.metrics.rejected += 1;
+            return None;
+        }
+        state.metrics.running += 1;
+        state.metrics.admitted += 1;
+        Some(Permit { limiter: self })
+    }
+
+    /// Run the given operation once it is admitted.
+    pub fn run<T>(&self, f: impl FnOnce() -> T) -> Result<T, LimitError> {
+        let _permit = self.acquire()?;
+        Ok(f())
+    }
+
+    /// Current state and counters.
+    pub fn metrics(&self) -> LimiterMetrics {
+        self.lock().metrics
+    }
+}
+
+/// Iterator that holds a [`Permit`] until it is exhausted or dropped, see [`Hdt::triples_with_pattern_limited`].
+pub struct LimitedIter<'a, I> {
+    iter: I,
+    permit: Option<Permit<'a>>,
+}
+
+impl<I: Iterator> Iterator for LimitedIter<'_, I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let next = self.iter.next();
+        if next.is_none() {
+            self.permit = None;
+        }
+        next
+    }
+}
+
+impl Hdt {
+    /// Like [`Self::triples_with_pattern`] but patterns without a subject and without an object need a permit of the given limiter,
+    /// because they scan the whole triples section or all triples of a predicate.
+    /// The permit is held until the iterator is exhausted or dropped, other patterns run right away.
+    /// # Example
+    /// ```
+    /// fn labels<'a>(hdt: &'a hdt::Hdt, limiter: &'a hdt::limiter::Limiter) -> Vec<hdt::hdt::StringTriple> {
+    ///     let label = Some("http://www.w3.org/2000/01/rdf-schema#label");
+    ///     match hdt.triples_with_pattern_limited(limiter, None, label, None) {
+    ///         Ok(triples) => triples.collect(),
+    ///         Err(e) => {
+    ///             eprintln!("server busy: {e}");
+    ///             Vec::new()
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn triples_with_pattern_limited<'a>(
+        &'a self, limiter: &'a Limiter, sp: Option<&'a str>, pp: Option<&'a str>, op: Option<&'a str>,
+    ) -> Result<LimitedIter<'a, Box<dyn Iterator<Item = StringTriple> + 'a>>, LimitError> {
+        let permit = if sp.is_none() && op.is_none() { Some(limiter.acquire()?) } else { None };
+        Ok(LimitedIter { iter: self.triples_with_pattern(sp, pp, op), permit })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::init;
+    use pretty_assertions::assert_eq;
+    use std::fs::File;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn limiter() {
+        init();
+        let limiter = Arc::new(Limiter::new(1).with_max_queued(1));
+        let permit = limiter.acquire().unwrap();
+        assert!(limiter.try_acquire().is_none());
+        let waiting = {
+            let limiter = Arc::clone(&limiter);
+            thread::spawn(move || limiter.run(|| 42))
+        };
+        while limiter.metrics().queued == 0 {
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(Err(LimitError::QueueFull { queued: 1 }), limiter.acquire().map(drop));
+        drop(permit);
+        assert_eq!(Ok(42), waiting.join().unwrap());
+        let metrics = limiter.metrics();
+        assert_eq!((0, 0, 2, 2), (metrics.running, metrics.queued, metrics.admitted, metrics.rejected));
+        assert!(metrics.max_wait > Duration::ZERO && metrics.total_wait >= metrics.max_wait);
+
+        let limiter = Limiter::new(1).with_timeout(Duration::from_millis(10));
+        let _permit = limiter.acquire().unwrap();
+        assert!(
+            matches!(limiter.acquire(), Err(LimitError::Timeout { waited }) if waited >= Duration::from_millis(10))
+        );
+        assert_eq!((1, 0, 1), (limiter.metrics().running, limiter.metrics().queued, limiter.metrics().timed_out));
+    }
+
+    #[test]
+    fn fifo() {
+        init();
+        let limiter = Arc::new(Limiter::new(1));
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let permit = limiter.acquire().unwrap();
+        let waiting: Vec<_> = (0..3)
+            .map(|i| {
+                let (l, o) = (Arc::clone(&limiter), Arc::clone(&order));
+                let handle = thread::spawn(move || l.run(|| o.lock().unwrap().push(i)));
+                while limiter.metrics().queued <= i {
+                    thread::sleep(Duration::from_millis(1));
+                }
+                handle
+            })
+            .collect();
+        // admitted in the order of arrival, not in the order in which the threads wake up
+        drop(permit);
+        for handle in waiting {
+            handle.join().unwrap().unwrap();
+        }
+        assert_eq!(vec![0, 1, 2], *order.lock().unwrap());
+        assert_eq!(0, limiter.metrics().queued);
+    }
+
+    #[test]
+    fn triples_with_pattern_limited() {
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let hdt = Hdt::new(std::io::BufReader::new(file)).unwrap();
+        let limiter = Limiter::new(1).with_max_queued(0);
+        let label = Some("http://www.w3.org/2000/01/rdf-schema#label");
+        let mut scan = hdt.triples_with_pattern_limited(&limiter, None, label, None).unwrap();
+        assert_eq!(1, limiter.metrics().running);
+        assert!(hdt.triples_with_pattern_limited(&limiter, None, None, None).is_err());
+        // subject lookups are not limited
+        let s = Some("http://www.snik.eu/ontology/meta/uses");
+        assert_eq!(
+            hdt.triples_with_pattern(s, None, None).collect::<Vec<_>>(),
+            hdt.triples_with_pattern_limited(&limiter, s, None, None).unwrap().collect::<Vec<_>>()
+        );
+        assert_eq!(hdt.triples_with_pattern(None, label, None).count(), scan.by_ref().count());
+        // released when exhausted
+        assert_eq!(0, limiter.metrics().running);
+        assert_eq!(
+            hdt.triples.len(),
+            hdt.triples_with_pattern_limited(&limiter, None, None, None).unwrap().count()
+        );
+    }
+}
@@ -0,0 +1,187 @@
+//! Merging several HDT files into one without a round trip through N-Triples, like hdtCat of hdt-java, see [`Hdt::merge`].
+use crate::builder::{assemble, bitmap_triples, dictionary, DEFAULT_BLOCK_SIZE};
+use crate::changes::sorted_terms;
+use crate::compare_terms;
+use crate::four_sect_dict::IdKind;
+use crate::hdt::Hdt;
+use crate::triples::{Id, SubjectIter};
+use eyre::{eyre, Result};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// Next term of one of the inputs, ordered so that the smallest term is at the top of a [`BinaryHeap`].
+struct Head {
+    term: String,
+    id: Id,
+    input: usize,
+}
+
+impl Ord for Head {
+    fn cmp(&self, other: &Self) -> Ordering {
+        compare_terms(&other.term, &self.term).then_with(|| other.input.cmp(&self.input))
+    }
+}
+
+impl PartialOrd for Head {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Head {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Head {}
+
+/// Index in the merged terms by the ID in each input.
+type Indexes = Vec<Vec<Option<usize>>>;
+
+/// Distinct terms of the given kind of all inputs in the order of [`compare_terms`], each with its index,
+/// and the index in the merged terms by the ID in each input, `None` for strings that cannot be extracted.
+fn merge_terms(hdts: &[Hdt], kind: &IdKind) -> (Vec<(String, usize)>, Indexes) {
+    let mut iters: Vec<_> = hdts.iter().map(|hdt| sorted_terms(hdt, kind)).collect();
+    let mut indexes: Indexes = hdts
+        .iter()
+        .map(|hdt| {
+            let dict = &hdt.dict;
+            let ids = match kind {
+                IdKind::Subject => dict.shared.num_strings() + dict.subjects.num_strings(),
+                IdKind::Predicate => dict.predicates.num_strings(),
+                IdKind::Object => dict.shared.num_strings() + dict.objects.num_strings(),
+            };
+            vec![None; ids + 1]
+        })
+        .collect();
+    let mut heap = BinaryHeap::new();
+    for (input, iter) in iters.iter_mut().enumerate() {
+        if let Some((term, id)) = iter.next() {
+            heap.push(Head { term, id, input });
+        }
+    }
+    let mut terms: Vec<(String, usize)> = Vec::new();
+    while let Some(Head { term, id, input }) = heap.pop() {
+        if terms.last().is_none_or(|(last, _)| *last != term) {
+            terms.push((term, terms.len()));
+        }
+        indexes[input][id] = Some(terms.len() - 1);
+        if let Some((term, id)) = iters[input].next() {
+            heap.push(Head { term, id, input });
+        }
+    }
+    (terms, indexes)
+}
+
+impl Hdt {
+    /// Merge the given HDTs into one that contains each distinct triple of any of them once, like hdtCat of hdt-java.
+    /// The dictionaries are merged in a single pass over their sorted sections, so that each distinct term is extracted once per input,
+    /// and the triples are merged as IDs that are mapped to the merged dictionary, without extracting the strings of any triple.
+    /// Terms that are subjects in one input and objects in another end up in the shared section.
+    /// The result has bitmap triples in SPO order and dictionary sections with the block size [`DEFAULT_BLOCK_SIZE`].
+    /// Strings that cannot be extracted from damaged dictionary sections are logged and their triples are skipped.
+    /// Quad files with a graph section are not supported.
+    /// # Example
+    /// ```
+    /// fn cat(a: &str, b: &str, out: &str) -> Result<(), Box<dyn std::error::Error>> {
+    ///     let read = |file| hdt::Hdt::new(std::io::BufReader::new(std::fs::File::open(file)?));
+    ///     let merged = hdt::Hdt::merge(&[read(a)?, read(b)?])?;
+    ///     merged.write_to(std::io::BufWriter::new(std::fs::File::create(out)?), "http://example.org/merged")?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn merge(hdts: &[Hdt]) -> Result<Hdt> {
+        if hdts.iter().any(|hdt| hdt.dict.graphs.is_some()) {
+            return Err(eyre!("Merging quad files with a graph section is not supported"));
+        }
+        let (s_terms, s_indexes) = merge_terms(hdts, &IdKind::Subject);
+        let (o_terms, o_indexes) = merge_terms(hdts, &IdKind::Object);
+        let (p_terms, p_indexes) = merge_terms(hdts, &IdKind::Predicate);
+
+        let keys = [s_terms.len(), p_terms.len(), o_terms.len()];
+        let (dict, ids) = dictionary([&s_terms, &p_terms, &o_terms], keys, DEFAULT_BLOCK_SIZE);
+        // 0 marks missing terms
+        let map = |indexes: &[Option<usize>], ids: &[Id]| -> Vec<Id> {
+            indexes.iter().map(|index| index.map_or(0, |index| ids[index])).collect()
+        };
+        let mut triples = Vec::with_capacity(hdts.iter().map(|hdt| hdt.triples.len()).sum());
+        for (input, hdt) in hdts.iter().enumerate() {
+            let s_map = map(&s_indexes[input], &ids[0]);
+            let p_map = map(&p_indexes[input], &ids[1]);
+            let o_map = map(&o_indexes[input], &ids[2]);
+            for t in SubjectIter::new(&hdt.triples) {
+                let t = [s_map[t.subject_id], p_map[t.predicate_id], o_map[t.object_id]];
+                if !t.contains(&0) {
+                    triples.push(t);
+                }
+            }
+        }
+        triples.sort_unstable();
+        triples.dedup();
+        Ok(assemble(dict, bitmap_triples(&triples)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::HdtBuilder;
+    use crate::tests::init;
+    use pretty_assertions::assert_eq;
+    use std::collections::BTreeSet;
+    use std::fs::File;
+
+    fn build(triples: &[[&str; 3]]) -> Hdt {
+        let mut builder = HdtBuilder::new();
+        for [s, p, o] in triples {
+            builder.add_triple(s, p, o);
+        }
+        builder.build().unwrap()
+    }
+
+    fn triple_set(hdt: &Hdt) -> BTreeSet<(String, String, String)> {
+        hdt.triples().map(|(s, p, o)| (s.to_string(), p.to_string(), o.to_string())).collect()
+    }
+
+    #[test]
+    fn merge() {
+        init();
+        let a = build(&[
+            ["http://example.org/a", "http://example.org/p", "http://example.org/b"],
+            ["http://example.org/a", "http://example.org/q", "\"x\"@en"],
+        ]);
+        // b is only an object in a and only a subject here, so it is shared in the result
+        let b = build(&[
+            ["http://example.org/b", "http://example.org/p", "http://example.org/c"],
+            ["http://example.org/a", "http://example.org/q", "\"x\"@en"],
+            ["_:b1", "http://example.org/r", "\"1\"^^<http://www.w3.org/2001/XMLSchema#integer>"],
+        ]);
+        let merged = Hdt::merge(&[a, b]).unwrap();
+        assert_eq!(4, merged.triples.len());
+        assert_eq!(1, merged.dict.shared.num_strings());
+        assert_eq!(2, merged.dict.subjects.num_strings());
+        assert_eq!(3, merged.dict.predicates.num_strings());
+        assert_eq!(3, merged.dict.objects.num_strings());
+        assert_eq!(
+            vec![("http://example.org/b".into(), "http://example.org/p".into(), "http://example.org/c".into())],
+            merged
+                .triples_with_pattern(Some("http://example.org/b"), None, None)
+                .map(|(s, p, o)| (s.to_string(), p.to_string(), o.to_string()))
+                .collect::<Vec<(String, String, String)>>()
+        );
+
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let snik = Hdt::new(std::io::BufReader::new(file)).unwrap();
+        let c = build(&[["http://www.snik.eu/ontology/meta", "http://example.org/p", "\"new\""]]);
+        let mut expected = triple_set(&snik);
+        expected.extend(triple_set(&c));
+        let merged = Hdt::merge(&[snik, c]).unwrap();
+        assert_eq!(expected, triple_set(&merged));
+        assert_eq!(expected.len(), merged.triples.len());
+        // merging with itself changes nothing
+        let again = Hdt::merge(std::slice::from_ref(&merged)).unwrap();
+        assert_eq!(expected, triple_set(&again));
+        assert!(Hdt::merge(&[]).unwrap().triples().next().is_none());
+    }
+}
@@ -0,0 +1,20 @@
+//! Commonly used types, intended to be glob imported with `use hdt::prelude::*;`.
+//!
+//! The paths in this module are part of the stable public API and only change with a new major version,
+//! so prefer them over the paths of the modules that define the types.
+//! # Example
+//! ```
+//! use hdt::prelude::*;
+//!
+//! fn label_ids(hdt: &Hdt) -> Vec<TripleId> {
+//!     let label = hdt.dict.string_to_id("http://www.w3.org/2000/01/rdf-schema#label", &IdKind::Predicate);
+//!     PredicateIter::new(&hdt.triples, label).collect()
+//! }
+//! ```
+pub use crate::four_sect_dict::{DictErr, Dictionary, IdKind};
+pub use crate::hdt::{Hdt, TermPool, TranslateErr, TripleCache};
+#[cfg(feature = "sophia")]
+pub use crate::hdt_graph::{HdtGraph, HdtTerm};
+pub use crate::triples::{
+    Id, ObjectIter, PredicateIter, PredicateObjectIter, SampleIter, SubjectIter, TripleId, TriplesBitmap,
+};
@@ -0,0 +1,144 @@
+//! Triple patterns that are resolved once and executed many times, such as the hot queries of a server.
+use crate::hdt::{Hdt, StringTriple, TripleCache};
+use crate::triples::{Id, ObjectIter, PredicateIter, PredicateObjectIter, SubjectIter, TripleId, TriplesBitmap};
+use log::error;
+use std::iter;
+
+/// Iterator that is used to execute a pattern, chosen by the positions of its constants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    /// Subject index, for patterns with a constant subject.
+    Subject,
+    /// Object index filtered by predicate, for patterns with a constant predicate and object but no subject.
+    PredicateObject,
+    /// Wavelet matrix of predicates, for patterns with only a constant predicate.
+    Predicate,
+    /// Object index, for patterns with only a constant object.
+    Object,
+    /// All triples, for patterns without constants.
+    All,
+    /// No results, because a constant does not occur in the graph.
+    Empty,
+}
+
+/// Triple pattern with resolved IDs and a chosen [`Strategy`], which avoids dictionary lookups when executed repeatedly.
+/// Only valid for the HDT it was created with.
+/// # Example
+/// ```
+/// use hdt::prepared::PreparedPattern;
+/// fn labels(hdt: &hdt::Hdt) {
+///     let pattern = PreparedPattern::new(hdt, None, Some("http://www.w3.org/2000/01/rdf-schema#label"), None);
+///     // first page
+///     for t in pattern.execute(hdt, 0, Some(100)) {
+///         println!("{t:?}");
+///     }
+///     // second page without resolving the predicate again
+///     let second_page: Vec<_> = pattern.execute(hdt, 100, Some(100)).collect();
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PreparedPattern {
+    pattern: TripleId,
+    strategy: Strategy,
+}
+
+impl PreparedPattern {
+    /// Resolve the given pattern of strings, where `None` stands for a variable.
+    pub fn new(hdt: &Hdt, sp: Option<&str>, pp: Option<&str>, op: Option<&str>) -> Self {
+        match hdt.pattern_ids(sp, pp, op) {
+            Some(pattern) => Self::from_ids(pattern),
+            None => PreparedPattern { pattern: TripleId::new(0, 0, 0), strategy: Strategy::Empty },
+        }
+    }
+
+    /// Prepare a pattern of IDs, where 0 stands for a variable.
+    pub const fn from_ids(pattern: TripleId) -> Self {
+        let strategy = match (pattern.subject_id != 0, pattern.predicate_id != 0, pattern.object_id != 0) {
+            (true, _, _) => Strategy::Subject,
+            (false, true, true) => Strategy::PredicateObject,
+            (false, true, false) => Strategy::Predicate,
+            (false, false, true) => Strategy::Object,
+            (false, false, false) => Strategy::All,
+        };
+        PreparedPattern { pattern, strategy }
+    }
+
+    /// The resolved IDs, where 0 stands for a variable.
+    pub const fn pattern(&self) -> TripleId {
+        self.pattern
+    }
+
+    /// The strategy used for execution.
+    pub const fn strategy(&self) -> Strategy {
+        self.strategy
+    }
+
+    /// Whether the pattern has no results because a constant does not occur in the graph.
+    pub fn is_empty(&self) -> bool {
+        self.strategy == Strategy::Empty
+    }
+
+    /// All triple IDs that fit the pattern, in the same order as [`TriplesBitmap::triples_with_pattern`].
+    pub fn ids<'a>(&self, triples: &'a TriplesBitmap) -> Box<dyn Iterator<Item = TripleId> + 'a> {
+        let pat = self.pattern;
+        let (p, o): (Id, Id) = (pat.predicate_id, pat.object_id);
+        match self.strategy {
+            Strategy::Subject => Box::new(SubjectIter::with_pattern(triples, &pat)),
+            Strategy::PredicateObject => {
+                Box::new(PredicateObjectIter::new(triples, p, o).map(move |s| TripleId::new(s, p, o)))
+            }
+            Strategy::Predicate => Box::new(PredicateIter::new(triples, p)),
+            Strategy::Object => Box::new(ObjectIter::new(triples, o)),
+            Strategy::All => Box::new(SubjectIter::new(triples)),
+            Strategy::Empty => Box::new(iter::empty()),
+        }
+    }
+
+    /// Triples that fit the pattern as strings, skipping the first `offset` results and returning at most `limit` ones.
+    /// Skipped results are not translated.
+    pub fn execute<'a>(
+        &self, hdt: &'a Hdt, offset: usize, limit: Option<usize>,
+    ) -> Box<dyn Iterator<Item = StringTriple> + 'a> {
+        let mut cache = TripleCache::new(hdt);
+        Box::new(
+            self.ids(&hdt.triples)
+                .skip(offset)
+                .take(limit.unwrap_or(usize::MAX))
+                .filter_map(move |t| cache.translate(t).map_err(|e| error!("{e}")).ok()),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::init;
+    use pretty_assertions::assert_eq;
+    use std::fs::File;
+
+    #[test]
+    fn prepared() {
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let hdt = Hdt::new(std::io::BufReader::new(file)).unwrap();
+        let label = Some("http://www.w3.org/2000/01/rdf-schema#label");
+        let top = Some("http://www.snik.eu/ontology/meta/Top");
+        for (sp, pp, op, strategy) in [
+            (top, label, None, Strategy::Subject),
+            (None, label, Some("\"top class\"@en"), Strategy::PredicateObject),
+            (None, label, None, Strategy::Predicate),
+            (None, None, top, Strategy::Object),
+            (None, None, None, Strategy::All),
+            (Some("http://example.org/doesnotexist"), None, None, Strategy::Empty),
+        ] {
+            let pattern = PreparedPattern::new(&hdt, sp, pp, op);
+            assert_eq!(strategy, pattern.strategy());
+            let expected: Vec<_> = hdt.triples_with_pattern(sp, pp, op).collect();
+            assert_eq!(expected, pattern.execute(&hdt, 0, None).collect::<Vec<_>>());
+            // executions are independent and can be paged
+            let page: Vec<_> = pattern.execute(&hdt, 1, Some(2)).collect();
+            assert_eq!(expected.iter().skip(1).take(2).cloned().collect::<Vec<_>>(), page);
+        }
+        assert!(PreparedPattern::new(&hdt, Some("http://example.org/doesnotexist"), None, None).is_empty());
+    }
+}
@@ -0,0 +1,122 @@
+//! Per-phase timings of triple pattern queries, to find out whether the dictionary, the triples navigation or the string materialization dominates.
+use crate::hdt::{Hdt, StringTriple, TripleCache};
+use crate::triples::TripleId;
+use log::error;
+use std::iter;
+use std::time::{Duration, Instant};
+
+/// Time spent in each phase of a query.
+/// High lookup times indicate that resolving the constants dominates, which can be avoided with a [`PreparedPattern`](crate::prepared::PreparedPattern).
+/// High materialization times indicate that translating IDs to strings dominates, which can be reduced with a [`TermPool`](crate::hdt::TermPool) or by working on IDs.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Profile {
+    /// Translating the constants of the pattern to IDs in the dictionary.
+    pub lookup: Duration,
+    /// Navigating the bitmaps, sequences and wavelet matrix of the triples section to find the matching triple IDs.
+    pub navigation: Duration,
+    /// Translating the resulting triple IDs to strings.
+    pub materialization: Duration,
+    /// Number of results returned so far.
+    pub results: usize,
+}
+
+impl Profile {
+    /// Time spent in all phases.
+    pub fn total(&self) -> Duration {
+        self.lookup + self.navigation + self.materialization
+    }
+}
+
+/// Iterator over the results of a query that measures the time spent in each phase, see [`triples_with_pattern`].
+/// Measuring costs two clock reads per phase and result, so only use it for analysis.
+pub struct ProfiledIter<'a> {
+    ids: Box<dyn Iterator<Item = TripleId> + 'a>,
+    cache: TripleCache<'a>,
+    profile: Profile,
+}
+
+impl ProfiledIter<'_> {
+    /// Timings of the query so far, complete once the iterator is exhausted.
+    pub const fn profile(&self) -> Profile {
+        self.profile
+    }
+}
+
+impl Iterator for ProfiledIter<'_> {
+    type Item = StringTriple;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let start = Instant::now();
+            let t = self.ids.next();
+            self.profile.navigation += start.elapsed();
+            let t = t?;
+            let start = Instant::now();
+            let triple = self.cache.translate(t);
+            self.profile.materialization += start.elapsed();
+            match triple {
+                Ok(triple) => {
+                    self.profile.results += 1;
+                    return Some(triple);
+                }
+                Err(e) => error!("{e}"),
+            }
+        }
+    }
+}
+
+/// Get all triples that fit the given triple pattern like [`Hdt::triples_with_pattern`] and measure the time spent in each phase.
+/// # Example
+/// ```
+/// fn profile_labels(hdt: &hdt::Hdt) {
+///     let mut query = hdt::profile::triples_with_pattern(hdt, None, Some("http://www.w3.org/2000/01/rdf-schema#label"), None);
+///     query.by_ref().for_each(drop);
+///     println!("{:?}", query.profile());
+/// }
+/// ```
+pub fn triples_with_pattern<'a>(
+    hdt: &'a Hdt, sp: Option<&str>, pp: Option<&str>, op: Option<&str>,
+) -> ProfiledIter<'a> {
+    let start = Instant::now();
+    let pat = hdt.pattern_ids(sp, pp, op);
+    let lookup = start.elapsed();
+    let start = Instant::now();
+    let ids: Box<dyn Iterator<Item = TripleId> + 'a> = match pat {
+        Some(pat) => hdt.triples.triples_with_pattern(&pat),
+        None => Box::new(iter::empty()),
+    };
+    // iterator construction already searches the start positions
+    let navigation = start.elapsed();
+    ProfiledIter {
+        ids,
+        cache: TripleCache::new(hdt),
+        profile: Profile { lookup, navigation, ..Profile::default() },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::init;
+    use pretty_assertions::assert_eq;
+    use std::fs::File;
+
+    #[test]
+    fn profile() {
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let hdt = Hdt::new(std::io::BufReader::new(file)).unwrap();
+        let label = Some("http://www.w3.org/2000/01/rdf-schema#label");
+        let mut query = triples_with_pattern(&hdt, None, label, None);
+        let results: Vec<_> = query.by_ref().collect();
+        assert_eq!(hdt.triples_with_pattern(None, label, None).collect::<Vec<_>>(), results);
+        let profile = query.profile();
+        assert_eq!(results.len(), profile.results);
+        assert!(profile.lookup > Duration::ZERO && profile.materialization > Duration::ZERO);
+        assert_eq!(profile.lookup + profile.navigation + profile.materialization, profile.total());
+
+        let mut query = triples_with_pattern(&hdt, Some("http://example.org/doesnotexist"), None, None);
+        assert_eq!(0, query.by_ref().count());
+        assert_eq!(Duration::ZERO, query.profile().materialization);
+    }
+}
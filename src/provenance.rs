@@ -0,0 +1,133 @@
+//! Internal coordinates of triples, so that a data discrepancy can be traced back to the parts of the file that encode it.
+//! Include the output of [`Hdt::locate`] when reporting a triple that differs from what other implementations return.
+use crate::four_sect_dict::IdKind;
+use crate::hdt::Hdt;
+use crate::triples::{Id, TripleId};
+use crate::DictSectPFC;
+
+/// Dictionary section that contains a term.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Section {
+    /// Terms that occur both as subject and object.
+    Shared,
+    /// Terms that only occur as subjects.
+    Subjects,
+    /// Predicates.
+    Predicates,
+    /// Terms that only occur as objects.
+    Objects,
+}
+
+/// Location of a term in the dictionary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TermLocation {
+    /// ID of the term in the triples.
+    pub id: Id,
+    /// Section that contains the term.
+    pub section: Section,
+    /// ID within the section, which differs from [`Self::id`] for terms of the subject and object sections.
+    pub local_id: Id,
+    /// Index of the front coded block that contains the term within the section.
+    pub block: usize,
+    /// Offset in bytes of that block in the packed data of the section.
+    pub block_offset: usize,
+}
+
+/// Location of a triple in the dictionary and the triples section.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TripleLocation {
+    /// Location of the subject, whose index in the X level is its ID minus one.
+    pub subject: TermLocation,
+    /// Location of the predicate.
+    pub predicate: TermLocation,
+    /// Location of the object.
+    pub object: TermLocation,
+    /// Position of the subject-predicate pair in the Y level sequence and bitmap.
+    pub pos_y: usize,
+    /// Position of the triple in the Z level sequence and bitmap, see [`TriplesBitmap::triple_at`](crate::triples::TriplesBitmap::triple_at).
+    pub pos_z: usize,
+    /// Offset in bits of the object ID in the data of the Z level sequence.
+    pub z_bit_offset: usize,
+}
+
+impl Hdt {
+    /// Locate the triple with the given subject, predicate and object strings, None if it does not exist.
+    /// # Example
+    /// ```
+    /// fn report(hdt: &hdt::Hdt, s: &str, p: &str, o: &str) {
+    ///     match hdt.locate(s, p, o) {
+    ///         Some(location) => eprintln!("{location:#?}"),
+    ///         None => eprintln!("triple not found"),
+    ///     }
+    /// }
+    /// ```
+    pub fn locate(&self, s: &str, p: &str, o: &str) -> Option<TripleLocation> {
+        let t = TripleId::new(
+            self.dict.string_to_id(s, &IdKind::Subject),
+            self.dict.string_to_id(p, &IdKind::Predicate),
+            self.dict.string_to_id(o, &IdKind::Object),
+        );
+        self.locate_id(&t)
+    }
+
+    /// Like [`Self::locate`] but with the IDs of the triple.
+    pub fn locate_id(&self, t: &TripleId) -> Option<TripleLocation> {
+        let pos_z = self.triples.position(t)?;
+        let shared = self.dict.shared.num_strings();
+        let term = |id: Id, section: Section, sect: &DictSectPFC, local_id: Id| {
+            let (block, block_offset) = sect.block_of(local_id)?;
+            Some(TermLocation { id, section, local_id, block, block_offset })
+        };
+        let subject_object = |id: Id, own: Section, sect: &DictSectPFC| {
+            if id <= shared {
+                term(id, Section::Shared, &self.dict.shared, id)
+            } else {
+                term(id, own, sect, id - shared)
+            }
+        };
+        Some(TripleLocation {
+            subject: subject_object(t.subject_id, Section::Subjects, &self.dict.subjects)?,
+            predicate: term(t.predicate_id, Section::Predicates, &self.dict.predicates, t.predicate_id)?,
+            object: subject_object(t.object_id, Section::Objects, &self.dict.objects)?,
+            pos_y: self.triples.adjlist_z.bitmap.rank(pos_z),
+            pos_z,
+            z_bit_offset: pos_z * self.triples.adjlist_z.sequence.bits_per_entry(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::init;
+    use pretty_assertions::assert_eq;
+    use std::fs::File;
+
+    #[test]
+    fn locate() {
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let hdt = Hdt::new(std::io::BufReader::new(file)).unwrap();
+        for pos_z in [0, 100, hdt.triples.len() - 1] {
+            let t = hdt.triples.triple_at(pos_z);
+            let location = hdt.locate_id(&t).unwrap();
+            assert_eq!(pos_z, location.pos_z);
+            assert_eq!(t.predicate_id, hdt.triples.wavelet_y.access(location.pos_y).unwrap());
+            for (term, kind) in [(&location.subject, &IdKind::Subject), (&location.object, &IdKind::Object)] {
+                let sect = match term.section {
+                    Section::Shared => &hdt.dict.shared,
+                    Section::Subjects => &hdt.dict.subjects,
+                    Section::Objects => &hdt.dict.objects,
+                    Section::Predicates => unreachable!("subject or object in predicate section"),
+                };
+                assert_eq!(hdt.dict.id_to_string(term.id, kind).unwrap(), sect.extract(term.local_id).unwrap());
+                assert_eq!(term.block, (term.local_id - 1) / sect.block_size());
+            }
+            let s = hdt.dict.id_to_string(t.subject_id, &IdKind::Subject).unwrap();
+            let p = hdt.dict.id_to_string(t.predicate_id, &IdKind::Predicate).unwrap();
+            let o = hdt.dict.id_to_string(t.object_id, &IdKind::Object).unwrap();
+            assert_eq!(Some(location), hdt.locate(&s, &p, &o));
+        }
+        assert_eq!(None, hdt.locate("http://example.org/s", "http://example.org/p", "http://example.org/o"));
+    }
+}
@@ -0,0 +1,288 @@
+//! Evaluation of basic graph patterns, which are sets of triple patterns with shared variables, as in the WHERE clause of a simple SPARQL query.
+//! Joins are performed on dictionary IDs, strings are only extracted for the final bindings.
+use crate::four_sect_dict::IdKind;
+use crate::hdt::{Hdt, TermPool};
+use crate::triples::{Id, TripleId};
+use log::error;
+use std::iter;
+use std::sync::Arc;
+
+/// Kinds of the subject, predicate and object position.
+const KINDS: [&IdKind; 3] = [&IdKind::Subject, &IdKind::Predicate, &IdKind::Object];
+
+/// Term of a triple pattern in a step of the evaluation plan.
+#[derive(Debug, Clone)]
+enum Slot {
+    /// Constant that is not in the dictionary, so that there are no solutions.
+    Missing,
+    /// Constant with its ID in this position.
+    Const(Id),
+    /// Variable with the given index that is already bound by an earlier step.
+    Bound(usize),
+    /// Variable with the given index that is bound by this step.
+    New(usize),
+}
+
+/// Basic graph pattern, which can be evaluated on any [`Hdt`].
+/// Terms starting with `?` are variables, all others are constants in the string format of the HDT dictionary, see [`Hdt::literal_matcher`].
+/// # Example
+/// ```
+/// use hdt::query::Query;
+/// fn labels_of_classes(hdt: &hdt::Hdt) {
+///     let query = Query::new(&[
+///         ["?class", "http://www.w3.org/1999/02/22-rdf-syntax-ns#type", "http://www.w3.org/2002/07/owl#Class"],
+///         ["?class", "http://www.w3.org/2000/01/rdf-schema#label", "?label"],
+///     ]);
+///     for solution in query.execute(hdt) {
+///         println!("{} {}", solution[0], solution[1]);
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Query {
+    variables: Vec<String>,
+    patterns: Vec<[String; 3]>,
+}
+
+impl Query {
+    /// Query with the given triple patterns, which all need to match.
+    pub fn new<S: AsRef<str>>(patterns: &[[S; 3]]) -> Self {
+        let mut variables: Vec<String> = Vec::new();
+        for term in patterns.iter().flatten().map(AsRef::as_ref) {
+            if let Some(var) = term.strip_prefix('?') {
+                if !variables.iter().any(|v| v == var) {
+                    variables.push(var.to_owned());
+                }
+            }
+        }
+        let patterns = patterns.iter().map(|p| p.each_ref().map(|t| t.as_ref().to_owned())).collect();
+        Query { variables, patterns }
+    }
+
+    /// Names of the variables without the leading `?`, in the order of their first occurrence and of the values of each solution.
+    pub fn variables(&self) -> &[String] {
+        &self.variables
+    }
+
+    /// Order the patterns so that each step has as many bound terms as possible and resolve the constants.
+    fn plan(&self, hdt: &Hdt) -> Vec<[Slot; 3]> {
+        let var_index =
+            |term: &str| term.strip_prefix('?').map(|v| self.variables.iter().position(|x| x == v).unwrap());
+        let mut bound = vec![false; self.variables.len()];
+        let mut remaining: Vec<&[String; 3]> = self.patterns.iter().collect();
+        let mut plan = Vec::with_capacity(remaining.len());
+        while !remaining.is_empty() {
+            // bound terms restrict the results, a bound subject allows the fastest access
+            let score = |p: &[String; 3]| {
+                let bound: Vec<bool> = p.iter().map(|t| var_index(t).is_none_or(|v| bound[v])).collect();
+                bound.iter().filter(|b| **b).count() * 2 + usize::from(bound[0])
+            };
+            let best = (0..remaining.len()).rev().max_by_key(|&i| score(remaining[i])).unwrap();
+            let pattern = remaining.remove(best);
+            let mut slots = [Slot::Missing, Slot::Missing, Slot::Missing];
+            for (i, term) in pattern.iter().enumerate() {
+                slots[i] = match var_index(term) {
+                    Some(v) if bound[v] => Slot::Bound(v),
+                    // repeated variable in the same pattern
+                    Some(v) if slots[..i].iter().any(|s| matches!(s, Slot::New(w) if *w == v)) => Slot::Bound(v),
+                    Some(v) => Slot::New(v),
+                    None => match hdt.dict.string_to_id(term, KINDS[i]) {
+                        0 => Slot::Missing,
+                        id => Slot::Const(id),
+                    },
+                };
+            }
+            for slot in &slots {
+                if let Slot::New(v) = slot {
+                    bound[*v] = true;
+                }
+            }
+            plan.push(slots);
+        }
+        plan
+    }
+
+    /// Evaluate the query and return the IDs of the variable bindings of each solution, in the order of [`Self::variables`], together with the position they were bound in.
+    /// The same term can have different IDs depending on the position, see [`IdKind`].
+    pub fn ids<'a>(&self, hdt: &'a Hdt) -> IdSolutions<'a> {
+        let plan = self.plan(hdt);
+        let done = plan.iter().flatten().any(|s| matches!(s, Slot::Missing));
+        IdSolutions { hdt, plan, bindings: vec![(0, 0); self.variables.len()], iters: Vec::new(), done }
+    }
+
+    /// Evaluate the query and return the strings of the variable bindings of each solution, in the order of [`Self::variables`].
+    pub fn execute<'a>(&self, hdt: &'a Hdt) -> Solutions<'a> {
+        Solutions { ids: self.ids(hdt), pool: TermPool::new(hdt) }
+    }
+}
+
+/// Iterator over the solutions of a [`Query`] as IDs, see [`Query::ids`].
+pub struct IdSolutions<'a> {
+    hdt: &'a Hdt,
+    plan: Vec<[Slot; 3]>,
+    // ID and index of the kind of each variable
+    bindings: Vec<(Id, usize)>,
+    // open iterator of each step that has been started
+    iters: Vec<Box<dyn Iterator<Item = TripleId> + 'a>>,
+    done: bool,
+}
+
+impl<'a> IdSolutions<'a> {
+    /// ID of the bound variable for the given position, if the term can occur there.
+    fn convert(&self, v: usize, kind: usize) -> Option<Id> {
+        let (id, bound_kind) = self.bindings[v];
+        if bound_kind == kind {
+            return Some(id);
+        }
+        let dict = &self.hdt.dict;
+        // subjects and objects share IDs only in the shared section
+        if bound_kind != 1 && kind != 1 {
+            return (id <= dict.shared.num_strings()).then_some(id);
+        }
+        // predicates have their own dictionary
+        let term = dict.id_to_string(id, KINDS[bound_kind]).map_err(|e| error!("{e}")).ok()?;
+        Some(dict.string_to_id(&term, KINDS[kind])).filter(|&id| id != 0)
+    }
+
+    /// Iterator over the triples matching the given step with the current bindings.
+    fn open(&self, step: usize) -> Box<dyn Iterator<Item = TripleId> + 'a> {
+        let mut pat = [0; 3];
+        for (kind, slot) in self.plan[step].iter().enumerate() {
+            pat[kind] = match slot {
+                Slot::Missing => return Box::new(iter::empty()),
+                Slot::Const(id) => *id,
+                // bound by an earlier position of the same pattern, checked after matching
+                Slot::Bound(v) if self.plan[step][..kind].iter().any(|s| matches!(s, Slot::New(w) if w == v)) => 0,
+                Slot::Bound(v) => match self.convert(*v, kind) {
+                    Some(id) => id,
+                    None => return Box::new(iter::empty()),
+                },
+                Slot::New(_) => 0,
+            };
+        }
+        self.hdt.triples.triples_with_pattern(&TripleId::new(pat[0], pat[1], pat[2]))
+    }
+
+    /// Bind the new variables of the given step to the given triple, returns false if a repeated variable does not match.
+    fn bind(&mut self, step: usize, t: TripleId) -> bool {
+        let ids = [t.subject_id, t.predicate_id, t.object_id];
+        for (kind, id) in ids.into_iter().enumerate() {
+            match self.plan[step][kind] {
+                Slot::New(v) => self.bindings[v] = (id, kind),
+                Slot::Bound(v) if self.convert(v, kind) != Some(id) => return false,
+                _ => {}
+            }
+        }
+        true
+    }
+}
+
+impl Iterator for IdSolutions<'_> {
+    type Item = Vec<(Id, &'static IdKind)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        if self.plan.is_empty() {
+            // the empty pattern has exactly one solution without bindings
+            self.done = true;
+            return Some(Vec::new());
+        }
+        if self.iters.is_empty() {
+            let iter = self.open(0);
+            self.iters.push(iter);
+        }
+        loop {
+            let step = self.iters.len() - 1;
+            match self.iters[step].next() {
+                // a failed repeated variable check continues with the next triple of the same step
+                Some(t) if !self.bind(step, t) => {}
+                Some(_) if step + 1 == self.plan.len() => {
+                    return Some(self.bindings.iter().map(|&(id, kind)| (id, KINDS[kind])).collect());
+                }
+                Some(_) => {
+                    let iter = self.open(step + 1);
+                    self.iters.push(iter);
+                }
+                None => {
+                    self.iters.pop();
+                    if self.iters.is_empty() {
+                        self.done = true;
+                        return None;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Iterator over the solutions of a [`Query`] as strings, see [`Query::execute`].
+pub struct Solutions<'a> {
+    ids: IdSolutions<'a>,
+    pool: TermPool<'a>,
+}
+
+impl Iterator for Solutions<'_> {
+    type Item = Vec<Arc<str>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let ids = self.ids.next()?;
+            let terms: Result<Vec<_>, _> = ids.into_iter().map(|(id, kind)| self.pool.term(id, kind)).collect();
+            match terms {
+                Ok(terms) => return Some(terms),
+                Err(e) => error!("{e}"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::init;
+    use pretty_assertions::assert_eq;
+    use std::collections::BTreeSet;
+    use std::fs::File;
+
+    const TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+    const LABEL: &str = "http://www.w3.org/2000/01/rdf-schema#label";
+
+    #[test]
+    fn query() {
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let hdt = Hdt::new(std::io::BufReader::new(file)).unwrap();
+        let query = Query::new(&[["?s", LABEL, "?l"], ["?s", TYPE, "?c"]]);
+        assert_eq!(["s", "l", "c"], query.variables());
+        let actual: BTreeSet<Vec<String>> =
+            query.execute(&hdt).map(|sol| sol.iter().map(ToString::to_string).collect()).collect();
+        let mut expected = BTreeSet::new();
+        for (s, _, c) in hdt.triples_with_pattern(None, Some(TYPE), None) {
+            for (_, _, l) in hdt.triples_with_pattern(Some(&s), Some(LABEL), None) {
+                expected.insert(vec![s.to_string(), l.to_string(), c.to_string()]);
+            }
+        }
+        assert!(!expected.is_empty());
+        assert_eq!(expected, actual);
+        assert_eq!(expected.len(), query.ids(&hdt).count());
+
+        // join between object and subject position
+        let query = Query::new(&[["?a", "?p", "?b"], ["?b", "?q", "?c"]]);
+        let mut expected = 0;
+        for (_, _, b) in hdt.triples_with_pattern(None, None, None) {
+            expected += hdt.triples_with_pattern(Some(&b), None, None).count();
+        }
+        assert_ne!(0, expected);
+        assert_eq!(expected, query.execute(&hdt).count());
+
+        // repeated variable
+        let query = Query::new(&[["?x", "?p", "?x"]]);
+        let expected = hdt.triples_with_pattern(None, None, None).filter(|(s, _, o)| s == o).count();
+        assert_eq!(expected, query.execute(&hdt).count());
+
+        assert_eq!(0, Query::new(&[["?s", "http://example.org/doesnotexist", "?o"]]).execute(&hdt).count());
+        assert_eq!(vec![Vec::<Arc<str>>::new()], Query::new::<&str>(&[]).execute(&hdt).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,96 @@
+//! Export of triples with RDF-star annotations, which HDT cannot store itself, such as per-triple provenance kept by the application.
+use crate::containers::rdf::nt_term;
+use crate::hdt::{Hdt, StringTriple, TripleCache};
+use crate::triples::TripleId;
+use log::error;
+use std::io::{self, Write};
+
+/// Write all triples that fit the given triple pattern as N-Triples-star, followed by their annotations.
+/// The function `annotations` returns the annotations of a triple as pairs of predicate and object in the string format of the HDT dictionary.
+/// Each annotation is written as a triple with the quoted annotated triple as subject, for example `<< s p o >> ap ao .`.
+/// Returns the number of annotations written.
+/// # Example
+/// ```
+/// use hdt::triples::TripleId;
+/// use std::collections::HashMap;
+/// fn export(hdt: &hdt::Hdt, sources: &HashMap<TripleId, String>) -> std::io::Result<usize> {
+///     let mut out = std::io::stdout().lock();
+///     hdt::rdf_star::write_annotated(hdt, &mut out, None, None, None, |id, _| {
+///         sources.get(&id).map(|source| ("http://www.w3.org/ns/prov#wasDerivedFrom".to_owned(), source.clone()))
+///     })
+/// }
+/// ```
+pub fn write_annotated<W, F, I>(
+    hdt: &Hdt, w: &mut W, sp: Option<&str>, pp: Option<&str>, op: Option<&str>, mut annotations: F,
+) -> io::Result<usize>
+where
+    W: Write,
+    F: FnMut(TripleId, &StringTriple) -> I,
+    I: IntoIterator<Item = (String, String)>,
+{
+    let Some(pat) = hdt.pattern_ids(sp, pp, op) else {
+        return Ok(0);
+    };
+    let mut cache = TripleCache::new(hdt);
+    let mut written = 0;
+    for id in hdt.triples.triples_with_pattern(&pat) {
+        let triple = match cache.translate(id) {
+            Ok(triple) => triple,
+            Err(e) => {
+                error!("{e}");
+                continue;
+            }
+        };
+        let nt = format!("{} {} {}", nt_term(&triple.0), nt_term(&triple.1), nt_term(&triple.2));
+        writeln!(w, "{nt} .")?;
+        for (p, o) in annotations(id, &triple) {
+            writeln!(w, "<< {nt} >> {} {} .", nt_term(&p), nt_term(&o))?;
+            written += 1;
+        }
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::init;
+    use pretty_assertions::assert_eq;
+    use std::collections::HashMap;
+    use std::fs::File;
+
+    #[test]
+    fn write_annotated() {
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let hdt = Hdt::new(std::io::BufReader::new(file)).unwrap();
+        let label = Some("http://www.w3.org/2000/01/rdf-schema#label");
+        let ids: Vec<_> = hdt.triples.triples_with_pattern(&hdt.pattern_ids(None, label, None).unwrap()).collect();
+        let source = "http://www.w3.org/ns/prov#wasDerivedFrom";
+        let sources = HashMap::from([(ids[0], "http://example.org/book"), (ids[2], "\"page 5\"")]);
+        let mut out = Vec::new();
+        let written = super::write_annotated(&hdt, &mut out, None, label, None, |id, _| {
+            sources.get(&id).map(|o| (source.to_owned(), (*o).to_owned()))
+        })
+        .unwrap();
+        assert_eq!(2, written);
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<_> = out.lines().collect();
+        assert_eq!(ids.len() + 2, lines.len());
+        let (s, p, o) = hdt.triples_with_pattern(None, label, None).next().unwrap();
+        let nt = format!("{} {} {}", nt_term(&s), nt_term(&p), nt_term(&o));
+        assert_eq!(format!("{nt} ."), lines[0]);
+        assert_eq!(format!("<< {nt} >> <{source}> <http://example.org/book> ."), lines[1]);
+        assert!(
+            lines.contains(&format!("<< {} >> <{source}> \"page 5\" .", &lines[3][..lines[3].len() - 2]).as_str())
+        );
+
+        let mut out = Vec::new();
+        let written =
+            super::write_annotated(&hdt, &mut out, Some("http://example.org/doesnotexist"), None, None, |_, _| {
+                vec![(source.to_owned(), "http://example.org/book".to_owned())]
+            })
+            .unwrap();
+        assert_eq!((0, 0), (written, out.len()));
+    }
+}
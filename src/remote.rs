@@ -0,0 +1,328 @@
+//! Opening HDT files on web servers without downloading them, see [`LazyHdt::open_url`].
+//! Sections are read with HTTP range requests, so only the metadata, the dictionary sections and the parts that are queried are transferred.
+use crate::containers::ByteStore;
+use crate::lazy::{LazyHdt, SeekRead, Source};
+use std::error::Error;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::ops::Range;
+use std::sync::OnceLock;
+
+/// Forward seeks up to this number of bytes read and discard the rest of the current response instead of sending a new request.
+const MAX_SKIP: u64 = 64 * 1024;
+
+/// HDT file at a URL of a web server that supports range requests, which a [`LazyHdt`] reads its sections from.
+/// Each reader sends a request for the rest of the file from its position and a new request after seeking further away.
+#[derive(Debug, Clone)]
+pub struct HttpSource {
+    url: String,
+    agent: ureq::Agent,
+}
+
+impl HttpSource {
+    /// Source for the given URL with the default configuration of [`ureq`].
+    pub fn new(url: impl Into<String>) -> Self {
+        Self::with_agent(url, ureq::Agent::new_with_defaults())
+    }
+
+    /// Source for the given URL that sends its requests with the given agent, for example to configure timeouts or a proxy.
+    pub fn with_agent(url: impl Into<String>, agent: ureq::Agent) -> Self {
+        HttpSource { url: url.into(), agent }
+    }
+
+    /// The URL of the HDT file.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+impl Source for HttpSource {
+    fn reader(&self) -> io::Result<Box<dyn SeekRead>> {
+        Ok(Box::new(HttpReader { source: self.clone(), pos: 0, len: None, body: None }))
+    }
+
+    /// Send a single request for exactly the given range.
+    fn read_range(&self, range: Range<u64>) -> io::Result<Vec<u8>> {
+        let Range { start, end } = range;
+        let mut bytes = Vec::new();
+        if start >= end {
+            return Ok(bytes);
+        }
+        let url = &self.url;
+        let request = self.agent.get(url).header("Range", format!("bytes={start}-{}", end - 1));
+        let response = request.call().map_err(io::Error::other)?;
+        match response.status().as_u16() {
+            206 => {}
+            200 if start == 0 => {}
+            status => {
+                return Err(io::Error::other(format!("{url} does not support range requests, status {status}")));
+            }
+        }
+        response.into_body().into_reader().take(end - start).read_to_end(&mut bytes)?;
+        if bytes.len() as u64 != end - start {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(bytes)
+    }
+}
+
+/// Reader of the response body of a range request, which is only sent on the first read after creating or seeking.
+struct HttpReader {
+    source: HttpSource,
+    pos: u64,
+    /// total length of the file, known after the first response
+    len: Option<u64>,
+    body: Option<ureq::BodyReader<'static>>,
+}
+
+/// Total length from a `Content-Range` header such as `bytes 0-99/1234`.
+fn total_length(content_range: &str) -> Option<u64> {
+    content_range.rsplit_once('/')?.1.parse().ok()
+}
+
+impl HttpReader {
+    /// Request the file from the current position to the end, no body is kept if the position is at or after the end.
+    fn request(&mut self) -> io::Result<()> {
+        let url = &self.source.url;
+        let response = match self.source.agent.get(url).header("Range", format!("bytes={}-", self.pos)).call() {
+            Ok(response) => response,
+            // range not satisfiable
+            Err(ureq::Error::StatusCode(416)) => {
+                self.len.get_or_insert(self.pos);
+                return Ok(());
+            }
+            Err(e) => return Err(io::Error::other(e)),
+        };
+        let header = |name: &str| response.headers().get(name).and_then(|v| v.to_str().ok());
+        self.len = match response.status().as_u16() {
+            206 => header("Content-Range").and_then(total_length),
+            // servers may ignore the range header, which only matters when not starting at the beginning
+            200 if self.pos == 0 => header("Content-Length").and_then(|v| v.parse().ok()),
+            status => {
+                return Err(io::Error::other(format!("{url} does not support range requests, status {status}")));
+            }
+        }
+        .or(self.len);
+        self.body = Some(response.into_body().into_reader());
+        Ok(())
+    }
+}
+
+impl Read for HttpReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() || self.len.is_some_and(|len| self.pos >= len) {
+            return Ok(0);
+        }
+        if self.body.is_none() {
+            self.request()?;
+        }
+        let Some(body) = &mut self.body else {
+            return Ok(0);
+        };
+        let n = body.read(buf)?;
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for HttpReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(offset) => self.pos.checked_add_signed(offset),
+            SeekFrom::End(offset) => {
+                if self.len.is_none() {
+                    self.request()?;
+                }
+                let len =
+                    self.len.ok_or_else(|| io::Error::other(format!("unknown length of {}", self.source.url)))?;
+                len.checked_add_signed(offset)
+            }
+        }
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "seek to a negative position"))?;
+        if target != self.pos {
+            match (&mut self.body, target.checked_sub(self.pos)) {
+                (Some(body), Some(skip)) if skip <= MAX_SKIP => {
+                    if io::copy(&mut body.take(skip), &mut io::sink())? < skip {
+                        self.body = None;
+                    }
+                }
+                _ => self.body = None,
+            }
+            self.pos = target;
+        }
+        Ok(target)
+    }
+}
+
+/// Range of a file on a web server that is requested on first access and then kept in memory, see [`ByteStore`].
+/// For example, the packed strings of a rarely used dictionary section can stay on the server with
+/// [`DictSectPFC::set_packed_data`](crate::DictSectPFC::set_packed_data).
+pub struct HttpBytes {
+    source: HttpSource,
+    range: Range<u64>,
+    bytes: OnceLock<Vec<u8>>,
+}
+
+impl HttpBytes {
+    /// The bytes of the given range of the file of the given source.
+    pub const fn new(source: HttpSource, range: Range<u64>) -> Self {
+        HttpBytes { source, range, bytes: OnceLock::new() }
+    }
+
+    /// Request exactly the range of the bytes.
+    fn fetch(&self) -> io::Result<Vec<u8>> {
+        self.source.read_range(self.range.clone())
+    }
+}
+
+impl ByteStore for HttpBytes {
+    fn len(&self) -> usize {
+        usize::try_from(self.range.end.saturating_sub(self.range.start)).unwrap_or(usize::MAX)
+    }
+
+    fn bytes(&self) -> io::Result<&[u8]> {
+        if let Some(bytes) = self.bytes.get() {
+            return Ok(bytes);
+        }
+        let bytes = self.fetch()?;
+        Ok(self.bytes.get_or_init(|| bytes))
+    }
+
+    fn heap_len(&self) -> usize {
+        self.bytes.get().map_or(0, Vec::len)
+    }
+}
+
+impl LazyHdt {
+    /// Read the metadata and the shared, subject and predicate sections of the HDT file at the given URL,
+    /// see [`Self::open`] and [`HttpSource`].
+    /// The object and triples sections are requested when first needed, which transfers only a part of large files
+    /// for looking up the triples of a few subjects.
+    /// Use [`HttpSource::with_agent`] with [`Self::from_source`] to configure the requests.
+    /// # Example
+    /// ```no_run
+    /// let lazy = hdt::lazy::LazyHdt::open_url("https://example.org/dataset.hdt").unwrap();
+    /// for (s, p, o) in lazy.triples_with_subject("http://example.org/resource").unwrap() {
+    ///     println!("{s} {p} {o}");
+    /// }
+    /// ```
+    pub fn open_url(url: &str) -> Result<Self, Box<dyn Error>> {
+        Self::from_source(Box::new(HttpSource::new(url)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dict_sect_pfc::ExtractError;
+    use crate::four_sect_dict::IdKind;
+    use crate::hdt::Hdt;
+    use crate::tests::init;
+    use pretty_assertions::assert_eq;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// Answer a range request for the given data with status 206, ignoring errors of clients that stop reading.
+    fn respond(stream: &TcpStream, data: &[u8]) {
+        let mut reader = BufReader::new(stream);
+        let (mut start, mut end) = (0, data.len());
+        let mut line = String::new();
+        while reader.read_line(&mut line).is_ok_and(|n| n > 2) {
+            if let Some(range) = line.to_ascii_lowercase().strip_prefix("range: bytes=") {
+                let (first, last) = range.trim().split_once('-').unwrap();
+                start = first.parse().unwrap();
+                end = last.parse::<usize>().map_or(data.len(), |last| last + 1);
+            }
+            line.clear();
+        }
+        let mut stream = stream;
+        let len = data.len();
+        let _ = if start >= len {
+            write!(stream, "HTTP/1.1 416 Range Not Satisfiable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+        } else {
+            write!(
+                stream,
+                "HTTP/1.1 206 Partial Content\r\nContent-Range: bytes {start}-{}/{len}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                end - 1,
+                end - start
+            )
+            .and_then(|()| stream.write_all(&data[start..end]))
+        };
+    }
+
+    #[test]
+    fn remote() {
+        init();
+        let data = std::fs::read("tests/resources/snikmeta.hdt").unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/snikmeta.hdt", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&requests);
+        let served = data.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                counter.fetch_add(1, Ordering::SeqCst);
+                respond(&stream, &served);
+            }
+        });
+
+        let hdt = Hdt::new(data.as_slice()).unwrap();
+        let lazy = LazyHdt::open_url(&url).unwrap();
+        assert_eq!(hdt.dict.subjects.num_strings(), lazy.subjects.num_strings());
+        assert_eq!(hdt.dict.objects.num_strings() + hdt.dict.shared.num_strings(), lazy.num_objects());
+        let opened = requests.load(Ordering::SeqCst);
+        let s = "http://www.snik.eu/ontology/meta";
+        let expected: Vec<_> = hdt.triples_with_pattern(Some(s), None, None).collect();
+        assert!(!expected.is_empty());
+        assert_eq!(expected, lazy.triples_with_subject(s).unwrap());
+        let shared = hdt.dict.shared.num_strings();
+        let block_size = hdt.dict.objects.block_size();
+        let mut blocks: Vec<_> = hdt
+            .triples_with_pattern(Some(s), None, None)
+            .map(|(_, _, o)| hdt.dict.string_to_id(&o, &IdKind::Object))
+            .filter(|&id| id > shared)
+            .map(|id| (id - shared - 1) / block_size)
+            .collect();
+        blocks.sort_unstable();
+        blocks.dedup();
+        // one request for the bitmaps, one each for the entries of the Y and Z level and one per block of objects
+        assert_eq!(opened + 3 + blocks.len(), requests.load(Ordering::SeqCst));
+        // the bitmaps are only read once
+        assert_eq!(expected, lazy.triples_with_subject(s).unwrap());
+        assert_eq!(opened + 5 + 2 * blocks.len(), requests.load(Ordering::SeqCst));
+        assert_eq!(hdt.triples.len(), lazy.num_triples().unwrap());
+
+        let mut reader = HttpSource::new(&url).reader().unwrap();
+        assert_eq!(data.len() as u64, reader.seek(SeekFrom::End(0)).unwrap());
+        reader.seek(SeekFrom::Start(10)).unwrap();
+        let mut buf = [0; 20];
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(&data[10..30], buf);
+        assert_eq!(hdt.triples.len(), lazy.load().unwrap().triples.len());
+
+        // keep the packed strings of the object section on the server
+        let mut hdt = hdt;
+        let packed = hdt.dict.objects.packed_data().unwrap().to_vec();
+        let start = data.windows(packed.len()).position(|w| w == packed).unwrap() as u64;
+        let store = HttpBytes::new(HttpSource::new(&url), start..start + packed.len() as u64);
+        assert_eq!(0, store.heap_len());
+        let before = requests.load(Ordering::SeqCst);
+        // a single request on the first access, which also checks the checksum
+        hdt.dict.objects.set_packed_data(store).unwrap();
+        assert_eq!(before, requests.load(Ordering::SeqCst));
+        let objects: Vec<_> = hdt.triples_with_pattern(Some(s), None, None).collect();
+        assert_eq!(expected, objects);
+        assert!(hdt.dict.objects.verify_crc());
+        assert_eq!(before + 1, requests.load(Ordering::SeqCst));
+        let short = HttpBytes::new(HttpSource::new(&url), 0..10);
+        assert!(hdt.dict.objects.set_packed_data(short).is_err());
+        // connection errors surface when the strings are accessed
+        let closed = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let missing =
+            HttpBytes::new(HttpSource::new(format!("http://{closed}/")), start..start + packed.len() as u64);
+        hdt.dict.objects.set_packed_data(missing).unwrap();
+        assert!(matches!(hdt.dict.objects.extract(1), Err(ExtractError::Unavailable { id: 1, .. })));
+    }
+}
@@ -0,0 +1,188 @@
+//! Summary of the classes in a graph and the properties used by their instances.
+use crate::containers::rdf::nt_term;
+use crate::four_sect_dict::{DictErr, IdKind};
+use crate::hdt::Hdt;
+use crate::triples::{Id, PredicateIter, SubjectIter};
+use log::error;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+const VOID: &str = "http://rdfs.org/ns/void#";
+const XSD_INTEGER: &str = "http://www.w3.org/2001/XMLSchema#integer";
+const SKOS_EXAMPLE: &str = "http://www.w3.org/2004/02/skos/core#example";
+
+/// Per-class summary of the properties used by the instances of each `rdf:type` class.
+#[derive(Debug, Clone, Default)]
+pub struct SchemaSummary {
+    /// Classes sorted by descending number of instances.
+    pub classes: Vec<ClassSummary>,
+}
+
+/// Usage of properties by the instances of a class.
+#[derive(Debug, Clone)]
+pub struct ClassSummary {
+    /// IRI of the class.
+    pub class: String,
+    /// Number of subjects with the class as `rdf:type`.
+    pub instances: usize,
+    /// Properties used by the instances, sorted by descending number of instances using them.
+    pub properties: Vec<PropertySummary>,
+}
+
+/// Usage of a property by the instances of a class.
+#[derive(Debug, Clone)]
+pub struct PropertySummary {
+    /// IRI of the property.
+    pub property: String,
+    /// Number of triples with an instance of the class as subject and the property as predicate.
+    pub triples: usize,
+    /// Number of instances of the class that use the property at least once.
+    pub instances: usize,
+    /// Distinct example values in the HDT dictionary string format.
+    pub examples: Vec<String>,
+}
+
+impl SchemaSummary {
+    /// Derive the schema summary of the given graph, collecting up to `max_examples` example values per property.
+    /// All counting is done on IDs, only classes, properties and examples are translated into strings.
+    /// # Example
+    /// ```
+    /// fn print_schema(hdt: &hdt::Hdt) {
+    ///     for c in hdt::schema::SchemaSummary::new(hdt, 3).classes {
+    ///         println!("{} ({} instances)", c.class, c.instances);
+    ///         for p in c.properties {
+    ///             println!("  {} used by {} instances, e.g. {:?}", p.property, p.instances, p.examples);
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn new(hdt: &Hdt, max_examples: usize) -> Self {
+        let type_id = hdt.dict.string_to_id(RDF_TYPE, &IdKind::Predicate);
+        if type_id == 0 {
+            return Self::default();
+        }
+        // subjects are returned in ascending order
+        let mut instances = BTreeMap::<Id, Vec<Id>>::new();
+        for t in PredicateIter::new(&hdt.triples, type_id) {
+            instances.entry(t.object_id).or_default().push(t.subject_id);
+        }
+        let mut classes: Vec<ClassSummary> = instances
+            .into_iter()
+            .filter_map(|(class_id, subjects)| {
+                let class = hdt
+                    .dict
+                    .id_to_string(class_id, &IdKind::Object)
+                    .map_err(|e| error!("Error translating class: {e}"))
+                    .ok()?;
+                let properties = Self::properties(hdt, &subjects, max_examples);
+                Some(ClassSummary { class, instances: subjects.len(), properties })
+            })
+            .collect();
+        classes.sort_by_key(|c| Reverse(c.instances));
+        SchemaSummary { classes }
+    }
+
+    /// Summarize the properties used by the given subjects.
+    fn properties(hdt: &Hdt, subjects: &[Id], max_examples: usize) -> Vec<PropertySummary> {
+        // property ID -> (triples, instances, example object IDs)
+        let mut usage = BTreeMap::<Id, (usize, usize, Vec<Id>)>::new();
+        for &s in subjects {
+            let mut last_p = 0;
+            // triples of a subject are sorted by predicate
+            for t in SubjectIter::with_s(&hdt.triples, s) {
+                let (triples, instances, examples) = usage.entry(t.predicate_id).or_default();
+                *triples += 1;
+                if t.predicate_id != last_p {
+                    *instances += 1;
+                    last_p = t.predicate_id;
+                }
+                if examples.len() < max_examples && !examples.contains(&t.object_id) {
+                    examples.push(t.object_id);
+                }
+            }
+        }
+        let mut properties: Vec<PropertySummary> = usage
+            .into_iter()
+            .filter_map(|(pid, (triples, instances, examples))| {
+                let translate = || -> Result<PropertySummary, DictErr> {
+                    Ok(PropertySummary {
+                        property: hdt.dict.id_to_string(pid, &IdKind::Predicate)?,
+                        triples,
+                        instances,
+                        examples: examples
+                            .into_iter()
+                            .map(|oid| hdt.dict.id_to_string(oid, &IdKind::Object))
+                            .collect::<Result<_, _>>()?,
+                    })
+                };
+                translate().map_err(|e| error!("Error translating property summary: {e}")).ok()
+            })
+            .collect();
+        properties.sort_by_key(|p| Reverse(p.instances));
+        properties
+    }
+
+    /// Write the summary as N-Triples using VoID class and property partitions of the given dataset IRI.
+    /// Example values are attached to the property partitions with `skos:example`.
+    pub fn write_void<W: Write>(&self, w: &mut W, dataset: &str) -> io::Result<()> {
+        let int = |n: usize| format!("\"{n}\"^^<{XSD_INTEGER}>");
+        for (i, c) in self.classes.iter().enumerate() {
+            let cp = format!("_:class{i}");
+            writeln!(w, "<{dataset}> <{VOID}classPartition> {cp} .")?;
+            writeln!(w, "{cp} <{VOID}class> {} .", nt_term(&c.class))?;
+            writeln!(w, "{cp} <{VOID}entities> {} .", int(c.instances))?;
+            for (j, p) in c.properties.iter().enumerate() {
+                let pp = format!("_:class{i}property{j}");
+                writeln!(w, "{cp} <{VOID}propertyPartition> {pp} .")?;
+                writeln!(w, "{pp} <{VOID}property> {} .", nt_term(&p.property))?;
+                writeln!(w, "{pp} <{VOID}triples> {} .", int(p.triples))?;
+                writeln!(w, "{pp} <{VOID}distinctSubjects> {} .", int(p.instances))?;
+                for example in &p.examples {
+                    writeln!(w, "{pp} <{SKOS_EXAMPLE}> {} .", nt_term(example))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::init;
+    use pretty_assertions::assert_eq;
+    use std::fs::File;
+
+    #[test]
+    fn schema_summary() {
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let hdt = Hdt::new(std::io::BufReader::new(file)).unwrap();
+        let summary = SchemaSummary::new(&hdt, 2);
+        assert!(!summary.classes.is_empty());
+        assert!(summary.classes.windows(2).all(|w| w[0].instances >= w[1].instances));
+        for c in &summary.classes {
+            let instances: Vec<_> = hdt.triples_with_pattern(None, Some(RDF_TYPE), Some(&c.class)).collect();
+            assert_eq!(instances.len(), c.instances, "instances of {}", c.class);
+            let type_usage =
+                c.properties.iter().find(|p| p.property == RDF_TYPE).expect("rdf:type not summarized");
+            assert_eq!(c.instances, type_usage.instances);
+            for p in &c.properties {
+                assert!(p.instances <= c.instances && p.instances <= p.triples);
+                assert!(!p.examples.is_empty() && p.examples.len() <= 2);
+                let triples: usize = instances
+                    .iter()
+                    .map(|(s, _, _)| hdt.triples_with_pattern(Some(s), Some(&p.property), None).count())
+                    .sum();
+                assert_eq!(triples, p.triples, "triples of {} {}", c.class, p.property);
+            }
+        }
+        let mut void = Vec::new();
+        summary.write_void(&mut void, "http://www.snik.eu/ontology/meta").unwrap();
+        let void = String::from_utf8(void).unwrap();
+        assert!(void.lines().all(|l| l.ends_with(" .")));
+        assert_eq!(summary.classes.len(), void.lines().filter(|l| l.contains("classPartition")).count());
+    }
+}
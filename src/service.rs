@@ -0,0 +1,161 @@
+//! Dataset statistics for the service description of an endpoint backed by an HDT file, such as a Triple Pattern Fragments or SPARQL server.
+//! Federation engines read the VoID property partitions to estimate the cardinalities of triple patterns before sending any query.
+use crate::containers::rdf::nt_term;
+use crate::four_sect_dict::IdKind;
+use crate::hdt::Hdt;
+use crate::triples::TripleId;
+use log::error;
+use std::io::{self, Write};
+
+const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+const SD: &str = "http://www.w3.org/ns/sparql-service-description#";
+const VOID: &str = "http://rdfs.org/ns/void#";
+const XSD_INTEGER: &str = "http://www.w3.org/2001/XMLSchema#integer";
+
+/// Numbers of triples, distinct subjects and distinct objects of the whole dataset and of each predicate.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DatasetStatistics {
+    /// Number of triples.
+    pub triples: usize,
+    /// Number of distinct subjects.
+    pub distinct_subjects: usize,
+    /// Number of distinct objects.
+    pub distinct_objects: usize,
+    /// Statistics of each predicate in the order of the predicate IDs.
+    pub properties: Vec<PropertyStatistics>,
+}
+
+/// Numbers of triples, distinct subjects and distinct objects of the triples with a given predicate.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PropertyStatistics {
+    /// IRI of the predicate.
+    pub property: String,
+    /// Number of triples with the predicate, the cardinality of the pattern (?s, p, ?o).
+    pub triples: usize,
+    /// Number of distinct subjects of the triples with the predicate.
+    pub distinct_subjects: usize,
+    /// Number of distinct objects of the triples with the predicate.
+    pub distinct_objects: usize,
+}
+
+impl DatasetStatistics {
+    /// Count the statistics of the given HDT on IDs, with rank queries where possible.
+    /// The triples per predicate are counted in a single pass over the subject-predicate pairs.
+    pub fn new(hdt: &Hdt) -> Self {
+        let triples = &hdt.triples;
+        let mut counts = vec![0; hdt.dict.predicates.num_strings() + 1];
+        // each subject-predicate pair in the Y level has the objects between two one bits of the Z level
+        let mut start_z = 0;
+        for pos_y in 0..triples.wavelet_y.len() {
+            let end_z = triples.adjlist_z.find(pos_y + 1);
+            counts[triples.wavelet_y.access(pos_y).unwrap()] += end_z - start_z;
+            start_z = end_z;
+        }
+        let properties = counts
+            .into_iter()
+            .enumerate()
+            .skip(1)
+            .filter_map(|(p, count)| {
+                let property = hdt
+                    .dict
+                    .id_to_string(p, &IdKind::Predicate)
+                    .map_err(|e| error!("Error translating predicate {p}: {e}"))
+                    .ok()?;
+                let pat = TripleId::new(0, p, 0);
+                Some(PropertyStatistics {
+                    property,
+                    triples: count,
+                    distinct_subjects: triples.count_distinct_subjects(&pat),
+                    distinct_objects: triples.count_distinct_objects(&pat),
+                })
+            })
+            .collect();
+        let all = TripleId::new(0, 0, 0);
+        DatasetStatistics {
+            triples: triples.len(),
+            distinct_subjects: triples.count_distinct_subjects(&all),
+            distinct_objects: triples.count_distinct_objects(&all),
+            properties,
+        }
+    }
+
+    /// Write the statistics as N-Triples using VoID with one property partition per predicate of the given dataset IRI.
+    /// Class partitions can be added with [`SchemaSummary::write_void`](crate::schema::SchemaSummary::write_void) for the same dataset IRI.
+    pub fn write_void<W: Write>(&self, w: &mut W, dataset: &str) -> io::Result<()> {
+        let int = |n: usize| format!("\"{n}\"^^<{XSD_INTEGER}>");
+        writeln!(w, "<{dataset}> <{RDF_TYPE}> <{VOID}Dataset> .")?;
+        writeln!(w, "<{dataset}> <{VOID}triples> {} .", int(self.triples))?;
+        writeln!(w, "<{dataset}> <{VOID}distinctSubjects> {} .", int(self.distinct_subjects))?;
+        writeln!(w, "<{dataset}> <{VOID}distinctObjects> {} .", int(self.distinct_objects))?;
+        writeln!(w, "<{dataset}> <{VOID}properties> {} .", int(self.properties.len()))?;
+        for (i, p) in self.properties.iter().enumerate() {
+            let pp = format!("_:property{i}");
+            writeln!(w, "<{dataset}> <{VOID}propertyPartition> {pp} .")?;
+            writeln!(w, "{pp} <{VOID}property> {} .", nt_term(&p.property))?;
+            writeln!(w, "{pp} <{VOID}triples> {} .", int(p.triples))?;
+            writeln!(w, "{pp} <{VOID}distinctSubjects> {} .", int(p.distinct_subjects))?;
+            writeln!(w, "{pp} <{VOID}distinctObjects> {} .", int(p.distinct_objects))?;
+        }
+        Ok(())
+    }
+
+    /// Write a SPARQL service description of the given endpoint IRI, whose default dataset is described with [`Self::write_void`].
+    /// # Example
+    /// ```
+    /// fn describe(hdt: &hdt::Hdt) -> std::io::Result<Vec<u8>> {
+    ///     let mut out = Vec::new();
+    ///     let stats = hdt::service::DatasetStatistics::new(hdt);
+    ///     stats.write_service_description(&mut out, "http://example.org/sparql", "http://example.org/dataset")?;
+    ///     Ok(out)
+    /// }
+    /// ```
+    pub fn write_service_description<W: Write>(&self, w: &mut W, endpoint: &str, dataset: &str) -> io::Result<()> {
+        writeln!(w, "<{endpoint}> <{RDF_TYPE}> <{SD}Service> .")?;
+        writeln!(w, "<{endpoint}> <{SD}endpoint> <{endpoint}> .")?;
+        writeln!(w, "<{endpoint}> <{SD}defaultDataset> <{dataset}> .")?;
+        writeln!(w, "<{dataset}> <{RDF_TYPE}> <{SD}Dataset> .")?;
+        writeln!(w, "<{dataset}> <{VOID}sparqlEndpoint> <{endpoint}> .")?;
+        self.write_void(w, dataset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::init;
+    use pretty_assertions::assert_eq;
+    use std::fs::File;
+
+    #[test]
+    fn statistics() {
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let hdt = Hdt::new(std::io::BufReader::new(file)).unwrap();
+        let stats = DatasetStatistics::new(&hdt);
+        assert_eq!(328, stats.triples);
+        assert_eq!(hdt.dict.predicates.num_strings(), stats.properties.len());
+        assert_eq!(stats.triples, stats.properties.iter().map(|p| p.triples).sum::<usize>());
+        for p in &stats.properties {
+            let triples: Vec<_> = hdt.triples_with_pattern(None, Some(&p.property), None).collect();
+            assert_eq!(triples.len(), p.triples, "{}", p.property);
+            let mut subjects: Vec<_> = triples.iter().map(|(s, _, _)| s).collect();
+            subjects.sort_unstable();
+            subjects.dedup();
+            assert_eq!(subjects.len(), p.distinct_subjects, "{}", p.property);
+            let mut objects: Vec<_> = triples.iter().map(|(_, _, o)| o).collect();
+            objects.sort_unstable();
+            objects.dedup();
+            assert_eq!(objects.len(), p.distinct_objects, "{}", p.property);
+        }
+        let mut out = Vec::new();
+        stats
+            .write_service_description(&mut out, "http://example.org/sparql", "http://example.org/dataset")
+            .unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.lines().all(|l| l.ends_with(" .")));
+        assert_eq!(stats.properties.len(), out.lines().filter(|l| l.contains("propertyPartition")).count());
+        assert!(out.contains(&format!("<http://example.org/dataset> <{VOID}triples> \"328\"^^<{XSD_INTEGER}> .")));
+    }
+}
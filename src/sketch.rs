@@ -0,0 +1,305 @@
+//! Compact signatures of the triples or terms of an HDT file, to estimate the similarity and overlap of datasets without comparing them triple by triple.
+//! Sketches hash the strings of terms instead of their IDs, so sketches of different files and of data that is not in HDT are comparable.
+//! Use [`MinHash`] to estimate how much of one dataset is contained in another and [`HyperLogLog`] to estimate distinct counts across several files.
+use crate::four_sect_dict::IdKind;
+use crate::hdt::Hdt;
+use crate::triples::TripleId;
+use std::collections::BinaryHeap;
+
+/// Items of an HDT file that a sketch summarizes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Items {
+    /// Triples, which are equal if their subjects, predicates and objects are equal.
+    Triples,
+    /// Distinct subjects.
+    Subjects,
+    /// Distinct predicates.
+    Predicates,
+    /// Distinct objects.
+    Objects,
+}
+
+/// Final step of SplitMix64, which spreads small differences of the input over all bits.
+const fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Stable 64 bit hash of a term in the HDT dictionary string format, which does not change between versions and platforms.
+pub fn hash_term(term: &str) -> u64 {
+    // FNV-1a
+    let fnv = term.bytes().fold(0xCBF2_9CE4_8422_2325, |h: u64, b| (h ^ b as u64).wrapping_mul(0x0100_0000_01B3));
+    mix(fnv)
+}
+
+/// Stable 64 bit hash of a triple from the hashes of its terms, see [`hash_term`].
+pub const fn hash_triple(s: u64, p: u64, o: u64) -> u64 {
+    mix(mix(s ^ p.rotate_left(21)) ^ o.rotate_left(42))
+}
+
+/// Bottom-k MinHash sketch, which keeps the k smallest hashes of a set of items.
+/// The larger k, the more accurate the estimates, with a standard error of about 1/√k.
+/// Sketches are only comparable if their items are hashed with [`hash_term`] and [`hash_triple`].
+/// # Example
+/// ```
+/// use hdt::sketch::Items;
+/// fn contained(a: &hdt::Hdt, b: &hdt::Hdt) -> f64 {
+///     a.minhash(Items::Triples, 1024).containment(&b.minhash(Items::Triples, 1024))
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MinHash {
+    /// Maximum number of hashes.
+    pub k: usize,
+    /// Number of inserted items, which are assumed to be distinct.
+    pub len: usize,
+    /// The smallest hashes of the items in ascending order, at most k.
+    pub hashes: Vec<u64>,
+}
+
+impl MinHash {
+    /// Sketch of the given hashes of distinct items that keeps the k smallest ones.
+    pub fn new(k: usize, hashes: impl IntoIterator<Item = u64>) -> Self {
+        let mut heap = BinaryHeap::with_capacity(k + 1);
+        let mut len = 0;
+        for h in hashes {
+            len += 1;
+            if heap.len() < k {
+                heap.push(h);
+            } else if heap.peek().is_some_and(|&max| h < max) {
+                heap.pop();
+                heap.push(h);
+            }
+        }
+        let mut hashes = heap.into_sorted_vec();
+        hashes.dedup();
+        MinHash { k, len, hashes }
+    }
+
+    /// Estimated Jaccard similarity, which is the number of items in both sets divided by the number of items in either set.
+    /// Two empty sets are equal, so their similarity is 1.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn jaccard(&self, other: &MinHash) -> f64 {
+        // the k smallest hashes of the union are a uniform sample of the union
+        let mut union: Vec<u64> = self.hashes.iter().chain(&other.hashes).copied().collect();
+        union.sort_unstable();
+        union.dedup();
+        union.truncate(self.k.min(other.k));
+        if union.is_empty() {
+            return 1.0;
+        }
+        let contains = |sketch: &MinHash, h: &u64| sketch.hashes.binary_search(h).is_ok();
+        let both = union.iter().filter(|h| contains(self, h) && contains(other, h)).count();
+        both as f64 / union.len() as f64
+    }
+
+    /// Estimated fraction of the items of this set that are also in the other set, such as the triples of a dataset that another dataset contains.
+    /// Derived from the Jaccard similarity and the exact numbers of items, the empty set is contained in every set.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn containment(&self, other: &MinHash) -> f64 {
+        if self.len == 0 {
+            return 1.0;
+        }
+        self.intersection(other) / self.len as f64
+    }
+
+    /// Estimated number of items in both sets.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn intersection(&self, other: &MinHash) -> f64 {
+        let j = self.jaccard(other);
+        let total = (self.len + other.len) as f64;
+        (j * total / (1.0 + j)).min(self.len.min(other.len) as f64)
+    }
+}
+
+/// HyperLogLog sketch, which estimates the number of distinct items with 2^precision registers of one byte each.
+/// The standard error is about 1.04/√(2^precision), for example 1.6% with a precision of 12.
+/// Unlike [`MinHash`], sketches of several datasets can be merged to estimate the number of distinct items in all of them.
+/// # Example
+/// ```
+/// use hdt::sketch::Items;
+/// fn distinct_subjects(files: &[hdt::Hdt]) -> f64 {
+///     let mut sketches = files.iter().map(|hdt| hdt.hyperloglog(Items::Subjects, 12));
+///     let Some(mut all) = sketches.next() else { return 0.0 };
+///     sketches.for_each(|sketch| all.merge(&sketch));
+///     all.estimate()
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HyperLogLog {
+    precision: u8,
+    registers: Vec<u8>,
+}
+
+impl HyperLogLog {
+    /// Empty sketch with 2^precision registers.
+    /// Panics if the precision is not between 4 and 18.
+    pub fn new(precision: u8) -> Self {
+        assert!((4..=18).contains(&precision), "HyperLogLog precision {precision} not between 4 and 18");
+        HyperLogLog { precision, registers: vec![0; 1 << precision] }
+    }
+
+    /// Number of bits of a hash that select the register.
+    pub const fn precision(&self) -> u8 {
+        self.precision
+    }
+
+    /// Add an item with the given hash, adding the same hash again has no effect.
+    pub fn insert(&mut self, hash: u64) {
+        let p = self.precision;
+        let register = (hash >> (64 - p)) as usize;
+        // position of the first one bit in the remaining bits
+        let rank = ((hash << p).leading_zeros() + 1).min(65 - p as u32) as u8;
+        self.registers[register] = self.registers[register].max(rank);
+    }
+
+    /// Add the items of the other sketch, so that this sketch summarizes the union of both sets.
+    /// Panics if the precisions differ.
+    pub fn merge(&mut self, other: &HyperLogLog) {
+        assert_eq!(self.precision, other.precision, "cannot merge HyperLogLog sketches of different precisions");
+        for (r, o) in self.registers.iter_mut().zip(&other.registers) {
+            *r = (*r).max(*o);
+        }
+    }
+
+    /// Estimated number of distinct items, with linear counting for small sets.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn estimate(&self) -> f64 {
+        let m = self.registers.len() as f64;
+        let alpha = match self.registers.len() {
+            16 => 0.673,
+            32 => 0.697,
+            64 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / m),
+        };
+        let (sum, zeros) = self
+            .registers
+            .iter()
+            .fold((0.0, 0), |(sum, zeros), &r| (sum + (-(r as f64)).exp2(), zeros + usize::from(r == 0)));
+        let raw = alpha * m * m / sum;
+        if raw <= 2.5 * m && zeros > 0 {
+            m * (m / zeros as f64).ln()
+        } else {
+            raw
+        }
+    }
+}
+
+impl Extend<u64> for HyperLogLog {
+    fn extend<T: IntoIterator<Item = u64>>(&mut self, hashes: T) {
+        hashes.into_iter().for_each(|h| self.insert(h));
+    }
+}
+
+impl Hdt {
+    /// Hashes of the terms of the given kind indexed by their IDs, 0 at index 0 and for terms that cannot be extracted.
+    fn term_hashes(&self, kind: &IdKind) -> Vec<u64> {
+        let len = match kind {
+            IdKind::Subject => self.dict.shared.num_strings() + self.dict.subjects.num_strings(),
+            IdKind::Predicate => self.dict.predicates.num_strings(),
+            IdKind::Object => self.dict.shared.num_strings() + self.dict.objects.num_strings(),
+        };
+        let mut hashes = vec![0; len + 1];
+        for (id, term) in self.dict.terms(kind) {
+            hashes[id] = hash_term(&term);
+        }
+        hashes
+    }
+
+    /// Hashes of the given items, one for each triple or term.
+    /// For triples, the hashes of all terms are computed first, which needs 8 bytes per term.
+    fn item_hashes(&self, items: Items) -> Box<dyn Iterator<Item = u64> + '_> {
+        let kind = match items {
+            Items::Triples => {
+                let [s, p, o] =
+                    [&IdKind::Subject, &IdKind::Predicate, &IdKind::Object].map(|k| self.term_hashes(k));
+                return Box::new(
+                    self.triples
+                        .into_iter()
+                        .map(move |t: TripleId| hash_triple(s[t.subject_id], p[t.predicate_id], o[t.object_id])),
+                );
+            }
+            Items::Subjects => &IdKind::Subject,
+            Items::Predicates => &IdKind::Predicate,
+            Items::Objects => &IdKind::Object,
+        };
+        Box::new(self.dict.terms(kind).map(|(_, term)| hash_term(&term)))
+    }
+
+    /// [`MinHash`] sketch of the given items that keeps the k smallest hashes.
+    pub fn minhash(&self, items: Items, k: usize) -> MinHash {
+        MinHash::new(k, self.item_hashes(items))
+    }
+
+    /// [`HyperLogLog`] sketch of the given items with 2^precision registers.
+    pub fn hyperloglog(&self, items: Items, precision: u8) -> HyperLogLog {
+        let mut sketch = HyperLogLog::new(precision);
+        sketch.extend(self.item_hashes(items));
+        sketch
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::HdtBuilder;
+    use crate::tests::init;
+    use pretty_assertions::assert_eq;
+    use std::fs::File;
+
+    #[test]
+    fn minhash() {
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let hdt = Hdt::new(std::io::BufReader::new(file)).unwrap();
+        let mut builder = HdtBuilder::new();
+        for (s, p, o) in hdt.triples().step_by(2) {
+            builder.add_triple(&s, &p, &o);
+        }
+        let half = builder.build().unwrap();
+        assert_eq!(164, half.triples.len());
+
+        // k larger than the number of triples keeps all hashes, so the estimates are exact
+        let full = hdt.minhash(Items::Triples, 1024);
+        assert_eq!(hdt.triples.len(), full.hashes.len());
+        let sub = half.minhash(Items::Triples, 1024);
+        assert!((full.jaccard(&full) - 1.0).abs() < 1e-9);
+        assert!((sub.containment(&full) - 1.0).abs() < 1e-9);
+        assert!((full.containment(&sub) - 0.5).abs() < 0.01);
+        assert!((full.intersection(&sub) - 164.0).abs() < 1e-6);
+
+        let (full, sub) = (hdt.minhash(Items::Triples, 64), half.minhash(Items::Triples, 64));
+        assert_eq!(64, full.hashes.len());
+        assert!((full.jaccard(&sub) - 0.5).abs() < 0.2, "{}", full.jaccard(&sub));
+        assert!(sub.containment(&full) > 0.7, "{}", sub.containment(&full));
+        // the predicates of the half are a subset of those of the full dataset
+        let predicates = half.minhash(Items::Predicates, 1024).containment(&hdt.minhash(Items::Predicates, 1024));
+        assert!((predicates - 1.0).abs() < 1e-9);
+        let empty = MinHash::new(64, []);
+        assert!((empty.containment(&full) - 1.0).abs() < 1e-9);
+        assert!(full.containment(&empty).abs() < 1e-9);
+    }
+
+    #[test]
+    fn hyperloglog() {
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let hdt = Hdt::new(std::io::BufReader::new(file)).unwrap();
+        let subjects = hdt.dict.shared.num_strings() + hdt.dict.subjects.num_strings();
+        let objects = hdt.dict.shared.num_strings() + hdt.dict.objects.num_strings();
+        for (items, exact) in [(Items::Subjects, subjects), (Items::Objects, objects)] {
+            let exact = f64::from(u32::try_from(exact).unwrap());
+            let sketch = hdt.hyperloglog(items, 10);
+            assert!((sketch.estimate() - exact).abs() < exact * 0.1, "{items:?}: {} {exact}", sketch.estimate());
+            let mut merged = sketch.clone();
+            merged.merge(&sketch);
+            assert_eq!(sketch, merged);
+        }
+        let triples = hdt.hyperloglog(Items::Triples, 12).estimate();
+        assert!((triples - 328.0).abs() < 328.0 * 0.1, "{triples}");
+        assert!(HyperLogLog::new(4).estimate().abs() < 1e-9);
+        assert_eq!(hash_term("http://example.org/a"), hash_term("http://example.org/a"));
+        assert_ne!(hash_triple(1, 2, 3), hash_triple(3, 2, 1));
+    }
+}
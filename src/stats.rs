@@ -0,0 +1,225 @@
+//! Profile of a dataset with counts, degree distributions and the shares of IRIs, literals and blank nodes, see [`GraphStatistics`].
+//! Everything is computed on the ID structures with rank and select queries, so no triple and almost no term needs to be decoded.
+use crate::hdt::Hdt;
+use crate::service::DatasetStatistics;
+use crate::triples::Id;
+use crate::DictSectPFC;
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+/// Numbers of IRIs, literals and blank nodes.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TermKinds {
+    /// Number of IRIs.
+    pub iris: usize,
+    /// Number of literals.
+    pub literals: usize,
+    /// Number of blank nodes.
+    pub blank_nodes: usize,
+}
+
+impl TermKinds {
+    /// Sum of all kinds.
+    pub const fn total(&self) -> usize {
+        self.iris + self.literals + self.blank_nodes
+    }
+
+    /// Share of the literals in the total, 0 if there are none.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn literal_ratio(&self) -> f64 {
+        if self.total() == 0 {
+            0.0
+        } else {
+            self.literals as f64 / self.total() as f64
+        }
+    }
+
+    /// Share of the IRIs in the total, 0 if there are none.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn iri_ratio(&self) -> f64 {
+        if self.total() == 0 {
+            0.0
+        } else {
+            self.iris as f64 / self.total() as f64
+        }
+    }
+}
+
+/// Numbers of terms by their number of triples.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DegreeDistribution {
+    /// Largest degree, 0 if there are no terms.
+    pub max: usize,
+    /// Pairs of a degree and the number of terms with that degree in ascending order of the degree.
+    pub histogram: Vec<(usize, usize)>,
+}
+
+impl DegreeDistribution {
+    fn new(degrees: impl Iterator<Item = usize>) -> Self {
+        let mut histogram = BTreeMap::new();
+        for degree in degrees {
+            *histogram.entry(degree).or_insert(0) += 1;
+        }
+        let max = histogram.last_key_value().map_or(0, |(&degree, _)| degree);
+        DegreeDistribution { max, histogram: histogram.into_iter().collect() }
+    }
+
+    /// Number of terms.
+    pub fn terms(&self) -> usize {
+        self.histogram.iter().map(|(_, terms)| terms).sum()
+    }
+
+    /// Average degree, 0 if there are no terms.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn mean(&self) -> f64 {
+        let terms = self.terms();
+        if terms == 0 {
+            return 0.0;
+        }
+        self.histogram.iter().map(|(degree, terms)| degree * terms).sum::<usize>() as f64 / terms as f64
+    }
+}
+
+/// Profile of a dataset for analyzing its structure, for example before choosing indexes or query strategies.
+/// Serializable with the `serde` feature.
+/// # Example
+/// ```
+/// fn profile(hdt: &hdt::Hdt) {
+///     let stats = hdt::stats::GraphStatistics::new(hdt);
+///     println!("{} triples, {:.0}% with literal objects", stats.dataset.triples, stats.object_triples.literal_ratio() * 100.0);
+///     println!("mean out degree {:.1}, max in degree {}", stats.out_degrees.mean(), stats.in_degrees.max);
+/// }
+/// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GraphStatistics {
+    /// Numbers of triples, distinct subjects and distinct objects of the whole dataset and of each predicate.
+    pub dataset: DatasetStatistics,
+    /// Distinct subjects by kind, which are never literals.
+    pub subjects: TermKinds,
+    /// Distinct objects by kind.
+    pub objects: TermKinds,
+    /// Triples by the kind of their object.
+    pub object_triples: TermKinds,
+    /// Distribution of the numbers of triples of each subject.
+    pub out_degrees: DegreeDistribution,
+    /// Distribution of the numbers of triples of each object.
+    pub in_degrees: DegreeDistribution,
+}
+
+/// ID ranges of the literals and blank nodes of a dictionary section.
+struct KindRanges {
+    literals: Range<Id>,
+    blank_nodes: Range<Id>,
+}
+
+impl KindRanges {
+    /// Literals start with a quotation mark and blank nodes with `_:` in the HDT dictionary string format.
+    /// As the sections are sorted, both are contiguous ranges, which are shifted so that the first string has the given ID.
+    fn new(sect: &DictSectPFC, first: Id) -> Self {
+        let shift = |r: Range<Id>| r.start + first - 1..r.end + first - 1;
+        KindRanges { literals: shift(sect.prefix_range("\"")), blank_nodes: shift(sect.prefix_range("_:")) }
+    }
+}
+
+impl TermKinds {
+    /// Count the given number of occurrences of the term with the given ID in one of the given sections.
+    fn add(&mut self, sections: &[KindRanges], id: Id, n: usize) {
+        if sections.iter().any(|s| s.literals.contains(&id)) {
+            self.literals += n;
+        } else if sections.iter().any(|s| s.blank_nodes.contains(&id)) {
+            self.blank_nodes += n;
+        } else {
+            self.iris += n;
+        }
+    }
+}
+
+impl GraphStatistics {
+    /// Compute the statistics of the given HDT, see [`DatasetStatistics::new`] for those of the predicates.
+    /// The degrees are counted with a select query per subject and object.
+    pub fn new(hdt: &Hdt) -> Self {
+        let (dict, triples) = (&hdt.dict, &hdt.triples);
+        let shared = dict.shared.num_strings();
+        let subject_sections = [KindRanges::new(&dict.shared, 1), KindRanges::new(&dict.subjects, shared + 1)];
+        let object_sections = [KindRanges::new(&dict.shared, 1), KindRanges::new(&dict.objects, shared + 1)];
+
+        let mut subjects = TermKinds::default();
+        let num_subjects = triples.bitmap_y.rank(triples.bitmap_y.len());
+        let out_degrees = DegreeDistribution::new((1..=num_subjects).map(|s| {
+            subjects.add(&subject_sections, s, 1);
+            triples.subject_degree(s)
+        }));
+
+        let (mut objects, mut object_triples) = (TermKinds::default(), TermKinds::default());
+        let num_objects = triples.op_index.bitmap.rank(triples.op_index.bitmap.len());
+        let in_degrees = DegreeDistribution::new((1..=num_objects).map(|o| {
+            let degree = triples.object_degree(o);
+            objects.add(&object_sections, o, 1);
+            object_triples.add(&object_sections, o, degree);
+            degree
+        }));
+
+        GraphStatistics {
+            dataset: DatasetStatistics::new(hdt),
+            subjects,
+            objects,
+            object_triples,
+            out_degrees,
+            in_degrees,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::init;
+    use pretty_assertions::assert_eq;
+    use std::collections::HashMap;
+    use std::fs::File;
+
+    #[test]
+    fn statistics() {
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let hdt = Hdt::new(std::io::BufReader::new(file)).unwrap();
+        let stats = GraphStatistics::new(&hdt);
+        assert_eq!(DatasetStatistics::new(&hdt), stats.dataset);
+        assert_eq!(stats.dataset.distinct_subjects, stats.subjects.total());
+        assert_eq!(stats.dataset.distinct_subjects, stats.out_degrees.terms());
+        assert_eq!(stats.dataset.distinct_objects, stats.objects.total());
+        assert_eq!(stats.dataset.distinct_objects, stats.in_degrees.terms());
+        assert_eq!(stats.dataset.triples, stats.object_triples.total());
+        assert_eq!(0, stats.subjects.literals);
+
+        // compare with the decoded triples
+        let (mut out, mut inc) = (HashMap::<_, usize>::new(), HashMap::<_, usize>::new());
+        let mut expected = TermKinds::default();
+        for (s, _, o) in hdt.triples() {
+            *out.entry(s).or_default() += 1;
+            if o.starts_with('"') {
+                expected.literals += 1;
+            } else if o.starts_with("_:") {
+                expected.blank_nodes += 1;
+            } else {
+                expected.iris += 1;
+            }
+            *inc.entry(o).or_default() += 1;
+        }
+        assert_eq!(expected, stats.object_triples);
+        assert!(stats.object_triples.literals > 0);
+        assert_eq!(out.values().max().copied().unwrap(), stats.out_degrees.max);
+        assert_eq!(inc.values().max().copied().unwrap(), stats.in_degrees.max);
+        let degree_one = inc.values().filter(|&&d| d == 1).count();
+        assert_eq!(Some(&(1, degree_one)), stats.in_degrees.histogram.first());
+        let literals = inc.keys().filter(|o| o.starts_with('"')).count();
+        assert_eq!(literals, stats.objects.literals);
+        let total: usize = stats.out_degrees.histogram.iter().map(|(degree, terms)| degree * terms).sum();
+        assert_eq!(hdt.triples.len(), total);
+        assert!(stats.out_degrees.mean() > 1.0);
+        assert!(stats.objects.literal_ratio() > 0.0 && stats.objects.literal_ratio() < 1.0);
+    }
+}
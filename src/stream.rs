@@ -0,0 +1,106 @@
+//! [`Stream`] adapters for async consumers, such as servers on tokio that stream huge result sets.
+//! *This module is available only if HDT is built with the `"async"` feature.*
+use crate::hdt::{Hdt, SharedTripleIter};
+use futures_core::Stream;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+/// Default number of items after which an [`IterStream`] yields to the executor.
+pub const DEFAULT_BUDGET: usize = 128;
+
+/// Stream over the items of an iterator that cooperatively yields to the executor every `budget` items.
+/// Iterating over HDT triples never blocks on IO but may take long for large results,
+/// so without yielding, a single stream could starve the other tasks of an executor thread.
+#[derive(Debug, Clone)]
+pub struct IterStream<I> {
+    iter: I,
+    budget: usize,
+    count: usize,
+}
+
+impl<I: Iterator> IterStream<I> {
+    /// Stream over the given iterator that yields every [`DEFAULT_BUDGET`] items.
+    pub const fn new(iter: I) -> Self {
+        Self::with_budget(iter, DEFAULT_BUDGET)
+    }
+
+    /// Stream over the given iterator that yields every `budget` items, where 0 means never.
+    pub const fn with_budget(iter: I, budget: usize) -> Self {
+        IterStream { iter, budget, count: 0 }
+    }
+}
+
+impl<I: Iterator + Unpin> Stream for IterStream<I> {
+    type Item = I::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.budget > 0 && self.count >= self.budget {
+            self.count = 0;
+            // ready to continue immediately, but let the executor schedule other tasks first
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+        self.count += 1;
+        Poll::Ready(self.iter.next())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+/// Stream over all triples that fit the given triple pattern, see [`Hdt::triples_with_pattern`].
+/// The stream holds a reference count of the HDT, so it can be moved into a spawned task.
+/// # Example
+/// ```
+/// use futures_core::Stream;
+/// use std::sync::Arc;
+/// fn labels(hdt: &Arc<hdt::Hdt>) -> impl Stream<Item = hdt::hdt::StringTriple> + Send + 'static {
+///     hdt::stream::triples_with_pattern(hdt, None, Some("http://www.w3.org/2000/01/rdf-schema#label"), None)
+/// }
+/// ```
+pub fn triples_with_pattern(
+    hdt: &Arc<Hdt>, sp: Option<&str>, pp: Option<&str>, op: Option<&str>,
+) -> IterStream<SharedTripleIter> {
+    IterStream::new(Hdt::shared_triples_with_pattern(hdt, sp, pp, op))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::init;
+    use pretty_assertions::assert_eq;
+    use std::fs::File;
+    use std::task::Waker;
+
+    /// Poll the stream to the end with a waker that does nothing, returning the items and the number of yields.
+    fn drain<S: Stream + Unpin>(mut stream: S) -> (Vec<S::Item>, usize) {
+        let mut cx = Context::from_waker(Waker::noop());
+        let (mut items, mut pending) = (Vec::new(), 0);
+        loop {
+            match Pin::new(&mut stream).poll_next(&mut cx) {
+                Poll::Ready(Some(item)) => items.push(item),
+                Poll::Ready(None) => return (items, pending),
+                Poll::Pending => pending += 1,
+            }
+        }
+    }
+
+    #[test]
+    fn stream() {
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let hdt = Arc::new(Hdt::new(std::io::BufReader::new(file)).unwrap());
+        let (items, pending) = drain(IterStream::with_budget(hdt.triples_with_pattern(None, None, None), 10));
+        assert_eq!(hdt.triples().collect::<Vec<_>>(), items);
+        // the final poll that returns None also counts against the budget
+        assert_eq!((items.len() + 1).div_ceil(10) - 1, pending);
+        let (items, pending) = drain(IterStream::with_budget(hdt.triples(), 0));
+        assert_eq!((hdt.triples.len(), 0), (items.len(), pending));
+
+        let label = Some("http://www.w3.org/2000/01/rdf-schema#label");
+        let (items, _) = drain(triples_with_pattern(&hdt, None, label, None));
+        assert_eq!(hdt.triples_with_pattern(None, label, None).collect::<Vec<_>>(), items);
+    }
+}
@@ -0,0 +1,141 @@
+//! Extraction of the triples matching a pattern or predicates into a new HDT with a compacted dictionary, see [`Hdt::subset`].
+use crate::builder::{assemble, bitmap_triples, dictionary, DEFAULT_BLOCK_SIZE};
+use crate::compare_terms;
+use crate::four_sect_dict::IdKind;
+use crate::hdt::Hdt;
+use crate::triples::{Id, TripleId};
+use eyre::{eyre, Result};
+
+impl Hdt {
+    /// New HDT with the triples matching the given pattern, where `None` stands for a variable,
+    /// which contains only the terms of those triples, see [`Self::subset_ids`].
+    /// Terms that do not exist in the graph result in an empty HDT.
+    /// # Example
+    /// ```
+    /// fn labels(hdt: &hdt::Hdt, out: &str) -> Result<(), Box<dyn std::error::Error>> {
+    ///     let subset = hdt.subset(None, Some("http://www.w3.org/2000/01/rdf-schema#label"), None)?;
+    ///     subset.write_to(std::io::BufWriter::new(std::fs::File::create(out)?), "http://example.org/labels")?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn subset(&self, sp: Option<&str>, pp: Option<&str>, op: Option<&str>) -> Result<Hdt> {
+        match self.pattern_ids(sp, pp, op) {
+            Some(pat) => self.subset_ids(self.triples.triples_with_pattern(&pat)),
+            None => self.subset_ids(std::iter::empty()),
+        }
+    }
+
+    /// New HDT with the triples that have any of the given predicates, which contains only the terms of those triples,
+    /// see [`Self::subset_ids`]. Predicates that do not exist in the graph are ignored.
+    pub fn subset_predicates(&self, predicates: &[&str]) -> Result<Hdt> {
+        let ids = predicates.iter().map(|p| self.dict.string_to_id(p, &IdKind::Predicate)).filter(|&id| id != 0);
+        self.subset_ids(ids.flat_map(|p| self.triples.triples_with_pattern(&TripleId::new(0, p, 0))))
+    }
+
+    /// New HDT with the given triples of this one in any order, whose dictionary only contains the terms of these triples.
+    /// Terms that are shared between subjects and objects here but only occur in one position in the given triples
+    /// move from the shared into the subject or object section.
+    /// Only the strings of the remaining terms are extracted, without parsing any triples like a conversion from N-Triples would.
+    /// The result has bitmap triples in SPO order and dictionary sections with the block size [`DEFAULT_BLOCK_SIZE`].
+    /// Fails for quad files with a graph section, on IDs outside of the dictionary
+    /// and on strings that cannot be extracted from damaged dictionary sections.
+    pub fn subset_ids(&self, triples: impl IntoIterator<Item = TripleId>) -> Result<Hdt> {
+        if self.dict.graphs.is_some() {
+            return Err(eyre!("Subsets of quad files with a graph section are not supported"));
+        }
+        let dict = &self.dict;
+        let mut triples: Vec<[Id; 3]> =
+            triples.into_iter().map(|t| [t.subject_id, t.predicate_id, t.object_id]).collect();
+        let mut s_used = vec![false; dict.shared.num_strings() + dict.subjects.num_strings() + 1];
+        let mut p_used = vec![false; dict.predicates.num_strings() + 1];
+        let mut o_used = vec![false; dict.shared.num_strings() + dict.objects.num_strings() + 1];
+        let keys = [s_used.len(), p_used.len(), o_used.len()];
+        for t in &triples {
+            // 0 and IDs past the end of their section
+            if t.iter().zip(&keys).any(|(&id, &keys)| id == 0 || id >= keys) {
+                return Err(eyre!("Triple {t:?} has IDs outside of the dictionary"));
+            }
+            s_used[t[0]] = true;
+            p_used[t[1]] = true;
+            o_used[t[2]] = true;
+        }
+
+        // the terms of the subset with their IDs here
+        let section = |used: &[bool], kind: &'static IdKind| -> Result<Vec<(String, Id)>> {
+            let mut terms = Vec::new();
+            for id in (1..used.len()).filter(|&id| used[id]) {
+                terms.push((dict.id_to_string(id, kind)?, id));
+            }
+            terms.sort_unstable_by(|a, b| compare_terms(&a.0, &b.0));
+            Ok(terms)
+        };
+        let sections = [
+            section(&s_used, &IdKind::Subject)?,
+            section(&p_used, &IdKind::Predicate)?,
+            section(&o_used, &IdKind::Object)?,
+        ];
+        let (dict, new_ids) = dictionary([&sections[0], &sections[1], &sections[2]], keys, DEFAULT_BLOCK_SIZE);
+        for t in &mut triples {
+            *t = [new_ids[0][t[0]], new_ids[1][t[1]], new_ids[2][t[2]]];
+        }
+        triples.sort_unstable();
+        triples.dedup();
+        Ok(assemble(dict, bitmap_triples(&triples)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::init;
+    use pretty_assertions::assert_eq;
+    use std::collections::BTreeSet;
+    use std::fs::File;
+
+    fn triple_set(triples: impl Iterator<Item = crate::hdt::StringTriple>) -> BTreeSet<String> {
+        triples.map(|(s, p, o)| format!("{s} {p} {o}")).collect()
+    }
+
+    #[test]
+    fn subset() {
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let hdt = Hdt::new(std::io::BufReader::new(file)).unwrap();
+        let label = "http://www.w3.org/2000/01/rdf-schema#label";
+        let domain = "http://www.w3.org/2000/01/rdf-schema#domain";
+        let labels = hdt.subset(None, Some(label), None).unwrap();
+        let expected = triple_set(hdt.triples_with_pattern(None, Some(label), None));
+        assert_eq!(expected, triple_set(labels.triples()));
+        assert_eq!(expected.len(), labels.triples.len());
+        assert_eq!(1, labels.dict.predicates.num_strings());
+        // labels are only objects and their subjects are only subjects
+        assert_eq!(0, labels.dict.shared.num_strings());
+        let subjects: BTreeSet<_> = labels.triples().map(|(s, _, _)| s).collect();
+        assert_eq!(subjects.len(), labels.dict.subjects.num_strings());
+        // every term can be found in its section
+        for (s, p, o) in labels.triples() {
+            assert_eq!(1, labels.triples_with_pattern(Some(&s), Some(&p), Some(&o)).count());
+        }
+
+        let both = hdt.subset_predicates(&[label, domain, "http://example.org/missing"]).unwrap();
+        let mut expected = expected;
+        expected.extend(triple_set(hdt.triples_with_pattern(None, Some(domain), None)));
+        assert_eq!(expected, triple_set(both.triples()));
+        assert_eq!(2, both.dict.predicates.num_strings());
+
+        // the subset survives a round trip through a file
+        let mut buf = Vec::new();
+        both.write_to(&mut buf, "http://example.org/subset").unwrap();
+        let read = Hdt::new(buf.as_slice()).unwrap();
+        assert_eq!(expected, triple_set(read.triples()));
+
+        let all = hdt.subset(None, None, None).unwrap();
+        assert_eq!(triple_set(hdt.triples()), triple_set(all.triples()));
+        assert_eq!(hdt.dict.shared.num_strings(), all.dict.shared.num_strings());
+        assert!(hdt.subset(Some("http://example.org/missing"), None, None).unwrap().triples().next().is_none());
+        assert!(hdt
+            .subset_ids([TripleId::new(1, 1, hdt.dict.shared.num_strings() + hdt.dict.objects.num_strings() + 1)])
+            .is_err());
+        assert!(hdt.subset_ids([TripleId::new(0, 1, 1)]).is_err());
+    }
+}
@@ -0,0 +1,192 @@
+//! Deterministic synthetic HDT files for benchmarks and tests without distributing large real datasets, see [`Generator`].
+use crate::builder::HdtBuilder;
+use crate::containers::rdf::nt_term;
+use crate::hdt::Hdt;
+use crate::triples::SplitMix64;
+use eyre::Result;
+use std::io::{self, Write};
+
+const BASE: &str = "http://example.org/synthetic/";
+const XSD: &str = "http://www.w3.org/2001/XMLSchema#";
+const LANGUAGES: [&str; 6] = ["en", "de", "ja", "ar", "ru", "el"];
+const ASCII_WORDS: [&str; 8] = ["alpha", "beta", "gamma", "delta", "epsilon", "zeta", "eta", "theta"];
+/// Words from different scripts and UTF-8 lengths, including combining marks, right-to-left text and characters outside the BMP.
+const UNICODE_WORDS: [&str; 12] = [
+    "caf\u{e9}", "stra\u{df}e", "\u{3b1}\u{3bb}\u{3c6}\u{3b1}", "\u{43c}\u{438}\u{440}", "\u{6771}\u{4eac}",
+    "\u{3072}\u{3089}\u{304c}\u{306a}", "\u{627}\u{644}\u{633}\u{644}\u{627}\u{645}",
+    "\u{5e9}\u{5dc}\u{5d5}\u{5dd}", "\u{939}\u{93f}\u{928}\u{94d}\u{926}\u{940}", "e\u{301}te\u{301}",
+    "\u{1f600}\u{1f680}", "\u{1d538}\u{1d539}",
+];
+
+/// How the triples of a [`Generator`] connect their subjects and objects.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Shape {
+    /// Hubs with about 100 triples each, whose objects are distinct entities, like the descriptions of popular resources.
+    #[default]
+    Star,
+    /// Each entity links to the next one, so that most entities are in the shared section, like paths and linked lists.
+    Chain,
+    /// Subjects and objects from two disjoint sets of entities, like links between persons and documents.
+    Bipartite,
+}
+
+/// Generator of synthetic graphs with exactly the configured number of distinct triples,
+/// which are the same for the same configuration and seed on all platforms and in all versions with the same major version.
+/// # Example
+/// ```
+/// use hdt::synthetic::{Generator, Shape};
+/// let hdt = Generator::new(1000).with_shape(Shape::Chain).with_literals(50).with_unicode(true).build().unwrap();
+/// assert_eq!(1000, hdt.triples().count());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Generator {
+    triples: usize,
+    shape: Shape,
+    predicates: usize,
+    literals: u8,
+    unicode: bool,
+    seed: u64,
+}
+
+impl Generator {
+    /// Generator of the given number of triples in the [`Shape::Star`] shape with 10 predicates, 30 percent literal objects,
+    /// ASCII literals and seed 0.
+    pub const fn new(triples: usize) -> Self {
+        Generator { triples, shape: Shape::Star, predicates: 10, literals: 30, unicode: false, seed: 0 }
+    }
+
+    /// Set how subjects and objects are connected.
+    #[must_use]
+    pub const fn with_shape(mut self, shape: Shape) -> Self {
+        self.shape = shape;
+        self
+    }
+
+    /// Set the number of distinct predicates, at least 1.
+    #[must_use]
+    pub const fn with_predicates(mut self, predicates: usize) -> Self {
+        self.predicates = if predicates == 0 { 1 } else { predicates };
+        self
+    }
+
+    /// Set the percentage of objects that are literals, capped at 100.
+    /// Literals are evenly mixed between plain, language-tagged, integer and date literals.
+    #[must_use]
+    pub const fn with_literals(mut self, percent: u8) -> Self {
+        self.literals = if percent > 100 { 100 } else { percent };
+        self
+    }
+
+    /// Set whether literals contain words from many scripts, including combining marks, right-to-left text and emoji,
+    /// instead of only ASCII.
+    #[must_use]
+    pub const fn with_unicode(mut self, unicode: bool) -> Self {
+        self.unicode = unicode;
+        self
+    }
+
+    /// Set the seed of the random choices of predicates, literal kinds and words.
+    #[must_use]
+    pub const fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Generate the triples in the string format of the HDT dictionary, see [`crate::containers::rdf`].
+    /// The pairs of subject and object index are distinct by construction and literals contain their object index,
+    /// so that no triples are duplicates.
+    pub fn triples(&self) -> impl Iterator<Item = [String; 3]> + '_ {
+        let n = self.triples;
+        // number of subject entities for stars and bipartite graphs
+        let hubs = n.div_ceil(100).max(1);
+        let left = n.isqrt().max(1);
+        let right = n.div_ceil(left).max(1);
+        let mut rng = SplitMix64::new(self.seed);
+        (0..n).map(move |i| {
+            let (s, o) = match self.shape {
+                Shape::Star => (format!("{BASE}hub{}", i % hubs), i),
+                Shape::Chain => (format!("{BASE}e{i}"), i + 1),
+                // for a fixed subject, the object index is a bijection of i / left
+                Shape::Bipartite => (format!("{BASE}a{}", i % left), n + (i / left + 7 * (i % left)) % right),
+            };
+            let p = format!("{BASE}p{}", rng.below(self.predicates));
+            let o = if rng.below(100) < usize::from(self.literals) {
+                self.literal(o, &mut rng)
+            } else {
+                format!("{BASE}e{o}")
+            };
+            [s, p, o]
+        })
+    }
+
+    /// Literal that contains the given object index.
+    fn literal(&self, o: usize, rng: &mut SplitMix64) -> String {
+        let word = if self.unicode {
+            UNICODE_WORDS[rng.below(UNICODE_WORDS.len())]
+        } else {
+            ASCII_WORDS[rng.below(ASCII_WORDS.len())]
+        };
+        match rng.below(4) {
+            0 => format!("\"{word} {o}\""),
+            1 => format!("\"{word} {o}\"@{}", LANGUAGES[rng.below(LANGUAGES.len())]),
+            2 => format!("\"{o}\"^^<{XSD}integer>"),
+            _ => format!("\"{:04}-{:02}-{:02}\"^^<{XSD}date>", 1900 + o / 336, o / 28 % 12 + 1, o % 28 + 1),
+        }
+    }
+
+    /// Write the triples as N-Triples, for example to benchmark conversions.
+    pub fn write_ntriples<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        for [s, p, o] in self.triples() {
+            writeln!(writer, "{} {} {} .", nt_term(&s), nt_term(&p), nt_term(&o))?;
+        }
+        Ok(())
+    }
+
+    /// Build an HDT with the triples, which can be saved with [`Hdt::write_to`].
+    pub fn build(&self) -> Result<Hdt> {
+        let mut builder = HdtBuilder::new();
+        for [s, p, o] in self.triples() {
+            builder.add_triple(&s, &p, &o);
+        }
+        builder.build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::init;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn synthetic() {
+        init();
+        for shape in [Shape::Star, Shape::Chain, Shape::Bipartite] {
+            let generator = Generator::new(1000).with_shape(shape).with_unicode(true);
+            let hdt = generator.build().unwrap();
+            assert_eq!(1000, hdt.triples.len(), "{shape:?}");
+            assert_eq!(10, hdt.dict.predicates.num_strings(), "{shape:?}");
+            let triples: Vec<[String; 3]> = generator.triples().collect();
+            assert_eq!(triples, generator.triples().collect::<Vec<_>>(), "not deterministic");
+            assert_ne!(triples, generator.clone().with_seed(1).triples().collect::<Vec<_>>());
+            let literals = triples.iter().filter(|[_, _, o]| o.starts_with('"')).count();
+            assert!((200..400).contains(&literals), "{shape:?} has {literals} literals");
+            assert!(triples.iter().any(|[_, _, o]| o.chars().any(|c| c > '\u{ffff}')));
+            let shared = hdt.dict.shared.num_strings();
+            match shape {
+                Shape::Star => assert_eq!((10, 0), (hdt.dict.subjects.num_strings(), shared)),
+                Shape::Chain => assert!(shared > 500, "{shared}"),
+                Shape::Bipartite => assert_eq!((31, 0), (hdt.dict.subjects.num_strings(), shared)),
+            }
+        }
+
+        let ascii = Generator::new(100).with_literals(100).with_predicates(0);
+        assert!(ascii.triples().all(|[_, p, o]| o.starts_with('"') && o.is_ascii() && p.ends_with("p0")));
+        let mut nt = Vec::new();
+        ascii.write_ntriples(&mut nt).unwrap();
+        let mut builder = HdtBuilder::new();
+        builder.read_ntriples(nt.as_slice()).unwrap();
+        assert_eq!(100, builder.build().unwrap().triples.len());
+        assert_eq!(0, Generator::new(0).build().unwrap().triples.len());
+    }
+}
@@ -0,0 +1,158 @@
+//! Filtering of triples by the validity intervals of their subjects, as used by temporal and statistical datasets.
+use crate::four_sect_dict::IdKind;
+use crate::hdt::{Hdt, StringTriple, TripleCache};
+use crate::triples::{Id, SubjectIter, TripleId};
+use log::error;
+use std::cmp::Ordering;
+use std::iter;
+
+const SCHEMA_START_DATE: &str = "http://schema.org/startDate";
+const SCHEMA_END_DATE: &str = "http://schema.org/endDate";
+
+/// Validity intervals of subjects given by a start and an end predicate, such as `schema:startDate` and `schema:endDate`.
+/// The objects of those predicates must be ISO 8601 literals like `xsd:date`, `xsd:dateTime` or `xsd:gYear`.
+/// Points in time are compared on the precision they have in common, so that the date `"2020-12-31"` contains `"2020-12-31T23:00:00"` and the year `"2020"` contains `"2020-06-01"`.
+/// Time zones are not taken into account and negative years are not supported.
+/// A subject without a start or end is unbounded in that direction.
+#[derive(Debug, Clone)]
+pub struct Validity<'a> {
+    hdt: &'a Hdt,
+    start_id: Id,
+    end_id: Id,
+}
+
+impl<'a> Validity<'a> {
+    /// Validity intervals given by the start and end predicates with the given IRIs.
+    /// Predicates that do not occur in the graph are treated as unbounded.
+    pub fn new(hdt: &'a Hdt, start: &str, end: &str) -> Self {
+        Validity {
+            hdt,
+            start_id: hdt.dict.string_to_id(start, &IdKind::Predicate),
+            end_id: hdt.dict.string_to_id(end, &IdKind::Predicate),
+        }
+    }
+
+    /// Validity intervals given by `schema:startDate` and `schema:endDate`.
+    pub fn schema(hdt: &'a Hdt) -> Self {
+        Self::new(hdt, SCHEMA_START_DATE, SCHEMA_END_DATE)
+    }
+
+    /// Whether the subject with the given ID is valid at the given point in time, such as `"2020-06-01"`.
+    /// If a subject has multiple starts or ends, the earliest start and the latest end are used.
+    pub fn contains(&self, subject_id: Id, time: &str) -> bool {
+        let bound = |pid: Id, ok: fn(Ordering) -> bool| {
+            if pid == 0 {
+                return true;
+            }
+            let mut values = self.values(subject_id, pid).peekable();
+            values.peek().is_none() || values.any(|v| ok(compare(&v, time)))
+        };
+        bound(self.start_id, Ordering::is_le) && bound(self.end_id, Ordering::is_ge)
+    }
+
+    /// Lexical forms of the objects of the given subject and predicate.
+    fn values(&self, subject_id: Id, pid: Id) -> impl Iterator<Item = String> + '_ {
+        SubjectIter::with_pattern(&self.hdt.triples, &TripleId::new(subject_id, pid, 0)).filter_map(|t| {
+            let o = self
+                .hdt
+                .dict
+                .id_to_string(t.object_id, &IdKind::Object)
+                .map_err(|e| error!("Error translating validity bound: {e}"))
+                .ok()?;
+            Some(lexical(&o).to_owned())
+        })
+    }
+
+    /// Get all triples that fit the given triple pattern like [`Hdt::triples_with_pattern`], restricted to subjects valid at the given point in time.
+    /// # Example
+    /// Population figures valid on the first of January 2020:
+    /// ```
+    /// use hdt::temporal::Validity;
+    /// fn population(hdt: &hdt::Hdt) {
+    ///     let validity = Validity::schema(hdt);
+    ///     for t in validity.triples_with_pattern(None, Some("http://example.org/population"), None, "2020-01-01") {
+    ///         println!("{t:?}");
+    ///     }
+    /// }
+    /// ```
+    pub fn triples_with_pattern(
+        &self, sp: Option<&str>, pp: Option<&str>, op: Option<&str>, time: &'a str,
+    ) -> Box<dyn Iterator<Item = StringTriple> + 'a> {
+        let Some(pat) = self.hdt.pattern_ids(sp, pp, op) else {
+            return Box::new(iter::empty());
+        };
+        let validity = self.clone();
+        let mut cache = TripleCache::new(self.hdt);
+        // results of a subject are often adjacent, so remember the last decision
+        let mut last: Option<(Id, bool)> = None;
+        Box::new(
+            self.hdt
+                .triples
+                .triples_with_pattern(&pat)
+                .filter(move |t| match last {
+                    Some((s, valid)) if s == t.subject_id => valid,
+                    _ => {
+                        let valid = validity.contains(t.subject_id, time);
+                        last = Some((t.subject_id, valid));
+                        valid
+                    }
+                })
+                .filter_map(move |t| cache.translate(t).map_err(|e| error!("{e}")).ok()),
+        )
+    }
+}
+
+/// Lexical form of a literal in the HDT dictionary string format, other terms are returned as is.
+fn lexical(term: &str) -> &str {
+    match (term.strip_prefix('"'), term.rfind('"')) {
+        (Some(_), Some(end)) if end > 0 => &term[1..end],
+        _ => term,
+    }
+}
+
+/// Compare two ISO 8601 points in time on their common precision.
+fn compare(a: &str, b: &str) -> Ordering {
+    let len = a.len().min(b.len());
+    a.as_bytes()[..len].cmp(&b.as_bytes()[..len])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::init;
+    use pretty_assertions::assert_eq;
+    use std::fs::File;
+
+    #[test]
+    fn validity() {
+        init();
+        assert_eq!("2022-10-20", lexical("\"2022-10-20\"^^<http://www.w3.org/2001/XMLSchema#date>"));
+        assert_eq!("http://example.org", lexical("http://example.org"));
+        assert_eq!(Ordering::Equal, compare("2020", "2020-06-01"));
+        assert_eq!(Ordering::Equal, compare("2020-12-31", "2020-12-31T23:00:00"));
+        assert_eq!(Ordering::Less, compare("2020-12-31", "2021-01-01"));
+
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let hdt = Hdt::new(std::io::BufReader::new(file)).unwrap();
+        // the ontology was modified on 2022-10-20 and has no end
+        let validity = Validity::new(&hdt, "http://purl.org/dc/terms/modified", "http://example.org/end");
+        let meta = "http://www.snik.eu/ontology/meta";
+        let meta_id = hdt.dict.string_to_id(meta, &IdKind::Subject);
+        assert!(validity.contains(meta_id, "2022-10-20"));
+        assert!(validity.contains(meta_id, "2023"));
+        assert!(!validity.contains(meta_id, "2022-10-19T23:59"));
+        let all = hdt.triples_with_pattern(Some(meta), None, None).count();
+        assert_eq!(all, validity.triples_with_pattern(Some(meta), None, None, "2024-01-01").count());
+        assert_eq!(0, validity.triples_with_pattern(Some(meta), None, None, "2021-01-01").count());
+        // subjects without a start are always valid
+        let top = Some("http://www.snik.eu/ontology/meta/Top");
+        assert_eq!(
+            hdt.triples_with_pattern(top, None, None).collect::<Vec<_>>(),
+            validity.triples_with_pattern(top, None, None, "1900").collect::<Vec<_>>()
+        );
+        // the end bound works the same way
+        let validity = Validity::new(&hdt, "http://example.org/start", "http://purl.org/dc/terms/modified");
+        assert!(validity.contains(meta_id, "2021-01-01"));
+        assert!(!validity.contains(meta_id, "2023-01-01"));
+    }
+}
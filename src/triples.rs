@@ -1,11 +1,30 @@
-use crate::containers::{AdjList, Bitmap, Sequence};
+use crate::containers::{
+    read_sequence, write_sequence, write_sequence_min_bits, AdjList, Bitmap, ControlType, LogSequence, Sequence,
+};
+#[cfg(feature = "mmap")]
+use crate::containers::{read_sequence_mapped, SequenceLayout};
+use crate::error::{HdtError, Part};
 use crate::ControlInfo;
 use bytesize::ByteSize;
 use eyre::{eyre, Result, WrapErr};
 use log::{debug, error};
-use std::cmp::Ordering;
+#[cfg(feature = "mmap")]
+use memmap2::Mmap;
+use std::borrow::Borrow;
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
 use std::fmt;
-use std::io::BufRead;
+#[cfg(feature = "mmap")]
+use std::fs::File;
+use std::io::{self, BufRead, Write};
+use std::iter;
+use std::ops::Range;
+#[cfg(feature = "mmap")]
+use std::path::Path;
+#[cfg(feature = "mmap")]
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 use sucds::{
     bit_vectors::{BitVector, Rank9Sel},
     char_sequences::WaveletMatrix,
@@ -14,13 +33,30 @@ use sucds::{
 };
 
 mod subject_iter;
-pub use subject_iter::SubjectIter;
+pub use subject_iter::{SubjectIter, SubjectIterOf};
 mod predicate_iter;
-pub use predicate_iter::PredicateIter;
+pub use predicate_iter::{PredicateIter, PredicateIterOf};
 mod predicate_object_iter;
-pub use predicate_object_iter::PredicateObjectIter;
+pub use predicate_object_iter::{PredicateObjectIter, PredicateObjectIterOf};
 mod object_iter;
-pub use object_iter::ObjectIter;
+pub use object_iter::{ObjectIter, ObjectIterOf};
+mod sample_iter;
+pub use sample_iter::SampleIter;
+pub(crate) use sample_iter::SplitMix64;
+mod quads;
+pub(crate) use quads::require_hdtq;
+pub use quads::{QuadId, QuadInfo};
+mod cursor;
+pub use cursor::{Cursor, ResumeError, ResumeToken};
+mod tombstones;
+pub use tombstones::Tombstones;
+
+pub(crate) const TRIPLES_BITMAP: &str = "<http://purl.org/HDT/hdt#triplesBitmap>";
+pub(crate) const TRIPLES_BITMAP_QUAD: &str = "<http://purl.org/HDT/hdt#triplesBitmapQuad>";
+/// Format of the index files written by [`TriplesBitmap::write_index`], which are specific to this library.
+const TRIPLES_INDEX: &str = "<https://github.com/konradhoeffner/hdt#triplesIndex>";
+/// Number of results between two checks of the time budget in [`TriplesBitmap::count_with_pattern_bounded`].
+const BOUNDED_COUNT_INTERVAL: usize = 64;
 
 /// Order of the triple sections.
 /// Only SPO is tested, others probably don't work correctly.
@@ -38,9 +74,9 @@ pub enum Order {
 }
 
 impl TryFrom<u32> for Order {
-    type Error = eyre::Error;
+    type Error = HdtError;
 
-    fn try_from(original: u32) -> Result<Self> {
+    fn try_from(original: u32) -> Result<Self, Self::Error> {
         match original {
             0 => Ok(Order::Unknown),
             1 => Ok(Order::SPO),
@@ -49,7 +85,7 @@ impl TryFrom<u32> for Order {
             4 => Ok(Order::POS),
             5 => Ok(Order::OSP),
             6 => Ok(Order::OPS),
-            _ => Err(eyre!("Unrecognized order")),
+            _ => Err(HdtError::invalid(Part::Triples, format!("unrecognized order {original}"))),
         }
     }
 }
@@ -58,7 +94,8 @@ impl TryFrom<u32> for Order {
 /// Used for logarithmic (?) time access instead of linear time sequential search.
 pub struct OpIndex {
     /// Compact integer vector of object positions.
-    pub sequence: CompactVector,
+    /// Stays in the memory map when read from an index file with [`Hdt::map_with_index`](crate::Hdt::map_with_index).
+    pub sequence: Sequence,
     /// Bitmap with a one bit for every new object to allow finding the starting point for a given object id.
     pub bitmap: Bitmap,
 }
@@ -69,8 +106,8 @@ impl fmt::Debug for OpIndex {
         writeln!(
             f,
             "    sequence: {} with {} bits,",
-            ByteSize(self.sequence.len() as u64 * self.sequence.width() as u64 / 8),
-            self.sequence.width()
+            ByteSize(self.sequence.len() as u64 * self.sequence.bits_per_entry() as u64 / 8),
+            self.sequence.bits_per_entry()
         )?;
         write!(f, "    bitmap: {:#?}\n}}", self.bitmap)
     }
@@ -79,8 +116,9 @@ impl fmt::Debug for OpIndex {
 impl OpIndex {
     /// Size in bytes on the heap.
     pub fn size_in_bytes(&self) -> usize {
-        self.sequence.len() * self.sequence.width() / 8 + self.bitmap.size_in_bytes()
+        self.sequence.size_in_bytes() + self.bitmap.size_in_bytes()
     }
+
     /// Find the first position in the OP index of the given object ID.
     pub fn find(&self, o: Id) -> usize {
         self.bitmap.select1(o - 1).unwrap() as usize
@@ -89,7 +127,241 @@ impl OpIndex {
     pub fn last(&self, o: Id) -> usize {
         match self.bitmap.select1(o) {
             Some(index) => index as usize - 1,
-            None => self.bitmap.len() - 1,
+            // the bitmap of an index file is padded to whole words
+            None => self.sequence.len() - 1,
+        }
+    }
+}
+
+/// The bitmaps, the wavelet matrix and the object index of a triples section, which take most of the time of loading,
+/// read from an index file written by [`TriplesBitmap::write_index`], see [`Hdt::map_with_index`](crate::Hdt::map_with_index).
+#[cfg(feature = "mmap")]
+pub struct TriplesIndex {
+    /// bitmap of the Y level with its rank and select index
+    pub bitmap_y: Bitmap,
+    /// bitmap of the Z level with its rank and select index
+    pub bitmap_z: Bitmap,
+    /// wavelet matrix of the predicate IDs
+    pub wavelet_y: WaveletMatrix<Rank9Sel>,
+    /// object index, whose sequence stays in the memory map
+    pub op_index: OpIndex,
+}
+
+#[cfg(feature = "mmap")]
+impl TriplesIndex {
+    /// Map an index file written by [`TriplesBitmap::write_index`].
+    /// The sequence of the object index stays in the memory map, the other parts are copied from it,
+    /// because the structures of sucds own their memory, but they are not built again.
+    /// Fails if the index file was written with another fingerprint, because it belongs to another HDT file.
+    /// The file must not be modified while it is mapped.
+    pub fn map(path: &Path, fingerprint: &str) -> Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: undefined behaviour if the file is modified while mapped, which is documented above
+        let map = Arc::new(unsafe { Mmap::map(&file)? });
+        let mut reader = &map[..];
+        let index_ci = ControlInfo::read(&mut reader).wrap_err("Failed to read index control info")?;
+        if index_ci.format != TRIPLES_INDEX {
+            let (format, supported) =
+                (index_ci.format, "only triples indexes written by this crate are supported");
+            return Err(HdtError::UnsupportedFormat { part: Part::Index, format, supported }.into());
+        }
+        let found = index_ci.get("fingerprint").unwrap_or_default();
+        if found != fingerprint {
+            let message = format!("index file for the HDT file with fingerprint {found} instead of {fingerprint}");
+            return Err(HdtError::invalid(Part::Index, message).into());
+        }
+        let bitmap = Bitmap::read(&mut reader).wrap_err("Failed to read object index bitmap")?;
+        let sequence = read_sequence_mapped(&mut reader, &map).wrap_err("Failed to read object index sequence")?;
+        // sucds reports errors without a position, so the offset is the start of the structure that failed
+        let invalid = |offset, what: &str, e: &dyn fmt::Display| HdtError::Invalid {
+            part: Part::Index,
+            offset: Some(offset),
+            message: format!("{what}: {e}"),
+        };
+        let offset = map.len() - reader.len();
+        let wavelet_y = WaveletMatrix::deserialize_from(&mut reader)
+            .map_err(|e| invalid(offset, "failed to read wavelet matrix", &e))?;
+        let offset = map.len() - reader.len();
+        let bitmap_y = Rank9Sel::deserialize_from(&mut reader)
+            .map_err(|e| invalid(offset, "failed to read Y level bitmap", &e))?;
+        let offset = map.len() - reader.len();
+        let bitmap_z = Rank9Sel::deserialize_from(&mut reader)
+            .map_err(|e| invalid(offset, "failed to read Z level bitmap", &e))?;
+        Ok(TriplesIndex {
+            bitmap_y: Bitmap { dict: bitmap_y },
+            bitmap_z: Bitmap { dict: bitmap_z },
+            wavelet_y,
+            op_index: OpIndex { sequence, bitmap },
+        })
+    }
+}
+
+/// Control information, positions and stored checksums of the parts of a triples section, found without reading their data.
+#[cfg(feature = "mmap")]
+pub(crate) struct SectionLayout {
+    pub triples_ci: ControlInfo,
+    /// length in bytes of the control information
+    pub ci_len: usize,
+    /// stored CRC32C checksums of the data of the Y and Z level bitmaps
+    pub bitmap_crcs: [u32; 2],
+    pub sequence_y: SequenceLayout,
+    /// position of the Z level sequence in the section
+    pub z_start: usize,
+    pub sequence_z: SequenceLayout,
+}
+
+#[cfg(feature = "mmap")]
+impl SectionLayout {
+    /// Read the layout of the triples section at the beginning of the given bytes.
+    pub fn read(section: &[u8]) -> Result<Self> {
+        let mut cursor = io::Cursor::new(section);
+        let triples_ci = ControlInfo::read(&mut cursor)?;
+        let ci_len = usize::try_from(cursor.position())?;
+        let bitmap_crcs = [
+            Bitmap::skip(&mut cursor).wrap_err("Failed to read Y level bitmap")?,
+            Bitmap::skip(&mut cursor).wrap_err("Failed to read Z level bitmap")?,
+        ];
+        let sequence_y = SequenceLayout::skip(&mut cursor).wrap_err("Failed to read Y level sequence")?;
+        let z_start = usize::try_from(cursor.position())?;
+        let sequence_z = SequenceLayout::skip(&mut cursor).wrap_err("Failed to read Z level sequence")?;
+        Ok(SectionLayout { triples_ci, ci_len, bitmap_crcs, sequence_y, z_start, sequence_z })
+    }
+
+    /// Stored checksums of all parts, to identify the section without reading it.
+    pub const fn crcs(&self) -> [u32; 4] {
+        [self.bitmap_crcs[0], self.bitmap_crcs[1], self.sequence_y.crc32, self.sequence_z.crc32]
+    }
+}
+
+/// Index from predicate ID to the positions of its occurrences in the wavelet matrix, like the predicate index of hdt-java.
+/// Makes (?S,P,?O) queries independent of the select performance of the wavelet matrix, see [`TriplesBitmap::build_predicate_index`].
+pub struct PredicateIndex {
+    /// Positions in the wavelet matrix, grouped by predicate and ascending within each group.
+    pub positions: CompactVector,
+    /// Group boundaries, the occurrences of predicate ID p are at `positions[offsets[p - 1]..offsets[p]]`.
+    pub offsets: CompactVector,
+}
+
+impl fmt::Debug for PredicateIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} with {} positions of {} predicates",
+            ByteSize(self.size_in_bytes() as u64),
+            self.positions.len(),
+            self.offsets.len().saturating_sub(1)
+        )
+    }
+}
+
+impl PredicateIndex {
+    /// Index the positions of all predicates in the given wavelet matrix.
+    pub fn new(wavelet_y: &WaveletMatrix<Rank9Sel>) -> Result<Self> {
+        let predicates = (0..wavelet_y.len()).map(|pos_y| wavelet_y.access(pos_y).unwrap());
+        let mut starts = vec![0; wavelet_y.alph_size() + 1];
+        for p in predicates.clone() {
+            starts[p + 1] += 1;
+        }
+        for p in 1..starts.len() {
+            starts[p] += starts[p - 1];
+        }
+        // fill the groups in a second pass, starts[p] is the next free position of predicate ID p
+        let mut positions = vec![0; wavelet_y.len()];
+        for (pos_y, p) in predicates.enumerate() {
+            positions[starts[p]] = pos_y;
+            starts[p] += 1;
+        }
+        // now starts[p] is the end of the group of p, the last entry is the number of positions
+        starts.pop();
+        let compact = |values: &[usize]| {
+            CompactVector::from_slice(values).map_err(|err| eyre!("Failed to build predicate index: {err}"))
+        };
+        Ok(PredicateIndex { positions: compact(&positions)?, offsets: compact(&starts)? })
+    }
+
+    /// Range in `positions` of the occurrences of the given predicate ID, empty if it does not occur.
+    pub fn range(&self, p: Id) -> Range<usize> {
+        if p == 0 || p >= self.offsets.len() {
+            return 0..0;
+        }
+        self.offsets.get_int(p - 1).unwrap()..self.offsets.get_int(p).unwrap()
+    }
+
+    /// Size in bytes on the heap.
+    pub const fn size_in_bytes(&self) -> usize {
+        (self.positions.len() * self.positions.width() + self.offsets.len() * self.offsets.width()) / 8
+    }
+}
+
+/// Index structures that are built in memory, see [`IndexKind::estimate_memory`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexKind {
+    /// The [`OpIndex`] for ?PO and ??O patterns, built when loading unless mapped from an index file.
+    Object,
+    /// The wavelet matrix of the predicates in the Y level, built when loading.
+    Wavelet,
+    /// The optional [`PredicateIndex`] for ?P? patterns.
+    Predicate,
+}
+
+/// Estimated memory usage of an index in bytes, see [`IndexKind::estimate_memory`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndexMemory {
+    /// Size of the finished index on the heap.
+    pub size: usize,
+    /// Upper bound of the heap usage during construction, including the finished index and temporary buffers.
+    pub peak: usize,
+}
+
+impl IndexKind {
+    /// Estimate the memory needed to build this index from the number of triples, subject-predicate pairs, predicates and objects.
+    /// Only depends on these numbers and not on the machine or the allocator, so the result is the same wherever it is computed.
+    /// # Example
+    /// ```
+    /// use hdt::triples::IndexKind;
+    /// // 1 billion triples with 400 million subject-predicate pairs, 50,000 predicates and 300 million objects
+    /// let memory = IndexKind::Object.estimate_memory(1_000_000_000, 400_000_000, 50_000, 300_000_000);
+    /// assert!(memory.peak > memory.size);
+    /// ```
+    pub const fn estimate_memory(
+        self, triples: usize, pairs: usize, predicates: usize, objects: usize,
+    ) -> IndexMemory {
+        /// bytes of a compact vector with the given number of entries and bits per entry
+        const fn compact(len: usize, width: usize) -> usize {
+            (len * width).div_ceil(64) * 8
+        }
+        /// bits needed for values up to x, like `sucds::utils::needed_bits`
+        const fn needed_bits(x: usize) -> usize {
+            if x == 0 {
+                1
+            } else {
+                (usize::BITS - x.leading_zeros()) as usize
+            }
+        }
+        /// bytes of a bit vector with rank9 blocks of two words per 512 bits
+        const fn rank9(bits: usize) -> usize {
+            bits.div_ceil(64) * 8 + (bits.div_ceil(512) + 1) * 16
+        }
+        match self {
+            IndexKind::Object => {
+                // entries are positions in the Y level
+                let size = compact(triples, needed_bits(triples)) + rank9(triples);
+                // one vector of positions per object, starting with a capacity of 4 and at most doubled when growing
+                let buffers = objects * size_of::<Vec<u32>>() + (4 * objects + 2 * triples) * size_of::<u32>();
+                IndexMemory { size, peak: size + buffers }
+            }
+            IndexKind::Wavelet => {
+                let width = needed_bits(predicates);
+                // one bit vector per level with select hints every 1024 ones or zeros, built from the input and one working copy
+                let size = width * (rank9(pairs) + pairs.div_ceil(1024) * 8);
+                IndexMemory { size, peak: size + 2 * compact(pairs, width) }
+            }
+            IndexKind::Predicate => {
+                let width = needed_bits(pairs);
+                let size = compact(pairs, width) + compact(predicates + 1, width);
+                // uncompressed positions and group starts
+                IndexMemory { size, peak: size + (pairs + predicates + 2) * size_of::<usize>() }
+            }
         }
     }
 }
@@ -106,6 +378,10 @@ pub struct TriplesBitmap {
     pub op_index: OpIndex,
     /// wavelet matrix for predicate-based access
     pub wavelet_y: WaveletMatrix<Rank9Sel>,
+    /// Optional index for predicate-based access, see [`Self::build_predicate_index`].
+    pub predicate_index: Option<PredicateIndex>,
+    /// Graphs of the triples in quad files, see [`Self::quads_with_pattern`].
+    pub quad_info: Option<QuadInfo>,
 }
 
 impl fmt::Debug for TriplesBitmap {
@@ -113,6 +389,8 @@ impl fmt::Debug for TriplesBitmap {
         writeln!(f, "total size {}", ByteSize(self.size_in_bytes() as u64))?;
         writeln!(f, "adjlist_z {:#?}", self.adjlist_z)?;
         writeln!(f, "op_index {:#?}", self.op_index)?;
+        writeln!(f, "predicate_index {:?}", self.predicate_index)?;
+        writeln!(f, "quad_info {:?}", self.quad_info)?;
         write!(f, "wavelet_y {}", ByteSize(self.wavelet_y.size_in_bytes() as u64))
     }
 }
@@ -120,18 +398,111 @@ impl fmt::Debug for TriplesBitmap {
 impl TriplesBitmap {
     /// read the whole triple section including control information
     pub fn read_sect<R: BufRead>(reader: &mut R) -> Result<Self> {
+        Self::read_sect_with(reader, read_sequence, None)
+    }
+
+    /// Like [`Self::read_sect`] but the Z level sequence stays in the memory map.
+    /// The bitmaps and the Y level sequence are still read into memory, because the rank and select indexes and the wavelet matrix are built from them.
+    /// The object index is only built if none is given.
+    #[cfg(feature = "mmap")]
+    pub(crate) fn read_sect_mapped(
+        reader: &mut &[u8], map: &Arc<Mmap>, op_index: Option<OpIndex>,
+    ) -> Result<Self> {
+        Self::read_sect_with(reader, |r| read_sequence_mapped(r, map), op_index)
+    }
+
+    /// Like [`Self::read_sect_mapped`] but takes the bitmaps, the wavelet matrix and the object index from the given index
+    /// instead of building them, so that only the metadata of the section with the given layout is read and the Z level sequence is mapped.
+    #[cfg(feature = "mmap")]
+    pub(crate) fn read_sect_indexed(
+        section: &[u8], layout: &SectionLayout, map: &Arc<Mmap>, index: TriplesIndex,
+    ) -> Result<Self> {
+        let triples_ci = &layout.triples_ci;
+        if ![TRIPLES_BITMAP, TRIPLES_BITMAP_QUAD].contains(&&triples_ci.format[..]) {
+            let (format, supported) = (triples_ci.format.clone(), "only bitmap triples are supported");
+            return Err(HdtError::UnsupportedFormat { part: Part::Triples, format, supported }.into());
+        }
+        if triples_ci.format == TRIPLES_BITMAP_QUAD {
+            require_hdtq(Part::Triples, TRIPLES_BITMAP_QUAD)?;
+        }
+        let Some(n) = triples_ci.get("order").and_then(|v| v.parse::<u32>().ok()) else {
+            return Err(HdtError::invalid(Part::Triples, "missing or invalid order").into());
+        };
+        let order = Order::try_from(n)?;
+        let TriplesIndex { bitmap_y, bitmap_z, wavelet_y, op_index } = index;
+        let (entries_y, entries_z) = (layout.sequence_y.entries, layout.sequence_z.entries);
+        if wavelet_y.len() != entries_y
+            || op_index.sequence.len() != entries_z
+            || bitmap_y.len() < entries_y
+            || bitmap_z.len() < entries_z
+        {
+            let message =
+                format!("index for {} predicates and {} objects", wavelet_y.len(), op_index.sequence.len());
+            return Err(HdtError::invalid(Part::Index, message).into());
+        }
+        let mut reader = section.get(layout.z_start..).ok_or_else(|| HdtError::Invalid {
+            part: Part::Triples,
+            offset: Some(layout.z_start),
+            message: format!("Z level sequence beyond the end of the section of {} bytes", section.len()),
+        })?;
+        let sequence_z = read_sequence_mapped(&mut reader, map).wrap_err("Failed to read Z level sequence")?;
+        let quad_info = if triples_ci.format == TRIPLES_BITMAP_QUAD {
+            Some(QuadInfo::read(&mut reader).wrap_err("Failed to read quad information")?)
+        } else {
+            None
+        };
+        Ok(TriplesBitmap {
+            order,
+            bitmap_y,
+            adjlist_z: AdjList::new(sequence_z, bitmap_z),
+            op_index,
+            wavelet_y,
+            predicate_index: None,
+            quad_info,
+        })
+    }
+
+    /// Write the bitmaps, the wavelet matrix and the object index to an index file in the format expected by [`TriplesIndex::map`],
+    /// with the given fingerprint that identifies the HDT file it belongs to.
+    /// The rank and select indexes of the bitmaps and the wavelet matrix are written in the serialization format of sucds.
+    pub fn write_index<W: Write>(&self, writer: &mut W, fingerprint: &str) -> io::Result<()> {
+        let mut index_ci = ControlInfo::new(ControlType::Index, TRIPLES_INDEX);
+        index_ci.set("fingerprint", fingerprint);
+        index_ci.write(writer)?;
+        let op_index = &self.op_index;
+        // one bit per entry
+        op_index.bitmap.write(writer, op_index.sequence.len())?;
+        write_sequence(writer, (0..op_index.sequence.len()).map(|i| op_index.sequence.get(i)))?;
+        self.wavelet_y.serialize_into(&mut *writer).map_err(io::Error::other)?;
+        self.bitmap_y.dict.serialize_into(&mut *writer).map_err(io::Error::other)?;
+        self.adjlist_z.bitmap.dict.serialize_into(&mut *writer).map_err(io::Error::other)?;
+        Ok(())
+    }
+
+    fn read_sect_with<R: BufRead>(
+        reader: &mut R, read_z: impl FnOnce(&mut R) -> Result<Sequence>, op_index: Option<OpIndex>,
+    ) -> Result<Self> {
         let triples_ci = ControlInfo::read(reader)?;
 
         match &triples_ci.format[..] {
-            "<http://purl.org/HDT/hdt#triplesBitmap>" => TriplesBitmap::read(reader, &triples_ci),
-            "<http://purl.org/HDT/hdt#triplesList>" => Err(eyre!("Triples Lists are not supported yet.")),
-            _ => Err(eyre!("Unknown triples listing format.")),
+            TRIPLES_BITMAP | TRIPLES_BITMAP_QUAD => TriplesBitmap::read(reader, &triples_ci, read_z, op_index),
+            // such as "<http://purl.org/HDT/hdt#triplesList>"
+            _ => Err(HdtError::UnsupportedFormat {
+                part: Part::Triples,
+                format: triples_ci.format,
+                supported: "only bitmap triples are supported",
+            }
+            .into()),
         }
     }
 
     /// Size in bytes on the heap.
     pub fn size_in_bytes(&self) -> usize {
-        self.adjlist_z.size_in_bytes() + self.op_index.size_in_bytes() + self.wavelet_y.size_in_bytes()
+        self.adjlist_z.size_in_bytes()
+            + self.op_index.size_in_bytes()
+            + self.wavelet_y.size_in_bytes()
+            + self.predicate_index.as_ref().map_or(0, PredicateIndex::size_in_bytes)
+            + self.quad_info.as_ref().map_or(0, QuadInfo::size_in_bytes)
     }
 
     /// Position in the wavelet index of the first predicate for the given subject ID.
@@ -168,19 +539,29 @@ impl TriplesBitmap {
         self.bin_search_y(property_id, self.find_y(subject_id), self.last_y(subject_id) + 1)
     }
 
-    fn build_wavelet(mut sequence: Sequence) -> WaveletMatrix<Rank9Sel> {
+    fn build_wavelet(mut sequence: Sequence) -> Result<WaveletMatrix<Rank9Sel>, HdtError> {
         debug!("Building wavelet matrix...");
+        // fixed width sequences may use more bits than needed
+        let max = sequence.into_iter().max().unwrap_or(0);
+        let invalid = |e| HdtError::invalid(Part::Sequence, format!("Y level sequence: {e}"));
         let mut builder =
-            CompactVector::new(sequence.bits_per_entry).expect("Failed to create wavelet matrix builder");
+            CompactVector::new(sucds::utils::needed_bits(max).min(sequence.bits_per_entry())).map_err(invalid)?;
         // possible refactor of Sequence to use sucds CompactVector, then builder can be removed
         for x in &sequence {
-            builder.push_int(x).unwrap();
+            builder.push_int(x).map_err(invalid)?;
+        }
+        if !sequence.take_crc_handle().is_none_or(|h| h.join().unwrap_or(false)) {
+            return Err(HdtError::checksum(Part::Sequence, "CRC32C", "Y level sequence"));
         }
-        assert!(sequence.crc_handle.take().unwrap().join().unwrap(), "Wavelet source CRC check failed.");
         drop(sequence);
-        let wavelet = WaveletMatrix::new(builder).expect("Error building the wavelet matrix. Aborting.");
+        // sucds does not support building empty wavelet matrices
+        let wavelet = if builder.is_empty() {
+            WaveletMatrix::default()
+        } else {
+            WaveletMatrix::new(builder).map_err(invalid)?
+        };
         debug!("Built wavelet matrix with length {}", wavelet.len());
-        wavelet
+        Ok(wavelet)
     }
 
     /*
@@ -191,13 +572,19 @@ impl TriplesBitmap {
     }
     */
 
-    fn read<R: BufRead>(reader: &mut R, triples_ci: &ControlInfo) -> Result<Self> {
+    fn read<R: BufRead>(
+        reader: &mut R, triples_ci: &ControlInfo, read_z: impl FnOnce(&mut R) -> Result<Sequence>,
+        op_index: Option<OpIndex>,
+    ) -> Result<Self> {
+        if triples_ci.format == TRIPLES_BITMAP_QUAD {
+            require_hdtq(Part::Triples, TRIPLES_BITMAP_QUAD)?;
+        }
         // read order
         let order: Order;
         if let Some(n) = triples_ci.get("order").and_then(|v| v.parse::<u32>().ok()) {
             order = Order::try_from(n)?;
         } else {
-            return Err(eyre!("Unrecognized order"));
+            return Err(HdtError::invalid(Part::Triples, "missing or invalid order").into());
         }
 
         // read bitmaps
@@ -205,16 +592,181 @@ impl TriplesBitmap {
         let bitmap_z = Bitmap::read(reader).wrap_err("Failed to read Z level bitmap")?;
 
         // read sequences
-        let sequence_y = Sequence::read(reader)?;
+        let sequence_y = read_sequence(reader)?;
         let wavelet_thread = std::thread::spawn(|| Self::build_wavelet(sequence_y));
-        let mut sequence_z = Sequence::read(reader)?;
+        let sequence_z = read_z(reader)?;
+        let quad_info = if triples_ci.format == TRIPLES_BITMAP_QUAD {
+            Some(QuadInfo::read(reader).wrap_err("Failed to read quad information")?)
+        } else {
+            None
+        };
+        let mut triples = Self::from_parts(order, bitmap_y, bitmap_z, wavelet_thread, sequence_z, op_index)?;
+        triples.quad_info = quad_info;
+        Ok(triples)
+    }
+
+    /// Read a triples section like [`Self::read_sect`] but only check its consistency instead of building the indexes, see [`Hdt::validate`](crate::Hdt::validate).
+    /// For SPO order, the IDs are checked against the given numbers of subjects, predicates and objects of the dictionary.
+    /// Returns an error if the section cannot be parsed at all.
+    pub(crate) fn check_sect<R: BufRead>(reader: &mut R, terms: [usize; 3]) -> Result<Vec<HdtError>> {
+        let triples_ci = ControlInfo::read(reader)?;
+        if ![TRIPLES_BITMAP, TRIPLES_BITMAP_QUAD].contains(&&triples_ci.format[..]) {
+            let (format, supported) = (triples_ci.format, "only bitmap triples are supported");
+            return Err(HdtError::UnsupportedFormat { part: Part::Triples, format, supported }.into());
+        }
+        if triples_ci.format == TRIPLES_BITMAP_QUAD {
+            require_hdtq(Part::Triples, TRIPLES_BITMAP_QUAD)?;
+        }
+        let Some(n) = triples_ci.get("order").and_then(|v| v.parse::<u32>().ok()) else {
+            return Err(HdtError::invalid(Part::Triples, "missing or invalid order").into());
+        };
+        let order = Order::try_from(n)?;
+        let bitmap_y = Bitmap::read(reader).wrap_err("Failed to read Y level bitmap")?;
+        let bitmap_z = Bitmap::read(reader).wrap_err("Failed to read Z level bitmap")?;
+        let mut sequence_y = read_sequence(reader).wrap_err("Failed to read Y level sequence")?;
+        let mut sequence_z = read_sequence(reader).wrap_err("Failed to read Z level sequence")?;
+        let quad_info = if triples_ci.format == TRIPLES_BITMAP_QUAD {
+            Some(QuadInfo::read(reader).wrap_err("Failed to read quad information")?)
+        } else {
+            None
+        };
+
+        let mut problems = Vec::new();
+        let invalid = |message: String| HdtError::invalid(Part::Triples, message);
+        for (name, sequence) in [("Y level", &mut sequence_y), ("Z level", &mut sequence_z)] {
+            if !sequence.take_crc_handle().is_none_or(|h| h.join().unwrap_or(false)) {
+                problems.push(HdtError::checksum(Part::Sequence, "CRC32C", format!("{name} sequence")));
+            }
+            if sequence.bits_per_entry() == 0 && !sequence.is_empty() {
+                let message = format!("{name} sequence with {} entries of 0 bits", sequence.len());
+                problems.push(HdtError::invalid(Part::Sequence, message));
+            }
+        }
+        let ones = |bitmap: &Bitmap| bitmap.rank(bitmap.len());
+        // bitmaps are read in whole words, so they may have more bits than the sequence has entries
+        for (name, bitmap, sequence) in [("Y", &bitmap_y, &sequence_y), ("Z", &bitmap_z, &sequence_z)] {
+            let entries = sequence.len();
+            if bitmap.len() < entries || ones(bitmap) != bitmap.rank(entries) {
+                problems.push(invalid(format!(
+                    "{name} level bitmap does not match the {entries} entries of the sequence"
+                )));
+            } else if entries > 0 && !bitmap.get(entries - 1) {
+                problems.push(invalid(format!("{name} level bitmap does not end with a one bit")));
+            }
+        }
+        // each entry of the Y level has a list in the Z level
+        if ones(&bitmap_z) != sequence_y.len() {
+            let message = format!(
+                "{} lists in the Z level for {} entries of the Y level",
+                ones(&bitmap_z),
+                sequence_y.len()
+            );
+            problems.push(invalid(message));
+        }
+        if let Some(quad_info) = &quad_info {
+            for graph_id in 1..=quad_info.num_graphs() {
+                let bits = quad_info.graph(graph_id).map_or(0, Bitmap::len);
+                if bits < sequence_z.len() {
+                    let message =
+                        format!("bitmap of graph {graph_id} with {bits} bits for {} triples", sequence_z.len());
+                    problems.push(invalid(message));
+                }
+            }
+        }
+        if !problems.is_empty() {
+            // the entries may not be accessible
+            return Ok(problems);
+        }
+        let [subjects, predicates, objects] = terms;
+        let spo = matches!(order, Order::SPO);
+        if spo && ones(&bitmap_y) > subjects {
+            problems.push(invalid(format!("{} subjects but only {subjects} in the dictionary", ones(&bitmap_y))));
+        }
+        for (name, sequence, bitmap, max) in [
+            ("Y", &sequence_y, &bitmap_y, spo.then_some(predicates)),
+            ("Z", &sequence_z, &bitmap_z, spo.then_some(objects)),
+        ] {
+            // IDs start at 1 and are strictly increasing within each list, which the binary searches rely on
+            let (mut out_of_range, mut unsorted) = (None, None);
+            let mut previous = 0;
+            for pos in 0..sequence.len() {
+                let id = sequence.get(pos);
+                if id == 0 || max.is_some_and(|max| id > max) {
+                    out_of_range.get_or_insert((pos, id));
+                }
+                if pos > 0 && !bitmap.get(pos - 1) && id <= previous {
+                    unsorted.get_or_insert(pos);
+                }
+                previous = id;
+            }
+            if let Some((pos, id)) = out_of_range {
+                let max = max.map(|max| format!(" of at most {max}")).unwrap_or_default();
+                problems.push(invalid(format!(
+                    "{name} level entry {pos} is ID {id} instead of an ID{max} starting at 1"
+                )));
+            }
+            if let Some(pos) = unsorted {
+                problems.push(invalid(format!(
+                    "{name} level entry {pos} is not greater than its predecessor in the same list"
+                )));
+            }
+        }
+        Ok(problems)
+    }
 
+    /// Triples section of the given order from its bitmaps and sequences, building the wavelet matrix and the object index.
+    /// The Y level contains the predicate IDs of each subject and the Z level the object IDs of each subject-predicate pair,
+    /// where a one bit in the bitmaps marks the last entry of each list.
+    pub fn new(
+        order: Order, bitmap_y: Bitmap, bitmap_z: Bitmap, sequence_y: Sequence, sequence_z: Sequence,
+    ) -> Result<Self> {
+        let wavelet_thread = std::thread::spawn(|| Self::build_wavelet(sequence_y));
+        Self::from_parts(order, bitmap_y, bitmap_z, wavelet_thread, sequence_z, None)
+    }
+
+    fn from_parts(
+        order: Order, bitmap_y: Bitmap, bitmap_z: Bitmap,
+        wavelet_thread: JoinHandle<Result<WaveletMatrix<Rank9Sel>, HdtError>>, mut sequence_z: Sequence,
+        op_index: Option<OpIndex>,
+    ) -> Result<Self> {
+        let (op_index, wavelet_y) = match op_index {
+            Some(op_index) if op_index.sequence.len() != sequence_z.len() => {
+                let message = format!(
+                    "object index with {} entries does not match the {} triples",
+                    op_index.sequence.len(),
+                    sequence_z.len()
+                );
+                return Err(HdtError::invalid(Part::Index, message).into());
+            }
+            Some(op_index) => (op_index, join_wavelet(wavelet_thread)?),
+            None => Self::build_op_index(&sequence_z, &bitmap_z, wavelet_thread)?,
+        };
+        if !sequence_z.take_crc_handle().is_none_or(|h| h.join().unwrap_or(false)) {
+            return Err(HdtError::checksum(Part::Sequence, "CRC32C", "Z level sequence").into());
+        }
         // construct adjacency lists
+        let adjlist_z = AdjList::new(sequence_z, bitmap_z);
+        Ok(TriplesBitmap {
+            order,
+            bitmap_y,
+            adjlist_z,
+            op_index,
+            wavelet_y,
+            predicate_index: None,
+            quad_info: None,
+        })
+    }
+
+    /// Build the object index while the wavelet matrix is built in the given thread, which is joined to sort by predicate.
+    fn build_op_index(
+        sequence_z: &Sequence, bitmap_z: &Bitmap,
+        wavelet_thread: JoinHandle<Result<WaveletMatrix<Rank9Sel>, HdtError>>,
+    ) -> Result<(OpIndex, WaveletMatrix<Rank9Sel>)> {
         // construct object-based index to traverse from the leaves and support ??O and ?PO queries
         debug!("Building OPS index...");
-        let entries = sequence_z.entries;
+        let entries = sequence_z.len();
         // if it takes too long to calculate, can also pass in as parameter
-        let max_object = sequence_z.into_iter().max().unwrap().to_owned();
+        let max_object = sequence_z.into_iter().max().unwrap_or(0);
         // limited to < 2^32 objects
         let mut indicess = vec![Vec::<u32>::with_capacity(4); max_object];
 
@@ -233,9 +785,8 @@ impl TriplesBitmap {
         }
         // reduce memory consumption of index by using adjacency list
         let mut bitmap_index_bitvector = BitVector::new();
-        let mut cv = CompactVector::with_capacity(entries, sucds::utils::needed_bits(entries))
-            .map_err(|err| eyre!(Box::new(err)))?;
-        let wavelet_y = wavelet_thread.join().unwrap();
+        let mut positions = LogSequence::with_capacity(sucds::utils::needed_bits(entries), entries);
+        let wavelet_y = join_wavelet(wavelet_thread)?;
         /*
         let get_p = |pos_z: u32| {
             let pos_y = bitmap_z.dict.rank(pos_z.to_owned() as u64, true);
@@ -249,15 +800,408 @@ impl TriplesBitmap {
             for index in indices {
                 bitmap_index_bitvector.push_bit(first);
                 first = false;
-                cv.push_int(index as usize).unwrap();
+                positions.push(index as usize);
             }
         }
         let bitmap_index = Bitmap { dict: Rank9Sel::new(bitmap_index_bitvector) };
-        let op_index = OpIndex { sequence: cv, bitmap: bitmap_index };
+        let op_index = OpIndex { sequence: Sequence::Log(positions), bitmap: bitmap_index };
         debug!("built OPS index");
-        assert!(sequence_z.crc_handle.take().unwrap().join().unwrap(), "sequence_z CRC check failed.");
-        let adjlist_z = AdjList::new(sequence_z, bitmap_z);
-        Ok(TriplesBitmap { order, bitmap_y, adjlist_z, op_index, wavelet_y })
+        Ok((op_index, wavelet_y))
+    }
+
+    /// Iterator over all triple IDs that fit the given pattern, where 0 stands for a variable.
+    /// Chooses the appropriate iterator for the pattern shape, subject-bound patterns use [`SubjectIter`].
+    /// # Example
+    /// ```text
+    /// // all triples with predicate ID 2 and object ID 3
+    /// triples.triples_with_pattern(&TripleId::new(0, 2, 3));
+    /// ```
+    pub fn triples_with_pattern(&self, pat: &TripleId) -> Box<dyn Iterator<Item = TripleId> + '_> {
+        pattern_iter(self, pat)
+    }
+
+    /// The k-th triple, counting from 0, in the results of [`Self::triples_with_pattern`] for the given pattern.
+    /// Jumps directly to the position with select and rank queries instead of iterating over the preceding results,
+    /// except for S?O patterns, whose results are not contiguous, and for ?P? patterns, which skip over whole subjects.
+    /// # Example
+    /// ```text
+    /// // the 1000th triple with predicate ID 2 and object ID 3, for example to start a result page
+    /// triples.nth_result(&TripleId::new(0, 2, 3), 999);
+    /// ```
+    pub fn nth_result(&self, pat: &TripleId, k: usize) -> Option<TripleId> {
+        let (s, p, o) = (pat.subject_id, pat.predicate_id, pat.object_id);
+        match (s != 0, p != 0, o != 0) {
+            (true, _, _) => SubjectIter::with_pattern(self, pat).nth(k),
+            (false, true, true) => PredicateObjectIter::new(self, p, o).nth(k).map(|s| TripleId::new(s, p, o)),
+            (false, true, false) => PredicateIter::new(self, p).nth(k),
+            (false, false, true) => ObjectIter::new(self, o).nth(k),
+            (false, false, false) => (k < self.len()).then(|| self.triple_at(k)),
+        }
+    }
+
+    /// Like [`Self::triples_with_pattern`] but the position can be saved as a [`ResumeToken`] to continue later with [`Self::resume`],
+    /// for example to split a long export of all triples with the pattern (0, 0, 0) over several runs.
+    /// # Example
+    /// ```text
+    /// let mut cursor = triples.cursor(&TripleId::new(0, 2, 0));
+    /// let page: Vec<_> = cursor.by_ref().take(1000).collect();
+    /// std::fs::write("token.txt", cursor.token().to_string())?;
+    /// ```
+    pub fn cursor(&self, pat: &TripleId) -> Cursor<'_> {
+        Cursor::new(self, pat)
+    }
+
+    /// Continue iterating over the results of a pattern after the position of the given token, see [`Self::cursor`].
+    /// Results in an error if the token does not fit this file.
+    /// # Example
+    /// ```text
+    /// let token: ResumeToken = std::fs::read_to_string("token.txt")?.parse()?;
+    /// let next_page: Vec<_> = triples.resume(&token)?.take(1000).collect();
+    /// ```
+    pub fn resume(&self, token: &ResumeToken) -> Result<Cursor<'_>, ResumeError> {
+        Cursor::resume(self, token)
+    }
+
+    /// Like [`Self::triples_with_pattern`] but in reverse order, such as descending subject IDs for patterns without a constant object.
+    /// Iterates from the end using rank and select queries, so the first results are available without materializing the whole result.
+    /// # Example
+    /// ```text
+    /// // the last 10 triples with predicate ID 2
+    /// triples.triples_with_pattern_rev(&TripleId::new(0, 2, 0)).take(10);
+    /// ```
+    pub fn triples_with_pattern_rev(&self, pat: &TripleId) -> Box<dyn Iterator<Item = TripleId> + '_> {
+        Box::new(pattern_iter(self, pat).rev())
+    }
+
+    /// Like [`Self::triples_with_pattern`] but without the triples that are marked as deleted in the given tombstones.
+    /// For patterns whose results are contiguous in the Z level, such as (S,?,?), each result is checked with a single bit access,
+    /// otherwise its position is located first.
+    /// # Example
+    /// ```text
+    /// let mut tombstones = Tombstones::new(triples.len());
+    /// tombstones.delete(&triples, &TripleId::new(1, 2, 3));
+    /// triples.triples_with_pattern_except(&TripleId::new(1, 0, 0), &tombstones);
+    /// ```
+    pub fn triples_with_pattern_except<'a>(
+        &'a self, pat: &TripleId, tombstones: &'a Tombstones,
+    ) -> Box<dyn Iterator<Item = TripleId> + 'a> {
+        if let Some(range) = self.contiguous_range_z(pat) {
+            return Box::new(
+                range.filter(|&pos_z| !tombstones.is_deleted_at(pos_z)).map(|pos_z| self.triple_at(pos_z)),
+            );
+        }
+        Box::new(pattern_iter(self, pat).filter(|t| {
+            !tombstones.is_deleted_at(self.position(t).expect("result of a triple pattern not found"))
+        }))
+    }
+
+    /// Write the triples section including its control information in the format expected by [`Self::read_sect`].
+    /// The predicate sequence is extracted from the wavelet matrix and the object index is not written, because both are built when reading.
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let mut triples_ci = ControlInfo::new(ControlType::Triples, self.format());
+        triples_ci.set("order", &(self.order.clone() as u8).to_string());
+        triples_ci.write(writer)?;
+        // the bitmaps are padded to whole words in memory
+        self.bitmap_y.write(writer, self.wavelet_y.len())?;
+        self.adjlist_z.bitmap.write(writer, self.adjlist_z.len())?;
+        write_sequence(writer, (0..self.wavelet_y.len()).map(|pos_y| self.wavelet_y.access(pos_y).unwrap()))?;
+        // the objects keep the width they were read with, which may be larger than needed
+        let objects = (0..self.adjlist_z.len()).map(|pos_z| self.adjlist_z.sequence.get(pos_z));
+        write_sequence_min_bits(writer, objects, self.adjlist_z.sequence.bits_per_entry())?;
+        if let Some(quad_info) = &self.quad_info {
+            quad_info.write(writer, self.len())?;
+        }
+        Ok(())
+    }
+
+    /// Format of the triples control information, which depends on whether there is graph information.
+    pub const fn format(&self) -> &'static str {
+        if self.quad_info.is_some() {
+            TRIPLES_BITMAP_QUAD
+        } else {
+            TRIPLES_BITMAP
+        }
+    }
+
+    /// Position of the given triple in the Z level, None if it does not exist or contains a variable.
+    pub fn position(&self, t: &TripleId) -> Option<usize> {
+        let (s, p, o) = (t.subject_id, t.predicate_id, t.object_id);
+        if s == 0 || p == 0 || o == 0 || s > self.bitmap_y.rank(self.bitmap_y.len()) {
+            return None;
+        }
+        let pos_y = self.search_y(s - 1, p)?;
+        self.adjlist_z.search(pos_y, o)
+    }
+
+    /// Iterator over all quad IDs that fit the given triple pattern in the graph with the given ID, where 0 stands for a variable.
+    /// A triple that is contained in several graphs results in one quad for each of them.
+    /// Files without graph information only contain the default graph, whose quads have the graph ID 0.
+    /// # Example
+    /// ```text
+    /// // all triples with predicate ID 2 in the graph with ID 3
+    /// triples.quads_with_pattern(&TripleId::new(0, 2, 0), 3);
+    /// ```
+    pub fn quads_with_pattern(&self, pat: &TripleId, graph_id: Id) -> Box<dyn Iterator<Item = QuadId> + '_> {
+        let Some(quad_info) = &self.quad_info else {
+            if graph_id != 0 {
+                return Box::new(iter::empty());
+            }
+            return Box::new(self.triples_with_pattern(pat).map(|t| QuadId::from_triple(t, 0)));
+        };
+        if graph_id == 0 {
+            return Box::new(self.positions_with_pattern(pat).flat_map(move |pos_z| {
+                let t = self.triple_at(pos_z);
+                quad_info.graphs_at(pos_z).map(move |graph_id| QuadId::from_triple(t, graph_id))
+            }));
+        }
+        let Some(bitmap) = quad_info.graph(graph_id) else {
+            return Box::new(iter::empty());
+        };
+        if let Some(range) = self.contiguous_range_z(pat) {
+            // the triples of the graph in the range, found with select queries
+            let start = bitmap.rank(range.start.min(bitmap.len()));
+            return Box::new(
+                (start..)
+                    .map_while(|k| bitmap.select1(k))
+                    .take_while(move |&pos_z| pos_z < range.end)
+                    .map(move |pos_z| QuadId::from_triple(self.triple_at(pos_z), graph_id)),
+            );
+        }
+        Box::new(
+            self.positions_with_pattern(pat)
+                .filter(|&pos_z| bitmap.get(pos_z))
+                .map(move |pos_z| QuadId::from_triple(self.triple_at(pos_z), graph_id)),
+        )
+    }
+
+    /// Positions in the Z level of the triples that fit the given pattern, where 0 stands for a variable.
+    /// Follows the order of [`Self::triples_with_pattern`] but avoids looking up the position of each result.
+    pub(crate) fn positions_with_pattern(&self, pat: &TripleId) -> Box<dyn Iterator<Item = usize> + '_> {
+        if let Some(range) = self.contiguous_range_z(pat) {
+            return Box::new(range);
+        }
+        let (s, p, o) = (pat.subject_id, pat.predicate_id, pat.object_id);
+        if s != 0 {
+            if p != 0 {
+                return Box::new(self.position(pat).into_iter());
+            }
+            // (S,?P,O): the object among the objects of each predicate of the subject
+            if s > self.bitmap_y.rank(self.bitmap_y.len()) {
+                return Box::new(iter::empty());
+            }
+            let range_y = self.find_y(s - 1)..self.find_y(s);
+            return Box::new(range_y.filter_map(move |pos_y| self.adjlist_z.search(pos_y, o)));
+        }
+        if o != 0 {
+            // (?S,P,O) and (?S,?P,O): the positions in the Y level from the object index
+            if o > self.op_index.bitmap.rank(self.op_index.bitmap.len()) {
+                return Box::new(iter::empty());
+            }
+            let range = self.op_index.find(o)..self.op_index.last(o) + 1;
+            return Box::new(
+                range
+                    .map(|i| self.op_index.sequence.get(i))
+                    .filter(move |&pos_y| p == 0 || self.wavelet_y.access(pos_y) == Some(p))
+                    .filter_map(move |pos_y| self.adjlist_z.search(pos_y, o)),
+            );
+        }
+        // (?S,P,?O): all objects of the occurrences of the predicate in the Y level
+        let positions_y: Box<dyn Iterator<Item = usize>> = if let Some(index) = &self.predicate_index {
+            Box::new(index.range(p).map(|i| index.positions.get_int(i).unwrap()))
+        } else {
+            let occs = self.wavelet_y.rank(self.wavelet_y.len(), p).unwrap_or(0);
+            Box::new((0..occs).map(move |i| self.wavelet_y.select(i, p).unwrap()))
+        };
+        Box::new(positions_y.flat_map(|pos_y| self.adjlist_z.find(pos_y)..self.adjlist_z.find(pos_y + 1)))
+    }
+
+    /// Build the [`PredicateIndex`], which is then used by [`PredicateIter`] for (?S,P,?O) patterns instead of select queries on the wavelet matrix.
+    /// Takes about as long as iterating over all predicates and needs one integer per subject-predicate pair.
+    /// Does nothing if the index already exists.
+    pub fn build_predicate_index(&mut self) -> Result<()> {
+        if self.predicate_index.is_none() {
+            self.predicate_index = Some(PredicateIndex::new(&self.wavelet_y)?);
+        }
+        Ok(())
+    }
+
+    /// Order of the triples section.
+    pub const fn order(&self) -> &Order {
+        &self.order
+    }
+
+    /// Number of triples.
+    pub const fn len(&self) -> usize {
+        self.adjlist_z.len()
+    }
+
+    /// The triple at the given position in the order of the triples section, counting from 0.
+    /// Panics if the position is out of bounds.
+    pub fn triple_at(&self, pos_z: usize) -> TripleId {
+        let z = self.adjlist_z.get_id(pos_z);
+        let pos_y = self.adjlist_z.bitmap.rank(pos_z);
+        let y = self.wavelet_y.access(pos_y).unwrap() as Id;
+        let x = self.bitmap_y.rank(pos_y) as Id + 1;
+        self.coord_to_triple(x, y, z).unwrap()
+    }
+
+    /// Positions in the Z level of the triples with the given subject, empty if there is no such subject.
+    pub fn subject_range_z(&self, subject_id: Id) -> Range<usize> {
+        if subject_id == 0 || subject_id > self.bitmap_y.rank(self.bitmap_y.len()) {
+            return 0..0;
+        }
+        let min_z = self.adjlist_z.find(self.find_y(subject_id - 1) as Id);
+        let max_z = self.adjlist_z.find(self.find_y(subject_id) as Id);
+        min_z..max_z
+    }
+
+    /// Number of triples with the given subject, 0 if there is no such subject.
+    pub fn subject_degree(&self, subject_id: Id) -> usize {
+        self.subject_range_z(subject_id).len()
+    }
+
+    /// Number of triples with the given object, 0 if there is no such object, counted with two select queries on the object index.
+    pub fn object_degree(&self, object_id: Id) -> usize {
+        if object_id == 0 || object_id > self.op_index.bitmap.rank(self.op_index.bitmap.len()) {
+            return 0;
+        }
+        self.op_index.last(object_id) + 1 - self.op_index.find(object_id)
+    }
+
+    /// Uniform random sample of `n` triples drawn with replacement, reproducible with the same seed.
+    /// Each triple is located with rank queries, so sampling is fast even for huge graphs.
+    pub const fn sample(&self, n: usize, seed: u64) -> SampleIter<'_> {
+        SampleIter::new(self, n, seed)
+    }
+
+    /// Number of distinct subjects in the results of the given ID pattern, where 0 stands for a variable.
+    /// Uses rank queries where possible. For (?S,?P,O), the results of each predicate are sorted by subject,
+    /// so these runs are merged, which needs memory proportional to the number of results, and no hashing is performed.
+    pub fn count_distinct_subjects(&self, pat: &TripleId) -> usize {
+        let (s, p, o) = (pat.subject_id, pat.predicate_id, pat.object_id);
+        match (s != 0, p != 0, o != 0) {
+            (true, _, _) => usize::from(self.triples_with_pattern(pat).next().is_some()),
+            // each subject occurs at most once per predicate in the wavelet matrix
+            (false, true, false) => self.wavelet_y.rank(self.wavelet_y.len(), p).unwrap_or(0),
+            // each subject occurs at most once for a given predicate-object pair
+            (false, true, true) => PredicateObjectIter::new(self, p, o).count(),
+            // sorted by subject within each predicate but not across predicates
+            (false, false, true) => count_distinct_runs(ObjectIter::new(self, o).map(|t| t.subject_id)),
+            // the Y bitmap has a one bit for the last predicate of each subject
+            (false, false, false) => self.bitmap_y.rank(self.bitmap_y.len()),
+        }
+    }
+
+    /// Number of distinct objects in the results of the given ID pattern, where 0 stands for a variable.
+    /// Uses rank queries where possible and no hashing. (S,?P,?O) sorts the objects of the subject.
+    /// (?S,P,?O) marks the objects in a bit vector with one bit per object in the dictionary, which is allocated on each call,
+    /// because the results of a frequent predicate may be much more than the objects.
+    pub fn count_distinct_objects(&self, pat: &TripleId) -> usize {
+        let (s, p, o) = (pat.subject_id, pat.predicate_id, pat.object_id);
+        match (s != 0, p != 0, o != 0) {
+            (_, _, true) => usize::from(self.triples_with_pattern(pat).next().is_some()),
+            // objects of a subject-predicate pair are sorted and unique
+            (true, true, false) => SubjectIter::with_pattern(self, pat).count(),
+            // the results for a single subject are small enough to sort
+            (true, false, false) => {
+                let mut objects: Vec<Id> = SubjectIter::with_s(self, s).map(|t| t.object_id).collect();
+                objects.sort_unstable();
+                objects.dedup();
+                objects.len()
+            }
+            (false, true, false) => count_distinct(
+                PredicateIter::new(self, p).map(|t| t.object_id),
+                self.op_index.bitmap.rank(self.op_index.bitmap.len()),
+            ),
+            // the OP index has a one bit for the first position of each object
+            (false, false, false) => self.op_index.bitmap.rank(self.op_index.bitmap.len()),
+        }
+    }
+
+    /// Number of results of the given ID pattern, where 0 stands for a variable, giving up once the time budget is spent.
+    /// Patterns that [`Self::count_pattern`] counts exactly are answered by it regardless of the budget,
+    /// the (?S,P,?O) and (S,?P,O) patterns by iterating over their results, which yields a lower bound if the budget runs out.
+    /// Intended for metadata such as the estimated result size of Triple Pattern Fragments, which should not cause unbounded work.
+    ///
+    /// Of the ways to count the results of a pattern, [`Self::count_pattern`] is the cheapest and suits query planning,
+    /// this method gives the exact count within a time budget and `triples_with_pattern(pat).count()` always gives the exact count.
+    /// # Example
+    /// ```text
+    /// // all triples with predicate ID 2, which may be too many to count in time
+    /// let count = triples.count_with_pattern_bounded(&TripleId::new(0, 2, 0), Duration::from_millis(10));
+    /// ```
+    pub fn count_with_pattern_bounded(&self, pat: &TripleId, budget: Duration) -> BoundedCount {
+        let cardinality = self.count_pattern(pat);
+        if cardinality.exact {
+            return BoundedCount { count: cardinality.count, at_least: false };
+        }
+        // Duration::MAX does not fit into an Instant
+        let deadline = Instant::now().checked_add(budget);
+        let mut results = self.triples_with_pattern(pat);
+        let mut count: usize = 0;
+        loop {
+            // only check the time every few results, as it is more expensive than advancing the iterator
+            if count.is_multiple_of(BOUNDED_COUNT_INTERVAL) && deadline.is_some_and(|d| Instant::now() >= d) {
+                return BoundedCount { count, at_least: true };
+            }
+            if results.next().is_none() {
+                return BoundedCount { count, at_least: false };
+            }
+            count += 1;
+        }
+    }
+
+    /// Number of results of the given ID pattern, where 0 stands for a variable, without iterating over any results.
+    /// Exact for all patterns except (?S,P,?O), which assumes the average number of objects per subject-predicate pair of the whole triples section,
+    /// and (S,?P,O), which is estimated with the upper bound of the smaller of the number of predicates of the subject and the number of triples with the object.
+    /// Intended for join planners that order triple patterns by their selectivity, see [`Self::count_with_pattern_bounded`] for exact counts.
+    /// # Example
+    /// ```text
+    /// // all triples with predicate ID 2
+    /// let cardinality = triples.count_pattern(&TripleId::new(0, 2, 0));
+    /// ```
+    pub fn count_pattern(&self, pat: &TripleId) -> Cardinality {
+        let (s, p, o) = (pat.subject_id, pat.predicate_id, pat.object_id);
+        let exact = |count| Cardinality { count, exact: true };
+        match (s != 0, p != 0, o != 0) {
+            (true, true, true) => exact(usize::from(self.position(pat).is_some())),
+            // the OP index has one entry per triple with the given object
+            (false, false, true) => exact(self.object_degree(o)),
+            // the OP index is sorted by predicate within each object, 0 for objects past the end of the dictionary
+            (false, true, true) if self.object_degree(o) == 0 => exact(0),
+            (false, true, true) => exact(PredicateObjectIter::new(self, p, o).len()),
+            (false, true, false) => {
+                // the wavelet matrix ignores the bits of larger IDs than it was built for
+                if sucds::utils::needed_bits(p) > self.wavelet_y.alph_width() {
+                    return exact(0);
+                }
+                // each occurrence of the predicate in the Y level is a subject-predicate pair with at least one object
+                let pairs = self.wavelet_y.rank(self.wavelet_y.len(), p).unwrap_or(0);
+                let (len, len_y) = (self.len(), self.wavelet_y.len());
+                if pairs == 0 || len == len_y {
+                    return exact(pairs);
+                }
+                let estimate = (pairs as u128 * len as u128 / len_y as u128) as usize;
+                Cardinality { count: estimate.max(pairs), exact: false }
+            }
+            (true, false, true) => {
+                if s > self.bitmap_y.rank(self.bitmap_y.len()) {
+                    return exact(0);
+                }
+                // each subject-predicate pair contains the object at most once
+                let pairs = self.find_y(s) - self.find_y(s - 1);
+                // 0 for objects past the end of the dictionary
+                let occurrences = self.object_degree(o);
+                if occurrences == 0 {
+                    return exact(0);
+                }
+                Cardinality { count: pairs.min(occurrences), exact: false }
+            }
+            // the results are contiguous in the Z level, so they are counted with rank and select queries
+            (true, false | true, false) | (false, false, false) => {
+                exact(self.contiguous_range_z(pat).map_or(0, |range| range.len()))
+            }
+        }
     }
 
     /// Transform the given IDs of the layers in triple section order to a triple ID.
@@ -265,7 +1209,8 @@ impl TriplesBitmap {
     /// Other orders may lead to undefined behaviour.
     pub fn coord_to_triple(&self, x: Id, y: Id, z: Id) -> Result<TripleId> {
         if x == 0 || y == 0 || z == 0 {
-            return Err(eyre!(format!("({x},{y},{z}) none of the components of a triple may be 0."),));
+            let message = format!("({x},{y},{z}) none of the components of a triple may be 0");
+            return Err(HdtError::invalid(Part::Triples, message).into());
         }
         match self.order {
             Order::SPO => Ok(TripleId::new(x, y, z)),
@@ -274,11 +1219,73 @@ impl TriplesBitmap {
             Order::POS => Ok(TripleId::new(y, z, x)),
             Order::OSP => Ok(TripleId::new(z, x, y)),
             Order::OPS => Ok(TripleId::new(z, y, x)),
-            Order::Unknown => Err(eyre!("unknown triples order")),
+            Order::Unknown => Err(HdtError::invalid(Part::Triples, "unknown triples order").into()),
         }
     }
 }
 
+/// Like [`TriplesBitmap::triples_with_pattern`] but generic over how the triples are held.
+/// With [`SharedTriples`](crate::hdt::SharedTriples), the iterator owns its data and can be moved across threads and await points.
+pub fn pattern_iter<'a, T: Borrow<TriplesBitmap> + Send + 'a>(
+    triples: T, pat: &TripleId,
+) -> Box<dyn DoubleEndedIterator<Item = TripleId> + Send + 'a> {
+    let (s, p, o) = (pat.subject_id, pat.predicate_id, pat.object_id);
+    match (s != 0, p != 0, o != 0) {
+        (true, _, _) => Box::new(SubjectIterOf::with_pattern(triples, pat)),
+        (false, true, true) => {
+            Box::new(PredicateObjectIterOf::new(triples, p, o).map(move |s| TripleId::new(s, p, o)))
+        }
+        (false, true, false) => Box::new(PredicateIterOf::new(triples, p)),
+        (false, false, true) => Box::new(ObjectIterOf::new(triples, o)),
+        (false, false, false) => Box::new(SubjectIterOf::new(triples)),
+    }
+}
+
+/// Wait for the thread that builds the wavelet matrix, with an error instead of a panic if the thread panicked.
+fn join_wavelet(thread: JoinHandle<Result<WaveletMatrix<Rank9Sel>, HdtError>>) -> Result<WaveletMatrix<Rank9Sel>> {
+    Ok(thread.join().map_err(|_| eyre!("the thread building the wavelet matrix panicked"))??)
+}
+
+/// Count the distinct IDs from 1 to max using a bit vector instead of a hash set, which allocates `max` bits.
+fn count_distinct(ids: impl Iterator<Item = Id>, max: Id) -> usize {
+    let mut seen = vec![0_u64; max / 64 + 1];
+    let mut count = 0;
+    for id in ids {
+        let (word, bit) = (id / 64, 1 << (id % 64));
+        if seen[word] & bit == 0 {
+            seen[word] |= bit;
+            count += 1;
+        }
+    }
+    count
+}
+
+/// Count the distinct IDs of a sequence of ascending runs by merging the runs with a heap of one entry per run.
+fn count_distinct_runs(ids: impl Iterator<Item = Id>) -> usize {
+    let ids: Vec<Id> = ids.collect();
+    // each entry is the next ID of a run with its position and the end of the run
+    let mut heap = BinaryHeap::new();
+    let mut start = 0;
+    for end in 1..=ids.len() {
+        if end == ids.len() || ids[end] <= ids[end - 1] {
+            heap.push(Reverse((ids[start], start, end)));
+            start = end;
+        }
+    }
+    let (mut count, mut last) = (0, 0);
+    while let Some(Reverse((id, pos, end))) = heap.pop() {
+        // IDs start at 1
+        if id != last {
+            count += 1;
+            last = id;
+        }
+        if pos + 1 < end {
+            heap.push(Reverse((ids[pos + 1], pos + 1, end)));
+        }
+    }
+    count
+}
+
 impl<'a> IntoIterator for &'a TriplesBitmap {
     type Item = TripleId;
     type IntoIter = SubjectIter<'a>;
@@ -288,6 +1295,24 @@ impl<'a> IntoIterator for &'a TriplesBitmap {
     }
 }
 
+/// Result size of a triple pattern as determined by [`TriplesBitmap::count_with_pattern_bounded`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoundedCount {
+    /// Exact number of results or, if `at_least` is set, a lower bound.
+    pub count: usize,
+    /// Whether the time budget ran out, so that there may be more results than `count`.
+    pub at_least: bool,
+}
+
+/// Number of results of a triple pattern as determined by [`TriplesBitmap::count_pattern`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cardinality {
+    /// Number of results, exact or estimated.
+    pub count: usize,
+    /// Whether `count` is the exact number of results instead of an estimate.
+    pub exact: bool,
+}
+
 /// Subject, predicate or object ID, starting at 1.
 ///
 /// Subjects and predicate share IDs, starting at 1, for common values.
@@ -298,7 +1323,7 @@ pub type Id = usize;
 
 /// Type for a triple encoded as numeric IDs for subject, predicate and object, respectively.
 /// See <https://www.rdfhdt.org/hdt-binary-format/#triples>.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct TripleId {
     /// Index starting at 1 in the combined shared and subject section.
     pub subject_id: Id,
@@ -320,10 +1345,11 @@ mod tests {
     use super::*;
     use crate::header::Header;
     use crate::tests::init;
-    use crate::{FourSectDict, IdKind};
+    use crate::{DictSectPFC, FourSectDict, IdKind};
     use pretty_assertions::assert_eq;
     use std::fs::File;
     use std::io::BufReader;
+    use std::iter::FusedIterator;
 
     /// Iterator over all triples with a given ID in the specified position (subject, predicate or object).
     fn triples_with_id<'a>(
@@ -336,6 +1362,309 @@ mod tests {
         }
     }
 
+    /// Exhaust the iterator and check that it keeps returning `None` afterwards.
+    fn assert_fused(mut iter: impl FusedIterator<Item = impl fmt::Debug>) {
+        for _ in iter.by_ref() {}
+        for _ in 0..3 {
+            assert!(iter.next().is_none());
+        }
+    }
+
+    #[test]
+    fn fused_iterators() {
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let triples = crate::Hdt::new(BufReader::new(file)).unwrap().triples;
+        let v: Vec<TripleId> = triples.into_iter().collect();
+        // S?O, including subjects without the object, which are skipped in a loop
+        for s in 1..48 {
+            let expected: Vec<_> = v.iter().filter(|t| t.subject_id == s && t.object_id == 154).copied().collect();
+            assert_eq!(
+                expected,
+                SubjectIter::with_pattern(&triples, &TripleId::new(s, 0, 154)).collect::<Vec<_>>()
+            );
+        }
+        assert_fused(SubjectIter::new(&triples));
+        assert_fused(SubjectIter::with_pattern(&triples, &TripleId::new(14, 0, 154)));
+        assert_fused(SubjectIter::empty(&triples));
+        assert_fused(PredicateIter::new(&triples, 14));
+        assert_fused(ObjectIter::new(&triples, 154));
+        assert_fused(PredicateObjectIter::new(&triples, 14, 154));
+        // not found
+        assert_fused(PredicateObjectIter::new(&triples, 1, 154));
+        assert_fused(triples.sample(10, 1));
+    }
+
+    #[test]
+    fn subject_ranges() {
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let triples = crate::Hdt::new(BufReader::new(file)).unwrap().triples;
+        let v: Vec<TripleId> = triples.into_iter().collect();
+        for range in [0..1, 1..1, 1..5, 5..20, 20..48, 40..100, 60..70] {
+            let expected: Vec<_> = v.iter().filter(|t| range.contains(&t.subject_id)).copied().collect();
+            assert_eq!(
+                expected,
+                SubjectIter::with_subjects(&triples, range.clone()).collect::<Vec<_>>(),
+                "{range:?}"
+            );
+        }
+        let chunks: Vec<_> =
+            (0..10).flat_map(|i| SubjectIter::with_subjects(&triples, i * 5 + 1..i * 5 + 6)).collect();
+        assert_eq!(v, chunks);
+    }
+
+    /// Consume the iterator alternately from the front and the back, which must result in the same order as forward iteration.
+    fn alternate<I: DoubleEndedIterator>(mut iter: I) -> Vec<I::Item> {
+        let (mut front, mut back) = (Vec::new(), Vec::new());
+        loop {
+            match iter.next() {
+                Some(x) => front.push(x),
+                None => break,
+            }
+            match iter.next_back() {
+                Some(x) => back.push(x),
+                None => break,
+            }
+        }
+        front.extend(back.into_iter().rev());
+        front
+    }
+
+    #[test]
+    fn reverse_iterators() {
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let hdt = crate::Hdt::new(BufReader::new(file)).unwrap();
+        let triples = &hdt.triples;
+        let v: Vec<TripleId> = triples.into_iter().collect();
+        let mut patterns = vec![TripleId::new(0, 0, 0), TripleId::new(14, 0, 154), TripleId::new(12, 14, 154)];
+        for t in &v {
+            patterns.extend([
+                TripleId::new(t.subject_id, 0, 0),
+                TripleId::new(t.subject_id, t.predicate_id, 0),
+                TripleId::new(t.subject_id, t.predicate_id, t.object_id),
+                TripleId::new(0, t.predicate_id, 0),
+                TripleId::new(0, t.predicate_id, t.object_id),
+                TripleId::new(0, 0, t.object_id),
+            ]);
+        }
+        patterns.sort_unstable();
+        patterns.dedup();
+        for pat in patterns.clone() {
+            let forward: Vec<_> = triples.triples_with_pattern(&pat).collect();
+            let mut backward: Vec<_> = triples.triples_with_pattern_rev(&pat).collect();
+            backward.reverse();
+            assert_eq!(forward, backward, "{pat:?}");
+            assert_eq!(forward, alternate(pattern_iter(triples, &pat)), "{pat:?}");
+        }
+        for pat in &patterns {
+            let forward: Vec<_> = triples.triples_with_pattern(pat).collect();
+            for k in 0..=forward.len() {
+                assert_eq!(forward.get(k).copied(), triples.nth_result(pat, k), "{pat:?} {k}");
+            }
+            // skipping after partial consumption
+            let mut iter = pattern_iter(triples, pat);
+            iter.next();
+            assert_eq!(forward.get(3).copied(), iter.nth(2), "{pat:?}");
+            assert_eq!(forward.get(4).copied(), iter.next(), "{pat:?}");
+        }
+        // latest first
+        let last: Vec<_> = triples.triples_with_pattern_rev(&TripleId::new(0, 0, 0)).take(3).collect();
+        assert_eq!(v.iter().rev().take(3).copied().collect::<Vec<_>>(), last);
+    }
+
+    #[test]
+    fn predicate_index() {
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let mut hdt = crate::Hdt::new(BufReader::new(file)).unwrap();
+        let predicates = 1..=hdt.dict.predicates.num_strings();
+        let without: Vec<Vec<_>> =
+            predicates.clone().map(|p| PredicateIter::new(&hdt.triples, p).collect()).collect();
+        assert!(hdt.triples.predicate_index.is_none());
+        let size = hdt.triples.size_in_bytes();
+        hdt.triples.build_predicate_index().unwrap();
+        assert!(hdt.triples.size_in_bytes() > size);
+        let index = hdt.triples.predicate_index.as_ref().unwrap();
+        assert_eq!(hdt.triples.wavelet_y.len(), index.positions.len());
+        assert_eq!(0..0, index.range(0));
+        assert_eq!(0..0, index.range(predicates.end() + 1));
+        for (p, expected) in predicates.zip(without) {
+            assert_eq!(expected.len(), hdt.triples.triples_with_pattern(&TripleId::new(0, p, 0)).count());
+            let forward: Vec<_> = PredicateIter::new(&hdt.triples, p).collect();
+            assert_eq!(expected, forward, "{p}");
+            let mut backward: Vec<_> = PredicateIter::new(&hdt.triples, p).rev().collect();
+            backward.reverse();
+            assert_eq!(expected, backward, "{p}");
+            assert_eq!(expected, alternate(PredicateIter::new(&hdt.triples, p)), "{p}");
+            for k in 0..=expected.len() {
+                assert_eq!(expected.get(k).copied(), PredicateIter::new(&hdt.triples, p).nth(k), "{p} {k}");
+            }
+        }
+    }
+
+    #[test]
+    fn count_bounded() {
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let hdt = crate::Hdt::new(BufReader::new(file)).unwrap();
+        let triples = &hdt.triples;
+        for t in triples.into_iter().step_by(7) {
+            for mask in 0..8 {
+                let pat = TripleId::new(
+                    if mask & 1 == 0 { 0 } else { t.subject_id },
+                    if mask & 2 == 0 { 0 } else { t.predicate_id },
+                    if mask & 4 == 0 { 0 } else { t.object_id },
+                );
+                let expected = triples.triples_with_pattern(&pat).count();
+                let count = triples.count_with_pattern_bounded(&pat, Duration::MAX);
+                assert_eq!(BoundedCount { count: expected, at_least: false }, count, "{pat:?}");
+                // patterns without iteration stay exact, the others are a lower bound
+                let count = triples.count_with_pattern_bounded(&pat, Duration::ZERO);
+                assert!(count.count <= expected, "{pat:?}");
+                // ?P? and S?O iterate over their results, ?PO is counted with a binary search in the OP index
+                assert_eq!(matches!(mask, 2 | 5), count.at_least, "{pat:?}");
+            }
+        }
+        let label = Some("http://www.w3.org/2000/01/rdf-schema#label");
+        let count = hdt.count_with_pattern_bounded(None, label, None, Duration::MAX);
+        assert_eq!(hdt.triples_with_pattern(None, label, None).count(), count.count);
+        assert!(!count.at_least);
+        let count = hdt.count_with_pattern_bounded(Some("doesnotexist"), None, None, Duration::ZERO);
+        assert_eq!(BoundedCount { count: 0, at_least: false }, count);
+        // IDs past the end of the dictionary
+        for pat in [TripleId::new(100_000, 1, 0), TripleId::new(0, 0, 100_000)] {
+            assert_eq!(
+                BoundedCount { count: 0, at_least: false },
+                triples.count_with_pattern_bounded(&pat, Duration::MAX)
+            );
+        }
+    }
+
+    #[test]
+    fn count_pattern() {
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let hdt = crate::Hdt::new(BufReader::new(file)).unwrap();
+        let triples = &hdt.triples;
+        for t in triples.into_iter().step_by(7) {
+            for mask in 0..8 {
+                let pat = TripleId::new(
+                    if mask & 1 == 0 { 0 } else { t.subject_id },
+                    if mask & 2 == 0 { 0 } else { t.predicate_id },
+                    if mask & 4 == 0 { 0 } else { t.object_id },
+                );
+                let expected = triples.triples_with_pattern(&pat).count();
+                let cardinality = hdt.count_pattern(&pat);
+                if cardinality.exact {
+                    assert_eq!(expected, cardinality.count, "{pat:?}");
+                } else {
+                    // ?P? and S?O are estimated
+                    assert!(matches!(mask, 2 | 5), "{pat:?}");
+                    assert!(cardinality.count > 0, "{pat:?}");
+                }
+                if mask == 5 {
+                    assert!(cardinality.count >= expected, "{pat:?}");
+                }
+            }
+        }
+        assert_eq!(Cardinality { count: 0, exact: true }, triples.count_pattern(&TripleId::new(1000, 0, 1)));
+        assert_eq!(Cardinality { count: 0, exact: true }, triples.count_pattern(&TripleId::new(0, 1000, 0)));
+        assert_eq!(Cardinality { count: 0, exact: true }, triples.count_pattern(&TripleId::new(1, 0, 100_000)));
+    }
+
+    #[test]
+    fn quads() {
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let mut hdt = crate::Hdt::new(BufReader::new(file)).unwrap();
+        let all = TripleId::new(0, 0, 0);
+        let v: Vec<_> = hdt.triples.triples_with_pattern(&all).collect();
+        for (pos_z, t) in v.iter().enumerate() {
+            assert_eq!(Some(pos_z), hdt.triples.position(t));
+        }
+        assert_eq!(None, hdt.triples.position(&TripleId::new(12, 14, 154)));
+        assert_eq!(None, hdt.triples.position(&TripleId::new(1000, 1, 1)));
+        // without graph information, all triples are in the default graph
+        assert_eq!(v.len(), hdt.triples.quads_with_pattern(&all, 0).filter(|q| q.graph_id == 0).count());
+        assert_eq!(0, hdt.triples.quads_with_pattern(&all, 1).count());
+
+        // graph 1 contains the triples at even positions, graph 2 the first 10 triples
+        let even = Bitmap::from_bits((0..v.len()).map(|pos_z| pos_z % 2 == 0));
+        let first = Bitmap::from_bits((0..v.len()).map(|pos_z| pos_z < 10));
+        hdt.dict.graphs = Some(DictSectPFC::new(&["http://example.org/even", "http://example.org/first"], 16));
+        hdt.triples.quad_info = Some(QuadInfo::new(vec![even, first]).unwrap());
+        let triples = &hdt.triples;
+        assert_eq!(2, triples.quad_info.as_ref().unwrap().num_graphs());
+
+        let expected: Vec<_> = v.iter().step_by(2).map(|t| QuadId::from_triple(*t, 1)).collect();
+        assert_eq!(expected, triples.quads_with_pattern(&all, 1).collect::<Vec<_>>());
+        let expected: Vec<_> = v[..10].iter().map(|t| QuadId::from_triple(*t, 2)).collect();
+        assert_eq!(expected, triples.quads_with_pattern(&all, 2).collect::<Vec<_>>());
+        assert_eq!(0, triples.quads_with_pattern(&all, 3).count());
+        let quads: Vec<_> = triples.quads_with_pattern(&all, 0).collect();
+        assert_eq!(v.len().div_ceil(2) + 10, quads.len());
+        assert_eq!(
+            vec![1, 2],
+            quads.iter().filter(|q| q.triple() == v[0]).map(|q| q.graph_id).collect::<Vec<_>>()
+        );
+        assert!(quads.iter().all(|q| q.graph_id != 0));
+        let pat = TripleId::new(v[0].subject_id, 0, 0);
+        for graph_id in 0..=2 {
+            let expected: Vec<_> = quads
+                .iter()
+                .filter(|q| q.subject_id == pat.subject_id && (graph_id == 0 || q.graph_id == graph_id))
+                .copied()
+                .collect();
+            let mut actual: Vec<_> = triples.quads_with_pattern(&pat, graph_id).collect();
+            actual.sort_unstable_by_key(|q| (q.triple(), q.graph_id));
+            assert_eq!(expected, actual, "{graph_id}");
+        }
+        for pat in [TripleId::new(0, v[0].predicate_id, 0), TripleId::new(0, 0, v[0].object_id), v[0]] {
+            let fits = |q: &&QuadId| {
+                [(pat.subject_id, q.subject_id), (pat.predicate_id, q.predicate_id), (pat.object_id, q.object_id)]
+                    .iter()
+                    .all(|&(x, y)| x == 0 || x == y)
+            };
+            let expected: Vec<_> = quads.iter().filter(fits).copied().collect();
+            let mut actual: Vec<_> = triples.quads_with_pattern(&pat, 0).collect();
+            actual.sort_unstable_by_key(|q| (q.triple(), q.graph_id));
+            assert_eq!(expected, actual, "{pat:?}");
+        }
+    }
+
+    #[test]
+    fn quad_file() {
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let mut hdt = crate::Hdt::new(BufReader::new(file)).unwrap();
+        let len = hdt.triples.len();
+        let even = Bitmap::from_bits((0..len).map(|pos_z| pos_z % 2 == 0));
+        let first = Bitmap::from_bits((0..len).map(|pos_z| pos_z < 10));
+        hdt.dict.graphs = Some(DictSectPFC::new(&["http://example.org/even", "http://example.org/first"], 16));
+        hdt.triples.quad_info = Some(QuadInfo::new(vec![even, first]).unwrap());
+        let all = TripleId::new(0, 0, 0);
+        let quads: Vec<_> = hdt.triples.quads_with_pattern(&all, 0).collect();
+        // the layout of quad files is only read with the experimental hdtq feature
+        let mut buf = Vec::new();
+        hdt.write_to(&mut buf, "http://example.org/dataset").unwrap();
+        let result = crate::Hdt::new(buf.as_slice());
+        if !cfg!(feature = "hdtq") {
+            let e = result.unwrap_err();
+            assert!(matches!(
+                HdtError::find(e.as_ref()),
+                Some(HdtError::UnsupportedFormat { part: Part::Dictionary, .. })
+            ));
+            return;
+        }
+        let read = result.unwrap();
+        assert_eq!("<http://purl.org/HDT/hdt#dictionaryFourQuad>", read.capabilities().dictionary);
+        assert_eq!("<http://purl.org/HDT/hdt#triplesBitmapQuad>", read.capabilities().triples);
+        assert_eq!(2, read.dict.graphs.as_ref().unwrap().num_strings());
+        assert_eq!(quads, read.triples.quads_with_pattern(&all, 0).collect::<Vec<_>>());
+    }
+
     #[test]
     fn read_triples() {
         init();
@@ -345,6 +1674,8 @@ mod tests {
         Header::read(&mut reader).unwrap();
         let _dict = FourSectDict::read(&mut reader).unwrap();
         let triples = TriplesBitmap::read_sect(&mut reader).unwrap();
+        // written by another HDT implementation with log arrays (sequence type 1)
+        assert!(matches!(triples.adjlist_z.sequence, Sequence::Log(_)));
         let v: Vec<TripleId> = triples.into_iter().collect::<Vec<TripleId>>();
         assert_eq!(v.len(), 328);
         assert_eq!(v[0].subject_id, 1);
@@ -390,5 +1721,52 @@ mod tests {
         assert_eq!(v, SubjectIter::with_pattern(&triples, &TripleId::new(0, 0, 0)).collect::<Vec<_>>());
         // SP? where S and P are in the graph, but not together
         assert_eq!(0, SubjectIter::with_pattern(&triples, &TripleId::new(12, 14, 154)).count());
+
+        // random access and sampling
+        assert_eq!(v.len(), triples.len());
+        for (i, t) in v.iter().enumerate() {
+            assert_eq!(*t, triples.triple_at(i));
+        }
+        let sample: Vec<TripleId> = triples.sample(1000, 42).collect();
+        assert_eq!(1000, sample.len());
+        assert_eq!(sample, triples.sample(1000, 42).collect::<Vec<_>>(), "same seed, different sample");
+        assert!(sample.iter().all(|t| v.binary_search(t).is_ok()));
+        // with 1000 samples out of 328 triples, most triples should be drawn at least once
+        let mut distinct = sample.clone();
+        distinct.sort_unstable();
+        distinct.dedup();
+        assert!(distinct.len() > 250, "only {} distinct triples sampled", distinct.len());
+
+        // pattern dispatch for all eight pattern shapes
+        for t in [v[0], v[100], v[327]] {
+            for mask in 0..8 {
+                let pat = TripleId::new(
+                    if mask & 4 != 0 { t.subject_id } else { 0 },
+                    if mask & 2 != 0 { t.predicate_id } else { 0 },
+                    if mask & 1 != 0 { t.object_id } else { 0 },
+                );
+                let mut expected: Vec<TripleId> = v
+                    .iter()
+                    .filter(|x| {
+                        (pat.subject_id == 0 || pat.subject_id == x.subject_id)
+                            && (pat.predicate_id == 0 || pat.predicate_id == x.predicate_id)
+                            && (pat.object_id == 0 || pat.object_id == x.object_id)
+                    })
+                    .copied()
+                    .collect();
+                let mut actual = triples.triples_with_pattern(&pat).collect::<Vec<_>>();
+                expected.sort_unstable();
+                actual.sort_unstable();
+                assert_eq!(expected, actual, "triples_with_pattern({pat:?})");
+                let distinct = |f: fn(&TripleId) -> Id| {
+                    let mut ids: Vec<Id> = expected.iter().map(f).collect();
+                    ids.sort_unstable();
+                    ids.dedup();
+                    ids.len()
+                };
+                assert_eq!(distinct(|t| t.subject_id), triples.count_distinct_subjects(&pat), "{pat:?}");
+                assert_eq!(distinct(|t| t.object_id), triples.count_distinct_objects(&pat), "{pat:?}");
+            }
+        }
     }
 }
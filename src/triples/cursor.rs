@@ -0,0 +1,178 @@
+use crate::triples::{Id, ObjectIter, PredicateIter, PredicateObjectIter, SubjectIter, TripleId, TriplesBitmap};
+use std::fmt;
+use std::iter::FusedIterator;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Position in the results of a triple pattern, which can be stored and used to resume the iteration later,
+/// for example after a restart of a long running export or crawl, see [`TriplesBitmap::resume`].
+/// Consists of IDs only, which stay the same as long as the HDT file does not change.
+/// Serialized as text with [`fmt::Display`] and parsed with [`FromStr`], such as `0 2 0 3 5 2 8`
+/// for the pattern (?, 2, ?) after 3 results, the last of which was (5, 2, 8).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ResumeToken {
+    /// Triple pattern, where 0 stands for a variable.
+    pub pattern: TripleId,
+    /// Number of results that have already been returned.
+    pub offset: usize,
+    /// The last result that has been returned, used to detect a changed file when resuming. None if there has been none.
+    pub last: Option<TripleId>,
+}
+
+/// The error type for parsing and resuming a [`ResumeToken`].
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum ResumeError {
+    /// The text is not a serialized resume token.
+    #[error("invalid resume token '{0}', expected the pattern and offset optionally followed by the last triple as IDs separated by spaces")]
+    Invalid(String),
+    /// The result before the position of the token is not the last result stored in the token.
+    #[error("result {offset} of the resume token is {actual:?} instead of {expected:?}, the file has changed")]
+    Mismatch {
+        /// Number of results before the position of the token.
+        offset: usize,
+        /// Last result according to the token.
+        expected: Option<TripleId>,
+        /// Actual result at that position, None if there are fewer results.
+        actual: Option<TripleId>,
+    },
+}
+
+impl fmt::Display for ResumeToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pat = &self.pattern;
+        write!(f, "{} {} {} {}", pat.subject_id, pat.predicate_id, pat.object_id, self.offset)?;
+        if let Some(t) = &self.last {
+            write!(f, " {} {} {}", t.subject_id, t.predicate_id, t.object_id)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for ResumeToken {
+    type Err = ResumeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ResumeError::Invalid(s.to_owned());
+        let numbers: Vec<usize> =
+            s.split_whitespace().map(str::parse).collect::<Result<_, _>>().map_err(|_| invalid())?;
+        let triple = |ids: &[Id]| TripleId::new(ids[0], ids[1], ids[2]);
+        match numbers.len() {
+            4 if numbers[3] == 0 => Ok(ResumeToken { pattern: triple(&numbers), offset: 0, last: None }),
+            7 if numbers[3] > 0 => Ok(ResumeToken {
+                pattern: triple(&numbers),
+                offset: numbers[3],
+                last: Some(triple(&numbers[4..])),
+            }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Iterator over the results of a triple pattern that keeps track of its position, see [`Self::token`].
+pub struct Cursor<'a> {
+    inner: Box<dyn Iterator<Item = TripleId> + 'a>,
+    token: ResumeToken,
+}
+
+impl<'a> Cursor<'a> {
+    /// Iterate over the results of the given pattern from the beginning.
+    pub fn new(triples: &'a TriplesBitmap, pat: &TripleId) -> Self {
+        Cursor {
+            inner: triples.triples_with_pattern(pat),
+            token: ResumeToken { pattern: *pat, offset: 0, last: None },
+        }
+    }
+
+    /// Continue after the results already returned according to the given token.
+    /// Skips the returned results with the same select and rank queries as [`TriplesBitmap::nth_result`], instead of iterating over them.
+    /// Results in an error if the last returned result differs from the one in the token, which means that the file has changed.
+    pub fn resume(triples: &'a TriplesBitmap, token: &ResumeToken) -> Result<Self, ResumeError> {
+        let pat = token.pattern;
+        let (s, p, o) = (pat.subject_id, pat.predicate_id, pat.object_id);
+        // iterator positioned before the last result
+        let mut inner: Box<dyn Iterator<Item = TripleId> + 'a> = match (s != 0, p != 0, o != 0) {
+            (true, _, _) => skipped(SubjectIter::with_pattern(triples, &pat), token.offset),
+            (false, true, true) => Box::new(
+                skipped(PredicateObjectIter::new(triples, p, o), token.offset)
+                    .map(move |s| TripleId::new(s, p, o)),
+            ),
+            (false, true, false) => skipped(PredicateIter::new(triples, p), token.offset),
+            (false, false, true) => skipped(ObjectIter::new(triples, o), token.offset),
+            (false, false, false) => skipped(SubjectIter::new(triples), token.offset),
+        };
+        if token.offset > 0 {
+            let actual = inner.next();
+            if actual != token.last {
+                return Err(ResumeError::Mismatch { offset: token.offset, expected: token.last, actual });
+            }
+        }
+        Ok(Cursor { inner, token: *token })
+    }
+
+    /// Position after the results returned so far, which can be stored and passed to [`Self::resume`] later.
+    pub const fn token(&self) -> ResumeToken {
+        self.token
+    }
+}
+
+/// Skip all but the last of the given number of results, so that the last returned result can be checked.
+fn skipped<'a, I: Iterator + 'a>(mut iter: I, offset: usize) -> Box<dyn Iterator<Item = I::Item> + 'a> {
+    if offset > 1 {
+        // nth is implemented with select and rank queries by the pattern iterators
+        let _ = iter.nth(offset - 2);
+    }
+    Box::new(iter)
+}
+
+impl Iterator for Cursor<'_> {
+    type Item = TripleId;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let t = self.inner.next()?;
+        self.token.offset += 1;
+        self.token.last = Some(t);
+        Some(t)
+    }
+}
+
+impl FusedIterator for Cursor<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::init;
+    use crate::Hdt;
+    use pretty_assertions::assert_eq;
+    use std::fs::File;
+
+    #[test]
+    fn resume() {
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let hdt = Hdt::new(std::io::BufReader::new(file)).unwrap();
+        let triples = &hdt.triples;
+        let t = triples.triple_at(100);
+        for pat in [
+            TripleId::new(0, 0, 0),
+            TripleId::new(t.subject_id, 0, 0),
+            TripleId::new(t.subject_id, t.predicate_id, 0),
+            TripleId::new(t.subject_id, 0, t.object_id),
+            TripleId::new(0, t.predicate_id, 0),
+            TripleId::new(0, t.predicate_id, t.object_id),
+            TripleId::new(0, 0, t.object_id),
+        ] {
+            let all: Vec<_> = triples.triples_with_pattern(&pat).collect();
+            for k in 0..=all.len() {
+                let mut cursor = triples.cursor(&pat);
+                assert_eq!(all[..k], cursor.by_ref().take(k).collect::<Vec<_>>());
+                let token: ResumeToken = cursor.token().to_string().parse().unwrap();
+                assert_eq!(cursor.token(), token);
+                assert_eq!(all[k..], triples.resume(&token).unwrap().collect::<Vec<_>>(), "{pat:?} {k}");
+            }
+        }
+        let token = ResumeToken { pattern: TripleId::new(0, 0, 0), offset: 1, last: Some(t) };
+        assert!(matches!(triples.resume(&token), Err(ResumeError::Mismatch { .. })));
+        assert!("1 2 3".parse::<ResumeToken>().is_err());
+        assert!("0 0 0 1".parse::<ResumeToken>().is_err());
+    }
+}
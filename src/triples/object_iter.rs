@@ -1,7 +1,8 @@
 use crate::triples::Id;
 use crate::triples::TripleId;
 use crate::triples::TriplesBitmap;
-use sucds::int_vectors::Access;
+use std::borrow::Borrow;
+use std::iter::FusedIterator;
 
 // see "Exchange and Consumption of Huge RDF Data" by Martinez et al. 2012
 // https://link.springer.com/chapter/10.1007/978-3-642-30284-8_36
@@ -9,36 +10,68 @@ use sucds::int_vectors::Access;
 // TODO test with other orders and fix if broken
 
 /// Iterator over all triples with a given object ID, answering an (?S,?P,O) query.
-pub struct ObjectIter<'a> {
-    triples: &'a TriplesBitmap,
+pub type ObjectIter<'a> = ObjectIterOf<&'a TriplesBitmap>;
+
+/// [`ObjectIter`] that is generic over how the triples are held, such as [`SharedTriples`](crate::hdt::SharedTriples) for an iterator that owns its data.
+pub struct ObjectIterOf<T> {
+    triples: T,
     o: Id,
     pos_index: usize,
-    max_index: usize,
+    // exclusive
+    end_index: usize,
 }
 
-impl<'a> ObjectIter<'a> {
+impl<T: Borrow<TriplesBitmap>> ObjectIterOf<T> {
     /// Create a new iterator over all triples with the given object ID.
     /// Panics if the object does not exist.
-    pub fn new(triples: &'a TriplesBitmap, o: Id) -> Self {
+    pub fn new(triples: T, o: Id) -> Self {
         assert!(o != 0, "object 0 does not exist, cant iterate");
-        let pos_index = triples.op_index.find(o);
-        let max_index = triples.op_index.last(o);
-        //println!("ObjectIter o={} pos_index={} max_index={}", o, pos_index, max_index);
-        ObjectIter { triples, o, pos_index, max_index }
+        let t: &TriplesBitmap = triples.borrow();
+        let pos_index = t.op_index.find(o);
+        let end_index = t.op_index.last(o) + 1;
+        //println!("ObjectIter o={} pos_index={} end_index={}", o, pos_index, end_index);
+        ObjectIterOf { triples, o, pos_index, end_index }
     }
 }
 
-impl Iterator for ObjectIter<'_> {
+impl<T: Borrow<TriplesBitmap>> Iterator for ObjectIterOf<T> {
     type Item = TripleId;
     fn next(&mut self) -> Option<Self::Item> {
-        if self.pos_index > self.max_index {
+        if self.pos_index >= self.end_index {
             return None;
         }
-        let pos_y = self.triples.op_index.sequence.access(self.pos_index).unwrap();
-        let y = self.triples.wavelet_y.access(pos_y).unwrap() as Id;
-        let x = self.triples.bitmap_y.rank(pos_y) as Id + 1;
+        let t = self.triple(self.pos_index);
         self.pos_index += 1;
-        Some(TripleId::new(x, y, self.o))
+        Some(t)
+    }
+
+    /// Jumps directly to the position in the object index.
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.pos_index = self.pos_index.saturating_add(n).min(self.end_index);
+        self.next()
+    }
+}
+
+impl<T: Borrow<TriplesBitmap>> DoubleEndedIterator for ObjectIterOf<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.pos_index >= self.end_index {
+            return None;
+        }
+        self.end_index -= 1;
+        Some(self.triple(self.end_index))
+    }
+}
+
+impl<T: Borrow<TriplesBitmap>> ObjectIterOf<T> {
+    /// Triple at the given position in the object index.
+    fn triple(&self, pos_index: usize) -> TripleId {
+        let triples: &TriplesBitmap = self.triples.borrow();
+        let pos_y = triples.op_index.sequence.get(pos_index);
+        let y = triples.wavelet_y.access(pos_y).unwrap() as Id;
+        let x = triples.bitmap_y.rank(pos_y) as Id + 1;
+        TripleId::new(x, y, self.o)
         //Some(self.triples.coord_to_triple(x, y, self.o).unwrap())
     }
 }
+
+impl<T: Borrow<TriplesBitmap>> FusedIterator for ObjectIterOf<T> {}
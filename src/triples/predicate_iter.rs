@@ -1,54 +1,156 @@
 use crate::triples::Id;
 use crate::triples::TripleId;
 use crate::triples::TriplesBitmap;
+use std::borrow::Borrow;
+use std::iter::FusedIterator;
+use std::ops::Range;
+
+/// Maximum distance in the wavelet matrix between consecutive occurrences of the predicate for which scanning is used.
+/// Within this distance, accessing each position is cheaper than a select query.
+const SCAN_GAP: usize = 8;
 
 /// Iterator over all triples with a given property ID, answering an (?S,P,?O) query.
-pub struct PredicateIter<'a> {
-    triples: &'a TriplesBitmap,
-    s: Id,
+/// Iterates in ascending subject order and in descending order from the back.
+/// Forward iteration adapts to the frequency of the predicate: Occurrences are located with select queries,
+/// but while they are dense, the iterator switches to scanning the following positions of the wavelet matrix.
+/// A scan that does not find the next occurrence within a few positions falls back to a select query,
+/// so the worst case per result is bounded in both modes.
+/// If the [`PredicateIndex`](crate::triples::PredicateIndex) has been built, it is used instead of the wavelet matrix to locate the occurrences.
+/// Object-bound patterns need no such switch, because the object index stores the occurrences of each object contiguously and sorted by predicate,
+/// so even an extremely frequent object costs one binary search and then constant time per result, see the `adaptive` benchmark.
+pub type PredicateIter<'a> = PredicateIterOf<&'a TriplesBitmap>;
+
+/// [`PredicateIter`] that is generic over how the triples are held, such as [`SharedTriples`](crate::hdt::SharedTriples) for an iterator that owns its data.
+pub struct PredicateIterOf<T> {
+    triples: T,
     p: Id,
-    i: usize,
-    os: usize,
-    pos_z: usize,
-    occs: usize,
+    // occurrences of the predicate in the wavelet matrix that are not yet opened by either end,
+    // counted from the start of the group of the predicate in the predicate index if it exists
+    occs: Range<usize>,
+    // subject and remaining positions in the Z level of the occurrence opened by the front
+    front: (Id, Range<usize>),
+    // subject and remaining positions in the Z level of the occurrence opened by the back
+    back: (Id, Range<usize>),
+    // position in the wavelet matrix of the occurrence opened last by the front
+    front_pos_y: Option<usize>,
+    scanning: bool,
 }
 
-impl<'a> PredicateIter<'a> {
+impl<T: Borrow<TriplesBitmap>> PredicateIterOf<T> {
     /// Create a new iterator over all triples with the given property ID.
     /// Panics if the object does not exist.
-    pub fn new(triples: &'a TriplesBitmap, p: Id) -> Self {
+    pub fn new(triples: T, p: Id) -> Self {
         assert!(p != 0, "object 0 does not exist, cant iterate");
-        let occs = triples.wavelet_y.rank(triples.wavelet_y.len(), p as usize).unwrap();
+        let t: &TriplesBitmap = triples.borrow();
+        let occs = match &t.predicate_index {
+            Some(index) => index.range(p),
+            None => 0..t.wavelet_y.rank(t.wavelet_y.len(), p as usize).unwrap(),
+        };
         //println!("the predicate {} is used by {} subjects in the index", p, occs);
-        PredicateIter { triples, p, i: 0, pos_z: 0, os: 0, s: 0, occs }
+        PredicateIterOf { triples, p, occs, front: (0, 0..0), back: (0, 0..0), front_pos_y: None, scanning: false }
+    }
+
+    /// Subject and positions in the Z level of the i-th occurrence of the predicate.
+    fn occurrence(&self, i: usize) -> (Id, Range<usize>) {
+        let triples: &TriplesBitmap = self.triples.borrow();
+        self.occurrence_at(self.select(triples, i))
+    }
+
+    /// Position in the wavelet matrix of the i-th occurrence of the predicate.
+    fn select(&self, triples: &TriplesBitmap, i: usize) -> usize {
+        match &triples.predicate_index {
+            Some(index) => index.positions.get_int(i).unwrap(),
+            // Algorithm 1 findSubj from Martinez et al. 2012 ******
+            None => triples.wavelet_y.select(i, self.p as usize).unwrap(),
+        }
+    }
+
+    /// Like [`Self::occurrence`] for the occurrence following the one opened last by the front.
+    /// Scans instead of selecting while the occurrences are dense.
+    fn next_occurrence(&mut self, i: usize) -> (Id, Range<usize>) {
+        let triples: &TriplesBitmap = self.triples.borrow();
+        let scanned = match self.front_pos_y {
+            // positions from the index are cheaper than scanning
+            _ if triples.predicate_index.is_some() => None,
+            Some(prev) if self.scanning => {
+                let end = (prev + 1 + SCAN_GAP).min(triples.wavelet_y.len());
+                (prev + 1..end).find(|&pos_y| triples.wavelet_y.access(pos_y) == Some(self.p as usize))
+            }
+            _ => None,
+        };
+        let pos_y = scanned.unwrap_or_else(|| self.select(triples, i));
+        self.scanning = self.front_pos_y.is_some_and(|prev| pos_y - prev <= SCAN_GAP);
+        self.front_pos_y = Some(pos_y);
+        self.occurrence_at(pos_y)
+    }
+
+    /// Subject and positions in the Z level of the occurrence at the given position in the wavelet matrix.
+    fn occurrence_at(&self, pos_y: usize) -> (Id, Range<usize>) {
+        let triples: &TriplesBitmap = self.triples.borrow();
+        let s = triples.bitmap_y.rank(pos_y) as Id + 1;
+        // *****************************************************
+        // SP can have multiple O
+        let pos_z = triples.adjlist_z.find(pos_y as Id);
+        let pos_z_end = triples.adjlist_z.last(pos_y as Id);
+        (s, pos_z..pos_z_end + 1)
+    }
+
+    fn triple(&self, s: Id, pos_z: usize) -> TripleId {
+        let triples: &TriplesBitmap = self.triples.borrow();
+        let o = triples.adjlist_z.sequence.get(pos_z) as Id;
+        triples.coord_to_triple(s, self.p, o).unwrap()
     }
 }
 
-impl Iterator for PredicateIter<'_> {
+impl<T: Borrow<TriplesBitmap>> Iterator for PredicateIterOf<T> {
     type Item = TripleId;
     fn next(&mut self) -> Option<Self::Item> {
-        if self.i >= self.occs {
-            return None;
+        if self.front.1.is_empty() {
+            if let Some(i) = self.occs.next() {
+                self.front = self.next_occurrence(i);
+            } else {
+                // the back has already opened the last occurrence
+                let pos_z = self.back.1.next()?;
+                return Some(self.triple(self.back.0, pos_z));
+            }
         }
-        if self.os == 0 {
-            // Algorithm 1 findSubj from Martinez et al. 2012 ******
-            let pos_y = self.triples.wavelet_y.select(self.i, self.p as usize).unwrap();
-            self.s = self.triples.bitmap_y.rank(pos_y) as Id + 1;
-            // *****************************************************
-            // SP can have multiple O
-            self.pos_z = self.triples.adjlist_z.find(pos_y as Id);
-            let pos_z_end = self.triples.adjlist_z.last(pos_y as Id);
-            //println!("**** found predicate {} between {} and {} (inclusive)", self.p, self.pos_z, pos_z_end);
-            self.os = pos_z_end - self.pos_z;
-        } else {
-            self.os -= 1;
-            self.pos_z += 1;
+        let pos_z = self.front.1.next()?;
+        Some(self.triple(self.front.0, pos_z))
+    }
+
+    /// Skips whole occurrences of the predicate without looking at their objects.
+    fn nth(&mut self, mut n: usize) -> Option<Self::Item> {
+        loop {
+            if n < self.front.1.len() {
+                self.front.1.start += n;
+                return self.next();
+            }
+            n -= self.front.1.len();
+            self.front.1.start = self.front.1.end;
+            let Some(i) = self.occs.next() else {
+                // the back has already opened the last occurrence
+                self.back.1.start = self.back.1.start.saturating_add(n).min(self.back.1.end);
+                return self.next();
+            };
+            self.front = self.next_occurrence(i);
         }
+    }
+}
 
-        let o = self.triples.adjlist_z.sequence.get(self.pos_z) as Id;
-        if self.os == 0 {
-            self.i += 1;
+impl<T: Borrow<TriplesBitmap>> DoubleEndedIterator for PredicateIterOf<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.back.1.is_empty() {
+            if let Some(i) = self.occs.next_back() {
+                self.back = self.occurrence(i);
+            } else {
+                // the front has already opened the last occurrence
+                let pos_z = self.front.1.next_back()?;
+                return Some(self.triple(self.front.0, pos_z));
+            }
         }
-        Some(self.triples.coord_to_triple(self.s, self.p, o).unwrap())
+        let pos_z = self.back.1.next_back()?;
+        Some(self.triple(self.back.0, pos_z))
     }
 }
+
+impl<T: Borrow<TriplesBitmap>> FusedIterator for PredicateIterOf<T> {}
@@ -1,29 +1,35 @@
 use crate::triples::Id;
 use crate::triples::TriplesBitmap;
+use std::borrow::Borrow;
 use std::cmp::Ordering;
-use sucds::int_vectors::Access;
+use std::iter::FusedIterator;
 
 // see filterPredSubj in "Exchange and Consumption of Huge RDF Data" by Martinez et al. 2012
 // https://link.springer.com/chapter/10.1007/978-3-642-30284-8_36
 
 /// Iterator over all subject IDs with a given predicate and object ID, answering an (?S,P,O) query.
-pub struct PredicateObjectIter<'a> {
-    triples: &'a TriplesBitmap,
+pub type PredicateObjectIter<'a> = PredicateObjectIterOf<&'a TriplesBitmap>;
+
+/// [`PredicateObjectIter`] that is generic over how the triples are held, such as [`SharedTriples`](crate::hdt::SharedTriples) for an iterator that owns its data.
+pub struct PredicateObjectIterOf<T> {
+    triples: T,
     pos_index: usize,
-    max_index: usize,
+    // exclusive
+    end_index: usize,
 }
 
-impl<'a> PredicateObjectIter<'a> {
+impl<T: Borrow<TriplesBitmap>> PredicateObjectIterOf<T> {
     /// Create a new iterator over all triples with the given predicate and object ID.
     /// Panics if the predicate or object ID is 0.
-    pub fn new(triples: &'a TriplesBitmap, p: Id, o: Id) -> Self {
+    pub fn new(triples: T, p: Id, o: Id) -> Self {
         assert_ne!(0, p, "predicate 0 does not exist, cant iterate");
         assert_ne!(0, o, "object 0 does not exist, cant iterate");
-        let mut low = triples.op_index.find(o);
-        let mut high = triples.op_index.last(o);
+        let t: &TriplesBitmap = triples.borrow();
+        let mut low = t.op_index.find(o);
+        let mut high = t.op_index.last(o);
         let get_y = |pos_index| {
-            let pos_y = triples.op_index.sequence.access(pos_index).unwrap();
-            triples.wavelet_y.access(pos_y).unwrap() as Id
+            let pos_y = t.op_index.sequence.get(pos_index);
+            t.wavelet_y.access(pos_y).unwrap() as Id
         };
         // Binary search with a twist:
         // Each value may occur multiple times, so we search for the left and right borders.
@@ -54,7 +60,7 @@ impl<'a> PredicateObjectIter<'a> {
                             _ => right_low = mid,
                         }
                     }
-                    return PredicateObjectIter { triples, pos_index: low, max_index: high };
+                    return PredicateObjectIterOf { triples, pos_index: low, end_index: high + 1 };
                 }
             }
             if (high == 0 && low == 0) || (high == low && high == mid) {
@@ -62,21 +68,54 @@ impl<'a> PredicateObjectIter<'a> {
             }
         }
         // not found
-        PredicateObjectIter { triples, pos_index: 999, max_index: 0 }
+        PredicateObjectIterOf { triples, pos_index: 0, end_index: 0 }
     }
 }
 
-impl Iterator for PredicateObjectIter<'_> {
+impl<T: Borrow<TriplesBitmap>> Iterator for PredicateObjectIterOf<T> {
     type Item = Id;
     fn next(&mut self) -> Option<Self::Item> {
-        if self.pos_index > self.max_index {
+        if self.pos_index >= self.end_index {
             return None;
         }
-        let pos_y = self.triples.op_index.sequence.access(self.pos_index).unwrap();
-        //let y = self.triples.wavelet_y.get(pos_y as usize) as Id;
-        //println!(" op p {y}");
-        let s = self.triples.bitmap_y.rank(pos_y) as Id + 1;
+        let s = self.subject(self.pos_index);
         self.pos_index += 1;
         Some(s)
     }
+
+    /// Jumps directly to the position in the object index.
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.pos_index = self.pos_index.saturating_add(n).min(self.end_index);
+        self.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.end_index.saturating_sub(self.pos_index);
+        (len, Some(len))
+    }
 }
+
+impl<T: Borrow<TriplesBitmap>> ExactSizeIterator for PredicateObjectIterOf<T> {}
+
+impl<T: Borrow<TriplesBitmap>> DoubleEndedIterator for PredicateObjectIterOf<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.pos_index >= self.end_index {
+            return None;
+        }
+        self.end_index -= 1;
+        Some(self.subject(self.end_index))
+    }
+}
+
+impl<T: Borrow<TriplesBitmap>> PredicateObjectIterOf<T> {
+    /// Subject ID at the given position in the object index.
+    fn subject(&self, pos_index: usize) -> Id {
+        let triples: &TriplesBitmap = self.triples.borrow();
+        let pos_y = triples.op_index.sequence.get(pos_index);
+        //let y = self.triples.wavelet_y.get(pos_y as usize) as Id;
+        //println!(" op p {y}");
+        triples.bitmap_y.rank(pos_y) as Id + 1
+    }
+}
+
+impl<T: Borrow<TriplesBitmap>> FusedIterator for PredicateObjectIterOf<T> {}
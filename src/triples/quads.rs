@@ -0,0 +1,148 @@
+use crate::containers::vbyte::{encode_vbyte, read_vbyte};
+use crate::containers::Bitmap;
+use crate::error::{HdtError, Part};
+use crate::triples::{Id, TripleId};
+use eyre::{eyre, Result, WrapErr};
+use std::io::{self, BufRead, Write};
+use sucds::int_vectors::CompactVector;
+
+/// Type for a quad encoded as numeric IDs for subject, predicate, object and graph, respectively.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct QuadId {
+    /// Index starting at 1 in the combined shared and subject section.
+    pub subject_id: Id,
+    /// Index starting at 1 in the predicate section.
+    pub predicate_id: Id,
+    /// Index starting at 1 in the combined shared and object section.
+    pub object_id: Id,
+    /// Index starting at 1 in the graph section, 0 for the default graph of files without graphs.
+    pub graph_id: Id,
+}
+
+impl QuadId {
+    /// Create a new quad ID.
+    pub const fn new(subject_id: Id, predicate_id: Id, object_id: Id, graph_id: Id) -> Self {
+        QuadId { subject_id, predicate_id, object_id, graph_id }
+    }
+
+    /// The given triple in the given graph.
+    pub const fn from_triple(t: TripleId, graph_id: Id) -> Self {
+        Self::new(t.subject_id, t.predicate_id, t.object_id, graph_id)
+    }
+
+    /// The triple without the graph.
+    pub const fn triple(&self) -> TripleId {
+        TripleId::new(self.subject_id, self.predicate_id, self.object_id)
+    }
+}
+
+/// Graph membership of the triples of quad files, with one bitmap per graph whose one bits mark the positions of its triples in the Z level.
+/// A triple that occurs in several graphs is only stored once in the triples section.
+/// Serialized after the Z level sequence as the number of graphs followed by a plain bitmap for each graph.
+/// This layout follows the HDTq proposal but is only tested with files written by this crate,
+/// so reading it requires the experimental `hdtq` feature.
+#[derive(Debug)]
+pub struct QuadInfo {
+    graphs: Vec<Bitmap>,
+    // graph IDs of each triple, those of the triple at pos_z are at graph_ids[offsets[pos_z]..offsets[pos_z + 1]]
+    graph_ids: CompactVector,
+    offsets: CompactVector,
+}
+
+/// Positions of the one bits of the given bitmap in ascending order.
+fn ones(bitmap: &Bitmap) -> impl Iterator<Item = usize> + '_ {
+    (0..).map_while(|k| bitmap.select1(k))
+}
+
+/// Return an error for the given quad format unless the experimental `hdtq` feature is active.
+pub(crate) fn require_hdtq(part: Part, format: &str) -> Result<(), HdtError> {
+    if cfg!(feature = "hdtq") {
+        return Ok(());
+    }
+    Err(HdtError::UnsupportedFormat {
+        part,
+        format: format.to_owned(),
+        supported: "quads require the experimental hdtq feature",
+    })
+}
+
+impl QuadInfo {
+    /// Graph membership from one bitmap per graph, ordered by graph ID.
+    /// Also builds the lookup from each triple to its graphs, which takes one pass over the one bits of all bitmaps.
+    pub fn new(graphs: Vec<Bitmap>) -> Result<Self> {
+        let num_triples = graphs.iter().map(Bitmap::len).max().unwrap_or(0);
+        let mut offsets = vec![0; num_triples + 1];
+        for bitmap in &graphs {
+            for pos_z in ones(bitmap) {
+                offsets[pos_z + 1] += 1;
+            }
+        }
+        for pos_z in 1..offsets.len() {
+            offsets[pos_z] += offsets[pos_z - 1];
+        }
+        // fill in a second pass in ascending graph order, starts[pos_z] is the next free entry of the triple at pos_z
+        let mut starts = offsets.clone();
+        let mut graph_ids = vec![0; offsets[num_triples]];
+        for (i, bitmap) in graphs.iter().enumerate() {
+            for pos_z in ones(bitmap) {
+                graph_ids[starts[pos_z]] = i + 1;
+                starts[pos_z] += 1;
+            }
+        }
+        let compact = |values: &[usize]| {
+            CompactVector::from_slice(values).map_err(|err| eyre!("Failed to build graph lookup: {err}"))
+        };
+        Ok(QuadInfo { graph_ids: compact(&graph_ids)?, offsets: compact(&offsets)?, graphs })
+    }
+
+    /// Read the number of graphs and their bitmaps.
+    pub fn read<R: BufRead>(reader: &mut R) -> Result<Self> {
+        let (num_graphs, _) = read_vbyte(reader).wrap_err("Failed to read the number of graphs")?;
+        // a corrupted number results in an error when reading the bitmaps instead of an excessive allocation
+        let mut graphs = Vec::new();
+        for graph_id in 1..=num_graphs {
+            graphs.push(
+                Bitmap::read(reader).wrap_err_with(|| format!("Failed to read bitmap of graph {graph_id}"))?,
+            );
+        }
+        Self::new(graphs)
+    }
+
+    /// Write the graph bitmaps with `num_triples` bits each in the format expected by [`Self::read`].
+    pub fn write<W: Write>(&self, writer: &mut W, num_triples: usize) -> io::Result<()> {
+        writer.write_all(&encode_vbyte(self.graphs.len()))?;
+        for bitmap in &self.graphs {
+            bitmap.write(writer, num_triples)?;
+        }
+        Ok(())
+    }
+
+    /// Number of graphs.
+    pub const fn num_graphs(&self) -> usize {
+        self.graphs.len()
+    }
+
+    /// Bitmap of the graph with the given ID, counting from 1.
+    pub fn graph(&self, graph_id: Id) -> Option<&Bitmap> {
+        graph_id.checked_sub(1).and_then(|i| self.graphs.get(i))
+    }
+
+    /// Whether the triple at the given position in the Z level is in the graph with the given ID.
+    pub fn contains(&self, graph_id: Id, pos_z: usize) -> bool {
+        self.graph(graph_id).is_some_and(|bitmap| bitmap.get(pos_z))
+    }
+
+    /// IDs of the graphs that contain the triple at the given position in the Z level.
+    /// Takes two lookups instead of testing each graph.
+    pub fn graphs_at(&self, pos_z: usize) -> impl Iterator<Item = Id> + '_ {
+        let (start, end) = (self.offsets.get_int(pos_z), self.offsets.get_int(pos_z + 1));
+        let range = start.zip(end).map_or(0..0, |(start, end)| start..end);
+        range.map(|i| self.graph_ids.get_int(i).unwrap())
+    }
+
+    /// Size in bytes on the heap.
+    pub fn size_in_bytes(&self) -> usize {
+        self.graphs.iter().map(Bitmap::size_in_bytes).sum::<usize>()
+            + (self.graph_ids.len() * self.graph_ids.width() + self.offsets.len() * self.offsets.width()) / 8
+    }
+}
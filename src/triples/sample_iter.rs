@@ -0,0 +1,62 @@
+use crate::triples::TripleId;
+use crate::triples::TriplesBitmap;
+use std::iter::FusedIterator;
+
+/// Iterator over a uniform random sample of triples, drawn with replacement.
+/// Uses a seeded pseudo random number generator, so that the same seed always yields the same sample.
+pub struct SampleIter<'a> {
+    triples: &'a TriplesBitmap,
+    remaining: usize,
+    rng: SplitMix64,
+}
+
+/// Small and fast seedable pseudo random number generator, see <https://prng.di.unimi.it/splitmix64.c>.
+/// Not suitable for cryptography.
+#[derive(Debug, Clone)]
+pub(crate) struct SplitMix64(u64);
+
+impl SplitMix64 {
+    pub(crate) const fn new(seed: u64) -> Self {
+        SplitMix64(seed)
+    }
+
+    pub(crate) const fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Random number in `0..n`, using multiply-shift instead of a biased modulo.
+    pub(crate) const fn below(&mut self, n: usize) -> usize {
+        ((self.next_u64() as u128 * n as u128) >> 64) as usize
+    }
+}
+
+impl<'a> SampleIter<'a> {
+    /// Create a new iterator over `n` triples sampled uniformly at random.
+    pub const fn new(triples: &'a TriplesBitmap, n: usize, seed: u64) -> Self {
+        let remaining = if triples.adjlist_z.is_empty() { 0 } else { n };
+        SampleIter { triples, remaining, rng: SplitMix64::new(seed) }
+    }
+}
+
+impl Iterator for SampleIter<'_> {
+    type Item = TripleId;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let pos_z = self.rng.below(self.triples.adjlist_z.len());
+        Some(self.triples.triple_at(pos_z))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl FusedIterator for SampleIter<'_> {}
@@ -1,10 +1,17 @@
 use super::{Id, TripleId, TriplesBitmap};
+use std::borrow::Borrow;
+use std::iter::FusedIterator;
+use std::ops::Range;
 
 /// Iterator over triples fitting an SPO, SP? S?? or ??? triple pattern.
+/// Iterates in ascending subject order and in descending order from the back.
+pub type SubjectIter<'a> = SubjectIterOf<&'a TriplesBitmap>;
+
+/// [`SubjectIter`] that is generic over how the triples are held, such as [`SharedTriples`](crate::hdt::SharedTriples) for an iterator that owns its data.
 //#[derive(Debug)]
-pub struct SubjectIter<'a> {
+pub struct SubjectIterOf<T> {
     // triples data
-    triples: &'a TriplesBitmap,
+    triples: T,
     // x-coordinate identifier
     x: Id,
     // current position
@@ -15,33 +22,53 @@ pub struct SubjectIter<'a> {
     search_z: usize, // for S?O
 }
 
-impl<'a> SubjectIter<'a> {
+impl<T: Borrow<TriplesBitmap>> SubjectIterOf<T> {
     /// Create an iterator over all triples.
-    pub fn new(triples: &'a TriplesBitmap) -> Self {
-        SubjectIter {
+    pub fn new(triples: T) -> Self {
+        let t: &TriplesBitmap = triples.borrow();
+        let max_y = t.wavelet_y.len(); // exclusive
+        let max_z = t.adjlist_z.len(); // exclusive
+        SubjectIterOf {
             triples,
             x: 1, // was 0 in the old code but it should start at 1
             pos_y: 0,
             pos_z: 0,
-            max_y: triples.wavelet_y.len(), // exclusive
-            max_z: triples.adjlist_z.len(), // exclusive
+            max_y,
+            max_z,
             search_z: 0,
         }
     }
 
     /// Use when no results are found.
-    pub const fn empty(triples: &'a TriplesBitmap) -> Self {
-        SubjectIter { triples, x: 1, pos_y: 0, pos_z: 0, max_y: 0, max_z: 0, search_z: 0 }
+    pub const fn empty(triples: T) -> Self {
+        SubjectIterOf { triples, x: 1, pos_y: 0, pos_z: 0, max_y: 0, max_z: 0, search_z: 0 }
     }
 
     /// Convenience method for the S?? triple pattern.
     /// See <https://github.com/rdfhdt/hdt-cpp/blob/develop/libhdt/src/triples/BitmapTriplesIterators.cpp>.
-    pub fn with_s(triples: &'a TriplesBitmap, subject_id: Id) -> Self {
-        let min_y = triples.find_y(subject_id - 1);
-        let min_z = triples.adjlist_z.find(min_y as Id);
-        let max_y = triples.find_y(subject_id);
-        let max_z = triples.adjlist_z.find(max_y as Id);
-        SubjectIter { triples, x: subject_id, pos_y: min_y, pos_z: min_z, max_y, max_z, search_z: 0 }
+    pub fn with_s(triples: T, subject_id: Id) -> Self {
+        let t: &TriplesBitmap = triples.borrow();
+        let min_y = t.find_y(subject_id - 1);
+        let min_z = t.adjlist_z.find(min_y as Id);
+        let max_y = t.find_y(subject_id);
+        let max_z = t.adjlist_z.find(max_y as Id);
+        SubjectIterOf { triples, x: subject_id, pos_y: min_y, pos_z: min_z, max_y, max_z, search_z: 0 }
+    }
+
+    /// Iterator over the triples of all subjects in the given ID range, such as a chunk of the subjects for parallel processing.
+    /// Subjects beyond the last one of the triples section are ignored.
+    pub fn with_subjects(triples: T, subjects: Range<Id>) -> Self {
+        let t: &TriplesBitmap = triples.borrow();
+        let start = subjects.start.max(1);
+        let end = subjects.end.min(t.bitmap_y.rank(t.bitmap_y.len()) + 1);
+        if start >= end {
+            return SubjectIterOf::empty(triples);
+        }
+        let min_y = t.find_y(start - 1);
+        let min_z = t.adjlist_z.find(min_y as Id);
+        let max_y = t.find_y(end - 1);
+        let max_z = t.adjlist_z.find(max_y as Id);
+        SubjectIterOf { triples, x: start, pos_y: min_y, pos_z: min_z, max_y, max_z, search_z: 0 }
     }
 
     /// Iterate over triples fitting the given SPO, SP? S??, S?O or ??? triple pattern.
@@ -57,7 +84,8 @@ impl<'a> SubjectIter<'a> {
     /// SubjectIter::with_pattern(triples, TripleId::new(1, 2, 3);
     /// ```
     // Translated from <https://github.com/rdfhdt/hdt-cpp/blob/develop/libhdt/src/triples/BitmapTriplesIterators.cpp>.
-    pub fn with_pattern(triples: &'a TriplesBitmap, pat: &TripleId) -> Self {
+    pub fn with_pattern(triples: T, pat: &TripleId) -> Self {
+        let t: &TriplesBitmap = triples.borrow();
         let (pat_x, pat_y, pat_z) = (pat.subject_id, pat.predicate_id, pat.object_id);
         let (min_y, max_y, min_z, max_z);
         let mut x = 1;
@@ -67,30 +95,30 @@ impl<'a> SubjectIter<'a> {
             // S X X
             if pat_y != 0 {
                 // S P X
-                match triples.search_y(pat_x - 1, pat_y) {
+                match t.search_y(pat_x - 1, pat_y) {
                     Some(y) => min_y = y,
-                    None => return SubjectIter::empty(triples),
+                    None => return SubjectIterOf::empty(triples),
                 };
                 max_y = min_y + 1;
                 if pat_z != 0 {
                     // S P O
                     // simply with try block when they come to stable Rust
-                    match triples.adjlist_z.search(min_y, pat_z) {
+                    match t.adjlist_z.search(min_y, pat_z) {
                         Some(pos_z) => min_z = pos_z,
-                        None => return SubjectIter::empty(triples),
+                        None => return SubjectIterOf::empty(triples),
                     };
                     max_z = min_z + 1;
                 } else {
                     // S P ?
-                    min_z = triples.adjlist_z.find(min_y);
-                    max_z = triples.adjlist_z.last(min_y) + 1;
+                    min_z = t.adjlist_z.find(min_y);
+                    max_z = t.adjlist_z.last(min_y) + 1;
                 }
             } else {
                 // S ? X
-                min_y = triples.find_y(pat_x - 1);
-                min_z = triples.adjlist_z.find(min_y);
-                max_y = triples.last_y(pat_x - 1) + 1;
-                max_z = triples.adjlist_z.find(max_y);
+                min_y = t.find_y(pat_x - 1);
+                min_z = t.adjlist_z.find(min_y);
+                max_y = t.last_y(pat_x - 1) + 1;
+                max_z = t.adjlist_z.find(max_y);
                 search_z = pat_z;
             }
             x = pat_x;
@@ -99,45 +127,47 @@ impl<'a> SubjectIter<'a> {
             // assume ? ? ?, other triple patterns are not supported by this iterator
             min_y = 0;
             min_z = 0;
-            max_y = triples.wavelet_y.len();
-            max_z = triples.adjlist_z.len();
+            max_y = t.wavelet_y.len();
+            max_z = t.adjlist_z.len();
         }
-        SubjectIter { triples, x, pos_y: min_y, pos_z: min_z, max_y, max_z, search_z }
+        SubjectIterOf { triples, x, pos_y: min_y, pos_z: min_z, max_y, max_z, search_z }
     }
 }
 
-impl Iterator for SubjectIter<'_> {
+impl<T: Borrow<TriplesBitmap>> Iterator for SubjectIterOf<T> {
     type Item = TripleId;
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.pos_y >= self.max_y {
             return None;
         }
-
-        let y = self.triples.wavelet_y.access(self.pos_y).unwrap() as Id;
+        let triples: &TriplesBitmap = self.triples.borrow();
 
         if self.search_z > 0 {
-            self.pos_y += 1;
-            match self.triples.adjlist_z.search(self.pos_y - 1, self.search_z) {
-                Some(_) => {
-                    return Some(self.triples.coord_to_triple(self.x, y, self.search_z).unwrap());
-                }
-                None => {
-                    return self.next();
+            // S?O: check each predicate of the subject for the object, looping instead of recursing for long runs without a match
+            while self.pos_y < self.max_y {
+                let pos_y = self.pos_y;
+                self.pos_y += 1;
+                if triples.adjlist_z.search(pos_y, self.search_z).is_some() {
+                    let y = triples.wavelet_y.access(pos_y).unwrap() as Id;
+                    return Some(triples.coord_to_triple(self.x, y, self.search_z).unwrap());
                 }
             }
+            return None;
         }
 
+        let y = triples.wavelet_y.access(self.pos_y).unwrap() as Id;
+
         if self.pos_z >= self.max_z {
             return None;
         }
-        let z = self.triples.adjlist_z.get_id(self.pos_z);
-        let triple_id = self.triples.coord_to_triple(self.x, y, z).unwrap();
+        let z = triples.adjlist_z.get_id(self.pos_z);
+        let triple_id = triples.coord_to_triple(self.x, y, z).unwrap();
 
         // theoretically the second condition should only be true if the first is as well but in practise it wasn't, which screwed up the subject identifiers
         // fixed by moving the second condition inside the first one but there may be another reason for the bug occuring in the first place
-        if self.triples.adjlist_z.at_last_sibling(self.pos_z) {
-            if self.triples.bitmap_y.at_last_sibling(self.pos_y) {
+        if triples.adjlist_z.at_last_sibling(self.pos_z) {
+            if triples.bitmap_y.at_last_sibling(self.pos_y) {
                 self.x += 1;
             }
             self.pos_y += 1;
@@ -145,4 +175,51 @@ impl Iterator for SubjectIter<'_> {
         self.pos_z += 1;
         Some(triple_id)
     }
+
+    /// Jumps directly to the position in the Z level and locates its subject and predicate with rank queries.
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        if self.search_z > 0 {
+            // S?O results are not contiguous
+            for _ in 0..n {
+                self.next()?;
+            }
+            return self.next();
+        }
+        if self.pos_y >= self.max_y {
+            return None;
+        }
+        if n >= self.max_z.saturating_sub(self.pos_z) {
+            self.pos_z = self.max_z;
+            return None;
+        }
+        let triples: &TriplesBitmap = self.triples.borrow();
+        self.pos_z += n;
+        self.pos_y = triples.adjlist_z.bitmap.rank(self.pos_z);
+        self.x = triples.bitmap_y.rank(self.pos_y) as Id + 1;
+        self.next()
+    }
 }
+
+impl<T: Borrow<TriplesBitmap>> DoubleEndedIterator for SubjectIterOf<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let triples: &TriplesBitmap = self.triples.borrow();
+        if self.search_z > 0 {
+            while self.pos_y < self.max_y {
+                self.max_y -= 1;
+                if triples.adjlist_z.search(self.max_y, self.search_z).is_some() {
+                    let y = triples.wavelet_y.access(self.max_y).unwrap() as Id;
+                    return Some(triples.coord_to_triple(self.x, y, self.search_z).unwrap());
+                }
+            }
+            return None;
+        }
+        if self.pos_y >= self.max_y || self.pos_z >= self.max_z {
+            return None;
+        }
+        // the subject of the last remaining triple is located with rank queries
+        self.max_z -= 1;
+        Some(triples.triple_at(self.max_z))
+    }
+}
+
+impl<T: Borrow<TriplesBitmap>> FusedIterator for SubjectIterOf<T> {}
@@ -0,0 +1,147 @@
+use crate::triples::{TripleId, TriplesBitmap};
+use std::ops::Range;
+use sucds::bit_vectors::{Access, BitVector};
+
+/// Deleted triples of a read-only triples section, as one bit per position in the Z level, see [`TriplesBitmap::position`].
+/// Needs one bit per triple regardless of the number of deletions, and the results of a pattern are filtered by looking up
+/// their positions instead of hashing each of them, see [`TriplesBitmap::triples_with_pattern_except`].
+#[derive(Debug, Clone)]
+pub struct Tombstones {
+    bits: BitVector,
+    deleted: usize,
+}
+
+impl Tombstones {
+    /// No deleted triples in a triples section with the given number of triples.
+    pub fn new(num_triples: usize) -> Self {
+        Tombstones { bits: BitVector::from_bit(false, num_triples), deleted: 0 }
+    }
+
+    /// Mark the given triple as deleted.
+    /// Returns false if it does not exist in the given triples section or has already been deleted.
+    pub fn delete(&mut self, triples: &TriplesBitmap, t: &TripleId) -> bool {
+        triples.position(t).is_some_and(|pos_z| self.delete_at(pos_z))
+    }
+
+    /// Mark the triple at the given position in the Z level as deleted.
+    /// Returns false if it has already been deleted.
+    /// Panics if the position is out of bounds.
+    pub fn delete_at(&mut self, pos_z: usize) -> bool {
+        if self.is_deleted_at(pos_z) {
+            return false;
+        }
+        self.bits.set_bit(pos_z, true).unwrap();
+        self.deleted += 1;
+        true
+    }
+
+    /// Remove the deletion mark of the triple at the given position in the Z level.
+    /// Returns false if it was not deleted.
+    /// Panics if the position is out of bounds.
+    pub fn restore_at(&mut self, pos_z: usize) -> bool {
+        if !self.is_deleted_at(pos_z) {
+            return false;
+        }
+        self.bits.set_bit(pos_z, false).unwrap();
+        self.deleted -= 1;
+        true
+    }
+
+    /// Whether the given triple exists in the given triples section and is marked as deleted.
+    pub fn is_deleted(&self, triples: &TriplesBitmap, t: &TripleId) -> bool {
+        triples.position(t).is_some_and(|pos_z| self.is_deleted_at(pos_z))
+    }
+
+    /// Whether the triple at the given position in the Z level is marked as deleted.
+    /// Panics if the position is out of bounds.
+    pub fn is_deleted_at(&self, pos_z: usize) -> bool {
+        self.bits.access(pos_z).unwrap()
+    }
+
+    /// Number of deleted triples.
+    pub const fn deleted(&self) -> usize {
+        self.deleted
+    }
+
+    /// Number of triples of the triples section, deleted or not.
+    pub const fn len(&self) -> usize {
+        self.bits.len()
+    }
+
+    /// Size in bytes on the heap.
+    pub fn size_in_bytes(&self) -> usize {
+        self.bits.num_words() * size_of::<usize>()
+    }
+}
+
+impl TriplesBitmap {
+    /// Positions in the Z level of the results of the given pattern if they are contiguous and in the same order as the results,
+    /// which is the case for patterns whose constants are a prefix of subject, predicate and object.
+    pub(crate) fn contiguous_range_z(&self, pat: &TripleId) -> Option<Range<usize>> {
+        let (s, p, o) = (pat.subject_id, pat.predicate_id, pat.object_id);
+        match (s != 0, p != 0, o != 0) {
+            (false, false, false) => Some(0..self.len()),
+            (true, false, false) => Some(self.subject_range_z(s)),
+            (true, true, false) => {
+                if s > self.bitmap_y.rank(self.bitmap_y.len()) {
+                    return Some(0..0);
+                }
+                Some(
+                    self.search_y(s - 1, p)
+                        .map_or(0..0, |pos_y| self.adjlist_z.find(pos_y)..self.adjlist_z.find(pos_y + 1)),
+                )
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::init;
+    use crate::Hdt;
+    use pretty_assertions::assert_eq;
+    use std::fs::File;
+
+    #[test]
+    fn tombstones() {
+        init();
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let hdt = Hdt::new(std::io::BufReader::new(file)).unwrap();
+        let triples = &hdt.triples;
+        let mut tombstones = Tombstones::new(triples.len());
+        let t = triples.triple_at(100);
+        let deleted: Vec<TripleId> =
+            triples.triples_with_pattern(&TripleId::new(t.subject_id, 0, 0)).step_by(2).collect();
+        for d in &deleted {
+            assert!(tombstones.delete(triples, d));
+            assert!(!tombstones.delete(triples, d));
+        }
+        assert!(!tombstones.delete(triples, &TripleId::new(t.subject_id, t.predicate_id, 1_000_000)));
+        assert_eq!(deleted.len(), tombstones.deleted());
+        for pat in [
+            TripleId::new(0, 0, 0),
+            TripleId::new(t.subject_id, 0, 0),
+            TripleId::new(t.subject_id, t.predicate_id, 0),
+            TripleId::new(t.subject_id, t.predicate_id, t.object_id),
+            TripleId::new(t.subject_id, 0, t.object_id),
+            TripleId::new(0, t.predicate_id, 0),
+            TripleId::new(0, t.predicate_id, t.object_id),
+            TripleId::new(0, 0, t.object_id),
+        ] {
+            let expected: Vec<TripleId> =
+                triples.triples_with_pattern(&pat).filter(|r| !deleted.contains(r)).collect();
+            assert_eq!(
+                expected,
+                triples.triples_with_pattern_except(&pat, &tombstones).collect::<Vec<_>>(),
+                "{pat:?}"
+            );
+        }
+        let pos_z = triples.position(&deleted[0]).unwrap();
+        assert!(tombstones.restore_at(pos_z));
+        assert!(!tombstones.restore_at(pos_z));
+        assert!(!tombstones.is_deleted(triples, &deleted[0]));
+        assert_eq!(deleted.len() - 1, tombstones.deleted());
+    }
+}
@@ -0,0 +1,135 @@
+//! Thorough check of HDT files, so that damaged or inconsistent data is reported in a structured way up front
+//! instead of being found out through a panic or wrong results of a later query, see [`Hdt::validate`].
+use crate::containers::ControlInfo;
+use crate::error::{HdtError, Part};
+use crate::four_sect_dict::FourSectDict;
+use crate::hdt::Hdt;
+use crate::header::Header;
+use crate::triples::TriplesBitmap;
+use std::io::{self, BufRead};
+
+/// Result of [`Hdt::validate`].
+#[derive(Debug, Default)]
+pub struct ValidationReport {
+    /// Problems in the order of the parts of the file, empty if the file is valid.
+    pub problems: Vec<HdtError>,
+    /// Whether all parts could be read, as validation stops at the first part whose structure cannot be parsed.
+    pub complete: bool,
+}
+
+impl ValidationReport {
+    /// Whether the whole file was read and no problem was found.
+    pub const fn is_valid(&self) -> bool {
+        self.complete && self.problems.is_empty()
+    }
+}
+
+/// Problem of the given part from an error that stopped reading, keeping structured errors.
+fn problem(part: Part, e: eyre::Report) -> HdtError {
+    match e.downcast::<HdtError>() {
+        Ok(e) => e,
+        Err(e) => match e.downcast::<io::Error>() {
+            Ok(e) => HdtError::Io(e),
+            Err(e) => HdtError::invalid(part, format!("{e:#}")),
+        },
+    }
+}
+
+impl Hdt {
+    /// Check all parts of HDT data without loading it for querying and report all problems found instead of failing at the first one.
+    /// Besides the checksums of all sections, which [`Hdt::new`] also verifies, this checks:
+    /// - dictionary sections: bit widths and monotonicity of the block offsets, decodability of all blocks and strictly increasing strings,
+    /// - triples: lengths of the bitmaps and sequences, bit widths, IDs within the dictionary and strictly increasing IDs within each list.
+    ///
+    /// The wavelet matrix and the object index are not built, so validation needs much less memory than loading.
+    /// # Example
+    /// ```
+    /// let file = std::fs::File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+    /// let report = hdt::Hdt::validate(std::io::BufReader::new(file));
+    /// for problem in &report.problems {
+    ///     eprintln!("{problem}");
+    /// }
+    /// assert!(report.is_valid());
+    /// ```
+    pub fn validate<R: BufRead>(mut reader: R) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        if let Err(e) = ControlInfo::read(&mut reader) {
+            report.problems.push(problem(Part::ControlInfo, e));
+            return report;
+        }
+        if let Err(e) = Header::read(&mut reader) {
+            report.problems.push(problem(Part::Header, e));
+            return report;
+        }
+        let (mut dict, checksums) = match FourSectDict::read(&mut reader) {
+            Ok(dict) => dict.checksums(),
+            Err(e) => {
+                report.problems.push(problem(Part::Dictionary, e));
+                return report;
+            }
+        };
+        let names = ["shared", "subject", "predicate", "object", "graph"];
+        let FourSectDict { shared, subjects, predicates, objects, graphs } = &mut dict;
+        let sections = [shared, subjects, predicates, objects].into_iter().chain(graphs);
+        for ((name, valid), sect) in names.iter().zip(checksums).zip(sections) {
+            if !valid {
+                let what = format!("packed data of the {name} dictionary section");
+                report.problems.push(HdtError::checksum(Part::Dictionary, "CRC32C", what));
+            }
+            report.problems.extend(sect.problems(name));
+        }
+        let shared = dict.shared.num_strings();
+        let terms = [
+            shared + dict.subjects.num_strings(),
+            dict.predicates.num_strings(),
+            shared + dict.objects.num_strings(),
+        ];
+        match TriplesBitmap::check_sect(&mut reader, terms) {
+            Ok(problems) => report.problems.extend(problems),
+            Err(e) => {
+                report.problems.push(problem(Part::Triples, e));
+                return report;
+            }
+        }
+        report.complete = true;
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::init;
+    use crate::DictSectPFC;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn validate() {
+        init();
+        let original = std::fs::read("tests/resources/snikmeta.hdt").expect("error reading file");
+        let report = Hdt::validate(original.as_slice());
+        assert!(report.is_valid(), "{report:?}");
+
+        // a string in the middle of the data is in the packed data of a dictionary section
+        let mut data = original.clone();
+        data[original.len() / 2] ^= 1;
+        let report = Hdt::validate(data.as_slice());
+        assert!(report.complete);
+        assert!(
+            report.problems.iter().any(|p| matches!(p, HdtError::Checksum { part: Part::Dictionary, .. })),
+            "{report:?}"
+        );
+
+        let report = Hdt::validate(&original[..original.len() - 10]);
+        assert!(!report.complete);
+        assert!(matches!(report.problems.as_slice(), [HdtError::Io(_)]), "{report:?}");
+
+        let mut sect = DictSectPFC::new(&["a", "c", "b", "d", "d"], 2);
+        let problems = sect.problems("test");
+        assert_eq!(1, problems.len());
+        assert_eq!(
+            "invalid dictionary: test section: 2 strings are not greater than their predecessors, the first has ID 3",
+            problems[0].to_string()
+        );
+    }
+}
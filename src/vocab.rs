@@ -0,0 +1,235 @@
+//! Well-known vocabulary terms whose IDs are resolved once when loading, so that frequent lookups of terms like `rdf:type` and `rdfs:label` in the dictionary can be avoided.
+use crate::four_sect_dict::{FourSectDict, IdKind};
+use crate::triples::Id;
+
+const RDF: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+const RDFS: &str = "http://www.w3.org/2000/01/rdf-schema#";
+const OWL: &str = "http://www.w3.org/2002/07/owl#";
+const XSD: &str = "http://www.w3.org/2001/XMLSchema#";
+const SKOS: &str = "http://www.w3.org/2004/02/skos/core#";
+
+/// Terms of the RDF, RDFS, OWL, XSD and SKOS vocabularies that are resolved when loading an [`Hdt`](crate::Hdt).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum Vocab {
+    RdfType,
+    RdfProperty,
+    RdfFirst,
+    RdfRest,
+    RdfNil,
+    RdfLangString,
+    RdfsLabel,
+    RdfsComment,
+    RdfsSeeAlso,
+    RdfsSubClassOf,
+    RdfsSubPropertyOf,
+    RdfsDomain,
+    RdfsRange,
+    RdfsClass,
+    RdfsResource,
+    OwlClass,
+    OwlThing,
+    OwlObjectProperty,
+    OwlDatatypeProperty,
+    OwlSameAs,
+    OwlEquivalentClass,
+    OwlInverseOf,
+    XsdString,
+    XsdInteger,
+    XsdDecimal,
+    XsdBoolean,
+    XsdDate,
+    XsdDateTime,
+    SkosConcept,
+    SkosPrefLabel,
+    SkosAltLabel,
+    SkosDefinition,
+    SkosBroader,
+    SkosNarrower,
+    SkosExample,
+}
+
+impl Vocab {
+    /// All terms in declaration order.
+    pub const ALL: [Vocab; 35] = [
+        Vocab::RdfType,
+        Vocab::RdfProperty,
+        Vocab::RdfFirst,
+        Vocab::RdfRest,
+        Vocab::RdfNil,
+        Vocab::RdfLangString,
+        Vocab::RdfsLabel,
+        Vocab::RdfsComment,
+        Vocab::RdfsSeeAlso,
+        Vocab::RdfsSubClassOf,
+        Vocab::RdfsSubPropertyOf,
+        Vocab::RdfsDomain,
+        Vocab::RdfsRange,
+        Vocab::RdfsClass,
+        Vocab::RdfsResource,
+        Vocab::OwlClass,
+        Vocab::OwlThing,
+        Vocab::OwlObjectProperty,
+        Vocab::OwlDatatypeProperty,
+        Vocab::OwlSameAs,
+        Vocab::OwlEquivalentClass,
+        Vocab::OwlInverseOf,
+        Vocab::XsdString,
+        Vocab::XsdInteger,
+        Vocab::XsdDecimal,
+        Vocab::XsdBoolean,
+        Vocab::XsdDate,
+        Vocab::XsdDateTime,
+        Vocab::SkosConcept,
+        Vocab::SkosPrefLabel,
+        Vocab::SkosAltLabel,
+        Vocab::SkosDefinition,
+        Vocab::SkosBroader,
+        Vocab::SkosNarrower,
+        Vocab::SkosExample,
+    ];
+
+    /// Namespace and local name of the IRI.
+    pub const fn parts(self) -> (&'static str, &'static str) {
+        match self {
+            Vocab::RdfType => (RDF, "type"),
+            Vocab::RdfProperty => (RDF, "Property"),
+            Vocab::RdfFirst => (RDF, "first"),
+            Vocab::RdfRest => (RDF, "rest"),
+            Vocab::RdfNil => (RDF, "nil"),
+            Vocab::RdfLangString => (RDF, "langString"),
+            Vocab::RdfsLabel => (RDFS, "label"),
+            Vocab::RdfsComment => (RDFS, "comment"),
+            Vocab::RdfsSeeAlso => (RDFS, "seeAlso"),
+            Vocab::RdfsSubClassOf => (RDFS, "subClassOf"),
+            Vocab::RdfsSubPropertyOf => (RDFS, "subPropertyOf"),
+            Vocab::RdfsDomain => (RDFS, "domain"),
+            Vocab::RdfsRange => (RDFS, "range"),
+            Vocab::RdfsClass => (RDFS, "Class"),
+            Vocab::RdfsResource => (RDFS, "Resource"),
+            Vocab::OwlClass => (OWL, "Class"),
+            Vocab::OwlThing => (OWL, "Thing"),
+            Vocab::OwlObjectProperty => (OWL, "ObjectProperty"),
+            Vocab::OwlDatatypeProperty => (OWL, "DatatypeProperty"),
+            Vocab::OwlSameAs => (OWL, "sameAs"),
+            Vocab::OwlEquivalentClass => (OWL, "equivalentClass"),
+            Vocab::OwlInverseOf => (OWL, "inverseOf"),
+            Vocab::XsdString => (XSD, "string"),
+            Vocab::XsdInteger => (XSD, "integer"),
+            Vocab::XsdDecimal => (XSD, "decimal"),
+            Vocab::XsdBoolean => (XSD, "boolean"),
+            Vocab::XsdDate => (XSD, "date"),
+            Vocab::XsdDateTime => (XSD, "dateTime"),
+            Vocab::SkosConcept => (SKOS, "Concept"),
+            Vocab::SkosPrefLabel => (SKOS, "prefLabel"),
+            Vocab::SkosAltLabel => (SKOS, "altLabel"),
+            Vocab::SkosDefinition => (SKOS, "definition"),
+            Vocab::SkosBroader => (SKOS, "broader"),
+            Vocab::SkosNarrower => (SKOS, "narrower"),
+            Vocab::SkosExample => (SKOS, "example"),
+        }
+    }
+
+    /// Full IRI of the term.
+    pub fn iri(self) -> String {
+        let (namespace, local) = self.parts();
+        format!("{namespace}{local}")
+    }
+}
+
+/// IDs of a term in each triple position, 0 if the term does not occur in that position.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VocabIds {
+    /// ID in subject position.
+    pub subject: Id,
+    /// ID in predicate position.
+    pub predicate: Id,
+    /// ID in object position.
+    pub object: Id,
+}
+
+/// IDs of all [`Vocab`] terms in a dictionary, available as [`Hdt::vocab`](crate::Hdt::vocab).
+/// The IDs can be used directly in [`TripleId`](crate::triples::TripleId) patterns.
+/// # Example
+/// ```
+/// use hdt::vocab::Vocab;
+/// use hdt::triples::TripleId;
+/// fn count_classes(hdt: &hdt::Hdt) -> usize {
+///     let (rdf_type, owl_class) = (hdt.vocab.predicate(Vocab::RdfType), hdt.vocab.object(Vocab::OwlClass));
+///     if rdf_type == 0 || owl_class == 0 {
+///         return 0;
+///     }
+///     hdt.triples.triples_with_pattern(&TripleId::new(0, rdf_type, owl_class)).count()
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Vocabulary {
+    ids: [VocabIds; Vocab::ALL.len()],
+}
+
+impl Vocabulary {
+    /// Look up all terms in the dictionary.
+    pub fn resolve(dict: &FourSectDict) -> Self {
+        let mut ids = [VocabIds::default(); Vocab::ALL.len()];
+        for (ids, term) in ids.iter_mut().zip(Vocab::ALL) {
+            let iri = term.iri();
+            *ids = VocabIds {
+                subject: dict.string_to_id(&iri, &IdKind::Subject),
+                predicate: dict.string_to_id(&iri, &IdKind::Predicate),
+                object: dict.string_to_id(&iri, &IdKind::Object),
+            };
+        }
+        Vocabulary { ids }
+    }
+
+    /// IDs of the term in all positions.
+    pub const fn get(&self, term: Vocab) -> VocabIds {
+        self.ids[term as usize]
+    }
+
+    /// ID of the term in subject position, 0 if it is not used as a subject.
+    pub const fn subject(&self, term: Vocab) -> Id {
+        self.get(term).subject
+    }
+
+    /// ID of the term in predicate position, 0 if it is not used as a predicate.
+    pub const fn predicate(&self, term: Vocab) -> Id {
+        self.get(term).predicate
+    }
+
+    /// ID of the term in object position, 0 if it is not used as an object.
+    pub const fn object(&self, term: Vocab) -> Id {
+        self.get(term).object
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hdt::Hdt;
+    use crate::tests::init;
+    use pretty_assertions::assert_eq;
+    use std::fs::File;
+
+    #[test]
+    fn vocab() {
+        init();
+        for (i, term) in Vocab::ALL.into_iter().enumerate() {
+            assert_eq!(i, term as usize, "{term:?} out of order");
+        }
+        assert_eq!("http://www.w3.org/2000/01/rdf-schema#label", Vocab::RdfsLabel.iri());
+        let file = File::open("tests/resources/snikmeta.hdt").expect("error opening file");
+        let hdt = Hdt::new(std::io::BufReader::new(file)).unwrap();
+        for term in Vocab::ALL {
+            let iri = term.iri();
+            let ids = hdt.vocab.get(term);
+            assert_eq!(hdt.dict.string_to_id(&iri, &IdKind::Subject), ids.subject, "{iri}");
+            assert_eq!(hdt.dict.string_to_id(&iri, &IdKind::Predicate), ids.predicate, "{iri}");
+            assert_eq!(hdt.dict.string_to_id(&iri, &IdKind::Object), ids.object, "{iri}");
+        }
+        assert_ne!(0, hdt.vocab.predicate(Vocab::RdfType));
+        assert_ne!(0, hdt.vocab.predicate(Vocab::RdfsLabel));
+        assert_ne!(0, hdt.vocab.object(Vocab::OwlClass));
+        assert_eq!(0, hdt.vocab.subject(Vocab::RdfType));
+    }
+}